@@ -21,7 +21,7 @@ use crate::errors::Result;
 use crate::policy::{BatchPolicy, Concurrency};
 use crate::Error;
 use crate::Key;
-use crate::{BatchRecord, Policy};
+use crate::{BatchRecord, FromBins, Policy, Record};
 use aerospike_rt::time::Duration;
 use std::collections::HashMap;
 use std::sync::Arc;
@@ -41,12 +41,29 @@ impl BatchExecutor {
         Ok(node)
     }
 
-    #[allow(clippy::option_if_let_else)]
     pub async fn execute(
         &self,
         policy: &BatchPolicy,
         batch_ops: &[BatchOperation],
     ) -> Result<Vec<BatchRecord>> {
+        let results = self.execute_as::<Record>(policy, batch_ops).await?;
+        Ok(results
+            .into_iter()
+            .map(|(mut batch_op, record)| {
+                batch_op.set_record(record);
+                batch_op.batch_record()
+            })
+            .collect())
+    }
+
+    // Executes the batch and returns the operations with their result codes set, together with
+    // the records decoded into `T`, in the order of `batch_ops`.
+    #[allow(clippy::option_if_let_else)]
+    pub async fn execute_as<T: FromBins>(
+        &self,
+        policy: &BatchPolicy,
+        batch_ops: &[BatchOperation],
+    ) -> Result<Vec<(BatchOperation, Option<T>)>> {
         if policy.total_timeout() > 0 {
             match aerospike_rt::timeout(
                 Duration::from_millis(u64::from(policy.total_timeout())),
@@ -63,11 +80,11 @@ impl BatchExecutor {
     }
 
     #[allow(clippy::mutable_key_type)]
-    pub async fn execute_batch_operate(
+    pub async fn execute_batch_operate<T: FromBins>(
         &self,
         policy: &BatchPolicy,
         batch_ops: &[BatchOperation],
-    ) -> Result<Vec<BatchRecord>> {
+    ) -> Result<Vec<(BatchOperation, Option<T>)>> {
         let batch_nodes = self.get_batch_operate_nodes(batch_ops, policy.replica)?;
         let jobs = batch_nodes
             .into_iter()
@@ -76,19 +93,22 @@ impl BatchExecutor {
         let ops = self
             .execute_batch_operate_jobs(jobs, policy.concurrency)
             .await?;
-        let mut all_results: Vec<_> = ops.into_iter().flat_map(|cmd| cmd.batch_ops).collect();
-        all_results.sort_by_key(|(_, i)| *i);
+        let mut all_results: Vec<_> = ops
+            .into_iter()
+            .flat_map(|cmd| cmd.batch_ops.into_iter().zip(cmd.records))
+            .collect();
+        all_results.sort_by_key(|((_, i), _)| *i);
         Ok(all_results
             .into_iter()
-            .map(|(b, _)| b.batch_record())
+            .map(|((b, _), record)| (b, record))
             .collect())
     }
 
-    async fn execute_batch_operate_jobs(
+    async fn execute_batch_operate_jobs<T: FromBins>(
         &self,
-        jobs: Vec<BatchOperateCommand>,
+        jobs: Vec<BatchOperateCommand<T>>,
         concurrency: Concurrency,
-    ) -> Result<Vec<BatchOperateCommand>> {
+    ) -> Result<Vec<BatchOperateCommand<T>>> {
         let handles = jobs
             .into_iter()
             .map(|job| job.execute(self.cluster.clone()));
@@ -120,11 +140,14 @@ impl BatchExecutor {
     ) -> Result<HashMap<Arc<Node>, Vec<(BatchOperation, usize)>>> {
         #![allow(clippy::type_complexity)]
         let mut map = HashMap::new();
+        let codecs = self.cluster.codecs();
         for (index, batch_op) in batch_ops.iter().enumerate() {
             let node = self.node_for_key(&batch_op.key(), replica)?;
+            let mut batch_op = batch_op.clone();
+            codecs.encode_batch_operation(&mut batch_op)?;
             map.entry(node)
                 .or_insert_with(Vec::new)
-                .push((batch_op.clone(), index));
+                .push((batch_op, index));
         }
         Ok(map)
     }
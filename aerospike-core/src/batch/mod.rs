@@ -36,9 +36,9 @@ pub use self::batch_record::BatchRecord;
 
 use crate::errors::{Error, Result};
 
-pub struct BatchRecordIndex {
+pub struct BatchRecordIndex<T = crate::Record> {
     pub batch_index: usize,
-    pub record: Option<crate::Record>,
+    pub record: Option<T>,
     pub result_code: ResultCode,
 }
 
@@ -435,6 +435,15 @@ impl BatchOperation {
         false
     }
 
+    pub(crate) const fn key_ref(&self) -> &Key {
+        match self {
+            Self::Read { br, .. }
+            | Self::Write { br, .. }
+            | Self::Delete { br, .. }
+            | Self::UDF { br, .. } => &br.key,
+        }
+    }
+
     pub(crate) fn key(&self) -> Key {
         match self {
             Self::Read { br, .. }
@@ -459,10 +468,16 @@ impl BatchOperation {
             Self::Read { br, .. }
             | Self::Write { br, .. }
             | Self::Delete { br, .. }
-            | Self::UDF { br, .. } => {
-                br.record = record;
-                br.result_code = Some(ResultCode::Ok);
-            }
+            | Self::UDF { br, .. } => br.record = record,
+        }
+    }
+
+    pub(crate) const fn result_code(&self) -> Option<ResultCode> {
+        match self {
+            Self::Read { br, .. }
+            | Self::Write { br, .. }
+            | Self::Delete { br, .. }
+            | Self::UDF { br, .. } => br.result_code,
         }
     }
 
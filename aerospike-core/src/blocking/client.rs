@@ -0,0 +1,466 @@
+// Copyright 2015-2020 Aerospike, Inc.
+//
+// Portions may be licensed to Aerospike, Inc. under one or more contributor
+// license agreements.
+//
+// Licensed under the Apache License, Version 2.0 (the "License"); you may not
+// use this file except in compliance with the License. You may obtain a copy of
+// the License at http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS, WITHOUT
+// WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied. See the
+// License for the specific language governing permissions and limitations under
+// the License.
+
+use crate::blocking::cluster::Cluster;
+use crate::blocking::commands::{
+    self, DeleteCommand, ExistsCommand, OperateCommand, ReadCommand, TouchCommand, WriteCommand,
+};
+use crate::errors::{Error, Result};
+use crate::interceptor::{CommandContext, Interception};
+use crate::net::ToHosts;
+use crate::operations::{OperateResults, Operation, OperationType};
+use crate::policy::{ClientPolicy, ReadPolicy, Replica, WritePolicy};
+use crate::{Bin, Bins, FromBins, Key, Record, RecordView, ResultCode, Value};
+
+/// Blocking client for single record commands that does not depend on an async runtime.
+///
+/// All network I/O happens on the calling thread using `std::net::TcpStream` with socket
+/// timeouts, and the client never spawns threads or tasks. Cluster tending, which the async
+/// client runs in a background task, is done on the calling thread instead: when the client is
+/// created, before a command once `ClientPolicy::tend_interval` has elapsed since the last
+/// tend, and whenever a command cannot find a node for its partition. Use
+/// [`tend`](Client::tend) to refresh the cluster explicitly.
+///
+/// Requests are encoded and responses parsed exactly as in the async client.
+///
+/// The client is thread-safe. Connections are pooled per node, bounded by
+/// `ClientPolicy::max_conns_per_node`.
+#[derive(Debug)]
+pub struct Client {
+    cluster: Cluster,
+}
+
+impl Client {
+    /// Initializes the client with the hosts used to seed the cluster map. Seeding, peer
+    /// discovery and the initial partition map fetch are done on the calling thread before
+    /// this returns.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// use aerospike::blocking::Client;
+    /// use aerospike::ClientPolicy;
+    ///
+    /// let hosts = std::env::var("AEROSPIKE_HOSTS").unwrap_or_else(|_| "127.0.0.1:3000".to_string());
+    /// let client = Client::new(&ClientPolicy::default(), &hosts).unwrap();
+    /// ```
+    pub fn new(policy: &ClientPolicy, hosts: &(dyn ToHosts + Send + Sync)) -> Result<Self> {
+        policy.validate()?;
+        let hosts = hosts.to_hosts()?;
+        let cluster = Cluster::new(policy.clone(), &hosts)?;
+        Ok(Client { cluster })
+    }
+
+    /// Closes all connections to the cluster.
+    pub fn close(&self) {
+        self.cluster.close();
+    }
+
+    /// Returns `true` if the client is connected to any cluster nodes.
+    pub fn is_connected(&self) -> bool {
+        self.cluster.is_connected()
+    }
+
+    /// Returns the names of all active nodes in the cluster.
+    pub fn node_names(&self) -> Vec<String> {
+        self.cluster
+            .nodes()
+            .iter()
+            .map(|node| node.name().to_owned())
+            .collect()
+    }
+
+    /// Refreshes the cluster map and partition table on the calling thread.
+    pub fn tend(&self) {
+        self.cluster.tend();
+    }
+
+    /// Read record for the specified key. Depending on the bins value provided, all record bins,
+    /// only selected record bins or only the record headers will be returned. The policy can be
+    /// used to specify timeouts.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// # use aerospike::*;
+    /// # use aerospike::blocking::Client;
+    /// # let hosts = std::env::var("AEROSPIKE_HOSTS").unwrap_or_else(|_| "127.0.0.1:3000".to_string());
+    /// # let client = Client::new(&ClientPolicy::default(), &hosts).unwrap();
+    /// let key = as_key!("test", "test", "mykey");
+    /// match client.get(&ReadPolicy::default(), &key, ["a", "b"]) {
+    ///     Ok(record)
+    ///         => println!("a={:?}", record.bins.get("a")),
+    ///     Err(Error::ServerError(ResultCode::KeyNotFoundError, _, _))
+    ///         => println!("No such record: {}", key),
+    ///     Err(err)
+    ///         => println!("Error fetching record: {}", err),
+    /// }
+    /// ```
+    pub fn get<T>(&self, policy: &ReadPolicy, key: &Key, bins: T) -> Result<Record>
+    where
+        T: Into<Bins>,
+    {
+        let mut interception = self.interception(CommandContext::for_key("get", key));
+        let policy = interception.before(policy)?;
+        let mut command = ReadCommand::new(&policy.base_policy, key, bins.into());
+        let res = commands::execute(
+            &self.cluster,
+            &*policy,
+            key,
+            policy.replica,
+            &mut command,
+            &interception,
+        );
+        let res = res.and_then(|()| self.decode_record(key, command.record.as_mut(), None));
+        interception.after(res.as_ref().map(|()| command.record.as_ref()));
+        res?;
+        command
+            .record
+            .ok_or_else(|| Error::BadResponse("Missing record in response".to_string()))
+    }
+
+    /// Read record for the specified key and pass a borrowed view of it to the callback, while
+    /// the response is still in the connection buffer. See
+    /// [`Client::get_with`](crate::Client::get_with).
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// # use aerospike::*;
+    /// # use aerospike::blocking::Client;
+    /// # let hosts = std::env::var("AEROSPIKE_HOSTS").unwrap_or_else(|_| "127.0.0.1:3000".to_string());
+    /// # let client = Client::new(&ClientPolicy::default(), &hosts).unwrap();
+    /// let key = as_key!("test", "test", "image");
+    /// let size = client.get_with(&ReadPolicy::default(), &key, ["data"], |record| {
+    ///     record.bin("data").and_then(|bin| bin.as_blob()).map_or(0, <[u8]>::len)
+    /// });
+    /// ```
+    pub fn get_with<T, F, R>(
+        &self,
+        policy: &ReadPolicy,
+        key: &Key,
+        bins: T,
+        view_fn: F,
+    ) -> Result<R>
+    where
+        T: Into<Bins>,
+        F: FnOnce(RecordView<'_>) -> R,
+    {
+        let mut interception = self.interception(CommandContext::for_key("get", key));
+        let policy = interception.before(policy)?;
+        let mut view_fn = Some(view_fn);
+        let mut result = None;
+        let mut call = |record: RecordView<'_>| {
+            if let Some(view_fn) = view_fn.take() {
+                result = Some(view_fn(record));
+            }
+        };
+        let mut command = ReadCommand::new(&policy.base_policy, key, bins.into());
+        command.view_fn = Some(&mut call);
+        let res = commands::execute(
+            &self.cluster,
+            &*policy,
+            key,
+            policy.replica,
+            &mut command,
+            &interception,
+        );
+        interception.complete(res)?;
+        drop(command);
+        result.ok_or_else(|| Error::BadResponse("Missing record in response".to_string()))
+    }
+
+    /// Read record for the specified key and decode it into a [`FromBins`] type. See
+    /// [`Client::get_as`](crate::Client::get_as).
+    pub fn get_as<T: FromBins>(&self, policy: &ReadPolicy, key: &Key, bins: Bins) -> Result<T> {
+        let codecs = self.cluster.codecs();
+        self.get_with(policy, key, bins, |record| {
+            record.decode_with::<T>(&codecs, key)
+        })?
+    }
+
+    /// Write record bin(s). The policy specifies the command timeouts, record expiration and
+    /// how the command is handled when the record already exists.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// # use aerospike::*;
+    /// # use aerospike::blocking::Client;
+    /// # let hosts = std::env::var("AEROSPIKE_HOSTS").unwrap_or_else(|_| "127.0.0.1:3000".to_string());
+    /// # let client = Client::new(&ClientPolicy::default(), &hosts).unwrap();
+    /// let key = as_key!("test", "test", "mykey");
+    /// let bin = as_bin!("i", 42);
+    /// match client.put(&WritePolicy::default(), &key, &[bin]) {
+    ///     Ok(()) => println!("Record written"),
+    ///     Err(err) => println!("Error writing record: {}", err),
+    /// }
+    /// ```
+    pub fn put(&self, policy: &WritePolicy, key: &Key, bins: &[Bin]) -> Result<()> {
+        self.write("put", policy, key, bins, OperationType::Write)
+    }
+
+    /// Write a JSON document to the record with the given key, as
+    /// [`aerospike::Client::put_json`](crate::Client::put_json) does. Requires the `json`
+    /// feature.
+    #[cfg(feature = "json")]
+    pub fn put_json(
+        &self,
+        policy: &WritePolicy,
+        key: &Key,
+        layout: &crate::json::JsonLayout,
+        doc: &serde_json::Value,
+    ) -> Result<()> {
+        let bins = crate::json::document_bins(layout, doc)?;
+        self.put(policy, key, &bins)
+    }
+
+    /// Read a JSON document written by [`put_json`](Self::put_json) with the same layout.
+    /// Requires the `json` feature.
+    #[cfg(feature = "json")]
+    pub fn get_json(
+        &self,
+        policy: &ReadPolicy,
+        key: &Key,
+        layout: &crate::json::JsonLayout,
+    ) -> Result<serde_json::Value> {
+        let record = self.get(policy, key, crate::json::layout_bins(layout))?;
+        crate::json::record_document(layout, &record)
+    }
+
+    /// Add integer bin values to existing record bin values. The policy specifies the command
+    /// timeout, record expiration and how the command is handled when the record already
+    /// exists. This call only works for integer values.
+    pub fn add(&self, policy: &WritePolicy, key: &Key, bins: &[Bin]) -> Result<()> {
+        self.write("add", policy, key, bins, OperationType::Incr)
+    }
+
+    /// Append bin string values to existing record bin values. The policy specifies the
+    /// command timeout, record expiration and how the command is handled when the record
+    /// already exists. This call only works for string values.
+    pub fn append(&self, policy: &WritePolicy, key: &Key, bins: &[Bin]) -> Result<()> {
+        self.write("append", policy, key, bins, OperationType::Append)
+    }
+
+    /// Prepend bin string values to existing record bin values. The policy specifies the
+    /// command timeout, record expiration and how the command is handled when the record
+    /// already exists. This call only works for string values.
+    pub fn prepend(&self, policy: &WritePolicy, key: &Key, bins: &[Bin]) -> Result<()> {
+        self.write("prepend", policy, key, bins, OperationType::Prepend)
+    }
+
+    fn write(
+        &self,
+        name: &'static str,
+        policy: &WritePolicy,
+        key: &Key,
+        bins: &[Bin],
+        operation: OperationType,
+    ) -> Result<()> {
+        let mut interception =
+            self.interception(CommandContext::for_key(name, key).with_bins(bins));
+        let policy = interception.before(policy)?;
+        let bins = self.cluster.codecs().encode_bins(key, bins, operation)?;
+        let mut command = WriteCommand::new(&policy, key, &bins, operation);
+        interception.complete(commands::execute(
+            &self.cluster,
+            &*policy,
+            key,
+            Replica::Sequence,
+            &mut command,
+            &interception,
+        ))
+    }
+
+    fn interception<'a>(&self, context: CommandContext<'a>) -> Interception<'a> {
+        Interception::new(self.cluster.interceptors(), context)
+    }
+
+    // Reverses the bin codecs of the client policy on a record that has been read.
+    fn decode_record(
+        &self,
+        key: &Key,
+        record: Option<&mut Record>,
+        op_results: Option<&mut Vec<(String, Value)>>,
+    ) -> Result<()> {
+        record.map_or(Ok(()), |record| {
+            self.cluster.codecs().decode_record(key, record, op_results)
+        })
+    }
+
+    /// Delete record for specified key. The policy specifies the command timeout. The call
+    /// returns `true` if the record existed on the server before deletion.
+    pub fn delete(&self, policy: &WritePolicy, key: &Key) -> Result<bool> {
+        let mut interception = self.interception(CommandContext::for_key("delete", key));
+        let policy = interception.before(policy)?;
+        let mut command = DeleteCommand::new(&policy, key);
+        interception.complete(commands::execute(
+            &self.cluster,
+            &*policy,
+            key,
+            Replica::Sequence,
+            &mut command,
+            &interception,
+        ))?;
+        Ok(command.existed)
+    }
+
+    /// Reset record's time to expiration using the policy's expiration. Fail if the record
+    /// does not exist.
+    pub fn touch(&self, policy: &WritePolicy, key: &Key) -> Result<()> {
+        let mut interception = self.interception(CommandContext::for_key("touch", key));
+        let policy = interception.before(policy)?;
+        let mut command = TouchCommand::new(&policy, key);
+        interception.complete(commands::execute(
+            &self.cluster,
+            &*policy,
+            key,
+            Replica::Sequence,
+            &mut command,
+            &interception,
+        ))
+    }
+
+    /// Determine if a record key exists. The policy can be used to specify timeouts.
+    pub fn exists(&self, policy: &ReadPolicy, key: &Key) -> Result<bool> {
+        let mut interception = self.interception(CommandContext::for_key("exists", key));
+        let policy = interception.before(policy)?;
+        let mut command = ExistsCommand::new(&policy, key);
+        interception.complete(commands::execute(
+            &self.cluster,
+            &*policy,
+            key,
+            Replica::Master,
+            &mut command,
+            &interception,
+        ))?;
+        Ok(command.exists)
+    }
+
+    /// Perform multiple read/write operations on a single key in one batch call.
+    ///
+    /// Operations on bins are executed in the order given. Read operations return the bin
+    /// values in the returned record.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// # use aerospike::*;
+    /// # use aerospike::blocking::Client;
+    /// # let hosts = std::env::var("AEROSPIKE_HOSTS").unwrap_or_else(|_| "127.0.0.1:3000".to_string());
+    /// # let client = Client::new(&ClientPolicy::default(), &hosts).unwrap();
+    /// let key = as_key!("test", "test", "mykey");
+    /// let bin = as_bin!("a", 42);
+    /// let ops = vec![
+    ///     operations::add(&bin),
+    ///     operations::get_bin("a"),
+    /// ];
+    /// match client.operate(&WritePolicy::default(), &key, &ops) {
+    ///     Ok(record) => println!("The new value is {}", record.bins.get("a").unwrap()),
+    ///     Err(err) => println!("Error writing record: {}", err),
+    /// }
+    /// ```
+    pub fn operate(&self, policy: &WritePolicy, key: &Key, ops: &[Operation]) -> Result<Record> {
+        if ops.is_empty() {
+            return Err(Error::ServerError(
+                ResultCode::ParameterError,
+                false,
+                "no operations defined".into(),
+            ));
+        }
+        let mut interception =
+            self.interception(CommandContext::for_key("operate", key).with_operations(ops));
+        let policy = interception.before(policy)?;
+        let ops = self.cluster.codecs().encode_operations(key, ops)?;
+        let mut command = OperateCommand::new(&policy, key, &ops);
+        let res = commands::execute(
+            &self.cluster,
+            &*policy,
+            key,
+            Replica::Master,
+            &mut command,
+            &interception,
+        );
+        let res = res.and_then(|()| self.decode_record(key, command.record.as_mut(), None));
+        interception.after(res.as_ref().map(|()| command.record.as_ref()));
+        res?;
+        command
+            .record
+            .ok_or_else(|| Error::BadResponse("Missing record in response".to_string()))
+    }
+
+    /// Perform multiple read/write operations on a single key in one batch call, like
+    /// [`operate`](Self::operate), and return the result of each operation separately.
+    ///
+    /// The command is sent with [`respond_per_each_op`](WritePolicy::respond_per_each_op) set, so
+    /// that every result can be assigned to the operation that produced it.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// # use aerospike::*;
+    /// # use aerospike::blocking::Client;
+    /// # let hosts = std::env::var("AEROSPIKE_HOSTS").unwrap_or_else(|_| "127.0.0.1:3000".to_string());
+    /// # let client = Client::new(&ClientPolicy::default(), &hosts).unwrap();
+    /// let key = as_key!("test", "test", "mykey");
+    /// let ops = vec![
+    ///     operations::add(&as_bin!("a", 42)),
+    ///     operations::get_bin("a"),
+    /// ];
+    /// let results = client.operate_results(&WritePolicy::default(), &key, &ops).unwrap();
+    /// println!("The new value is {}", results.as_i64(1).unwrap());
+    /// ```
+    pub fn operate_results(
+        &self,
+        policy: &WritePolicy,
+        key: &Key,
+        ops: &[Operation],
+    ) -> Result<OperateResults> {
+        if ops.is_empty() {
+            return Err(Error::ServerError(
+                ResultCode::ParameterError,
+                false,
+                "no operations defined".into(),
+            ));
+        }
+        let mut interception =
+            self.interception(CommandContext::for_key("operate", key).with_operations(ops));
+        let mut policy = interception.before(policy)?;
+        if !policy.respond_per_each_op {
+            policy.to_mut().respond_per_each_op = true;
+        }
+        let ops = self.cluster.codecs().encode_operations(key, ops)?;
+        let mut command = OperateCommand::new(&policy, key, &ops);
+        command.op_results = Some(Vec::with_capacity(ops.len()));
+        let res = commands::execute(
+            &self.cluster,
+            &*policy,
+            key,
+            Replica::Master,
+            &mut command,
+            &interception,
+        );
+        let res = res.and_then(|()| {
+            self.decode_record(key, command.record.as_mut(), command.op_results.as_mut())
+        });
+        interception.after(res.as_ref().map(|()| command.record.as_ref()));
+        res?;
+        let record = command
+            .record
+            .ok_or_else(|| Error::BadResponse("Missing record in response".to_string()))?;
+        let op_results = command.op_results.unwrap_or_default();
+        Ok(OperateResults::new(&ops, record, op_results))
+    }
+}
@@ -0,0 +1,599 @@
+// Copyright 2015-2020 Aerospike, Inc.
+//
+// Portions may be licensed to Aerospike, Inc. under one or more contributor
+// license agreements.
+//
+// Licensed under the Apache License, Version 2.0 (the "License"); you may not
+// use this file except in compliance with the License. You may obtain a copy of
+// the License at http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS, WITHOUT
+// WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied. See the
+// License for the specific language governing permissions and limitations under
+// the License.
+
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::atomic::{AtomicBool, AtomicIsize, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex, PoisonError, RwLock};
+use std::time::{Duration, Instant};
+
+use crate::blocking::Connection;
+use crate::cluster::node::PARTITION_GENERATION;
+use crate::cluster::partition::Partition;
+use crate::cluster::partition_tokenizer::PartitionTokenizer;
+use crate::cluster::peers_parser::PeersParser;
+use crate::cluster::PartitionForNamespace;
+use crate::codec::BinCodecs;
+use crate::commands::Message;
+use crate::errors::{Error, Result};
+use crate::interceptor::CommandInterceptor;
+use crate::net::{dns, Host};
+use crate::policy::{AdminPolicy, ClientPolicy, Replica};
+
+type PartitionTable = HashMap<String, PartitionForNamespace<Arc<Node>>>;
+
+// Number of consecutive failed refreshes after which a node is removed from the cluster.
+const MAX_FAILURES: usize = 5;
+
+// Upper bound on the number of tend cycles run while waiting for the cluster to stabilize.
+const MAX_STABILIZE_TENDS: usize = 5;
+
+/// Server node of a cluster used by the blocking client. Connections are pooled per node and
+/// created on the calling thread when the pool is empty.
+pub struct Node {
+    name: String,
+    host: Host,
+    client_policy: Arc<ClientPolicy>,
+    connections: Mutex<Vec<Connection>>,
+    total_connections: AtomicUsize,
+    partition_generation: AtomicIsize,
+    failures: AtomicUsize,
+    active: AtomicBool,
+}
+
+impl Node {
+    const fn new(name: String, host: Host, client_policy: Arc<ClientPolicy>) -> Self {
+        Node {
+            name,
+            host,
+            client_policy,
+            connections: Mutex::new(Vec::new()),
+            total_connections: AtomicUsize::new(0),
+            partition_generation: AtomicIsize::new(-1),
+            failures: AtomicUsize::new(0),
+            active: AtomicBool::new(true),
+        }
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn is_active(&self) -> bool {
+        self.active.load(Ordering::Relaxed)
+    }
+
+    // Takes an idle connection from the pool, or opens a new one if the pool is empty.
+    pub fn get_connection(&self, hashed_pass: Option<&String>) -> Result<Connection> {
+        loop {
+            let conn = self
+                .connections
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner)
+                .pop();
+            match conn {
+                Some(conn) if conn.is_idle() => self.invalidate_connection(conn),
+                Some(conn) => return Ok(conn),
+                None => break,
+            }
+        }
+
+        if self.total_connections.fetch_add(1, Ordering::AcqRel)
+            >= self.client_policy.max_conns_per_node
+        {
+            self.total_connections.fetch_sub(1, Ordering::AcqRel);
+            return Err(Error::NoMoreConnections);
+        }
+
+        Connection::new(&self.host, &self.client_policy, hashed_pass).inspect_err(|_| {
+            self.total_connections.fetch_sub(1, Ordering::AcqRel);
+        })
+    }
+
+    // Returns a healthy connection to the pool.
+    pub fn put_connection(&self, conn: Connection) {
+        if self.is_active() {
+            self.connections
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner)
+                .push(conn);
+        } else {
+            self.invalidate_connection(conn);
+        }
+    }
+
+    // Closes a connection that may hold unread data.
+    pub fn invalidate_connection(&self, conn: Connection) {
+        self.total_connections.fetch_sub(1, Ordering::AcqRel);
+        drop(conn);
+    }
+
+    fn info(
+        &self,
+        hashed_pass: Option<&String>,
+        commands: &[&str],
+    ) -> Result<HashMap<String, String>> {
+        let admin_policy = AdminPolicy {
+            timeout: self.client_policy.timeout,
+        };
+        let mut conn = self.get_connection(hashed_pass)?;
+        match Message::info_blocking(admin_policy, &mut conn, commands) {
+            Ok(info_map) => {
+                self.put_connection(conn);
+                Ok(info_map)
+            }
+            Err(err) => {
+                self.invalidate_connection(conn);
+                Err(err)
+            }
+        }
+    }
+
+    fn close(&self) {
+        self.active.store(false, Ordering::Relaxed);
+        let conns: Vec<Connection> = self
+            .connections
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .drain(..)
+            .collect();
+        for conn in conns {
+            self.invalidate_connection(conn);
+        }
+    }
+}
+
+impl fmt::Debug for Node {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Node")
+            .field("name", &self.name)
+            .field("host", &self.host)
+            .finish_non_exhaustive()
+    }
+}
+
+impl fmt::Display for Node {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.name, self.host)
+    }
+}
+
+/// Cluster state for the blocking client.
+///
+/// Unlike the async cluster there is no background tend task. The cluster is tended on the
+/// calling thread when the client is created, before a command whenever `tend_interval` has
+/// elapsed since the last tend, and when a command cannot find a node for its partition.
+#[derive(Debug)]
+pub struct Cluster {
+    client_policy: Arc<ClientPolicy>,
+    hashed_pass: Option<String>,
+
+    // Initial host nodes specified by user.
+    seeds: Vec<Host>,
+
+    // All known addresses of the cluster nodes.
+    aliases: RwLock<HashMap<Host, Arc<Node>>>,
+
+    // Active nodes in cluster.
+    nodes: RwLock<Vec<Arc<Node>>>,
+
+    // Which partition contains the key.
+    partition_map: RwLock<PartitionTable>,
+
+    // Serializes tending and records when it last happened.
+    last_tend: Mutex<Option<Instant>>,
+
+    closed: AtomicBool,
+}
+
+impl Cluster {
+    pub fn new(mut policy: ClientPolicy, hosts: &[Host]) -> Result<Self> {
+        // updated the hashed password
+        let _ = policy.set_auth_mode(policy.auth_mode.clone());
+
+        let cluster = Cluster {
+            hashed_pass: policy.hashed_pass(),
+            client_policy: Arc::new(policy),
+            seeds: hosts.to_vec(),
+            aliases: RwLock::new(HashMap::new()),
+            nodes: RwLock::new(Vec::new()),
+            partition_map: RwLock::new(HashMap::new()),
+            last_tend: Mutex::new(None),
+            closed: AtomicBool::new(false),
+        };
+
+        // Tend until the node count settles, so peers discovered through the seeds are
+        // refreshed and included in the partition map before the first command.
+        let mut count = 0;
+        for _ in 0..MAX_STABILIZE_TENDS {
+            cluster.tend();
+            let new_count = cluster
+                .nodes
+                .read()
+                .unwrap_or_else(PoisonError::into_inner)
+                .len();
+            if new_count == count {
+                break;
+            }
+            count = new_count;
+        }
+
+        if cluster.client_policy.fail_if_not_connected && !cluster.is_connected() {
+            return Err(Error::Connection(
+                "Failed to connect to host(s). The network \
+                 connection(s) to cluster nodes may have timed out, or \
+                 the cluster may be in a state of flux."
+                    .to_string(),
+            ));
+        }
+
+        Ok(cluster)
+    }
+
+    pub const fn hashed_pass(&self) -> Option<&String> {
+        self.hashed_pass.as_ref()
+    }
+
+    pub fn is_connected(&self) -> bool {
+        !self.closed.load(Ordering::Relaxed)
+            && !self
+                .nodes
+                .read()
+                .unwrap_or_else(PoisonError::into_inner)
+                .is_empty()
+    }
+
+    pub fn interceptors(&self) -> Vec<Arc<dyn CommandInterceptor>> {
+        self.client_policy.interceptors.clone()
+    }
+
+    pub fn codecs(&self) -> BinCodecs {
+        self.client_policy.codecs.clone()
+    }
+
+    pub fn nodes(&self) -> Vec<Arc<Node>> {
+        self.nodes
+            .read()
+            .unwrap_or_else(PoisonError::into_inner)
+            .clone()
+    }
+
+    // Tends the cluster if the tend interval has elapsed since the last tend.
+    pub fn maybe_tend(&self) {
+        let interval = Duration::from_millis(u64::from(self.client_policy.tend_interval));
+        let due = self
+            .last_tend
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .is_none_or(|last| last.elapsed() >= interval);
+        if due {
+            self.tend();
+        }
+    }
+
+    /// Refreshes all nodes, adds newly discovered peers, removes failed nodes and updates the
+    /// partition map. Seeds the cluster if no nodes are known.
+    pub fn tend(&self) {
+        if self.closed.load(Ordering::Relaxed) {
+            return;
+        }
+
+        let mut last_tend = self
+            .last_tend
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner);
+
+        if self
+            .nodes
+            .read()
+            .unwrap_or_else(PoisonError::into_inner)
+            .is_empty()
+        {
+            self.seed_nodes();
+        }
+
+        let mut friends: Vec<Host> = Vec::new();
+        let mut refreshed: Vec<Arc<Node>> = Vec::new();
+        for node in self.nodes() {
+            match self.refresh_node(&node) {
+                Ok((hosts, partitions_changed)) => {
+                    node.failures.store(0, Ordering::Relaxed);
+                    for host in hosts {
+                        if !friends.contains(&host) {
+                            friends.push(host);
+                        }
+                    }
+                    if partitions_changed {
+                        refreshed.push(node);
+                    }
+                }
+                Err(err) => {
+                    node.failures.fetch_add(1, Ordering::Relaxed);
+                    log_error_chain!(err, "Node `{}` refresh failed", node);
+                }
+            }
+        }
+
+        self.add_nodes(friends);
+        self.update_partitions(&refreshed);
+        self.remove_failed_nodes();
+
+        *last_tend = Some(Instant::now());
+    }
+
+    fn seed_nodes(&self) {
+        info!("Seeding the cluster. Seeds count: {}", self.seeds.len());
+        let timeout = self.client_policy.tend_timeout();
+        self.add_nodes(dns::resolve_seeds(&self.seeds, timeout));
+    }
+
+    // Validates the given hosts and adds nodes for the ones not yet part of the cluster.
+    fn add_nodes(&self, hosts: Vec<Host>) {
+        for host in hosts {
+            if self
+                .aliases
+                .read()
+                .unwrap_or_else(PoisonError::into_inner)
+                .contains_key(&host)
+            {
+                continue;
+            }
+
+            let node_name = match self.validate_host(&host) {
+                Ok(name) => name,
+                Err(err) => {
+                    log_error_chain!(err, "Adding node {} failed with error", host);
+                    continue;
+                }
+            };
+
+            let existing = self
+                .nodes
+                .read()
+                .unwrap_or_else(PoisonError::into_inner)
+                .iter()
+                .find(|node| node.name == node_name)
+                .cloned();
+            let node = existing.unwrap_or_else(|| {
+                let node = Arc::new(Node::new(
+                    node_name,
+                    host.clone(),
+                    self.client_policy.clone(),
+                ));
+                debug!("Adding node {node}");
+                self.nodes
+                    .write()
+                    .unwrap_or_else(PoisonError::into_inner)
+                    .push(node.clone());
+                node
+            });
+            self.aliases
+                .write()
+                .unwrap_or_else(PoisonError::into_inner)
+                .insert(host, node);
+        }
+    }
+
+    // Connects to the host and returns the name of the node behind it.
+    fn validate_host(&self, host: &Host) -> Result<String> {
+        let admin_policy = AdminPolicy {
+            timeout: self.client_policy.timeout,
+        };
+        let mut conn = Connection::new(host, &self.client_policy, self.hashed_pass())?;
+        let info_map = Message::info_blocking(admin_policy, &mut conn, &["node", "cluster-name"])?;
+        self.verify_cluster_name(&info_map)?;
+        info_map
+            .get("node")
+            .cloned()
+            .ok_or_else(|| Error::InvalidNode("Missing node name".to_string()))
+    }
+
+    // Refreshes the node and returns its peers and whether its partition generation changed.
+    fn refresh_node(&self, node: &Node) -> Result<(Vec<Host>, bool)> {
+        let peers_string = self.client_policy.peers_string();
+        let info_map = node
+            .info(
+                self.hashed_pass(),
+                &["node", "cluster-name", PARTITION_GENERATION, peers_string],
+            )
+            .map_err(|e| e.chain_error("Info command failed"))?;
+
+        match info_map.get("node") {
+            Some(name) if *name == node.name => (),
+            Some(name) => {
+                node.active.store(false, Ordering::Relaxed);
+                return Err(Error::InvalidNode(format!(
+                    "Node name has changed: '{}' => '{}'",
+                    node.name, name
+                )));
+            }
+            None => return Err(Error::InvalidNode("Missing node name".to_string())),
+        }
+        self.verify_cluster_name(&info_map)?;
+
+        let mut friends = Vec::new();
+        if let Some(peers) = info_map.get(peers_string).filter(|p| !p.is_empty()) {
+            let (_, hosts) = PeersParser::new(peers).parse()?;
+            for mut host in hosts {
+                if let Some(mapped) = self
+                    .client_policy
+                    .ip_map
+                    .as_ref()
+                    .and_then(|m| m.get(&host.name))
+                {
+                    host.name.clone_from(mapped);
+                }
+                friends.push(host);
+            }
+        }
+
+        let generation = info_map
+            .get(PARTITION_GENERATION)
+            .ok_or_else(|| Error::BadResponse("Missing partition generation".to_string()))?
+            .parse::<isize>()?;
+        let changed = node
+            .partition_generation
+            .swap(generation, Ordering::Relaxed)
+            != generation;
+
+        Ok((friends, changed))
+    }
+
+    #[allow(clippy::option_if_let_else)]
+    fn verify_cluster_name(&self, info_map: &HashMap<String, String>) -> Result<()> {
+        match self.client_policy.cluster_name {
+            None => Ok(()),
+            Some(ref expected) => match info_map.get("cluster-name") {
+                None => Err(Error::InvalidNode("Missing cluster name".to_string())),
+                Some(info_name) if info_name == expected => Ok(()),
+                Some(info_name) => Err(Error::InvalidNode(format!(
+                    "Cluster name mismatch: expected={expected}, got={info_name}"
+                ))),
+            },
+        }
+    }
+
+    // Fetches the partition ownership of the given nodes and merges it into a copy of the
+    // partition map, which then replaces the current one.
+    fn update_partitions(&self, nodes: &[Arc<Node>]) {
+        if nodes.is_empty() {
+            return;
+        }
+
+        let mut partition_map = self
+            .partition_map
+            .read()
+            .unwrap_or_else(PoisonError::into_inner)
+            .clone();
+        for node in nodes {
+            let res = node
+                .info(self.hashed_pass(), &["replicas"])
+                .and_then(|info_map| {
+                    let replicas = info_map
+                        .get("replicas")
+                        .ok_or_else(|| Error::BadResponse("Missing replicas info".to_string()))?;
+                    PartitionTokenizer::from_replicas(replicas)
+                        .update_partition(&mut partition_map, node)
+                });
+
+            if let Err(err) = res {
+                // Force a new partition map fetch on the next tend.
+                node.partition_generation.store(-1, Ordering::Relaxed);
+                log_error_chain!(err, "Error updating partition map for node `{}`", node);
+            }
+        }
+        *self
+            .partition_map
+            .write()
+            .unwrap_or_else(PoisonError::into_inner) = partition_map;
+    }
+
+    fn remove_failed_nodes(&self) {
+        let (remove, keep): (Vec<Arc<Node>>, Vec<Arc<Node>>) =
+            self.nodes().into_iter().partition(|node| {
+                !node.is_active() || node.failures.load(Ordering::Relaxed) >= MAX_FAILURES
+            });
+        if remove.is_empty() {
+            return;
+        }
+
+        for node in &remove {
+            debug!("Removing node {node}");
+            node.close();
+        }
+        self.aliases
+            .write()
+            .unwrap_or_else(PoisonError::into_inner)
+            .retain(|_, node| !remove.iter().any(|n| Arc::ptr_eq(n, node)));
+        *self.nodes.write().unwrap_or_else(PoisonError::into_inner) = keep;
+    }
+
+    pub fn get_node(
+        &self,
+        partition: &Partition<'_>,
+        replica: Replica,
+        last_tried: Option<&Arc<Node>>,
+    ) -> Result<Arc<Node>> {
+        let node = self
+            .partition_map
+            .read()
+            .unwrap_or_else(PoisonError::into_inner)
+            .get(partition.namespace)
+            .and_then(|ns| {
+                let mut replicas = ns
+                    .all_replicas(partition.partition_id)
+                    .flatten()
+                    .filter(|node| node.is_active());
+                match (replica, last_tried) {
+                    (Replica::Master, _) => ns
+                        .all_replicas(partition.partition_id)
+                        .next()
+                        .flatten()
+                        .filter(|node| node.is_active()),
+                    // The blocking client does not track node racks; prefer-rack reads fall back
+                    // to trying the replicas in sequence.
+                    (Replica::Sequence | Replica::PreferRack, Some(last_tried)) => {
+                        let mut after = replicas
+                            .by_ref()
+                            .skip_while(|node| !Arc::ptr_eq(node, last_tried));
+                        after.next();
+                        after.next().or_else(|| {
+                            ns.all_replicas(partition.partition_id)
+                                .flatten()
+                                .find(|node| node.is_active())
+                        })
+                    }
+                    (Replica::Sequence | Replica::PreferRack, None) => replicas.next(),
+                }
+            });
+
+        node.ok_or_else(|| {
+            Error::InvalidNode(format!(
+                "Cannot get appropriate node for namespace: {} partition: {}",
+                partition.namespace, partition.partition_id
+            ))
+        })
+    }
+
+    pub fn close(&self) {
+        if self.closed.swap(true, Ordering::SeqCst) {
+            return;
+        }
+
+        let nodes: Vec<Arc<Node>> = self
+            .nodes
+            .write()
+            .unwrap_or_else(PoisonError::into_inner)
+            .drain(..)
+            .collect();
+        for node in nodes {
+            node.close();
+        }
+        self.aliases
+            .write()
+            .unwrap_or_else(PoisonError::into_inner)
+            .clear();
+        self.partition_map
+            .write()
+            .unwrap_or_else(PoisonError::into_inner)
+            .clear();
+    }
+}
+
+impl Drop for Cluster {
+    fn drop(&mut self) {
+        self.close();
+    }
+}
@@ -0,0 +1,422 @@
+// Copyright 2015-2020 Aerospike, Inc.
+//
+// Portions may be licensed to Aerospike, Inc. under one or more contributor
+// license agreements.
+//
+// Licensed under the Apache License, Version 2.0 (the "License"); you may not
+// use this file except in compliance with the License. You may obtain a copy of
+// the License at http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS, WITHOUT
+// WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied. See the
+// License for the specific language governing permissions and limitations under
+// the License.
+
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::blocking::cluster::{Cluster, Node};
+use crate::blocking::Connection;
+use crate::cluster::partition::Partition;
+use crate::commands;
+use crate::commands::buffer::Buffer;
+use crate::errors::{Error, Result};
+use crate::interceptor::Interception;
+use crate::operations::{Operation, OperationType};
+use crate::policy::{BasePolicy, Policy, ReadPolicy, Replica, WritePolicy};
+use crate::{Bin, Bins, Key, Record, RecordView, ResultCode, Value};
+
+// Blocking counterpart of the async `Command` interface. Commands only encode their request
+// into the connection buffer and parse the response; node selection, retries and timeouts
+// are handled by `execute`.
+pub trait Command {
+    fn prepare_buffer(&mut self, buffer: &mut Buffer) -> Result<()>;
+    fn parse_result(&mut self, conn: &mut Connection) -> Result<()>;
+}
+
+// Executes a single record command on the node owning the key's partition, retrying on
+// network errors until `max_retries` or the total timeout is exceeded.
+pub fn execute(
+    cluster: &Cluster,
+    policy: &dyn Policy,
+    key: &Key,
+    replica: Replica,
+    cmd: &mut dyn Command,
+    interception: &Interception<'_>,
+) -> Result<()> {
+    cluster.maybe_tend();
+
+    let partition = Partition::new_by_key(key);
+    let deadline = match policy.total_timeout() {
+        0 => None,
+        timeout => Some(Instant::now() + Duration::from_millis(u64::from(timeout))),
+    };
+    let effective_attempt = policy.max_retries() + 1;
+    let mut last_tried: Option<Arc<Node>> = None;
+    let mut iterations = 0;
+    let mut last_err = None;
+
+    // Execute command until successful, timed out or maximum iterations have been reached.
+    loop {
+        iterations += 1;
+
+        // check for max retries
+        if iterations > effective_attempt {
+            // first attempt isn't a retry
+            return Err(Error::Timeout(format!("Timeout after {iterations} tries")));
+        }
+
+        // Sleep before trying again, after the first iteration
+        if iterations > 1 {
+            if let Some(err) = last_err.take() {
+                interception.retry(iterations, &err);
+            }
+
+            if let Some(sleep_between_retries) = policy.sleep_between_retries() {
+                thread::sleep(sleep_between_retries);
+            }
+        }
+
+        // check for command timeout
+        if deadline.is_some_and(|deadline| Instant::now() > deadline) {
+            break;
+        }
+
+        let node = match cluster.get_node(&partition, replica, last_tried.as_ref()) {
+            Ok(node) => node,
+            Err(err) => {
+                // The partition map may be stale. Refresh it before the next attempt.
+                warn!("Error selecting node from the partition table: {err}");
+                cluster.tend();
+                last_err = Some(err);
+                continue;
+            }
+        };
+        last_tried = Some(node.clone());
+
+        let mut conn = match node.get_connection(cluster.hashed_pass()) {
+            Ok(conn) => conn,
+            Err(err) => {
+                warn!("Node {node}: {err}");
+                last_err = Some(err);
+                continue;
+            }
+        };
+
+        conn.set_timeout(deadline, policy.socket_timeout());
+
+        cmd.prepare_buffer(&mut conn.buffer)
+            .map_err(|e| e.chain_error("Failed to prepare send buffer"))?;
+        conn.buffer.write_timeout(policy.server_timeout());
+
+        // Send command.
+        if let Err(err) = conn.flush() {
+            // IO errors are considered temporary anomalies. Retry.
+            // Close socket to flush out possible garbage. Do not put back in pool.
+            node.invalidate_connection(conn);
+            warn!("Node {node}: {err}");
+            last_err = Some(err);
+            continue;
+        }
+
+        // Parse results.
+        if let Err(err) = cmd.parse_result(&mut conn) {
+            // Server errors leave the connection in a clean state. Any other error, including
+            // a timeout, may leave unread data on the socket; throw the connection away.
+            if matches!(err, Error::ServerError(..)) {
+                node.put_connection(conn);
+            } else {
+                node.invalidate_connection(conn);
+            }
+
+            if commands::is_network_error(&err) {
+                last_err = Some(err);
+                continue;
+            }
+            return Err(err);
+        }
+
+        node.put_connection(conn);
+
+        // command has completed successfully. Exit method.
+        return Ok(());
+    }
+
+    Err(Error::Timeout(format!(
+        "Timeout after {iterations} attempts"
+    )))
+}
+
+// Reads the response header and returns the result code, leaving the buffer offset past
+// the message size so the body can be read or discarded.
+fn read_result_code(conn: &mut Connection) -> Result<ResultCode> {
+    conn.read_header()?;
+    conn.buffer.reset_offset();
+    Ok(ResultCode::from(conn.buffer.read_u8(Some(13))))
+}
+
+pub struct ReadCommand<'a> {
+    policy: &'a BasePolicy,
+    key: &'a Key,
+    bins: Bins,
+    pub record: Option<Record>,
+    // Called with a view of the record instead of parsing it into `record`, if set.
+    pub view_fn: Option<&'a mut dyn FnMut(RecordView<'_>)>,
+}
+
+impl<'a> ReadCommand<'a> {
+    pub const fn new(policy: &'a BasePolicy, key: &'a Key, bins: Bins) -> Self {
+        ReadCommand {
+            policy,
+            key,
+            bins,
+            record: None,
+            view_fn: None,
+        }
+    }
+}
+
+impl Command for ReadCommand<'_> {
+    fn prepare_buffer(&mut self, buffer: &mut Buffer) -> Result<()> {
+        buffer.set_read(self.policy, self.key, &self.bins)
+    }
+
+    fn parse_result(&mut self, conn: &mut Connection) -> Result<()> {
+        let view_fn = self
+            .view_fn
+            .as_mut()
+            .map(|view_fn| &mut **view_fn as &mut dyn FnMut(RecordView<'_>));
+        parse_record_result(conn, self.bins.is_none(), &mut self.record, None, view_fn)
+    }
+}
+
+pub struct OperateCommand<'a> {
+    policy: &'a WritePolicy,
+    key: &'a Key,
+    operations: &'a [Operation],
+    pub record: Option<Record>,
+    // The result of every operation in order, if requested.
+    pub op_results: Option<Vec<(String, Value)>>,
+}
+
+impl<'a> OperateCommand<'a> {
+    pub const fn new(policy: &'a WritePolicy, key: &'a Key, operations: &'a [Operation]) -> Self {
+        OperateCommand {
+            policy,
+            key,
+            operations,
+            record: None,
+            op_results: None,
+        }
+    }
+}
+
+impl Command for OperateCommand<'_> {
+    fn prepare_buffer(&mut self, buffer: &mut Buffer) -> Result<()> {
+        buffer.set_operate(self.policy, self.key, self.operations)
+    }
+
+    fn parse_result(&mut self, conn: &mut Connection) -> Result<()> {
+        let op_results = self.op_results.as_mut();
+        parse_record_result(conn, false, &mut self.record, op_results, None)
+    }
+}
+
+// Parses a single record response, as done by the async `ReadCommand`.
+fn parse_record_result(
+    conn: &mut Connection,
+    header_only: bool,
+    record: &mut Option<Record>,
+    op_results: Option<&mut Vec<(String, Value)>>,
+    view_fn: Option<&mut dyn FnMut(RecordView<'_>)>,
+) -> Result<()> {
+    conn.read_header()?;
+
+    conn.buffer.reset_offset();
+    let sz = conn.buffer.read_u64(Some(0));
+    let header_length = conn.buffer.read_u8(Some(8));
+    let result_code = conn.buffer.read_u8(Some(13));
+    let generation = conn.buffer.read_u32(Some(14));
+    let expiration = conn.buffer.read_u32(Some(18));
+    let field_count = conn.buffer.read_u16(Some(26)) as usize; // almost certainly 0
+    let op_count = conn.buffer.read_u16(Some(28)) as usize;
+    let receive_size = ((sz & 0xFFFF_FFFF_FFFF) - u64::from(header_length)) as usize;
+
+    // Read remaining message bytes
+    if receive_size > 0 {
+        conn.read_buffer(receive_size)?;
+    }
+
+    match ResultCode::from(result_code) {
+        ResultCode::Ok => {
+            if let Some(view_fn) = view_fn {
+                let data = &conn.buffer.data_buffer[conn.buffer.data_offset()..];
+                view_fn(RecordView::parse(
+                    data,
+                    field_count,
+                    op_count,
+                    generation,
+                    expiration,
+                )?);
+                return Ok(());
+            }
+
+            *record = Some(if header_only {
+                Record::new(None, HashMap::new(), generation, expiration)
+            } else {
+                commands::ReadCommand::parse_record_with_op_results(
+                    &mut conn.buffer,
+                    op_count,
+                    field_count,
+                    generation,
+                    expiration,
+                    op_results,
+                )?
+            });
+            Ok(())
+        }
+        ResultCode::UdfBadResponse => {
+            // record bin "FAILURE" contains details about the UDF error
+            let record = commands::ReadCommand::parse_record(
+                &mut conn.buffer,
+                op_count,
+                field_count,
+                generation,
+                expiration,
+            )?;
+            let reason = record
+                .bins
+                .get("FAILURE")
+                .map_or_else(|| String::from("UDF Error"), ToString::to_string);
+            Err(Error::UdfBadResponse(reason))
+        }
+        rc => Err(Error::ServerError(rc, false, conn.addr.clone())),
+    }
+}
+
+pub struct WriteCommand<'a> {
+    policy: &'a WritePolicy,
+    key: &'a Key,
+    bins: &'a [Bin],
+    operation: OperationType,
+}
+
+impl<'a> WriteCommand<'a> {
+    pub const fn new(
+        policy: &'a WritePolicy,
+        key: &'a Key,
+        bins: &'a [Bin],
+        operation: OperationType,
+    ) -> Self {
+        WriteCommand {
+            policy,
+            key,
+            bins,
+            operation,
+        }
+    }
+}
+
+impl Command for WriteCommand<'_> {
+    fn prepare_buffer(&mut self, buffer: &mut Buffer) -> Result<()> {
+        buffer.set_write(self.policy, self.operation, self.key, self.bins)
+    }
+
+    fn parse_result(&mut self, conn: &mut Connection) -> Result<()> {
+        let result_code = read_result_code(conn)?;
+        if result_code != ResultCode::Ok {
+            return Err(Error::ServerError(result_code, false, conn.addr.clone()));
+        }
+        conn.empty_socket()
+    }
+}
+
+pub struct TouchCommand<'a> {
+    policy: &'a WritePolicy,
+    key: &'a Key,
+}
+
+impl<'a> TouchCommand<'a> {
+    pub const fn new(policy: &'a WritePolicy, key: &'a Key) -> Self {
+        TouchCommand { policy, key }
+    }
+}
+
+impl Command for TouchCommand<'_> {
+    fn prepare_buffer(&mut self, buffer: &mut Buffer) -> Result<()> {
+        buffer.set_touch(self.policy, self.key)
+    }
+
+    fn parse_result(&mut self, conn: &mut Connection) -> Result<()> {
+        let result_code = read_result_code(conn)?;
+        if result_code != ResultCode::Ok {
+            return Err(Error::ServerError(result_code, false, conn.addr.clone()));
+        }
+        conn.empty_socket()
+    }
+}
+
+pub struct DeleteCommand<'a> {
+    policy: &'a WritePolicy,
+    key: &'a Key,
+    pub existed: bool,
+}
+
+impl<'a> DeleteCommand<'a> {
+    pub const fn new(policy: &'a WritePolicy, key: &'a Key) -> Self {
+        DeleteCommand {
+            policy,
+            key,
+            existed: false,
+        }
+    }
+}
+
+impl Command for DeleteCommand<'_> {
+    fn prepare_buffer(&mut self, buffer: &mut Buffer) -> Result<()> {
+        buffer.set_delete(self.policy, self.key)
+    }
+
+    fn parse_result(&mut self, conn: &mut Connection) -> Result<()> {
+        let result_code = read_result_code(conn)?;
+        if result_code != ResultCode::Ok && result_code != ResultCode::KeyNotFoundError {
+            return Err(Error::ServerError(result_code, false, conn.addr.clone()));
+        }
+        self.existed = result_code == ResultCode::Ok;
+        conn.empty_socket()
+    }
+}
+
+pub struct ExistsCommand<'a> {
+    policy: &'a ReadPolicy,
+    key: &'a Key,
+    pub exists: bool,
+}
+
+impl<'a> ExistsCommand<'a> {
+    pub const fn new(policy: &'a ReadPolicy, key: &'a Key) -> Self {
+        ExistsCommand {
+            policy,
+            key,
+            exists: false,
+        }
+    }
+}
+
+impl Command for ExistsCommand<'_> {
+    fn prepare_buffer(&mut self, buffer: &mut Buffer) -> Result<()> {
+        buffer.set_exists(self.policy, self.key)
+    }
+
+    fn parse_result(&mut self, conn: &mut Connection) -> Result<()> {
+        let result_code = read_result_code(conn)?;
+        if result_code != ResultCode::Ok && result_code != ResultCode::KeyNotFoundError {
+            return Err(Error::ServerError(result_code, false, conn.addr.clone()));
+        }
+        self.exists = result_code == ResultCode::Ok;
+        conn.empty_socket()
+    }
+}
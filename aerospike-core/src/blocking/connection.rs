@@ -0,0 +1,381 @@
+// Copyright 2015-2020 Aerospike, Inc.
+//
+// Portions may be licensed to Aerospike, Inc. under one or more contributor
+// license agreements.
+//
+// Licensed under the Apache License, Version 2.0 (the "License"); you may not
+// use this file except in compliance with the License. You may obtain a copy of
+// the License at http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS, WITHOUT
+// WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied. See the
+// License for the specific language governing permissions and limitations under
+// the License.
+
+use std::cmp::min;
+use std::io::{self, Read, Write};
+use std::net::{Shutdown, TcpStream, ToSocketAddrs};
+use std::time::{Duration, Instant};
+
+#[cfg(feature = "tls")]
+use std::convert::TryFrom;
+#[cfg(feature = "tls")]
+use std::sync::Arc;
+
+use crate::commands::admin_command::{self, AdminCommand};
+use crate::commands::buffer::{self, Buffer};
+use crate::errors::{Error, Result};
+use crate::net::{self, Host};
+use crate::policy::ClientPolicy;
+
+#[cfg(feature = "tls")]
+use rustls::pki_types::ServerName;
+#[cfg(feature = "tls")]
+use rustls::{ClientConnection, StreamOwned};
+
+// Underlying blocking socket (TCP or TLS).
+#[derive(Debug)]
+enum Stream {
+    Tcp(TcpStream),
+    #[cfg(feature = "tls")]
+    Tls(Box<StreamOwned<ClientConnection, TcpStream>>),
+}
+
+impl Stream {
+    const fn tcp(&self) -> &TcpStream {
+        match self {
+            Stream::Tcp(ref conn) => conn,
+            #[cfg(feature = "tls")]
+            Stream::Tls(ref conn) => &conn.sock,
+        }
+    }
+}
+
+impl Read for Stream {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        match self {
+            Stream::Tcp(ref mut conn) => conn.read(buf),
+            #[cfg(feature = "tls")]
+            Stream::Tls(ref mut conn) => conn.read(buf),
+        }
+    }
+}
+
+impl Write for Stream {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        match self {
+            Stream::Tcp(ref mut conn) => conn.write(buf),
+            #[cfg(feature = "tls")]
+            Stream::Tls(ref mut conn) => conn.write(buf),
+        }
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        match self {
+            Stream::Tcp(ref mut conn) => conn.flush(),
+            #[cfg(feature = "tls")]
+            Stream::Tls(ref mut conn) => conn.flush(),
+        }
+    }
+}
+
+/// Blocking connection to a server node. All reads and writes are bounded by socket timeouts
+/// set directly on the underlying `std::net::TcpStream`.
+#[derive(Debug)]
+pub struct Connection {
+    pub addr: String,
+    conn: Stream,
+    socket_timeout: u32,
+    deadline: Option<Instant>,
+    // duration after which connection is considered idle
+    idle_timeout: Option<Duration>,
+    idle_deadline: Option<Instant>,
+    pub buffer: Buffer,
+}
+
+impl Connection {
+    pub fn new(host: &Host, policy: &ClientPolicy, hashed_pass: Option<&String>) -> Result<Self> {
+        let stream = Self::connect(host, policy.timeout())?;
+        stream.set_nodelay(true)?;
+        let stream = Self::get_stream(stream, host, policy)?;
+
+        let idle_timeout = if policy.idle_timeout > 0 {
+            Some(Duration::from_millis(u64::from(policy.idle_timeout)))
+        } else {
+            None
+        };
+
+        let mut conn = Connection {
+            addr: host.address(),
+            conn: stream,
+            socket_timeout: policy.timeout().as_millis() as u32,
+            deadline: None,
+            idle_timeout,
+            idle_deadline: None,
+            buffer: Buffer::new(policy.buffer_reclaim_threshold),
+        };
+        conn.authenticate(policy, hashed_pass)?;
+        conn.refresh();
+        Ok(conn)
+    }
+
+    fn connect(host: &Host, timeout: Duration) -> Result<TcpStream> {
+        let mut last_err = None;
+        for addr in (host.name.as_str(), host.port).to_socket_addrs()? {
+            match TcpStream::connect_timeout(&addr, timeout) {
+                Ok(stream) => return Ok(stream),
+                Err(err) => last_err = Some(err),
+            }
+        }
+
+        Err(Error::Connection(last_err.map_or_else(
+            || format!("Failed to find addresses for {host}"),
+            |err| format!("Could not open network connection to {host}: {err}"),
+        )))
+    }
+
+    #[cfg(feature = "tls")]
+    fn get_stream(stream: TcpStream, host: &Host, policy: &ClientPolicy) -> Result<Stream> {
+        if let Some(tls_config) = policy.tls_config.clone() {
+            let server_name = host
+                .tls_name
+                .clone()
+                .unwrap_or_else(|| policy.cluster_name.clone().unwrap_or_default());
+            let domain =
+                ServerName::try_from(server_name).map_err(|e| Error::ClientError(e.to_string()))?;
+            let tls = ClientConnection::new(Arc::new(tls_config), domain)
+                .map_err(|e| Error::ClientError(e.to_string()))?;
+            Ok(Stream::Tls(Box::new(StreamOwned::new(tls, stream))))
+        } else {
+            Ok(Stream::Tcp(stream))
+        }
+    }
+
+    #[cfg(not(feature = "tls"))]
+    #[allow(clippy::unnecessary_wraps)]
+    fn get_stream(stream: TcpStream, _host: &Host, _policy: &ClientPolicy) -> Result<Stream> {
+        Ok(Stream::Tcp(stream))
+    }
+
+    fn authenticate(&mut self, policy: &ClientPolicy, hashed_pass: Option<&String>) -> Result<()> {
+        if !AdminCommand::set_login(&mut self.buffer, &policy.auth_mode, hashed_pass)? {
+            return Ok(());
+        }
+
+        let res = self.flush().and_then(|()| {
+            self.read_buffer(admin_command::HEADER_SIZE)?;
+            let receive_size = AdminCommand::parse_login_header(&mut self.buffer, &self.addr)?;
+            self.read_buffer(receive_size)
+        });
+
+        if res.is_err() {
+            self.close();
+        }
+        res
+    }
+
+    pub fn close(&self) {
+        let _ = self.conn.tcp().shutdown(Shutdown::Both);
+    }
+
+    /// Sets the deadline and socket timeout for the next command on this connection.
+    pub const fn set_timeout(&mut self, deadline: Option<Instant>, socket_timeout: u32) {
+        self.deadline = deadline;
+        if socket_timeout > 0 {
+            self.socket_timeout = socket_timeout;
+        } else {
+            self.socket_timeout = 30_000; // 30 secs
+        }
+    }
+
+    // Applies the remaining time until the earlier of the socket timeout and the command
+    // deadline to the socket.
+    fn apply_timeout(&self) -> Result<()> {
+        let socket_timeout = Duration::from_millis(u64::from(self.socket_timeout));
+        let timeout = match self.deadline {
+            Some(deadline) => {
+                let remaining = deadline.saturating_duration_since(Instant::now());
+                if remaining.is_zero() {
+                    return Err(Error::Timeout("Command deadline exceeded".to_string()));
+                }
+                min(remaining, socket_timeout)
+            }
+            None => socket_timeout,
+        };
+
+        let tcp = self.conn.tcp();
+        tcp.set_read_timeout(Some(timeout))?;
+        tcp.set_write_timeout(Some(timeout))?;
+        Ok(())
+    }
+
+    /// Writes the command buffer to the connection.
+    pub fn flush(&mut self) -> Result<()> {
+        self.apply_timeout()?;
+        let res = self.conn.write_all(&self.buffer.data_buffer);
+        res.and_then(|()| self.conn.flush())
+            .map_err(|e| Self::map_io_error(e, "Timeout writing to the network connection"))?;
+        self.refresh();
+        Ok(())
+    }
+
+    /// Writes to the connection until done or timeout has been reached.
+    pub fn write_all(&mut self, buf: &[u8]) -> Result<()> {
+        self.apply_timeout()?;
+        self.conn
+            .write_all(buf)
+            .and_then(|()| self.conn.flush())
+            .map_err(|e| Self::map_io_error(e, "Timeout writing to the network connection"))?;
+        self.refresh();
+        Ok(())
+    }
+
+    /// Reads from the connection until the buffer is full or timeout has been reached.
+    pub fn read_all(&mut self, buf: &mut [u8]) -> Result<()> {
+        self.apply_timeout()?;
+        self.conn
+            .read_exact(buf)
+            .map_err(|e| Self::map_io_error(e, "Timeout reading from the network connection"))?;
+        self.refresh();
+        Ok(())
+    }
+
+    pub fn read_buffer(&mut self, size: usize) -> Result<()> {
+        self.buffer.resize_buffer(size)?;
+        self.apply_timeout()?;
+        self.conn
+            .read_exact(&mut self.buffer.data_buffer)
+            .map_err(|e| Self::map_io_error(e, "Timeout reading from the network connection"))?;
+        self.buffer.reset_offset();
+        self.refresh();
+        Ok(())
+    }
+
+    // Reads a standard message header and validates it.
+    pub fn read_header(&mut self) -> Result<()> {
+        self.read_buffer(buffer::MSG_TOTAL_HEADER_SIZE as usize)?;
+        let proto = self.buffer.read_u64(Some(0));
+        net::Connection::validate_header(proto)
+    }
+
+    // Reads the rest of the message after the header, discarding any bytes the command
+    // does not need.
+    pub fn empty_socket(&mut self) -> Result<()> {
+        let sz = self.buffer.read_i64(None);
+        let header_length = i64::from(self.buffer.read_u8(None));
+        let receive_size = ((sz & 0xFFFF_FFFF_FFFF) - header_length) as usize;
+
+        if receive_size > 0 {
+            self.read_buffer(receive_size)?;
+        }
+        Ok(())
+    }
+
+    pub fn is_idle(&self) -> bool {
+        self.idle_deadline
+            .is_some_and(|idle_dl| Instant::now() >= idle_dl)
+    }
+
+    fn refresh(&mut self) {
+        self.idle_deadline = self.idle_timeout.map(|timeout| Instant::now() + timeout);
+    }
+
+    fn map_io_error(err: io::Error, timeout_msg: &str) -> Error {
+        match err.kind() {
+            io::ErrorKind::WouldBlock | io::ErrorKind::TimedOut => {
+                Error::Timeout(timeout_msg.to_string())
+            }
+            io::ErrorKind::UnexpectedEof
+            | io::ErrorKind::ConnectionReset
+            | io::ErrorKind::ConnectionAborted
+            | io::ErrorKind::BrokenPipe => Error::Connection(err.to_string()),
+            _ => err.into(),
+        }
+    }
+}
+
+impl Drop for Connection {
+    fn drop(&mut self) {
+        self.close();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::{Read, Write};
+    use std::net::TcpListener;
+    use std::thread;
+    use std::time::Instant;
+
+    use super::Connection;
+    use crate::commands::Message;
+    use crate::errors::Error;
+    use crate::net::Host;
+    use crate::policy::{AdminPolicy, ClientPolicy};
+
+    fn listen() -> (TcpListener, Host) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+        (listener, Host::new("127.0.0.1", port))
+    }
+
+    #[test]
+    fn info_round_trip() {
+        let (listener, host) = listen();
+        let server = thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut header = [0u8; 8];
+            stream.read_exact(&mut header).unwrap();
+            assert_eq!(header[0], 2);
+            assert_eq!(header[1], 1);
+            let len = u64::from_be_bytes([
+                0, 0, header[2], header[3], header[4], header[5], header[6], header[7],
+            ]);
+            let mut request = vec![0u8; len as usize];
+            stream.read_exact(&mut request).unwrap();
+            assert_eq!(request, b"node\n");
+
+            let body = b"node\tBB9000000000000\n";
+            let mut response = (body.len() as u64).to_be_bytes();
+            response[0] = 2;
+            response[1] = 1;
+            stream.write_all(&response).unwrap();
+            stream.write_all(body).unwrap();
+        });
+
+        let mut conn = Connection::new(&host, &ClientPolicy::default(), None).unwrap();
+        let info = Message::info_blocking(AdminPolicy::default(), &mut conn, &["node"]).unwrap();
+        assert_eq!(
+            info.get("node").map(String::as_str),
+            Some("BB9000000000000")
+        );
+        server.join().unwrap();
+    }
+
+    #[test]
+    fn read_times_out() {
+        let (listener, host) = listen();
+        let server = thread::spawn(move || {
+            let (stream, _) = listener.accept().unwrap();
+            thread::sleep(std::time::Duration::from_millis(500));
+            drop(stream);
+        });
+
+        let mut conn = Connection::new(&host, &ClientPolicy::default(), None).unwrap();
+        conn.set_timeout(None, 50);
+        let start = Instant::now();
+        let res = conn.read_header();
+        assert!(matches!(res, Err(Error::Timeout(_))), "{:?}", res);
+        assert!(start.elapsed().as_millis() < 400);
+        server.join().unwrap();
+    }
+
+    #[test]
+    fn connect_refused() {
+        let (listener, host) = listen();
+        drop(listener);
+        let res = Connection::new(&host, &ClientPolicy::default(), None);
+        assert!(matches!(res, Err(Error::Connection(_))), "{:?}", res);
+    }
+}
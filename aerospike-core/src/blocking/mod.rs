@@ -0,0 +1,35 @@
+// Copyright 2015-2020 Aerospike, Inc.
+//
+// Portions may be licensed to Aerospike, Inc. under one or more contributor
+// license agreements.
+//
+// Licensed under the Apache License, Version 2.0 (the "License"); you may not
+// use this file except in compliance with the License. You may obtain a copy of
+// the License at http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS, WITHOUT
+// WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied. See the
+// License for the specific language governing permissions and limitations under
+// the License.
+
+//! Truly blocking client that does not run on, or spawn, an async runtime.
+//!
+//! The `sync` client wraps the async client and still relies on a runtime for network I/O and
+//! cluster tending. The client in this module instead uses `std::net::TcpStream` with socket
+//! timeouts, performs all I/O and tending on the calling thread and never starts background
+//! threads or tasks, which makes it suitable for hosts that forbid runtimes and thread pools
+//! they do not control. Commands share the wire encoding and response parsing of the async
+//! client.
+//!
+//! This module requires the `blocking` feature. Only single record commands are supported. A
+//! runtime feature such as `rt-tokio` must still be enabled, but only to satisfy the
+//! `aerospike-rt` dependency; the blocking client never uses the runtime.
+
+mod client;
+mod cluster;
+mod commands;
+mod connection;
+
+pub use self::client::Client;
+pub(crate) use self::connection::Connection;
@@ -25,7 +25,8 @@ use std::sync::LazyLock;
 
 use aerospike_rt::{sleep, Mutex};
 
-use crate::batch::{BatchExecutor, BatchOperation};
+use crate::batch::{BatchExecutor, BatchOperation, BatchReadPolicy};
+use crate::cluster::partition_map::PartitionMap;
 use crate::cluster::{Cluster, Node};
 use crate::commands::admin_command::AdminCommand;
 use crate::commands::buffer::Buffer;
@@ -35,15 +36,18 @@ use crate::commands::{
 };
 use crate::errors::{Error, Result};
 use crate::expressions::Expression;
+use crate::interceptor::{CommandContext, Interception};
 use crate::net::ToHosts;
 use crate::operations::cdt_context::{to_base64, CdtContext};
-use crate::operations::{Operation, OperationType};
-use crate::policy::{AdminPolicy, BatchPolicy, ClientPolicy, QueryPolicy, ReadPolicy, WritePolicy};
+use crate::operations::{OperateResults, Operation, OperationType};
+use crate::policy::{
+    AdminPolicy, BatchPolicy, ClientPolicy, LargeObjectPolicy, QueryPolicy, ReadPolicy, WritePolicy,
+};
 use crate::query::{PartitionFilter, PartitionTracker};
 use crate::task::{DropIndexTask, ExecuteTask, IndexTask, RegisterTask, UdfRemoveTask};
 use crate::{
-    BatchRecord, Bin, Bins, CollectionIndexType, IndexType, Key, Privilege, Record, Recordset,
-    ResultCode, Role, Statement, UDFLang, User, Value,
+    BatchRecord, Bin, Bins, CollectionIndexType, FromBins, IndexType, Key, LargeObject, Privilege,
+    Record, RecordView, Recordset, ResultCode, Role, Statement, UDFLang, User, Value,
 };
 use crate::{Policy, Version};
 use aerospike_rt::fs::File;
@@ -110,6 +114,12 @@ impl Client {
     ///
     /// Port 3000 is used by default if the port number is omitted for any of the hosts.
     ///
+    /// Host names starting with an underscore are looked up as DNS SRV records, which provide
+    /// the seed hosts and their ports, e.g. `_aerospike._tcp.aerospike.svc.cluster.local`. DNS
+    /// names, including SRV names, are resolved again whenever the client has lost all nodes
+    /// and reseeds the cluster. SRV lookups use the system's resolver configuration and require
+    /// the `dns-srv` feature; without it, SRV seeds are skipped.
+    ///
     /// # Arguments
     ///
     /// * `policy` — Client policy (timeouts, connection limits, authentication). Must pass [`ClientPolicy::validate`].
@@ -252,6 +262,60 @@ impl Client {
         self.cluster.nodes()
     }
 
+    /// Adds hosts to the seeds the client connects to when it has no nodes, e.g. after it lost
+    /// all nodes of the cluster. Hosts that are already seeds are ignored. DNS names are resolved
+    /// again every time the cluster is seeded, and names starting with an underscore, such as
+    /// `_aerospike._tcp.example.com`, are looked up as DNS SRV records.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,edition2021
+    /// # use aerospike::{Client, ClientPolicy};
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// # let hosts = std::env::var("AEROSPIKE_HOSTS").unwrap_or_else(|_| "127.0.0.1:3000".to_string());
+    /// # let client = Client::new(&ClientPolicy::default(), &hosts).await.unwrap();
+    /// client
+    ///     .add_seeds(&"_aerospike._tcp.aerospike.svc.cluster.local")
+    ///     .unwrap();
+    /// # }
+    /// ```
+    pub fn add_seeds(&self, hosts: &(dyn ToHosts + Send + Sync)) -> Result<()> {
+        let hosts = hosts.to_hosts()?;
+        self.cluster.add_seeds(&hosts);
+        Ok(())
+    }
+
+    /// Returns a snapshot of the partition map the client routes commands with: the nodes
+    /// holding each replica of every partition, per namespace, and the partition generation
+    /// of each node. Use [`PartitionMap::issues`] to find partitions without a master or with
+    /// missing replicas, e.g. during migrations.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,edition2021
+    /// # use aerospike::{Client, ClientPolicy};
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// # let hosts = std::env::var("AEROSPIKE_HOSTS").unwrap_or_else(|_| "127.0.0.1:3000".to_string());
+    /// # let client = Client::new(&ClientPolicy::default(), &hosts).await.unwrap();
+    /// let map = client.partition_map();
+    /// if let Some(routing) = map.partition("test", 0) {
+    ///     println!("partition 0 master: {:?}", routing.master());
+    /// }
+    /// for issue in map.issues() {
+    ///     println!("{}/{}: {:?}", issue.namespace, issue.partition_id, issue.problem);
+    /// }
+    /// # }
+    /// ```
+    pub fn partition_map(&self) -> PartitionMap {
+        self.cluster.partition_map()
+    }
+
+    fn interception<'a>(&self, context: CommandContext<'a>) -> Interception<'a> {
+        Interception::new(self.cluster.interceptors(), context)
+    }
+
     /// Read the record for the specified key. Depending on the bins value provided, all record bins,
     /// only selected record bins, or only the record headers will be returned. The policy can be
     /// used to specify timeouts.
@@ -330,6 +394,8 @@ impl Client {
         T: Into<Bins> + Send + Sync + 'static,
     {
         let bins = bins.into();
+        let mut interception = self.interception(CommandContext::for_key("get", key));
+        let policy = interception.before(policy)?;
         let mut command = ReadCommand::new(
             &policy.base_policy,
             self.cluster.clone(),
@@ -337,10 +403,121 @@ impl Client {
             bins,
             policy.replica,
         );
-        command.execute().await?;
+        let res = command.execute(&interception).await;
+        interception.after(res.as_ref().map(|()| command.record.as_ref()));
+        res?;
         Ok(command.record.unwrap())
     }
 
+    /// Read record for the specified key and pass a borrowed view of it to the callback, while
+    /// the response is still in the connection buffer.
+    ///
+    /// Unlike [`get`](Self::get), string and blob bins are not copied out of the response, and
+    /// list and map bins are only decoded as far as they are iterated. This avoids allocations
+    /// when the bin data is processed or streamed out right away. Returns the result of the
+    /// callback, which is called once if the record is found.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,edition2021
+    /// # use aerospike::*;
+    /// # use std::io::Write;
+    ///
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// # let hosts = std::env::var("AEROSPIKE_HOSTS").unwrap();
+    /// # let client = Client::new(&ClientPolicy::default(), &hosts).await.unwrap();
+    /// let key = as_key!("test", "test", "image");
+    /// let mut out = Vec::new();
+    /// let res = client.get_with(&ReadPolicy::default(), &key, ["data"], |record| {
+    ///     let data = record.bin("data").and_then(|bin| bin.as_blob()).unwrap_or_default();
+    ///     out.write_all(data)
+    /// }).await;
+    /// match res {
+    ///     Ok(Ok(())) => println!("copied {} bytes", out.len()),
+    ///     Ok(Err(err)) => println!("Error writing image: {}", err),
+    ///     Err(err) => println!("Error fetching record: {}", err),
+    /// }
+    /// # }
+    /// ```
+    pub async fn get_with<T, F, R>(
+        &self,
+        policy: &ReadPolicy,
+        key: &Key,
+        bins: T,
+        view_fn: F,
+    ) -> Result<R>
+    where
+        T: Into<Bins> + Send + Sync + 'static,
+        F: FnOnce(RecordView<'_>) -> R + Send,
+        R: Send,
+    {
+        let bins = bins.into();
+        let mut interception = self.interception(CommandContext::for_key("get", key));
+        let policy = interception.before(policy)?;
+        let mut view_fn = Some(view_fn);
+        let mut result = None;
+        let mut call = |record: RecordView<'_>| {
+            if let Some(view_fn) = view_fn.take() {
+                result = Some(view_fn(record));
+            }
+        };
+        let mut command = ReadCommand::new(
+            &policy.base_policy,
+            self.cluster.clone(),
+            key,
+            bins,
+            policy.replica,
+        );
+        command.view_fn = Some(&mut call);
+        let res = command.execute(&interception).await;
+        interception.complete(res)?;
+        drop(command);
+        result.ok_or_else(|| Error::BadResponse("Missing record in response".to_string()))
+    }
+
+    /// Read record for the specified key and decode it into a [`FromBins`] type, such as a
+    /// struct with `#[derive(FromBins)]`.
+    ///
+    /// Only the given bins are read, usually the ones returned by [`FromBins::bins`], and they are
+    /// decoded straight from the response into the fields of the type, without building a
+    /// [`Record`] first.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,edition2021
+    /// # use aerospike::*;
+    ///
+    /// #[derive(FromBins)]
+    /// struct User {
+    ///     name: String,
+    ///     age: Option<i64>,
+    /// }
+    ///
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// # let hosts = std::env::var("AEROSPIKE_HOSTS").unwrap();
+    /// # let client = Client::new(&ClientPolicy::default(), &hosts).await.unwrap();
+    /// let key = as_key!("test", "users", "ann");
+    /// match client.get_as::<User>(&ReadPolicy::default(), &key, User::bins()).await {
+    ///     Ok(user) => println!("{} is {:?} years old", user.name, user.age),
+    ///     Err(err) => println!("Error fetching user: {}", err),
+    /// }
+    /// # }
+    /// ```
+    pub async fn get_as<T: FromBins>(
+        &self,
+        policy: &ReadPolicy,
+        key: &Key,
+        bins: Bins,
+    ) -> Result<T> {
+        let codecs = self.cluster.codecs();
+        self.get_with(policy, key, bins, |record| {
+            record.decode_with::<T>(&codecs, key)
+        })
+        .await?
+    }
+
     /// Read multiple record for specified batch keys in one batch call. This method allows
     /// different namespaces/bins to be requested for each key in the batch. If the `BatchRead` key
     /// field is not found, the corresponding record field will be `None`. The policy can be used
@@ -436,8 +613,96 @@ impl Client {
         policy: &BatchPolicy,
         ops: &[BatchOperation],
     ) -> Result<Vec<BatchRecord>> {
+        let namespace = ops.first().map_or("", |op| &op.key_ref().namespace);
+        let mut interception =
+            self.interception(CommandContext::new("batch", namespace, "").with_batch(ops));
+        let policy = interception.before(policy)?;
+        let executor = BatchExecutor::new(self.cluster.clone());
+        interception.complete(executor.execute(&policy, ops).await)
+    }
+
+    /// Read multiple records in one batch call and decode them into a [`FromBins`] type, such as
+    /// a struct with `#[derive(FromBins)]`.
+    ///
+    /// Only the given bins are read, usually the ones returned by [`FromBins::bins`], and they are
+    /// decoded straight from the response into the fields of the type, without building a
+    /// [`Record`] first. The result has one entry per key, in the same order, which is `None` if the record does not exist or
+    /// was filtered out.
+    ///
+    /// # Errors
+    ///
+    /// * Returns an error if the batch request fails.
+    /// * Returns [`Error::ServerError`] with the result code if the server returns any other
+    ///   error for one of the keys. The error message names the key.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,edition2021
+    /// # use aerospike::*;
+    ///
+    /// #[derive(FromBins)]
+    /// struct User {
+    ///     name: String,
+    /// }
+    ///
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// # let hosts = std::env::var("AEROSPIKE_HOSTS").unwrap();
+    /// # let client = Client::new(&ClientPolicy::default(), &hosts).await.unwrap();
+    /// let keys = vec![as_key!("test", "users", "ann"), as_key!("test", "users", "bob")];
+    /// match client.batch_get_as::<User>(&BatchPolicy::default(), &keys, User::bins()).await {
+    ///     Ok(users) => {
+    ///         for (key, user) in keys.iter().zip(users) {
+    ///             match user {
+    ///                 Some(user) => println!("{}: {}", key, user.name),
+    ///                 None => println!("No such user: {}", key),
+    ///             }
+    ///         }
+    ///     }
+    ///     Err(err) => println!("Error executing batch request: {}", err),
+    /// }
+    /// # }
+    /// ```
+    pub async fn batch_get_as<T: FromBins>(
+        &self,
+        policy: &BatchPolicy,
+        keys: &[Key],
+        bins: Bins,
+    ) -> Result<Vec<Option<T>>> {
+        let read_policy = BatchReadPolicy::default();
+        let ops: Vec<BatchOperation> = keys
+            .iter()
+            .map(|key| BatchOperation::read(&read_policy, key.clone(), bins.clone()))
+            .collect();
+
+        let namespace = keys.first().map_or("", |key| &key.namespace);
+        let mut interception =
+            self.interception(CommandContext::new("batch", namespace, "").with_batch(&ops));
+        let policy = interception.before(policy)?;
         let executor = BatchExecutor::new(self.cluster.clone());
-        executor.execute(policy, ops).await
+        let res = executor
+            .execute_as::<T>(&policy, &ops)
+            .await
+            .and_then(|results| {
+                results
+                    .into_iter()
+                    .enumerate()
+                    .map(|(i, (batch_op, record))| match batch_op.result_code() {
+                        Some(ResultCode::Ok) => Ok(record),
+                        Some(ResultCode::KeyNotFoundError | ResultCode::FilteredOut) => Ok(None),
+                        Some(rc) => Err(Error::ServerError(
+                            rc,
+                            false,
+                            format!("Batch read failed for key {}", batch_op.key()),
+                        )),
+                        None => Err(Error::BadResponse(format!(
+                            "Missing result for key {} at batch index {i}",
+                            batch_op.key()
+                        ))),
+                    })
+                    .collect()
+            });
+        interception.complete(res)
     }
 
     /// Write record bin(s). The policy specifies the transaction timeout, record expiration, and
@@ -501,14 +766,84 @@ impl Client {
     /// # }
     /// ```
     pub async fn put(&self, policy: &WritePolicy, key: &Key, bins: &[Bin]) -> Result<()> {
+        let mut interception =
+            self.interception(CommandContext::for_key("put", key).with_bins(bins));
+        let policy = interception.before(policy)?;
+        let bins = self
+            .cluster
+            .codecs()
+            .encode_bins(key, bins, OperationType::Write)?;
         let mut command = WriteCommand::new(
-            policy,
+            &policy,
             self.cluster.clone(),
             key,
-            bins,
+            &bins,
             OperationType::Write,
         );
-        command.execute().await
+        interception.complete(command.execute(&interception).await)
+    }
+
+    /// Write a JSON document to the record with the given key. Depending on the layout, each
+    /// top-level field of the document is stored in its own bin, or the whole document is stored
+    /// in a single bin. The document is converted with the default
+    /// [`JsonPolicy`](crate::json::JsonPolicy). Requires the `json` feature.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,edition2021
+    /// # use aerospike::*;
+    /// # use aerospike::json::JsonLayout;
+    ///
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// # let hosts = std::env::var("AEROSPIKE_HOSTS").unwrap();
+    /// # let client = Client::new(&ClientPolicy::default(), &hosts).await.unwrap();
+    /// let key = as_key!("test", "test", "mykey");
+    /// let doc = serde_json::json!({"name": "Ann", "address": {"city": "Berlin"}});
+    /// let layout = JsonLayout::Bin("doc".to_string());
+    /// client.put_json(&WritePolicy::default(), &key, &layout, &doc).await.unwrap();
+    /// let stored = client.get_json(&ReadPolicy::default(), &key, &layout).await.unwrap();
+    /// assert_eq!(stored, doc);
+    /// # }
+    /// ```
+    #[cfg(feature = "json")]
+    pub async fn put_json(
+        &self,
+        policy: &WritePolicy,
+        key: &Key,
+        layout: &crate::json::JsonLayout,
+        doc: &serde_json::Value,
+    ) -> Result<()> {
+        let bins = crate::json::document_bins(layout, doc)?;
+        self.put(policy, key, &bins).await
+    }
+
+    /// Read a JSON document written by [`put_json`](Self::put_json) with the same layout. If the
+    /// document is stored in a single bin that does not exist, `null` is returned. Requires the
+    /// `json` feature.
+    #[cfg(feature = "json")]
+    pub async fn get_json(
+        &self,
+        policy: &ReadPolicy,
+        key: &Key,
+        layout: &crate::json::JsonLayout,
+    ) -> Result<serde_json::Value> {
+        let record = self
+            .get(policy, key, crate::json::layout_bins(layout))
+            .await?;
+        crate::json::record_document(layout, &record)
+    }
+
+    /// Returns a handle to the [`LargeObject`] stored under the given key, for blobs that are
+    /// larger than the write-block-size of the namespace. The object is split into chunk records
+    /// and a manifest record under the key itself.
+    ///
+    /// # See also
+    ///
+    /// * [`LargeObject::put`], [`LargeObject::get`] to write and read the whole object
+    /// * [`LargeObject::writer`], [`LargeObject::reader`] to stream the object
+    pub fn large_object(&self, policy: &LargeObjectPolicy, key: &Key) -> LargeObject<'_> {
+        LargeObject::new(self, policy, key)
     }
 
     /// Add integer bin values to existing record bin values. The policy specifies the transaction
@@ -528,6 +863,7 @@ impl Client {
     /// # Errors
     ///
     /// * Returns an error if the record does not exist, bins are not integers, or on cluster/network failure.
+    /// * Returns [`Error::InvalidArgument`] if a bin has a codec in [`ClientPolicy::codecs`].
     ///
     /// # See also
     ///
@@ -555,9 +891,21 @@ impl Client {
     /// # }
     /// ```
     pub async fn add(&self, policy: &WritePolicy, key: &Key, bins: &[Bin]) -> Result<()> {
-        let mut command =
-            WriteCommand::new(policy, self.cluster.clone(), key, bins, OperationType::Incr);
-        command.execute().await
+        let mut interception =
+            self.interception(CommandContext::for_key("add", key).with_bins(bins));
+        let policy = interception.before(policy)?;
+        let bins = self
+            .cluster
+            .codecs()
+            .encode_bins(key, bins, OperationType::Incr)?;
+        let mut command = WriteCommand::new(
+            &policy,
+            self.cluster.clone(),
+            key,
+            &bins,
+            OperationType::Incr,
+        );
+        interception.complete(command.execute(&interception).await)
     }
 
     /// Append bin string values to existing record bin values. The policy specifies the
@@ -577,6 +925,7 @@ impl Client {
     /// # Errors
     ///
     /// * Returns an error if the record does not exist, bins are not strings, or on cluster/network failure.
+    /// * Returns [`Error::InvalidArgument`] if a bin has a codec in [`ClientPolicy::codecs`].
     ///
     /// # See also
     ///
@@ -599,14 +948,21 @@ impl Client {
     /// # }
     /// ```
     pub async fn append(&self, policy: &WritePolicy, key: &Key, bins: &[Bin]) -> Result<()> {
+        let mut interception =
+            self.interception(CommandContext::for_key("append", key).with_bins(bins));
+        let policy = interception.before(policy)?;
+        let bins = self
+            .cluster
+            .codecs()
+            .encode_bins(key, bins, OperationType::Append)?;
         let mut command = WriteCommand::new(
-            policy,
+            &policy,
             self.cluster.clone(),
             key,
-            bins,
+            &bins,
             OperationType::Append,
         );
-        command.execute().await
+        interception.complete(command.execute(&interception).await)
     }
 
     /// Prepend bin string values to existing record bin values. The policy specifies the
@@ -622,6 +978,7 @@ impl Client {
     /// # Errors
     ///
     /// * Returns an error if the record does not exist, bins are not strings, or on cluster/network failure.
+    /// * Returns [`Error::InvalidArgument`] if a bin has a codec in [`ClientPolicy::codecs`].
     ///
     /// # See also
     ///
@@ -644,14 +1001,21 @@ impl Client {
     /// # }
     /// ```
     pub async fn prepend(&self, policy: &WritePolicy, key: &Key, bins: &[Bin]) -> Result<()> {
+        let mut interception =
+            self.interception(CommandContext::for_key("prepend", key).with_bins(bins));
+        let policy = interception.before(policy)?;
+        let bins = self
+            .cluster
+            .codecs()
+            .encode_bins(key, bins, OperationType::Prepend)?;
         let mut command = WriteCommand::new(
-            policy,
+            &policy,
             self.cluster.clone(),
             key,
-            bins,
+            &bins,
             OperationType::Prepend,
         );
-        command.execute().await
+        interception.complete(command.execute(&interception).await)
     }
 
     /// Delete record for a specified key. The policy specifies the transaction timeout.
@@ -694,8 +1058,10 @@ impl Client {
     /// # }
     /// ```
     pub async fn delete(&self, policy: &WritePolicy, key: &Key) -> Result<bool> {
-        let mut command = DeleteCommand::new(policy, self.cluster.clone(), key);
-        command.execute().await?;
+        let mut interception = self.interception(CommandContext::for_key("delete", key));
+        let policy = interception.before(policy)?;
+        let mut command = DeleteCommand::new(&policy, self.cluster.clone(), key);
+        interception.complete(command.execute(&interception).await)?;
         Ok(command.existed)
     }
 
@@ -736,8 +1102,10 @@ impl Client {
     /// # }
     /// ```
     pub async fn touch(&self, policy: &WritePolicy, key: &Key) -> Result<()> {
-        let mut command = TouchCommand::new(policy, self.cluster.clone(), key);
-        command.execute().await
+        let mut interception = self.interception(CommandContext::for_key("touch", key));
+        let policy = interception.before(policy)?;
+        let mut command = TouchCommand::new(&policy, self.cluster.clone(), key);
+        interception.complete(command.execute(&interception).await)
     }
 
     /// Determine if a record key exists. The policy can be used to specify timeouts.
@@ -776,8 +1144,10 @@ impl Client {
     /// # }
     /// ```
     pub async fn exists(&self, policy: &ReadPolicy, key: &Key) -> Result<bool> {
-        let mut command = ExistsCommand::new(policy, self.cluster.clone(), key);
-        command.execute().await?;
+        let mut interception = self.interception(CommandContext::for_key("exists", key));
+        let policy = interception.before(policy)?;
+        let mut command = ExistsCommand::new(&policy, self.cluster.clone(), key);
+        interception.complete(command.execute(&interception).await)?;
         Ok(command.exists)
     }
 
@@ -845,11 +1215,83 @@ impl Client {
                 "no operations defined".into(),
             ));
         }
-        let mut command = OperateCommand::new(policy, self.cluster.clone(), key, ops);
-        command.execute().await?;
+        let mut interception =
+            self.interception(CommandContext::for_key("operate", key).with_operations(ops));
+        let policy = interception.before(policy)?;
+        let ops = self.cluster.codecs().encode_operations(key, ops)?;
+        let mut command = OperateCommand::new(&policy, self.cluster.clone(), key, &ops);
+        let res = command.execute(&interception).await;
+        interception.after(res.as_ref().map(|()| command.read_command.record.as_ref()));
+        res?;
         Ok(command.read_command.record.unwrap())
     }
 
+    /// Read/Write multiple bins for a specified key in one database call, like
+    /// [`operate`](Self::operate), and return the result of each operation separately.
+    ///
+    /// The command is sent with [`respond_per_each_op`](WritePolicy::respond_per_each_op) set, so
+    /// that every result can be assigned to the operation that produced it. See
+    /// [`OperateResults`](crate::OperateResults) for which operations have a result.
+    ///
+    /// # Examples
+    ///
+    /// Append to a list and read its new size and first element in one database call.
+    ///
+    /// ```rust,edition2021
+    /// # use aerospike::*;
+    ///
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// # let hosts = std::env::var("AEROSPIKE_HOSTS").unwrap();
+    /// # let client = Client::new(&ClientPolicy::default(), &hosts).await.unwrap();
+    /// let key = as_key!("test", "test", "mykey");
+    /// let ops = vec![
+    ///     operations::lists::append(&ListPolicy::default(), "tags", as_val!("new")),
+    ///     operations::lists::get("tags", 0),
+    /// ];
+    /// match client.operate_results(&WritePolicy::default(), &key, &ops).await {
+    ///     Ok(results) => println!(
+    ///         "The list has {} elements, the first is {:?}",
+    ///         results.as_i64(0).unwrap(),
+    ///         results.get(1)
+    ///     ),
+    ///     Err(err) => println!("Error writing record: {}", err),
+    /// }
+    /// # }
+    /// ```
+    pub async fn operate_results(
+        &self,
+        policy: &WritePolicy,
+        key: &Key,
+        ops: &[Operation],
+    ) -> Result<OperateResults> {
+        if ops.is_empty() {
+            return Err(Error::ServerError(
+                ResultCode::ParameterError,
+                false,
+                "no operations defined".into(),
+            ));
+        }
+        let mut interception =
+            self.interception(CommandContext::for_key("operate", key).with_operations(ops));
+        let mut policy = interception.before(policy)?;
+        if !policy.respond_per_each_op {
+            policy.to_mut().respond_per_each_op = true;
+        }
+        let ops = self.cluster.codecs().encode_operations(key, ops)?;
+        let mut command = OperateCommand::new(&policy, self.cluster.clone(), key, &ops);
+        command.read_command.op_results = Some(Vec::with_capacity(ops.len()));
+        let res = command.execute(&interception).await;
+        interception.after(res.as_ref().map(|()| command.read_command.record.as_ref()));
+        res?;
+        let record = command
+            .read_command
+            .record
+            .ok_or_else(|| Error::BadResponse("Missing record in response".to_string()))?;
+        let op_results = command.read_command.op_results.unwrap_or_default();
+        Ok(OperateResults::new(&ops, record, op_results))
+    }
+
     /// Register a package containing user-defined functions (UDF) with the cluster. This
     /// asynchronous server call will return before the command is complete. The client registers
     /// the UDF package with a single, random cluster node; from there a copy will get distributed
@@ -1112,8 +1554,10 @@ impl Client {
         function_name: &str,
         args: Option<&[Value]>,
     ) -> Result<Option<Value>> {
+        let mut interception = self.interception(CommandContext::for_key("execute_udf", key));
+        let policy = interception.before(policy)?;
         let mut command = ExecuteUDFCommand::new(
-            policy,
+            &policy,
             self.cluster.clone(),
             key,
             server_path,
@@ -1121,7 +1565,9 @@ impl Client {
             args,
         );
 
-        command.execute().await?;
+        let res = command.execute(&interception).await;
+        interception.after(res.as_ref().map(|()| command.read_command.record.as_ref()));
+        res?;
 
         let record = command.read_command.record.unwrap();
 
@@ -1204,17 +1650,71 @@ impl Client {
         partition_filter: PartitionFilter,
         statement: Statement,
     ) -> Result<Arc<Recordset>> {
+        self.query_as(policy, partition_filter, statement).await
+    }
+
+    /// Execute a query on all server nodes like [`query`](Self::query), but decode the records
+    /// into a [`FromBins`] type, such as a struct with `#[derive(FromBins)]`.
+    ///
+    /// The records are decoded straight from the response into the fields of the type, without
+    /// building a [`Record`] first. The bins to read are taken from the statement; use
+    /// [`FromBins::bins`] to read only the bins the type uses. Records that fail to decode are
+    /// returned as errors by the record stream, without ending the query.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,edition2021
+    /// # extern crate aerospike;
+    /// # use aerospike::*;
+    /// # use futures::stream::StreamExt;
+    ///
+    /// #[derive(FromBins)]
+    /// struct User {
+    ///     name: String,
+    ///     #[aerospike(key)]
+    ///     key: Option<Key>,
+    /// }
+    ///
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// # let hosts = std::env::var("AEROSPIKE_HOSTS").unwrap_or(String::from("127.0.0.1:3000"));
+    /// # let client = Client::new(&ClientPolicy::default(), &hosts).await.unwrap();
+    /// let stmt = Statement::new("test", "users", User::bins());
+    /// let pf = PartitionFilter::all();
+    ///
+    /// match client.query_as::<User>(&QueryPolicy::default(), pf, stmt).await {
+    ///     Ok(recordset) => {
+    ///         let mut stream = recordset.into_stream();
+    ///         while let Some(result) = stream.next().await {
+    ///             match result {
+    ///                 Ok(user) => println!("{}: {:?}", user.name, user.key),
+    ///                 Err(err) => println!("Error executing query: {}", err),
+    ///             }
+    ///         }
+    ///     }
+    ///     Err(err) => println!("Failed to execute query: {}", err),
+    /// }
+    /// # }
+    /// ```
+    pub async fn query_as<T: FromBins>(
+        &self,
+        policy: &QueryPolicy,
+        partition_filter: PartitionFilter,
+        statement: Statement,
+    ) -> Result<Arc<Recordset<T>>> {
         statement.validate()?;
         let statement = Arc::new(statement);
+        let mut interception =
+            self.interception(CommandContext::for_statement("query", &statement));
+        let t_policy = interception.before(policy)?.into_owned();
 
         let nodes: Vec<Arc<Node>> = self.cluster.nodes();
-        let t_policy = policy.clone();
-        let tracker = Arc::new(Mutex::new(
-            PartitionTracker::new(&t_policy, Arc::new(Mutex::new(partition_filter)), nodes).await?,
-        ));
+        let tracker =
+            PartitionTracker::new(&t_policy, Arc::new(Mutex::new(partition_filter)), nodes).await;
+        let tracker = Arc::new(Mutex::new(interception.complete(tracker)?));
 
         let recordset = Arc::new(Recordset::new(
-            policy.record_queue_size,
+            t_policy.record_queue_size,
             usize::MAX, // will be reset later
             tracker.clone(),
         ));
@@ -1279,23 +1779,27 @@ impl Client {
             return Err(Error::Connection("No connections available".to_string()));
         }
 
+        let mut interception = self.interception(
+            CommandContext::for_statement("query_operate", &statement).with_operations(operations),
+        );
+        let write_policy = interception.before(write_policy)?;
+
         let task_id: u64 = rand::random();
         let scan = statement.filters.is_none();
 
         let mut last_err: Option<Error> = None;
         for node in &nodes {
             let mut cmd =
-                ServerCommand::new(node.clone(), write_policy, &statement, task_id, operations);
+                ServerCommand::new(node.clone(), &write_policy, &statement, task_id, operations);
             if let Err(err) = cmd.execute().await {
                 last_err = Some(err);
             }
         }
 
-        if let Some(err) = last_err {
-            return Err(err);
-        }
-
-        Ok(ExecuteTask::new(self.cluster.clone(), task_id, scan))
+        interception.complete(last_err.map_or_else(
+            || Ok(ExecuteTask::new(self.cluster.clone(), task_id, scan)),
+            Err,
+        ))
     }
 
     /// Apply a user-defined function to records matching the statement filter.
@@ -1348,30 +1852,35 @@ impl Client {
             return Err(Error::Connection("No connections available".to_string()));
         }
 
+        let mut interception = self.interception(CommandContext::for_statement(
+            "query_execute_udf",
+            &statement,
+        ));
+        let write_policy = interception.before(write_policy)?;
+
         let task_id: u64 = rand::random();
         let scan = statement.filters.is_none();
 
         let mut last_err: Option<Error> = None;
         for node in &nodes {
-            let mut cmd = ServerCommand::new_udf(node.clone(), write_policy, &statement, task_id);
+            let mut cmd = ServerCommand::new_udf(node.clone(), &write_policy, &statement, task_id);
             if let Err(err) = cmd.execute().await {
                 last_err = Some(err);
             }
         }
 
-        if let Some(err) = last_err {
-            return Err(err);
-        }
-
-        Ok(ExecuteTask::new(self.cluster.clone(), task_id, scan))
+        interception.complete(last_err.map_or_else(
+            || Ok(ExecuteTask::new(self.cluster.clone(), task_id, scan)),
+            Err,
+        ))
     }
 
-    async fn execute_query_timeout(
+    async fn execute_query_timeout<T: FromBins>(
         cluster: Arc<Cluster>,
         policy: &QueryPolicy,
         tracker: Arc<Mutex<PartitionTracker>>,
         statement: Arc<Statement>,
-        recordset: Arc<Recordset>,
+        recordset: Arc<Recordset<T>>,
     ) {
         if policy.total_timeout() > 0 {
             let rs_closer = recordset.clone();
@@ -1391,12 +1900,12 @@ impl Client {
         }
     }
 
-    async fn execute_query(
+    async fn execute_query<T: FromBins>(
         cluster: Arc<Cluster>,
         policy: &QueryPolicy,
         tracker: Arc<Mutex<PartitionTracker>>,
         statement: Arc<Statement>,
-        recordset: Arc<Recordset>,
+        recordset: Arc<Recordset<T>>,
     ) {
         let namespace = statement.namespace.clone();
         loop {
@@ -1435,6 +1944,7 @@ impl Client {
                         let policy = policy.clone();
                         let node_partition = node_partition.clone();
                         let statement = statement.clone();
+                        let codecs = cluster.codecs();
                         let handle = aerospike_rt::spawn(async move {
                             let permit = semaphore.acquire().await;
                             let result = if statement.filters.is_none() {
@@ -1445,6 +1955,7 @@ impl Client {
                                     statement.bins.clone(),
                                     recordset.clone(),
                                     node_partition,
+                                    codecs,
                                 )
                                 .await
                                 .execute()
@@ -1455,6 +1966,7 @@ impl Client {
                                     statement,
                                     recordset.clone(),
                                     node_partition,
+                                    codecs,
                                 )
                                 .await
                                 .execute()
@@ -16,6 +16,7 @@
 pub mod node;
 pub mod node_validator;
 pub mod partition;
+pub mod partition_map;
 pub mod partition_tokenizer;
 pub mod peers_parser;
 pub mod version_parser;
@@ -23,47 +24,91 @@ pub mod version_parser;
 use aerospike_rt::time::{Duration, Instant};
 use std::cell::OnceCell;
 use std::collections::HashMap;
+use std::future::Future;
+use std::pin::pin;
 use std::sync::atomic::{AtomicBool, AtomicIsize, Ordering};
-use std::sync::Arc;
+use std::sync::{Arc, PoisonError};
 use std::vec::Vec;
 
 pub use self::node::Node;
 
 use self::node_validator::NodeValidator;
 use self::partition::Partition;
+use self::partition_map::PartitionMap;
 use self::partition_tokenizer::PartitionTokenizer;
 
+use crate::codec::BinCodecs;
 use crate::commands::admin_command::AdminCommand;
 use crate::commands::Message;
 use crate::errors::{Error, Result};
-use crate::net::Host;
+use crate::interceptor::CommandInterceptor;
+use crate::net::{dns, Host};
 use crate::policy::ClientPolicy;
 use crate::policy::Replica;
+use crate::trace;
 use crate::AdminPolicy;
 use aerospike_rt::Mutex;
 use futures::channel::mpsc;
-use futures::channel::mpsc::{Receiver, Sender, TryRecvError};
+use futures::channel::mpsc::{Receiver, Sender};
+use futures::channel::oneshot;
+use futures::future::{self, Either};
+use futures::StreamExt;
 use hazarc::AtomicArc;
 
 static CLIENT_VERSION: &str = env!("CARGO_PKG_VERSION");
 
-#[derive(Debug, Default, Clone)]
-pub struct PartitionForNamespace {
-    nodes: Vec<(u32, Option<Arc<Node>>)>,
+#[derive(Debug, Clone)]
+pub struct PartitionForNamespace<N = Arc<Node>> {
+    nodes: Vec<(u32, Option<N>)>,
     replicas: usize,
 }
 
-type PartitionTable = HashMap<String, PartitionForNamespace>;
+impl<N> Default for PartitionForNamespace<N> {
+    fn default() -> Self {
+        PartitionForNamespace {
+            nodes: Vec::new(),
+            replicas: 0,
+        }
+    }
+}
 
-impl PartitionForNamespace {
-    fn all_replicas(&self, index: usize) -> impl Iterator<Item = Option<Arc<Node>>> + '_ {
+impl<N: Clone> PartitionForNamespace<N> {
+    // Returns the nodes holding each replica of the partition, master first.
+    pub(crate) fn all_replicas(&self, index: usize) -> impl Iterator<Item = Option<N>> + '_ {
         (0..self.replicas).map(move |i| {
             self.nodes
                 .get(i * node::PARTITIONS + index)
                 .and_then(|(_, item)| item.clone())
         })
     }
+}
+
+type PartitionTable = HashMap<String, PartitionForNamespace>;
+
+async fn before_deadline<T>(deadline: Instant, fut: impl Future<Output = Result<T>>) -> Result<T> {
+    let timeout = deadline.saturating_duration_since(Instant::now());
+    aerospike_rt::timeout(timeout, fut)
+        .await
+        .map_err(|_| Error::Timeout("Node refresh deadline exceeded".to_string()))?
+}
+
+// Runs a node refresh before the deadline. A refresh that times out may already have stored the
+// node's new generations, so they are reset to have the next tend request the partition map and
+// racks again.
+async fn refresh_before_deadline(
+    node: &Node,
+    deadline: Instant,
+    refresh: impl Future<Output = Result<Vec<Host>>>,
+) -> Result<Vec<Host>> {
+    let res = before_deadline(deadline, refresh).await;
+    if let Err(Error::Timeout(_)) = res {
+        node.reset_partition_generation();
+        node.reset_rebalance_generation();
+    }
+    res
+}
 
+impl PartitionForNamespace {
     fn get_node(
         &self,
         cluster: &Cluster,
@@ -165,6 +210,12 @@ pub struct Cluster {
 
     tend_channel: Mutex<Sender<()>>,
     closed: AtomicBool,
+
+    // Set while an on-demand tend is queued, so that concurrent requests
+    // coalesce into a single tend cycle.
+    tend_requested: AtomicBool,
+    // Callers waiting for the next tend cycle to complete.
+    tend_waiters: std::sync::Mutex<Vec<oneshot::Sender<()>>>,
 }
 
 impl Cluster {
@@ -172,21 +223,7 @@ impl Cluster {
         // updated the hashed password
         let _ = policy.set_auth_mode(policy.auth_mode.clone());
 
-        let (tx, rx) = mpsc::channel(100);
-        let cluster = Arc::new(Cluster {
-            hashed_pass: AtomicArc::from(policy.hashed_pass()),
-            client_policy: AtomicArc::from(policy),
-
-            seeds: AtomicArc::from(hosts.to_vec()),
-            aliases: AtomicArc::from(HashMap::new()),
-            nodes: AtomicArc::from(vec![]),
-
-            partition_map: AtomicArc::from(HashMap::default()),
-            node_index: AtomicIsize::new(0),
-
-            tend_channel: Mutex::new(tx),
-            closed: AtomicBool::new(false),
-        });
+        let (cluster, rx) = Cluster::new_unconnected(policy, hosts);
         // try to seed connections for first use
         Cluster::wait_till_stabilized(cluster.clone()).await?;
 
@@ -206,19 +243,45 @@ impl Cluster {
         Ok(cluster)
     }
 
-    async fn tend_thread(cluster: Arc<Cluster>, mut rx: Receiver<()>) {
-        let tend_interval = cluster.client_policy.load().tend_interval;
+    // Creates a cluster without nodes, and the receiving end of its tend channel.
+    fn new_unconnected(policy: ClientPolicy, hosts: &[Host]) -> (Arc<Self>, Receiver<()>) {
+        let (tx, rx) = mpsc::channel(100);
+        let cluster = Arc::new(Cluster {
+            hashed_pass: AtomicArc::from(policy.hashed_pass()),
+            client_policy: AtomicArc::from(policy),
 
+            seeds: AtomicArc::from(hosts.to_vec()),
+            aliases: AtomicArc::from(HashMap::new()),
+            nodes: AtomicArc::from(vec![]),
+
+            partition_map: AtomicArc::from(HashMap::default()),
+            node_index: AtomicIsize::new(0),
+
+            tend_channel: Mutex::new(tx),
+            closed: AtomicBool::new(false),
+
+            tend_requested: AtomicBool::new(false),
+            tend_waiters: std::sync::Mutex::new(vec![]),
+        });
+        (cluster, rx)
+    }
+
+    async fn tend_thread(cluster: Arc<Cluster>, mut rx: Receiver<()>) {
         loop {
-            match rx.try_recv() {
-                Ok(()) => unreachable!(),
-                Err(TryRecvError::Closed) => break,
-                Err(TryRecvError::Empty) => {
-                    if let Err(err) = cluster.tend().await {
-                        log_error_chain!(err, "Error tending cluster");
-                    }
-                    aerospike_rt::sleep(Duration::from_millis(u64::from(tend_interval))).await;
-                }
+            let waiters = cluster.start_tend_cycle();
+
+            if let Err(err) = trace::instrument(trace::tend_span(), cluster.tend()).await {
+                log_error_chain!(err, "Error tending cluster");
+            }
+            for waiter in waiters {
+                let _ = waiter.send(());
+            }
+
+            // Sleep until the next tick, unless a tend is requested earlier.
+            let tend_interval = cluster.client_policy.load().tend_interval;
+            let sleep = aerospike_rt::sleep(Duration::from_millis(u64::from(tend_interval)));
+            if let Either::Left((None, _)) = future::select(rx.next(), pin!(sleep)).await {
+                break;
             }
         }
 
@@ -231,12 +294,59 @@ impl Cluster {
         cluster.set_nodes(vec![]);
         cluster.aliases.store(Arc::new(HashMap::new()));
         cluster.seeds.store(Arc::new(vec![]));
+        cluster.take_tend_waiters();
+    }
+
+    // Returns the callers waiting for the tend cycle that is about to run. Tend requests made
+    // from here on need a newer tend than this one.
+    fn start_tend_cycle(&self) -> Vec<oneshot::Sender<()>> {
+        self.tend_requested.store(false, Ordering::SeqCst);
+        self.take_tend_waiters()
+    }
+
+    fn take_tend_waiters(&self) -> Vec<oneshot::Sender<()>> {
+        std::mem::take(
+            &mut *self
+                .tend_waiters
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner),
+        )
+    }
+
+    /// Requests an immediate tend and waits for it to complete, or until the client policy's
+    /// tend timeout or the given deadline passes, whichever comes first. Concurrent requests are
+    /// coalesced into a single tend cycle.
+    ///
+    /// Commands call this after routing failures, such as a partition that is unavailable on
+    /// the node it was sent to, so that their retries use a fresh partition map instead of
+    /// waiting for the next `tend_interval` tick.
+    pub async fn request_tend(&self, deadline: Option<Instant>) {
+        if self.is_closed() {
+            return;
+        }
+
+        let (tx, rx) = oneshot::channel();
+        self.tend_waiters
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .push(tx);
+        if !self.tend_requested.swap(true, Ordering::SeqCst) {
+            // A full or closed channel means a tend is already pending or the cluster is
+            // shutting down; either way the waiter is resolved by the tend thread.
+            let _ = self.tend_channel.lock().await.try_send(());
+        }
+
+        let mut timeout = self.client_policy.load().tend_timeout();
+        if let Some(deadline) = deadline {
+            timeout = timeout.min(deadline.saturating_duration_since(Instant::now()));
+        }
+        let _ = aerospike_rt::timeout(timeout, rx).await;
     }
 
     async fn tend(&self) -> Result<()> {
         // If close() has been called, bail before any work — otherwise an
         // in-flight cycle would repopulate nodes after close() cleared them.
-        if self.closed.load(Ordering::Relaxed) {
+        if self.is_closed() {
             return Ok(());
         }
 
@@ -250,42 +360,44 @@ impl Cluster {
             nodes = self.nodes();
         }
 
+        // Refresh all known nodes concurrently. The refreshes only update the state of their own
+        // node; the partition map is merged below, once all of them have finished or timed out.
+        let aliases = self.aliases();
+        let deadline = Instant::now() + self.client_policy.load().tend_timeout();
+        let refreshes = nodes.iter().filter(|node| node.is_active()).map(|node| {
+            let aliases = aliases.clone();
+            async move { (node, self.refresh_node(node, aliases, deadline).await) }
+        });
+        let refreshes = future::join_all(refreshes).await;
+
         let mut friend_list: Vec<Host> = vec![];
         let mut refresh_count = 0;
 
         let mut partition_map = OnceCell::new();
 
-        // Refresh all known nodes.
-        for node in nodes {
-            let old_gen = node.partition_generation();
-            let old_rebalance_gen = node.rebalance_generation();
-            if node.is_active() {
-                match node.refresh(self.aliases()).await {
-                    Ok(friends) => {
-                        refresh_count += 1;
-
-                        if !friends.is_empty() {
-                            friend_list.extend_from_slice(&friends);
+        for (node, res) in refreshes {
+            match res {
+                Ok((friends, partitions)) => {
+                    refresh_count += 1;
+                    friend_list.extend(friends);
+
+                    if let Some(partitions) = partitions {
+                        partition_map.get_or_init(|| {
+                            // this will clone the inner value
+                            (*self.partition_map.load().clone()).clone()
+                        });
+                        if let Err(err) =
+                            partitions.update_partition(partition_map.get_mut().unwrap(), node)
+                        {
+                            node.reset_partition_generation();
+                            warn!("Node `{node}` partition map update failed: {err}");
                         }
-
-                        if old_gen != node.partition_generation() {
-                            partition_map.get_or_init(|| {
-                                // this will clone the inner value
-                                (*self.partition_map.load().clone()).clone()
-                            });
-                            self.update_partitions(partition_map.get_mut().unwrap(), &node)
-                                .await?;
-                        }
-
-                        if old_rebalance_gen != node.rebalance_generation() {
-                            self.update_rack_ids(&node).await?;
-                        }
-                    }
-                    Err(err) => {
-                        node.increase_failures();
-                        warn!("Node `{node}` refresh failed: {err}");
                     }
                 }
+                Err(err) => {
+                    node.increase_failures();
+                    warn!("Node `{node}` refresh failed: {err}");
+                }
             }
         }
 
@@ -302,8 +414,21 @@ impl Cluster {
         // Handle nodes changes determined from refreshes.
         // Remove nodes in a batch.
         let remove_list = self.find_nodes_to_remove(refresh_count).await;
+        let remove_count = remove_list.len();
         self.remove_nodes_and_aliases(remove_list);
 
+        // Opening connections is bounded by the client policy timeout rather than the tend
+        // deadline, so it runs once the partition map and the node list have been updated.
+        let nodes = self.nodes();
+        future::join_all(nodes.iter().map(|node| node.fill_min_conns())).await;
+
+        trace::tend_cycle(
+            self.nodes().len(),
+            refresh_count,
+            add_list.len(),
+            remove_count,
+        );
+
         let aliases: Vec<String> = self
             .aliases
             .load()
@@ -335,7 +460,7 @@ impl Cluster {
                     break;
                 }
 
-                if let Err(err) = cluster.tend().await {
+                if let Err(err) = trace::instrument(trace::tend_span(), cluster.tend()).await {
                     log_error_chain!(err, "Error during initial cluster tend");
                 }
 
@@ -368,9 +493,37 @@ impl Cluster {
         (*self.client_policy.load().clone()).clone()
     }
 
+    /// Replaces the client policy of a running cluster, and applies it to the connection pools
+    /// of the current nodes. Open connections are kept; timeouts, connection limits and the
+    /// tend interval take effect for new connections, commands and tend cycles. Credentials
+    /// and the number of connection pools per node are not changed.
+    pub fn set_client_policy(&self, policy: ClientPolicy) {
+        let policy = Arc::new(policy);
+        self.client_policy.store(policy.clone());
+        for node in self.nodes() {
+            node.set_client_policy(policy.clone());
+        }
+    }
+
+    pub(crate) fn is_closed(&self) -> bool {
+        self.closed.load(Ordering::Relaxed)
+    }
+
+    pub fn interceptors(&self) -> Vec<Arc<dyn CommandInterceptor>> {
+        self.client_policy.load().interceptors.clone()
+    }
+
+    pub fn codecs(&self) -> BinCodecs {
+        self.client_policy.load().codecs.clone()
+    }
+
     pub fn add_seeds(&self, new_seeds: &[Host]) {
         let mut seeds = self.seeds.load().to_vec();
-        seeds.extend_from_slice(new_seeds);
+        for seed in new_seeds {
+            if !seeds.contains(seed) {
+                seeds.push(seed.clone());
+            }
+        }
         self.seeds.store(Arc::new(seeds));
     }
 
@@ -394,26 +547,60 @@ impl Cluster {
         res
     }
 
+    // Refreshes a node, and requests its partition map and racks if they have changed. Fails if
+    // the node is not refreshed before the deadline. Partition map and rack requests that fail are
+    // repeated on the next tend.
+    async fn refresh_node(
+        &self,
+        node: &Arc<Node>,
+        aliases: HashMap<Host, Arc<Node>>,
+        deadline: Instant,
+    ) -> Result<(Vec<Host>, Option<PartitionTokenizer>)> {
+        let old_gen = node.partition_generation();
+        let old_rebalance_gen = node.rebalance_generation();
+        let friends = refresh_before_deadline(node, deadline, node.refresh(aliases)).await?;
+
+        let mut partitions = None;
+        if old_gen != node.partition_generation() {
+            match before_deadline(deadline, self.request_partitions(node)).await {
+                Ok(tokens) => partitions = Some(tokens),
+                Err(err) => {
+                    node.reset_partition_generation();
+                    warn!("Node `{node}` partition map request failed: {err}");
+                }
+            }
+        }
+
+        if old_rebalance_gen != node.rebalance_generation() {
+            if let Err(err) = before_deadline(deadline, self.update_rack_ids(node)).await {
+                node.reset_rebalance_generation();
+                warn!("Node `{node}` rack request failed: {err}");
+            }
+        }
+
+        Ok((friends, partitions))
+    }
+
     pub async fn update_partitions(
         &self,
         partition_map: &mut PartitionTable,
         node: &Arc<Node>,
     ) -> Result<()> {
+        let tokens = self.request_partitions(node).await?;
+        tokens.update_partition(partition_map, node)
+    }
+
+    async fn request_partitions(&self, node: &Arc<Node>) -> Result<PartitionTokenizer> {
         let mut conn = node.get_connection(0).await?;
 
         let admin_policy = AdminPolicy {
             timeout: self.client_policy.load().timeout,
         };
         let tokens = PartitionTokenizer::new(&admin_policy, &mut conn, node).await;
-        if let Err(e) = tokens {
+        if tokens.is_err() {
             conn.invalidate();
-            return Err(e);
         }
-
-        let tokens = tokens.unwrap();
-        tokens.update_partition(partition_map, node)?;
-
-        Ok(())
+        tokens
     }
 
     pub async fn update_rack_ids(&self, node: &Arc<Node>) -> Result<()> {
@@ -439,12 +626,15 @@ impl Cluster {
     }
 
     pub async fn seed_nodes(&self) -> bool {
-        let seed_array = self.seeds.load();
+        // SRV seeds are looked up again every time the cluster is seeded, e.g. after all nodes
+        // were lost, so that seeds follow DNS changes.
+        let timeout = self.client_policy.load().tend_timeout();
+        let seed_array = dns::resolve_seeds_async(self.seeds.load().to_vec(), timeout).await;
 
         info!("Seeding the cluster. Seeds count: {}", seed_array.len());
 
         let mut list: Vec<Arc<Node>> = vec![];
-        for seed in seed_array.iter() {
+        for seed in &seed_array {
             let mut seed_node_validator = NodeValidator::new(self.client_policy());
             if let Err(err) = seed_node_validator.validate_node(self, seed).await {
                 log_error_chain!(err, "Failed to validate seed host: {}", seed);
@@ -645,6 +835,13 @@ impl Cluster {
         let mut nodes = self.nodes();
         nodes.extend(friend_list.iter().cloned());
         self.set_nodes(nodes);
+
+        // The nodes were created with the client policy of the time, and `set_client_policy`
+        // may have replaced it before they were added to the node list.
+        let policy = self.client_policy.load_owned();
+        for node in friend_list {
+            node.set_client_policy(policy.clone());
+        }
     }
 
     fn remove_nodes(&self, nodes_to_remove: &[Arc<Node>]) {
@@ -677,6 +874,15 @@ impl Cluster {
         (*self.nodes.load().clone()).clone()
     }
 
+    pub fn partition_map(&self) -> PartitionMap {
+        let generations = self
+            .nodes()
+            .iter()
+            .map(|node| (node.name().to_string(), node.partition_generation()))
+            .collect();
+        PartitionMap::new(&self.partition_map.load(), generations, |node| node.name())
+    }
+
     fn set_nodes(&self, new_nodes: Vec<Arc<Node>>) {
         self.nodes.store(Arc::new(new_nodes));
     }
@@ -757,8 +963,8 @@ impl Cluster {
 
         // Actually close the tend channel: locking the Mutex and dropping
         // the *guard* only releases the lock — it doesn't drop the Sender,
-        // which is what tend_thread's `try_recv` watches via TryRecvError::
-        // Closed. Use Sender::close_channel() to signal closure. The tend
+        // which is what tend_thread waits on alongside its interval
+        // sleep. Use Sender::close_channel() to signal closure. The tend
         // thread itself clears `nodes` and `aliases` as its last act before
         // exiting (see `tend_thread`), preserving the single-writer
         // invariant on those fields.
@@ -766,3 +972,139 @@ impl Cluster {
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use aerospike_rt::time::{Duration, Instant};
+
+    use std::collections::HashMap;
+    use std::sync::Arc;
+
+    use futures::future;
+    use futures::StreamExt;
+
+    use super::{before_deadline, node, refresh_before_deadline, Cluster, Node};
+    use crate::cluster::node_validator::NodeValidator;
+    use crate::errors::{Error, Result};
+    use crate::net::Host;
+    use crate::policy::ClientPolicy;
+    use crate::Version;
+
+    fn test_node() -> Node {
+        let policy = ClientPolicy::default();
+        let nv = Arc::new(NodeValidator {
+            name: "test-node".to_string(),
+            aliases: vec![Host::new("127.0.0.1", 3000)],
+            services: vec![],
+            address: "127.0.0.1:3000".to_string(),
+            client_policy: policy.clone(),
+            use_new_info: true,
+            version: Version::default(),
+        });
+        Node::new(policy, nv)
+    }
+
+    #[test]
+    fn client_policy_applies_to_added_nodes() {
+        let (cluster, _rx) = Cluster::new_unconnected(ClientPolicy::default(), &[]);
+        let node = Arc::new(test_node());
+
+        // the policy is replaced after the node was created, but before it was added
+        cluster.set_client_policy(ClientPolicy {
+            max_conns_per_node: 16,
+            ..ClientPolicy::default()
+        });
+        assert_eq!(node.client_policy().max_conns_per_node, 256);
+
+        cluster.add_nodes(std::slice::from_ref(&node));
+        assert_eq!(node.client_policy().max_conns_per_node, 16);
+    }
+
+    #[aerospike_macro::test]
+    async fn deadline() {
+        let deadline = Instant::now() + Duration::from_millis(50);
+        let res: Result<u32> = before_deadline(deadline, async { Ok(1) }).await;
+        assert_eq!(res.unwrap(), 1);
+
+        let res: Result<()> = before_deadline(deadline, async {
+            aerospike_rt::sleep(Duration::from_secs(5)).await;
+            Ok(())
+        })
+        .await;
+        assert!(matches!(res, Err(Error::Timeout(_))));
+
+        // a passed deadline still lets ready futures complete
+        let res: Result<u32> = before_deadline(Instant::now(), async { Ok(2) }).await;
+        assert_eq!(res.unwrap(), 2);
+    }
+
+    #[aerospike_macro::test]
+    async fn refresh_timeout_resets_generations() {
+        let node = test_node();
+        let info_map = HashMap::from([
+            (node::PARTITION_GENERATION.to_string(), "7".to_string()),
+            (node::REBALANCE_GENERATION.to_string(), "3".to_string()),
+        ]);
+
+        // The refresh stores the new generations, then stalls past the deadline.
+        let deadline = Instant::now() + Duration::from_millis(50);
+        let res = refresh_before_deadline(&node, deadline, async {
+            node.update_partitions(&info_map)?;
+            node.update_rebalance_generation(&info_map)?;
+            aerospike_rt::sleep(Duration::from_secs(5)).await;
+            Ok(vec![])
+        })
+        .await;
+        assert!(matches!(res, Err(Error::Timeout(_))));
+        assert_eq!(node.partition_generation(), -1);
+        assert_eq!(node.rebalance_generation(), -1);
+
+        // Refreshes that complete in time keep the generations they stored.
+        let deadline = Instant::now() + Duration::from_millis(50);
+        let res = refresh_before_deadline(&node, deadline, async {
+            node.update_partitions(&info_map)?;
+            node.update_rebalance_generation(&info_map)?;
+            Ok(vec![])
+        })
+        .await;
+        assert!(res.is_ok());
+        assert_eq!(node.partition_generation(), 7);
+        assert_eq!(node.rebalance_generation(), 3);
+    }
+
+    #[aerospike_macro::test]
+    async fn tend_requests_coalesce() {
+        let (cluster, mut rx) = Cluster::new_unconnected(ClientPolicy::default(), &[]);
+
+        let requests = future::join_all((0..10).map(|_| cluster.request_tend(None)));
+        let tend = async {
+            rx.next().await.unwrap();
+            let waiters = cluster.start_tend_cycle();
+            assert_eq!(waiters.len(), 10);
+            for waiter in waiters {
+                waiter.send(()).unwrap();
+            }
+        };
+        future::join(requests, tend).await;
+
+        // all requests were served by a single tend
+        assert!(rx.try_recv().is_err());
+
+        // a request made after the tend started asks for another one
+        let deadline = Instant::now() + Duration::from_millis(50);
+        cluster.request_tend(Some(deadline)).await;
+        assert!(rx.try_recv().is_ok());
+    }
+
+    #[aerospike_macro::test]
+    async fn tend_request_deadline() {
+        let (cluster, _rx) = Cluster::new_unconnected(ClientPolicy::default(), &[]);
+
+        // nobody tends the cluster, the request gives up at the command deadline
+        let start = Instant::now();
+        cluster
+            .request_tend(Some(start + Duration::from_millis(50)))
+            .await;
+        assert!(start.elapsed() < Duration::from_secs(5));
+    }
+}
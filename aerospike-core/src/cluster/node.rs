@@ -40,7 +40,7 @@ pub const REBALANCE_GENERATION: &str = "rebalance-generation";
 /// Exposed for usage in the sync client interface.
 #[derive(Debug)]
 pub struct Node {
-    client_policy: ClientPolicy,
+    client_policy: AtomicArc<ClientPolicy>,
     name: String,
     host: Host,
     aliases: AtomicArc<Vec<Host>>,
@@ -72,7 +72,7 @@ impl Node {
     #![allow(missing_docs)]
     pub fn new(client_policy: ClientPolicy, nv: Arc<NodeValidator>) -> Self {
         Node {
-            client_policy: client_policy.clone(),
+            client_policy: AtomicArc::from(client_policy.clone()),
             name: nv.name.clone(),
             aliases: AtomicArc::from(nv.aliases.clone()),
             address: nv.address.clone(),
@@ -111,8 +111,15 @@ impl Node {
     }
 
     // Returns the active client policy
-    pub const fn client_policy(&self) -> &ClientPolicy {
-        &self.client_policy
+    pub fn client_policy(&self) -> Arc<ClientPolicy> {
+        self.client_policy.load_owned()
+    }
+
+    // Replaces the client policy used by the node and its connection pool. Open connections
+    // are kept, new ones are made with the new policy.
+    pub(crate) fn set_client_policy(&self, policy: Arc<ClientPolicy>) {
+        self.connection_pool.set_policy(&policy);
+        self.client_policy.store(policy);
     }
 
     pub fn host(&self) -> Host {
@@ -136,19 +143,20 @@ impl Node {
         self.reference_count.store(0, Ordering::Relaxed);
         self.responded.store(false, Ordering::Relaxed);
         self.refresh_count.fetch_add(1, Ordering::Relaxed);
+        let client_policy = self.client_policy();
         let mut commands = vec![
             "node",
             "cluster-name",
             PARTITION_GENERATION,
-            self.client_policy.peers_string(),
+            client_policy.peers_string(),
         ];
 
-        if self.client_policy.rack_ids.is_some() {
+        if client_policy.rack_ids.is_some() {
             commands.push(REBALANCE_GENERATION);
         }
 
         let admin_policy = AdminPolicy {
-            timeout: self.client_policy.timeout,
+            timeout: client_policy.timeout,
         };
 
         let info_map = self
@@ -166,7 +174,6 @@ impl Node {
         self.update_rebalance_generation(&info_map)
             .map_err(|e| e.chain_error("Failed to update rebalance generation"))?;
         self.reset_failures();
-        let _ = self.fill_min_conns().await;
         Ok(friends)
     }
 
@@ -192,7 +199,7 @@ impl Node {
 
     #[allow(clippy::option_if_let_else)]
     fn verify_cluster_name(&self, info_map: &HashMap<String, String>) -> Result<()> {
-        match self.client_policy.cluster_name {
+        match self.client_policy.load().cluster_name {
             None => Ok(()),
             Some(ref expected) => match info_map.get("cluster-name") {
                 None => Err(Error::InvalidNode("Missing cluster name".to_string())),
@@ -215,7 +222,8 @@ impl Node {
     ) -> Result<Vec<Host>> {
         let mut friends: Vec<Host> = vec![];
 
-        let friend_string = match info_map.get(self.client_policy.peers_string()) {
+        let client_policy = self.client_policy();
+        let friend_string = match info_map.get(client_policy.peers_string()) {
             None => return Err(Error::BadResponse("Missing services list".to_string())),
             Some(friend_string) if friend_string.is_empty() => return Ok(friends),
             Some(friend_string) => friend_string,
@@ -223,7 +231,7 @@ impl Node {
 
         let (_, hosts) = PeersParser::new(friend_string).parse()?;
         for mut alias in hosts {
-            if let Some(ref ip_map) = self.client_policy.ip_map {
+            if let Some(ref ip_map) = client_policy.ip_map {
                 if let Some(mapped) = ip_map.get(&alias.name) {
                     alias.name.clone_from(mapped);
                 }
@@ -382,12 +390,23 @@ impl Node {
         self.rebalance_generation.load(Ordering::Relaxed)
     }
 
+    // Makes the next tend request the partition map of the node again
+    pub(crate) fn reset_partition_generation(&self) {
+        self.partition_generation.store(-1, Ordering::Relaxed);
+    }
+
+    // Makes the next tend request the racks of the node again
+    pub(crate) fn reset_rebalance_generation(&self) {
+        self.rebalance_generation.store(-1, Ordering::Relaxed);
+    }
+
     pub(crate) async fn send_user_agent_id(&self) {
         if !self.version().supports_app_id() {
             return;
         }
 
-        let app_id = self.client_policy().application_id();
+        let client_policy = self.client_policy();
+        let app_id = client_policy.application_id();
 
         // Source user-agent payload
         // Format: "1,rust-<version>,<application-id>"
@@ -396,7 +415,7 @@ impl Node {
         let user_agent_command = format!("user-agent-set:value={user_agent_id}");
 
         let policy = AdminPolicy {
-            timeout: self.client_policy().timeout,
+            timeout: client_policy.timeout,
         };
         let _ = self.info(&policy, &[&user_agent_command]).await;
     }
@@ -409,7 +428,9 @@ impl Node {
 
             let client_policy = self.client_policy();
             if client_policy.min_conns_per_node > 0 {
-                let to_fill = client_policy.min_conns_per_node - self.connection_pool.num_conns();
+                let to_fill = client_policy
+                    .min_conns_per_node
+                    .saturating_sub(self.connection_pool.num_conns());
                 for _ in 0..to_fill {
                     self.connection_pool.make_conn(count).await?;
                     count += 1;
@@ -0,0 +1,258 @@
+// Copyright 2015-2018 Aerospike, Inc.
+//
+// Portions may be licensed to Aerospike, Inc. under one or more contributor
+// license agreements.
+//
+// Licensed under the Apache License, Version 2.0 (the "License"); you may not
+// use this file except in compliance with the License. You may obtain a copy of
+// the License at http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS, WITHOUT
+// WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied. See the
+// License for the specific language governing permissions and limitations under
+// the License.
+
+use std::collections::HashMap;
+
+use crate::cluster::node;
+
+use super::PartitionForNamespace;
+
+/// Snapshot of the client's partition routing table, as returned by
+/// [`Client::partition_map`](crate::Client::partition_map).
+///
+/// The snapshot is not updated by later cluster tends; request a new one to observe changes,
+/// e.g. while partitions migrate.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PartitionMap {
+    /// Routing of each namespace, keyed by namespace name.
+    pub namespaces: HashMap<String, NamespacePartitions>,
+
+    /// Partition generation of each active node, keyed by node name. The generation is
+    /// incremented by the server every time the node's partition ownership changes.
+    pub generations: HashMap<String, isize>,
+}
+
+/// Routing of the partitions of a single namespace.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct NamespacePartitions {
+    /// Number of copies of each partition, master included, reported by the cluster.
+    pub replication_factor: usize,
+
+    /// Routing of each partition, indexed by partition id.
+    pub partitions: Vec<PartitionRouting>,
+}
+
+/// Routing of a single partition.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PartitionRouting {
+    /// Partition id, between 0 and 4095.
+    pub partition_id: usize,
+
+    /// Highest regime the partition's replicas were reported with. The regime is incremented
+    /// by the server with every cluster change in strong consistency namespaces, and is 0 in
+    /// namespaces without strong consistency.
+    pub regime: u32,
+
+    /// Names of the nodes holding each replica of the partition, master first. `None` where
+    /// no node is known to hold the replica.
+    pub replicas: Vec<Option<String>>,
+}
+
+impl PartitionRouting {
+    /// Returns the name of the node holding the master replica, if any.
+    pub fn master(&self) -> Option<&str> {
+        self.replicas.first().and_then(Option::as_deref)
+    }
+
+    /// Returns the number of replicas, master included, that are held by a node.
+    pub fn replica_count(&self) -> usize {
+        self.replicas.iter().flatten().count()
+    }
+}
+
+/// Problem found in the routing of a partition by [`PartitionMap::issues`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PartitionProblem {
+    /// No node is known to hold the master replica. Commands on the partition's keys fail
+    /// until a node takes ownership.
+    NoMaster,
+
+    /// The number of replicas held by nodes differs from the namespace's replication factor.
+    ReplicaCount {
+        /// The namespace's replication factor.
+        expected: usize,
+        /// The number of replicas held by nodes.
+        actual: usize,
+    },
+}
+
+/// Partition with a routing problem, as reported by [`PartitionMap::issues`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PartitionIssue {
+    /// Namespace of the partition.
+    pub namespace: String,
+    /// Partition id.
+    pub partition_id: usize,
+    /// Problem found in the partition's routing.
+    pub problem: PartitionProblem,
+}
+
+impl PartitionMap {
+    pub(crate) fn new<N>(
+        table: &HashMap<String, PartitionForNamespace<N>>,
+        generations: HashMap<String, isize>,
+        name: impl Fn(&N) -> &str,
+    ) -> Self {
+        let namespaces = table
+            .iter()
+            .map(|(namespace, table_entry)| {
+                let partitions: Vec<_> = (0..node::PARTITIONS)
+                    .map(|partition_id| {
+                        let mut routing = PartitionRouting {
+                            partition_id,
+                            ..PartitionRouting::default()
+                        };
+                        for replica in 0..table_entry.replicas {
+                            let (regime, node) = table_entry
+                                .nodes
+                                .get(replica * node::PARTITIONS + partition_id)
+                                .map_or((0, None), |(regime, node)| (*regime, node.as_ref()));
+                            routing.regime = routing.regime.max(regime);
+                            routing
+                                .replicas
+                                .push(node.map(|node| name(node).to_string()));
+                        }
+                        routing
+                    })
+                    .collect();
+                let namespace_partitions = NamespacePartitions {
+                    replication_factor: table_entry.replicas,
+                    partitions,
+                };
+                (namespace.clone(), namespace_partitions)
+            })
+            .collect();
+
+        PartitionMap {
+            namespaces,
+            generations,
+        }
+    }
+
+    /// Returns the routing of a partition, if the namespace is known.
+    pub fn partition(&self, namespace: &str, partition_id: usize) -> Option<&PartitionRouting> {
+        self.namespaces
+            .get(namespace)
+            .and_then(|partitions| partitions.partitions.get(partition_id))
+    }
+
+    /// Returns the partitions that have no master, or whose number of replicas held by nodes
+    /// differs from the namespace's replication factor, ordered by namespace and partition id.
+    ///
+    /// Issues are expected while partitions migrate, and should clear once the cluster is
+    /// stable again.
+    pub fn issues(&self) -> Vec<PartitionIssue> {
+        let mut namespaces: Vec<_> = self.namespaces.iter().collect();
+        namespaces.sort_by_key(|(namespace, _)| *namespace);
+
+        let mut issues = vec![];
+        for (namespace, partitions) in namespaces {
+            for routing in &partitions.partitions {
+                let mut issue = |problem| {
+                    issues.push(PartitionIssue {
+                        namespace: namespace.clone(),
+                        partition_id: routing.partition_id,
+                        problem,
+                    });
+                };
+
+                if routing.master().is_none() {
+                    issue(PartitionProblem::NoMaster);
+                }
+                let actual = routing.replica_count();
+                if actual != partitions.replication_factor {
+                    issue(PartitionProblem::ReplicaCount {
+                        expected: partitions.replication_factor,
+                        actual,
+                    });
+                }
+            }
+        }
+        issues
+    }
+
+    /// Returns true if every partition of every namespace has a master and the expected number
+    /// of replicas.
+    pub fn is_healthy(&self) -> bool {
+        self.issues().is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use base64::{engine::general_purpose::STANDARD as BASE64, Engine as _};
+
+    use super::{PartitionIssue, PartitionMap, PartitionProblem};
+    use crate::cluster::node::PARTITIONS;
+    use crate::cluster::partition_tokenizer::PartitionTokenizer;
+
+    // Encodes the bitmap of partitions a node holds for one replica.
+    fn bitmap(held: impl Fn(usize) -> bool) -> String {
+        let mut bytes = vec![0u8; PARTITIONS / 8];
+        for partition_id in (0..PARTITIONS).filter(|&id| held(id)) {
+            bytes[partition_id >> 3] |= 0x80 >> (partition_id & 7);
+        }
+        BASE64.encode(bytes)
+    }
+
+    #[test]
+    fn snapshot_and_issues() {
+        let mut table = HashMap::new();
+        // node A is master of every partition except 7, node B holds the prole of every
+        // partition except 9
+        let a = format!("test:3,2,{},{}", bitmap(|id| id != 7), bitmap(|_| false));
+        let b = format!("test:4,2,{},{}", bitmap(|_| false), bitmap(|id| id != 9));
+        for (node, replicas) in [("A".to_string(), a), ("B".to_string(), b)] {
+            PartitionTokenizer::from_replicas(&replicas)
+                .update_partition(&mut table, &node)
+                .unwrap();
+        }
+
+        let generations = HashMap::from([("A".to_string(), 10), ("B".to_string(), 12)]);
+        let map = PartitionMap::new(&table, generations, String::as_str);
+        assert_eq!(map.generations["B"], 12);
+
+        let routing = map.partition("test", 0).unwrap();
+        assert_eq!(routing.master(), Some("A"));
+        assert_eq!(routing.replicas, vec![Some("A".into()), Some("B".into())]);
+        assert_eq!(routing.regime, 4);
+        assert_eq!(map.partition("test", 7).unwrap().master(), None);
+        assert_eq!(map.namespaces["test"].replication_factor, 2);
+        assert_eq!(map.namespaces["test"].partitions.len(), PARTITIONS);
+        assert!(map.partition("bar", 0).is_none());
+
+        let issue = |partition_id, problem| PartitionIssue {
+            namespace: "test".into(),
+            partition_id,
+            problem,
+        };
+        let short = PartitionProblem::ReplicaCount {
+            expected: 2,
+            actual: 1,
+        };
+        assert_eq!(
+            map.issues(),
+            vec![
+                issue(7, PartitionProblem::NoMaster),
+                issue(7, short.clone()),
+                issue(9, short),
+            ]
+        );
+        assert!(!map.is_healthy());
+        assert!(PartitionMap::default().is_healthy());
+    }
+}
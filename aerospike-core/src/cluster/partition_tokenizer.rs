@@ -12,6 +12,7 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use std::collections::HashMap;
 use std::str;
 use std::sync::Arc;
 use std::vec::Vec;
@@ -25,7 +26,7 @@ use crate::errors::{Error, Result};
 use crate::net::Connection;
 use crate::AdminPolicy;
 
-use super::PartitionTable;
+use super::PartitionForNamespace;
 
 // Validates a Database server node
 #[derive(Debug, Clone)]
@@ -42,9 +43,7 @@ impl PartitionTokenizer {
         let command = "replicas";
         let info_map = Message::info(policy, conn, &[command, node::PARTITION_GENERATION]).await?;
         if let Some(buf) = info_map.get(command) {
-            return Ok(PartitionTokenizer {
-                buffer: buf.as_bytes().to_owned(),
-            });
+            return Ok(PartitionTokenizer::from_replicas(buf));
         }
 
         // We re-update the partitions right now (in case its changed since it was last polled)
@@ -53,7 +52,18 @@ impl PartitionTokenizer {
         Err(Error::BadResponse("Missing replicas info".to_string()))
     }
 
-    pub fn update_partition(&self, nmap: &mut PartitionTable, node: &Arc<Node>) -> Result<()> {
+    // Creates a tokenizer from the response to a `replicas` info command.
+    pub(crate) fn from_replicas(replicas: &str) -> Self {
+        PartitionTokenizer {
+            buffer: replicas.as_bytes().to_owned(),
+        }
+    }
+
+    pub fn update_partition<N: Clone>(
+        &self,
+        nmap: &mut HashMap<String, PartitionForNamespace<N>>,
+        node: &N,
+    ) -> Result<()> {
         // <ns>:<base64-encoded partition map>;<ns>:<base64-encoded partition map>; ...
         let part_str = str::from_utf8(&self.buffer)?;
         for part in part_str.trim_end().split(';') {
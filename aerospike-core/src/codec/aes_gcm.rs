@@ -0,0 +1,249 @@
+// Copyright 2015-2020 Aerospike, Inc.
+//
+// Portions may be licensed to Aerospike, Inc. under one or more contributor
+// license agreements.
+//
+// Licensed under the Apache License, Version 2.0 (the "License"); you may not
+// use this file except in compliance with the License. You may obtain a copy of
+// the License at http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS, WITHOUT
+// WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied. See the
+// License for the specific language governing permissions and limitations under
+// the License.
+
+use std::convert::{TryFrom, TryInto};
+use std::fmt;
+use std::sync::Arc;
+
+use ::aes_gcm::aead::{Aead, KeyInit, Payload};
+use ::aes_gcm::{Aes256Gcm, Nonce};
+
+use crate::codec::BinCodec;
+use crate::errors::{Error, Result};
+use crate::Key;
+
+const VERSION: u8 = 1;
+const KEY_SIZE: usize = 32;
+const NONCE_SIZE: usize = 12;
+const TAG_SIZE: usize = 16;
+const WRAPPED_KEY_SIZE: usize = KEY_SIZE + TAG_SIZE;
+
+type KeyProvider = dyn Fn(&str) -> Result<[u8; KEY_SIZE]> + Send + Sync;
+
+/// Codec that encrypts values with AES-256-GCM, using envelope encryption. Requires the `aes-gcm`
+/// feature.
+///
+/// Every value is encrypted with its own random data key, which is stored with the value,
+/// encrypted with a key encryption key. The key encryption key is returned by the key provider
+/// callback for the key id of the codec, e.g. from a key management service. The key id is stored
+/// with every value, so that values can still be decrypted after the codec has moved on to a new
+/// key id, as long as the provider returns the key for the old id. The namespace, set name and
+/// digest of the record's key and the bin name are authenticated with the value, so that encrypted
+/// values cannot be copied to other bins or records.
+///
+/// The key provider is called for every value that is encrypted or decrypted; it should cache the
+/// keys if they are expensive to fetch.
+///
+/// ```
+/// use std::sync::Arc;
+/// use aerospike::codec::AesGcmCodec;
+/// use aerospike::ClientPolicy;
+///
+/// let codec = AesGcmCodec::new("key-2024", |key_id| match key_id {
+///     "key-2024" => Ok([7; 32]),
+///     _ => Err(aerospike::Error::ClientError(format!("Unknown key {key_id}"))),
+/// });
+/// let mut policy = ClientPolicy::default();
+/// policy.codecs.add_bin("ssn", Arc::new(codec));
+/// ```
+#[derive(Clone)]
+pub struct AesGcmCodec {
+    key_id: String,
+    key_provider: Arc<KeyProvider>,
+}
+
+impl AesGcmCodec {
+    /// Creates a codec that encrypts values with the key returned by the provider for the given
+    /// key id. The key id must not be longer than 255 bytes.
+    pub fn new<F>(key_id: impl Into<String>, key_provider: F) -> Self
+    where
+        F: Fn(&str) -> Result<[u8; KEY_SIZE]> + Send + Sync + 'static,
+    {
+        AesGcmCodec {
+            key_id: key_id.into(),
+            key_provider: Arc::new(key_provider),
+        }
+    }
+
+    fn cipher(&self, key_id: &str) -> Result<Aes256Gcm> {
+        let key = (self.key_provider)(key_id)
+            .map_err(|err| err.chain_error(&format!("Failed to get key {key_id}")))?;
+        Ok(Aes256Gcm::new(&key.into()))
+    }
+}
+
+impl fmt::Debug for AesGcmCodec {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("AesGcmCodec")
+            .field("key_id", &self.key_id)
+            .finish_non_exhaustive()
+    }
+}
+
+// Encrypted values are stored as:
+// [version][key id length][key id][key nonce][wrapped data key][data nonce][ciphertext]
+impl BinCodec for AesGcmCodec {
+    fn encode(&self, key: &Key, bin: &str, data: &[u8]) -> Result<Vec<u8>> {
+        let key_id_len = u8::try_from(self.key_id.len())
+            .map_err(|_| Error::ClientError("Key id is longer than 255 bytes".to_string()))?;
+        let data_key: [u8; KEY_SIZE] = rand::random();
+        let key_nonce: [u8; NONCE_SIZE] = rand::random();
+        let data_nonce: [u8; NONCE_SIZE] = rand::random();
+
+        let wrapped_key = encrypt(
+            &self.cipher(&self.key_id)?,
+            &key_nonce,
+            &data_key,
+            self.key_id.as_bytes(),
+        )?;
+        let ciphertext = encrypt(
+            &Aes256Gcm::new(&data_key.into()),
+            &data_nonce,
+            data,
+            &value_aad(key, bin),
+        )?;
+
+        let mut out = Vec::with_capacity(
+            2 + self.key_id.len() + 2 * NONCE_SIZE + WRAPPED_KEY_SIZE + ciphertext.len(),
+        );
+        out.extend_from_slice(&[VERSION, key_id_len]);
+        out.extend_from_slice(self.key_id.as_bytes());
+        out.extend_from_slice(&key_nonce);
+        out.extend_from_slice(&wrapped_key);
+        out.extend_from_slice(&data_nonce);
+        out.extend_from_slice(&ciphertext);
+        Ok(out)
+    }
+
+    fn decode(&self, key: &Key, bin: &str, data: &[u8]) -> Result<Vec<u8>> {
+        let invalid = || Error::ClientError("Invalid encrypted value".to_string());
+        let [VERSION, key_id_len, rest @ ..] = data else {
+            return Err(invalid());
+        };
+        let key_id_len = usize::from(*key_id_len);
+        if rest.len() < key_id_len + 2 * NONCE_SIZE + WRAPPED_KEY_SIZE {
+            return Err(invalid());
+        }
+        let (key_id, rest) = rest.split_at(key_id_len);
+        let (key_nonce, rest) = rest.split_at(NONCE_SIZE);
+        let (wrapped_key, rest) = rest.split_at(WRAPPED_KEY_SIZE);
+        let (data_nonce, ciphertext) = rest.split_at(NONCE_SIZE);
+        let key_id = std::str::from_utf8(key_id).map_err(|_| invalid())?;
+
+        let data_key = decrypt(
+            &self.cipher(key_id)?,
+            key_nonce,
+            wrapped_key,
+            key_id.as_bytes(),
+        )?;
+        let data_key: [u8; KEY_SIZE] = data_key.try_into().map_err(|_| invalid())?;
+        decrypt(
+            &Aes256Gcm::new(&data_key.into()),
+            data_nonce,
+            ciphertext,
+            &value_aad(key, bin),
+        )
+    }
+}
+
+// Additional authenticated data of a value: the length prefixed namespace, set name and bin
+// name, and the digest of the record's key.
+fn value_aad(key: &Key, bin: &str) -> Vec<u8> {
+    let parts = [
+        key.namespace.as_bytes(),
+        key.set_name.as_bytes(),
+        bin.as_bytes(),
+    ];
+    let mut aad = Vec::with_capacity(parts.iter().map(|part| 4 + part.len()).sum::<usize>() + 20);
+    for part in parts {
+        aad.extend_from_slice(&(part.len() as u32).to_be_bytes());
+        aad.extend_from_slice(part);
+    }
+    aad.extend_from_slice(&key.digest);
+    aad
+}
+
+fn encrypt(cipher: &Aes256Gcm, nonce: &[u8], msg: &[u8], aad: &[u8]) -> Result<Vec<u8>> {
+    cipher
+        .encrypt(Nonce::from_slice(nonce), Payload { msg, aad })
+        .map_err(|_| Error::ClientError("Encryption failed".to_string()))
+}
+
+fn decrypt(cipher: &Aes256Gcm, nonce: &[u8], msg: &[u8], aad: &[u8]) -> Result<Vec<u8>> {
+    cipher
+        .decrypt(Nonce::from_slice(nonce), Payload { msg, aad })
+        .map_err(|_| Error::ClientError("Decryption failed".to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::AesGcmCodec;
+    use crate::codec::BinCodec;
+    use crate::errors::Error;
+
+    fn with_key(key_id: &str) -> AesGcmCodec {
+        AesGcmCodec::new(key_id, |key_id| match key_id {
+            "k1" => Ok([1; 32]),
+            "k2" => Ok([2; 32]),
+            _ => Err(Error::ClientError(format!("Unknown key {key_id}"))),
+        })
+    }
+
+    #[test]
+    fn round_trip() {
+        let codec = with_key("k1");
+        let key = as_key!("test", "users", 1);
+        let encrypted = codec.encode(&key, "ssn", b"123-45-6789").unwrap();
+        assert_ne!(
+            codec.encode(&key, "ssn", b"123-45-6789").unwrap(),
+            encrypted
+        );
+        assert_eq!(
+            codec.decode(&key, "ssn", &encrypted).unwrap(),
+            b"123-45-6789".to_vec()
+        );
+
+        // values encrypted with an older key can still be decrypted
+        assert_eq!(
+            with_key("k2").decode(&key, "ssn", &encrypted).unwrap(),
+            b"123-45-6789".to_vec()
+        );
+    }
+
+    #[test]
+    fn tampering_fails() {
+        let codec = with_key("k1");
+        let key = as_key!("test", "users", 1);
+        let mut encrypted = codec.encode(&key, "ssn", b"123-45-6789").unwrap();
+        assert!(codec.decode(&key, "name", &encrypted).is_err());
+        assert!(codec.decode(&key, "ssn", &encrypted[..20]).is_err());
+
+        // values copied to other records, sets or namespaces cannot be decrypted
+        for other in [
+            as_key!("test", "users", 2),
+            as_key!("test", "admins", 1),
+            as_key!("prod", "users", 1),
+        ] {
+            assert!(codec.decode(&other, "ssn", &encrypted).is_err());
+        }
+
+        let last = encrypted.len() - 1;
+        encrypted[last] ^= 1;
+        assert!(codec.decode(&key, "ssn", &encrypted).is_err());
+
+        let encrypted = with_key("k3").encode(&key, "ssn", b"x");
+        assert!(encrypted.is_err());
+    }
+}
@@ -0,0 +1,565 @@
+// Copyright 2015-2020 Aerospike, Inc.
+//
+// Portions may be licensed to Aerospike, Inc. under one or more contributor
+// license agreements.
+//
+// Licensed under the Apache License, Version 2.0 (the "License"); you may not
+// use this file except in compliance with the License. You may obtain a copy of
+// the License at http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS, WITHOUT
+// WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied. See the
+// License for the specific language governing permissions and limitations under
+// the License.
+
+//! Client-side bin codecs, which transform string and blob values before they are written and
+//! reverse the transformation when they are read.
+//!
+//! Codecs are registered in [`ClientPolicy::codecs`](crate::ClientPolicy::codecs) for a bin name,
+//! a set or a whole namespace. Values of string and blob bins that have a codec are encoded by
+//! `put` and by the `put` operations of `operate` and batch writes, and stored as blobs. They are
+//! decoded again, to their original type, by `get`, `operate`, batch reads and queries, including
+//! the [`FromBins`](crate::FromBins) variants of these commands.
+//! [`Client::get_with`](crate::Client::get_with) returns the stored data as is. Other commands
+//! and operations on bins that have a codec, like `append`, `add` or list and map operations,
+//! cannot work on encoded values and fail with [`Error::InvalidArgument`].
+//!
+//! Encoded values start with a header that holds a magic number, the length and a checksum of
+//! the encoded data, so that bins written before the codec was registered are still read as they
+//! are.
+//!
+//! ```
+//! use std::sync::Arc;
+//! use aerospike::codec::BinCodec;
+//! use aerospike::{ClientPolicy, Key, Result};
+//!
+//! /// Reverses the bytes of the value.
+//! #[derive(Debug)]
+//! struct Reverse;
+//!
+//! impl BinCodec for Reverse {
+//!     fn encode(&self, _key: &Key, _bin: &str, data: &[u8]) -> Result<Vec<u8>> {
+//!         Ok(data.iter().rev().copied().collect())
+//!     }
+//!
+//!     fn decode(&self, _key: &Key, _bin: &str, data: &[u8]) -> Result<Vec<u8>> {
+//!         Ok(data.iter().rev().copied().collect())
+//!     }
+//! }
+//!
+//! let mut policy = ClientPolicy::default();
+//! policy.codecs.add_bin("ssn", Arc::new(Reverse));
+//! policy.codecs.add_set("test", "users", Arc::new(Reverse));
+//! ```
+
+#[cfg(feature = "aes-gcm")]
+mod aes_gcm;
+#[cfg(feature = "zstd")]
+mod zstd;
+
+use std::borrow::Cow;
+use std::convert::TryFrom;
+use std::fmt;
+use std::sync::Arc;
+
+#[cfg(feature = "aes-gcm")]
+pub use self::aes_gcm::AesGcmCodec;
+#[cfg(feature = "zstd")]
+pub use self::zstd::ZstdCodec;
+
+use crate::batch::BatchOperation;
+use crate::commands::ParticleType;
+use crate::errors::{Error, Result};
+use crate::operations::{Operation, OperationBin, OperationData, OperationType};
+use crate::{Bin, BinView, FromBins, Key, Record, Value};
+
+// Header of encoded values: a magic number, the format version, the particle type of the
+// original value, and the length and CRC-32 of the encoded data, both big endian.
+const MAGIC: [u8; 4] = [0xae, b'B', b'C', b'E'];
+const VERSION: u8 = 1;
+const HEADER_SIZE: usize = 14;
+
+/// Transformation of the bytes of string and blob values, e.g. compression or encryption.
+pub trait BinCodec: fmt::Debug + Send + Sync {
+    /// Encodes the value of the given bin of the record with the given key before it is written.
+    fn encode(&self, key: &Key, bin: &str, data: &[u8]) -> Result<Vec<u8>>;
+
+    /// Decodes the value of the given bin of the record with the given key after it has been
+    /// read. Only the namespace, set name and digest of the key are set.
+    fn decode(&self, key: &Key, bin: &str, data: &[u8]) -> Result<Vec<u8>>;
+}
+
+// CRC-32 (IEEE) of the data.
+fn crc32(data: &[u8]) -> u32 {
+    let mut crc = !0u32;
+    for &byte in data {
+        crc ^= u32::from(byte);
+        for _ in 0..8 {
+            crc = (crc >> 1) ^ (0xedb8_8320 & (crc & 1).wrapping_neg());
+        }
+    }
+    !crc
+}
+
+// Splits an encoded value into the particle type of the original value and the encoded data.
+// Returns `None` for values without a valid header, i.e. values that were not encoded.
+fn split_envelope(data: &[u8]) -> Option<(u8, &[u8])> {
+    if data.len() < HEADER_SIZE || data[..4] != MAGIC || data[4] != VERSION {
+        return None;
+    }
+    let particle_type = data[5];
+    if particle_type != ParticleType::STRING as u8 && particle_type != ParticleType::BLOB as u8 {
+        return None;
+    }
+    let len = u32::from_be_bytes([data[6], data[7], data[8], data[9]]);
+    let checksum = u32::from_be_bytes([data[10], data[11], data[12], data[13]]);
+    let encoded = &data[HEADER_SIZE..];
+    if encoded.len() as u64 != u64::from(len) || crc32(encoded) != checksum {
+        return None;
+    }
+    Some((particle_type, encoded))
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Scope {
+    Bin(String),
+    Set(String, String),
+    Namespace(String),
+}
+
+/// Codecs registered for bins, sets and namespaces. See the [module documentation](self).
+///
+/// A codec registered for a bin name applies to bins of that name in all namespaces and sets, and
+/// takes precedence over a codec of the set, which takes precedence over a codec of the namespace.
+#[derive(Debug, Clone, Default)]
+pub struct BinCodecs {
+    codecs: Vec<(Scope, Arc<dyn BinCodec>)>,
+}
+
+impl BinCodecs {
+    /// Registers a codec for all bins with the given name.
+    pub fn add_bin(&mut self, bin_name: impl Into<String>, codec: Arc<dyn BinCodec>) -> &mut Self {
+        self.add(Scope::Bin(bin_name.into()), codec)
+    }
+
+    /// Registers a codec for all string and blob bins in the given set.
+    pub fn add_set(
+        &mut self,
+        namespace: impl Into<String>,
+        set_name: impl Into<String>,
+        codec: Arc<dyn BinCodec>,
+    ) -> &mut Self {
+        self.add(Scope::Set(namespace.into(), set_name.into()), codec)
+    }
+
+    /// Registers a codec for all string and blob bins in the given namespace.
+    pub fn add_namespace(
+        &mut self,
+        namespace: impl Into<String>,
+        codec: Arc<dyn BinCodec>,
+    ) -> &mut Self {
+        self.add(Scope::Namespace(namespace.into()), codec)
+    }
+
+    /// Returns whether no codecs are registered.
+    pub fn is_empty(&self) -> bool {
+        self.codecs.is_empty()
+    }
+
+    fn add(&mut self, scope: Scope, codec: Arc<dyn BinCodec>) -> &mut Self {
+        self.codecs.retain(|(registered, _)| *registered != scope);
+        self.codecs.push((scope, codec));
+        self
+    }
+
+    /// Returns the codec for the given bin, if any.
+    pub fn codec_for(&self, namespace: &str, set_name: &str, bin: &str) -> Option<&dyn BinCodec> {
+        let mut found: Option<(u8, &Arc<dyn BinCodec>)> = None;
+        for (scope, codec) in &self.codecs {
+            let rank = match scope {
+                Scope::Bin(name) if name == bin => 3,
+                Scope::Set(ns, set) if ns == namespace && set == set_name => 2,
+                Scope::Namespace(ns) if ns == namespace => 1,
+                _ => continue,
+            };
+            if found.is_none_or(|(best, _)| rank > best) {
+                found = Some((rank, codec));
+            }
+        }
+        found.map(|(_, codec)| &**codec)
+    }
+
+    /// Encodes a value of the given bin, if it is a string or blob and the bin has a codec.
+    pub fn encode_value(&self, key: &Key, bin: &str, value: &Value) -> Result<Option<Value>> {
+        let (particle_type, data) = match value {
+            Value::String(val) => (ParticleType::STRING, val.as_bytes()),
+            Value::Blob(val) => (ParticleType::BLOB, &val[..]),
+            _ => return Ok(None),
+        };
+        let Some(codec) = self.codec_for(&key.namespace, &key.set_name, bin) else {
+            return Ok(None);
+        };
+
+        let encoded = codec
+            .encode(key, bin, data)
+            .map_err(|err| err.chain_error(&format!("Failed to encode bin {bin}")))?;
+        let len = u32::try_from(encoded.len())
+            .map_err(|_| Error::ClientError(format!("Encoded bin {bin} is too large")))?;
+        let mut envelope = Vec::with_capacity(HEADER_SIZE + encoded.len());
+        envelope.extend_from_slice(&MAGIC);
+        envelope.extend_from_slice(&[VERSION, particle_type as u8]);
+        envelope.extend_from_slice(&len.to_be_bytes());
+        envelope.extend_from_slice(&crc32(&encoded).to_be_bytes());
+        envelope.extend_from_slice(&encoded);
+        Ok(Some(Value::Blob(envelope)))
+    }
+
+    /// Decodes a value of the given bin, if the bin has a codec and the value was encoded.
+    /// Returns the particle type and data of the original value.
+    pub(crate) fn decode_data(
+        &self,
+        key: &Key,
+        bin: &str,
+        particle_type: u8,
+        data: &[u8],
+    ) -> Result<Option<(u8, Vec<u8>)>> {
+        if particle_type != ParticleType::BLOB as u8 {
+            return Ok(None);
+        }
+        let Some((original_type, encoded)) = split_envelope(data) else {
+            return Ok(None);
+        };
+        let Some(codec) = self.codec_for(&key.namespace, &key.set_name, bin) else {
+            return Ok(None);
+        };
+
+        let decoded = codec
+            .decode(key, bin, encoded)
+            .map_err(|err| err.chain_error(&format!("Failed to decode bin {bin}")))?;
+        Ok(Some((original_type, decoded)))
+    }
+
+    /// Decodes a value of the given bin in place, if the bin has a codec and the value was
+    /// encoded.
+    pub fn decode_value(&self, key: &Key, bin: &str, value: &mut Value) -> Result<()> {
+        let Value::Blob(data) = value else {
+            return Ok(());
+        };
+        let decoded = self.decode_data(key, bin, ParticleType::BLOB as u8, data)?;
+        *value = match decoded {
+            None => return Ok(()),
+            Some((particle_type, data)) if particle_type == ParticleType::STRING as u8 => {
+                Value::String(String::from_utf8(data).map_err(|err| {
+                    Error::ClientError(format!("Decoded bin {bin} is not valid UTF-8: {err}"))
+                })?)
+            }
+            Some((_, data)) => Value::Blob(data),
+        };
+        Ok(())
+    }
+
+    // Fails if the bin has a codec, for commands and operations that would read or modify its
+    // stored value without the codec.
+    fn check_unencoded(&self, key: &Key, bin: &str) -> Result<()> {
+        if self.codec_for(&key.namespace, &key.set_name, bin).is_some() {
+            return Err(Error::InvalidArgument(format!(
+                "Bin {bin} has a codec and only supports put and get operations"
+            )));
+        }
+        Ok(())
+    }
+
+    // Encodes the bins of a write command. Commands other than `put` fail for bins that have a
+    // codec.
+    pub(crate) fn encode_bins<'b>(
+        &self,
+        key: &Key,
+        bins: &'b [Bin],
+        operation: OperationType,
+    ) -> Result<Cow<'b, [Bin]>> {
+        if self.is_empty() {
+            return Ok(Cow::Borrowed(bins));
+        }
+        if !matches!(operation, OperationType::Write) {
+            for bin in bins {
+                self.check_unencoded(key, &bin.name)?;
+            }
+            return Ok(Cow::Borrowed(bins));
+        }
+        let mut encoded: Option<Vec<Bin>> = None;
+        for (i, bin) in bins.iter().enumerate() {
+            if let Some(value) = self.encode_value(key, &bin.name, &bin.value)? {
+                encoded.get_or_insert_with(|| bins.to_vec())[i].value = value;
+            }
+        }
+        Ok(encoded.map_or(Cow::Borrowed(bins), Cow::Owned))
+    }
+
+    pub(crate) fn encode_operations<'o>(
+        &self,
+        key: &Key,
+        ops: &'o [Operation],
+    ) -> Result<Cow<'o, [Operation]>> {
+        if self.is_empty() {
+            return Ok(Cow::Borrowed(ops));
+        }
+        let mut encoded: Option<Vec<Operation>> = None;
+        for (i, op) in ops.iter().enumerate() {
+            let OperationBin::Name(bin) = &op.bin else {
+                continue;
+            };
+            match (op.op, &op.data) {
+                (OperationType::Write, OperationData::Value(value)) => {
+                    if let Some(value) = self.encode_value(key, bin, value)? {
+                        encoded.get_or_insert_with(|| ops.to_vec())[i].data =
+                            OperationData::Value(value);
+                    }
+                }
+                (OperationType::Read, _) => (),
+                _ => self.check_unencoded(key, bin)?,
+            }
+        }
+        Ok(encoded.map_or(Cow::Borrowed(ops), Cow::Owned))
+    }
+
+    pub(crate) fn encode_batch_operation(&self, batch_op: &mut BatchOperation) -> Result<()> {
+        if let BatchOperation::Write { br, ops, .. } = batch_op {
+            if let Cow::Owned(encoded) = self.encode_operations(&br.key, ops)? {
+                *ops = encoded;
+            }
+        }
+        Ok(())
+    }
+
+    // Decodes the bins of a record and, if given, the results of its operations.
+    pub(crate) fn decode_record(
+        &self,
+        key: &Key,
+        record: &mut Record,
+        op_results: Option<&mut Vec<(String, Value)>>,
+    ) -> Result<()> {
+        if self.is_empty() {
+            return Ok(());
+        }
+        for (bin, value) in &mut record.bins {
+            self.decode_value(key, bin, value)?;
+        }
+        for (bin, value) in op_results.into_iter().flatten() {
+            self.decode_value(key, bin, value)?;
+        }
+        Ok(())
+    }
+
+    // Decodes a bin into the builder of a `FromBins` type, decoding its value first if needed.
+    pub(crate) fn decode_bin<T: FromBins>(
+        &self,
+        key: &Key,
+        builder: &mut T::Builder,
+        bin: &BinView<'_>,
+    ) -> Result<()> {
+        if self.is_empty() {
+            return T::decode_bin(builder, bin);
+        }
+        let decoded = self.decode_data(key, bin.name(), bin.particle_type() as u8, bin.raw())?;
+        match decoded {
+            Some((particle_type, data)) => {
+                T::decode_bin(builder, &BinView::new(bin.name(), particle_type, &data))
+            }
+            None => T::decode_bin(builder, bin),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use super::{crc32, BinCodec, BinCodecs, HEADER_SIZE};
+    use crate::errors::{Error, Result};
+    use crate::operations::hll::{self, HLLPolicy};
+    use crate::operations::{
+        self, lists, maps, ListPolicy, MapPolicy, OperationData, OperationType,
+    };
+    use crate::{BatchOperation, BatchWritePolicy, Key, Value};
+
+    #[derive(Debug)]
+    struct Xor(u8);
+
+    impl BinCodec for Xor {
+        fn encode(&self, _key: &Key, _bin: &str, data: &[u8]) -> Result<Vec<u8>> {
+            Ok(data.iter().map(|b| b ^ self.0).collect())
+        }
+
+        fn decode(&self, key: &Key, bin: &str, data: &[u8]) -> Result<Vec<u8>> {
+            self.encode(key, bin, data)
+        }
+    }
+
+    fn codecs() -> BinCodecs {
+        let mut codecs = BinCodecs::default();
+        codecs
+            .add_namespace("test", Arc::new(Xor(1)))
+            .add_set("test", "users", Arc::new(Xor(2)))
+            .add_bin("ssn", Arc::new(Xor(3)));
+        codecs
+    }
+
+    fn users_key() -> Key {
+        as_key!("test", "users", 1)
+    }
+
+    #[test]
+    fn precedence() {
+        let codecs = codecs();
+        let key = users_key();
+        let xor = |ns, set, bin| {
+            codecs
+                .codec_for(ns, set, bin)
+                .map(|codec| codec.encode(&key, bin, &[0]).unwrap()[0])
+        };
+        assert_eq!(xor("test", "other", "name"), Some(1));
+        assert_eq!(xor("test", "users", "name"), Some(2));
+        assert_eq!(xor("test", "users", "ssn"), Some(3));
+        assert_eq!(xor("bar", "users", "ssn"), Some(3));
+        assert_eq!(xor("bar", "users", "name"), None);
+    }
+
+    #[test]
+    fn round_trip() {
+        let codecs = codecs();
+        let key = users_key();
+        for value in [Value::from("secret"), Value::Blob(vec![1, 2, 3])] {
+            let mut encoded = codecs.encode_value(&key, "name", &value).unwrap().unwrap();
+            assert!(matches!(encoded, Value::Blob(ref data) if data[0] == 0xae));
+            codecs.decode_value(&key, "name", &mut encoded).unwrap();
+            assert_eq!(encoded, value);
+        }
+
+        assert_eq!(
+            codecs.encode_value(&key, "n", &Value::from(1)).unwrap(),
+            None
+        );
+
+        // values written without the codec are read as they are
+        let mut plain = Value::Blob(vec![1, 2, 3]);
+        codecs.decode_value(&key, "name", &mut plain).unwrap();
+        assert_eq!(plain, Value::Blob(vec![1, 2, 3]));
+    }
+
+    #[test]
+    fn plain_blobs_with_header_prefix() {
+        let codecs = codecs();
+        let key = users_key();
+        let Value::Blob(encoded) = codecs
+            .encode_value(&key, "name", &Value::Blob(vec![1, 2, 3]))
+            .unwrap()
+            .unwrap()
+        else {
+            panic!("encoded value is not a blob");
+        };
+
+        // blobs that start like an encoded value, but whose length or checksum do not match,
+        // were not written by a codec
+        let truncated = encoded[..encoded.len() - 1].to_vec();
+        let mut corrupted = encoded;
+        corrupted[HEADER_SIZE] ^= 0xff;
+        let legacy = vec![0xae, 1, 4, 9, 9, 9];
+        for data in [truncated, corrupted, legacy] {
+            let mut value = Value::Blob(data.clone());
+            codecs.decode_value(&key, "name", &mut value).unwrap();
+            assert_eq!(value, Value::Blob(data));
+        }
+    }
+
+    #[test]
+    fn checksum() {
+        assert_eq!(crc32(b"123456789"), 0xcbf4_3926);
+        assert_eq!(crc32(b""), 0);
+    }
+
+    #[test]
+    fn encode_bins_and_operations() {
+        let codecs = codecs();
+        let bins = vec![as_bin!("a", 1), as_bin!("b", "x")];
+        let encoded = codecs
+            .encode_bins(&as_key!("bar", "users", 1), &bins, OperationType::Write)
+            .unwrap();
+        assert_eq!(&encoded[..], &bins[..]);
+        let encoded = codecs
+            .encode_bins(&users_key(), &bins, OperationType::Write)
+            .unwrap();
+        assert_eq!(encoded[0], bins[0]);
+        let mut envelope = vec![0xae, b'B', b'C', b'E', 1, 3, 0, 0, 0, 1];
+        envelope.extend_from_slice(&crc32(&[b'x' ^ 2]).to_be_bytes());
+        envelope.push(b'x' ^ 2);
+        assert_eq!(encoded[1].value, Value::Blob(envelope));
+
+        let ops = vec![
+            operations::put(&as_bin!("b", "x")),
+            operations::get_bin("b"),
+        ];
+        let encoded = codecs.encode_operations(&users_key(), &ops).unwrap();
+        assert!(matches!(
+            encoded[0].data,
+            OperationData::Value(Value::Blob(_))
+        ));
+        assert!(matches!(encoded[1].data, OperationData::None));
+    }
+
+    #[test]
+    fn reject_unencoded_commands() {
+        let codecs = codecs();
+        let bins = vec![as_bin!("b", "x")];
+        for operation in [
+            OperationType::Append,
+            OperationType::Prepend,
+            OperationType::Incr,
+        ] {
+            assert!(matches!(
+                codecs.encode_bins(&users_key(), &bins, operation),
+                Err(Error::InvalidArgument(_))
+            ));
+            let encoded = codecs
+                .encode_bins(&as_key!("bar", "users", 1), &bins, operation)
+                .unwrap();
+            assert_eq!(&encoded[..], &bins[..]);
+        }
+    }
+
+    #[test]
+    fn reject_unencoded_operations() {
+        let codecs = codecs();
+        let bin = as_bin!("b", "x");
+        let rejected = [
+            operations::append(&bin),
+            operations::prepend(&bin),
+            operations::add(&as_bin!("b", 1)),
+            lists::append(&ListPolicy::default(), "b", Value::from(1)),
+            lists::size("b"),
+            maps::put(&MapPolicy::default(), "b", Value::from("k"), Value::from(1)),
+            hll::add(&HLLPolicy::default(), "b", vec![Value::from("x")]),
+        ];
+        for op in rejected {
+            let ops = [operations::put(&bin), op];
+            assert!(matches!(
+                codecs.encode_operations(&users_key(), &ops),
+                Err(Error::InvalidArgument(_))
+            ));
+            let encoded = codecs
+                .encode_operations(&as_key!("bar", "users", 1), &ops)
+                .unwrap();
+            assert!(matches!(encoded, std::borrow::Cow::Borrowed(_)));
+        }
+
+        let mut batch_op = BatchOperation::write(
+            &BatchWritePolicy::default(),
+            users_key(),
+            vec![operations::append(&bin)],
+        );
+        assert!(matches!(
+            codecs.encode_batch_operation(&mut batch_op),
+            Err(Error::InvalidArgument(_))
+        ));
+    }
+}
@@ -0,0 +1,68 @@
+// Copyright 2015-2020 Aerospike, Inc.
+//
+// Portions may be licensed to Aerospike, Inc. under one or more contributor
+// license agreements.
+//
+// Licensed under the Apache License, Version 2.0 (the "License"); you may not
+// use this file except in compliance with the License. You may obtain a copy of
+// the License at http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS, WITHOUT
+// WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied. See the
+// License for the specific language governing permissions and limitations under
+// the License.
+
+use crate::codec::BinCodec;
+use crate::errors::{Error, Result};
+use crate::Key;
+
+/// Codec that compresses values with [Zstandard](https://facebook.github.io/zstd/). Requires the
+/// `zstd` feature.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ZstdCodec {
+    level: i32,
+}
+
+impl ZstdCodec {
+    /// Creates a codec with the given compression level, from 1 (fastest) to 22 (smallest).
+    pub const fn new(level: i32) -> Self {
+        ZstdCodec { level }
+    }
+}
+
+impl Default for ZstdCodec {
+    /// Creates a codec with the default compression level of 3.
+    fn default() -> Self {
+        ZstdCodec::new(3)
+    }
+}
+
+impl BinCodec for ZstdCodec {
+    fn encode(&self, _key: &Key, _bin: &str, data: &[u8]) -> Result<Vec<u8>> {
+        ::zstd::bulk::compress(data, self.level)
+            .map_err(|err| Error::ClientError(format!("Compression failed: {err}")))
+    }
+
+    fn decode(&self, _key: &Key, _bin: &str, data: &[u8]) -> Result<Vec<u8>> {
+        ::zstd::stream::decode_all(data)
+            .map_err(|err| Error::ClientError(format!("Decompression failed: {err}")))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ZstdCodec;
+    use crate::codec::BinCodec;
+
+    #[test]
+    fn round_trip() {
+        let codec = ZstdCodec::default();
+        let key = as_key!("test", "test", 1);
+        let data = "aerospike ".repeat(100).into_bytes();
+        let compressed = codec.encode(&key, "bin", &data).unwrap();
+        assert!(compressed.len() < data.len());
+        assert_eq!(codec.decode(&key, "bin", &compressed).unwrap(), data);
+        assert!(codec.decode(&key, "bin", b"not compressed").is_err());
+    }
+}
@@ -20,6 +20,7 @@ use std::str;
 use pwhash::bcrypt::{self, BcryptSetup, BcryptVariant};
 
 use crate::cluster::Cluster;
+use crate::commands::buffer::Buffer;
 use crate::errors::{Error, Result};
 use crate::net::Connection;
 use crate::net::PooledConnection;
@@ -69,7 +70,7 @@ const CONNECTIONS: u8 = 18;
 const MSG_VERSION: i64 = 2;
 const MSG_TYPE: i64 = 2;
 
-const HEADER_SIZE: usize = 24;
+pub const HEADER_SIZE: usize = 24;
 const HEADER_REMAINING: usize = 16;
 const RESULT_CODE: usize = 9;
 const QUERY_END: usize = 50;
@@ -86,7 +87,7 @@ impl AdminCommand {
         conn.buffer.size_buffer()?;
         let size = conn.buffer.data_offset;
         conn.buffer.reset_offset();
-        AdminCommand::write_size(&mut conn, size as i64);
+        AdminCommand::write_size(&mut conn.buffer, size as i64);
 
         conn.set_socket_timeout(None, policy.timeout());
 
@@ -119,7 +120,7 @@ impl AdminCommand {
         conn.set_socket_timeout(None, policy.timeout());
         let size = conn.buffer.data_offset;
         conn.buffer.reset_offset();
-        AdminCommand::write_size(&mut conn, size as i64);
+        AdminCommand::write_size(&mut conn.buffer, size as i64);
 
         // Send command.
         if let Err(err) = conn.flush().await {
@@ -223,7 +224,7 @@ impl AdminCommand {
         conn.set_socket_timeout(None, policy.timeout());
         let size = conn.buffer.data_offset();
         conn.buffer.reset_offset();
-        AdminCommand::write_size(&mut conn, size as i64);
+        AdminCommand::write_size(&mut conn.buffer, size as i64);
 
         // Send command.
         if let Err(err) = conn.flush().await {
@@ -388,43 +389,69 @@ impl AdminCommand {
         auth_mode: &AuthMode,
         hashed_pass: Option<&String>,
     ) -> Result<()> {
-        conn.buffer.resize_buffer(1024)?;
-        conn.buffer.reset_offset();
+        if !AdminCommand::set_login(&mut conn.buffer, auth_mode, hashed_pass)? {
+            return Ok(());
+        }
+
+        conn.flush().await?;
+        conn.read_buffer(HEADER_SIZE).await?;
+        let receive_size = AdminCommand::parse_login_header(&mut conn.buffer, &conn.addr)?;
+        conn.read_buffer(receive_size).await?;
+
+        Ok(())
+    }
+
+    // Writes the login command into the buffer. Returns false if the auth mode does not
+    // require a login.
+    pub(crate) fn set_login(
+        buffer: &mut Buffer,
+        auth_mode: &AuthMode,
+        hashed_pass: Option<&String>,
+    ) -> Result<bool> {
+        buffer.resize_buffer(1024)?;
+        buffer.reset_offset();
         match auth_mode {
             AuthMode::Internal(ref user, _) => {
-                AdminCommand::write_header(conn, LOGIN, 2);
-                AdminCommand::write_field_str(conn, USER, user);
-                AdminCommand::write_field_bytes(conn, CREDENTIAL, hashed_pass.unwrap().as_bytes());
+                AdminCommand::write_header(buffer, LOGIN, 2);
+                AdminCommand::write_field_str(buffer, USER, user);
+                AdminCommand::write_field_bytes(
+                    buffer,
+                    CREDENTIAL,
+                    hashed_pass.unwrap().as_bytes(),
+                );
             }
             AuthMode::External(ref user, ref password) => {
-                AdminCommand::write_header(conn, LOGIN, 3);
-                AdminCommand::write_field_str(conn, USER, user);
-                AdminCommand::write_field_bytes(conn, CREDENTIAL, hashed_pass.unwrap().as_bytes());
-                AdminCommand::write_field_str(conn, CLEAR_PASSWORD, password);
+                AdminCommand::write_header(buffer, LOGIN, 3);
+                AdminCommand::write_field_str(buffer, USER, user);
+                AdminCommand::write_field_bytes(
+                    buffer,
+                    CREDENTIAL,
+                    hashed_pass.unwrap().as_bytes(),
+                );
+                AdminCommand::write_field_str(buffer, CLEAR_PASSWORD, password);
             }
-            AuthMode::PKI => AdminCommand::write_header(conn, LOGIN, 0),
-            AuthMode::None => return Ok(()),
+            AuthMode::PKI => AdminCommand::write_header(buffer, LOGIN, 0),
+            AuthMode::None => return Ok(false),
         }
 
-        conn.buffer.size_buffer()?;
-        let size = conn.buffer.data_offset;
-        conn.buffer.reset_offset();
-        AdminCommand::write_size(conn, size as i64);
+        buffer.size_buffer()?;
+        let size = buffer.data_offset;
+        buffer.reset_offset();
+        AdminCommand::write_size(buffer, size as i64);
+        Ok(true)
+    }
 
-        conn.flush().await?;
-        conn.read_buffer(HEADER_SIZE).await?;
-        let result_code = conn.buffer.read_u8(Some(RESULT_CODE));
+    // Checks the result code of the login response header and returns the number of bytes
+    // remaining in the response.
+    pub(crate) fn parse_login_header(buffer: &mut Buffer, addr: &str) -> Result<usize> {
+        let result_code = buffer.read_u8(Some(RESULT_CODE));
         let result_code = ResultCode::from(result_code);
         if ResultCode::SecurityNotEnabled != result_code && ResultCode::Ok != result_code {
-            return Err(Error::ServerError(result_code, false, conn.addr.clone()));
+            return Err(Error::ServerError(result_code, false, addr.to_string()));
         }
 
-        // consume the rest of the buffer
-        let sz = conn.buffer.read_u64(Some(0));
-        let receive_size = (sz & 0xFFFF_FFFF_FFFF) - HEADER_REMAINING as u64;
-        conn.read_buffer(receive_size as usize).await?;
-
-        Ok(())
+        let sz = buffer.read_u64(Some(0));
+        Ok(((sz & 0xFFFF_FFFF_FFFF) - HEADER_REMAINING as u64) as usize)
     }
 
     pub(crate) async fn create_user(
@@ -439,9 +466,13 @@ impl AdminCommand {
 
         conn.buffer.resize_buffer(1024)?;
         conn.buffer.reset_offset();
-        AdminCommand::write_header(&mut conn, CREATE_USER, 3);
-        AdminCommand::write_field_str(&mut conn, USER, user);
-        AdminCommand::write_field_str(&mut conn, PASSWORD, &AdminCommand::hash_password(password)?);
+        AdminCommand::write_header(&mut conn.buffer, CREATE_USER, 3);
+        AdminCommand::write_field_str(&mut conn.buffer, USER, user);
+        AdminCommand::write_field_str(
+            &mut conn.buffer,
+            PASSWORD,
+            &AdminCommand::hash_password(password)?,
+        );
         AdminCommand::write_roles(&mut conn, roles);
 
         AdminCommand::execute(policy, conn).await
@@ -457,8 +488,8 @@ impl AdminCommand {
 
         conn.buffer.resize_buffer(1024)?;
         conn.buffer.reset_offset();
-        AdminCommand::write_header(&mut conn, DROP_USER, 1);
-        AdminCommand::write_field_str(&mut conn, USER, user);
+        AdminCommand::write_header(&mut conn.buffer, DROP_USER, 1);
+        AdminCommand::write_field_str(&mut conn.buffer, USER, user);
 
         AdminCommand::execute(policy, conn).await
     }
@@ -474,9 +505,13 @@ impl AdminCommand {
 
         conn.buffer.resize_buffer(1024)?;
         conn.buffer.reset_offset();
-        AdminCommand::write_header(&mut conn, SET_PASSWORD, 2);
-        AdminCommand::write_field_str(&mut conn, USER, user);
-        AdminCommand::write_field_str(&mut conn, PASSWORD, &AdminCommand::hash_password(password)?);
+        AdminCommand::write_header(&mut conn.buffer, SET_PASSWORD, 2);
+        AdminCommand::write_field_str(&mut conn.buffer, USER, user);
+        AdminCommand::write_field_str(
+            &mut conn.buffer,
+            PASSWORD,
+            &AdminCommand::hash_password(password)?,
+        );
 
         AdminCommand::execute(policy, conn).await
     }
@@ -492,12 +527,12 @@ impl AdminCommand {
 
         conn.buffer.resize_buffer(1024)?;
         conn.buffer.reset_offset();
-        AdminCommand::write_header(&mut conn, CHANGE_PASSWORD, 3);
-        AdminCommand::write_field_str(&mut conn, USER, user);
+        AdminCommand::write_header(&mut conn.buffer, CHANGE_PASSWORD, 3);
+        AdminCommand::write_field_str(&mut conn.buffer, USER, user);
         match cluster.client_policy().auth_mode {
             AuthMode::Internal(_, ref password) | AuthMode::External(_, ref password) => {
                 AdminCommand::write_field_str(
-                    &mut conn,
+                    &mut conn.buffer,
                     OLD_PASSWORD,
                     &AdminCommand::hash_password(password)?,
                 );
@@ -508,10 +543,14 @@ impl AdminCommand {
                     "Can't change PKI user's password".into(),
                 ))
             }
-            AuthMode::None => AdminCommand::write_field_str(&mut conn, OLD_PASSWORD, ""),
+            AuthMode::None => AdminCommand::write_field_str(&mut conn.buffer, OLD_PASSWORD, ""),
         }
 
-        AdminCommand::write_field_str(&mut conn, PASSWORD, &AdminCommand::hash_password(password)?);
+        AdminCommand::write_field_str(
+            &mut conn.buffer,
+            PASSWORD,
+            &AdminCommand::hash_password(password)?,
+        );
 
         AdminCommand::execute(policy, conn).await?;
         cluster.update_password(user, password)
@@ -548,8 +587,8 @@ impl AdminCommand {
 
         conn.buffer.resize_buffer(1024)?;
         conn.buffer.reset_offset();
-        AdminCommand::write_header(&mut conn, CREATE_ROLE, field_count);
-        AdminCommand::write_field_str(&mut conn, ROLE, role_name);
+        AdminCommand::write_header(&mut conn.buffer, CREATE_ROLE, field_count);
+        AdminCommand::write_field_str(&mut conn.buffer, ROLE, role_name);
 
         if !privileges.is_empty() {
             AdminCommand::write_privileges(&mut conn, privileges)?;
@@ -560,11 +599,11 @@ impl AdminCommand {
         }
 
         if read_quota > 0 {
-            AdminCommand::write_field_u32(&mut conn, READ_QUOTA, read_quota);
+            AdminCommand::write_field_u32(&mut conn.buffer, READ_QUOTA, read_quota);
         }
 
         if write_quota > 0 {
-            AdminCommand::write_field_u32(&mut conn, WRITE_QUOTA, write_quota);
+            AdminCommand::write_field_u32(&mut conn.buffer, WRITE_QUOTA, write_quota);
         }
 
         AdminCommand::execute(policy, conn).await
@@ -580,8 +619,8 @@ impl AdminCommand {
 
         conn.buffer.resize_buffer(1024)?;
         conn.buffer.reset_offset();
-        AdminCommand::write_header(&mut conn, DROP_ROLE, 1);
-        AdminCommand::write_field_str(&mut conn, ROLE, role_name);
+        AdminCommand::write_header(&mut conn.buffer, DROP_ROLE, 1);
+        AdminCommand::write_field_str(&mut conn.buffer, ROLE, role_name);
 
         AdminCommand::execute(policy, conn).await
     }
@@ -597,8 +636,8 @@ impl AdminCommand {
 
         conn.buffer.resize_buffer(1024)?;
         conn.buffer.reset_offset();
-        AdminCommand::write_header(&mut conn, GRANT_PRIVILEGES, 2);
-        AdminCommand::write_field_str(&mut conn, ROLE, role_name);
+        AdminCommand::write_header(&mut conn.buffer, GRANT_PRIVILEGES, 2);
+        AdminCommand::write_field_str(&mut conn.buffer, ROLE, role_name);
         AdminCommand::write_privileges(&mut conn, privileges)?;
 
         AdminCommand::execute(policy, conn).await
@@ -615,8 +654,8 @@ impl AdminCommand {
 
         conn.buffer.resize_buffer(1024)?;
         conn.buffer.reset_offset();
-        AdminCommand::write_header(&mut conn, REVOKE_PRIVILEGES, 2);
-        AdminCommand::write_field_str(&mut conn, ROLE, role_name);
+        AdminCommand::write_header(&mut conn.buffer, REVOKE_PRIVILEGES, 2);
+        AdminCommand::write_field_str(&mut conn.buffer, ROLE, role_name);
         AdminCommand::write_privileges(&mut conn, privileges)?;
 
         AdminCommand::execute(policy, conn).await
@@ -633,8 +672,8 @@ impl AdminCommand {
 
         conn.buffer.resize_buffer(1024)?;
         conn.buffer.reset_offset();
-        AdminCommand::write_header(&mut conn, SET_ALLOWLIST, 2);
-        AdminCommand::write_field_str(&mut conn, ROLE, role_name);
+        AdminCommand::write_header(&mut conn.buffer, SET_ALLOWLIST, 2);
+        AdminCommand::write_field_str(&mut conn.buffer, ROLE, role_name);
         AdminCommand::write_allowlist(&mut conn, allowlist);
 
         AdminCommand::execute(policy, conn).await
@@ -652,10 +691,10 @@ impl AdminCommand {
 
         conn.buffer.resize_buffer(1024)?;
         conn.buffer.reset_offset();
-        AdminCommand::write_header(&mut conn, SET_QUOTAS, 3);
-        AdminCommand::write_field_str(&mut conn, ROLE, role_name);
-        AdminCommand::write_field_u32(&mut conn, READ_QUOTA, read_quota);
-        AdminCommand::write_field_u32(&mut conn, WRITE_QUOTA, write_quota);
+        AdminCommand::write_header(&mut conn.buffer, SET_QUOTAS, 3);
+        AdminCommand::write_field_str(&mut conn.buffer, ROLE, role_name);
+        AdminCommand::write_field_u32(&mut conn.buffer, READ_QUOTA, read_quota);
+        AdminCommand::write_field_u32(&mut conn.buffer, WRITE_QUOTA, write_quota);
 
         AdminCommand::execute(policy, conn).await
     }
@@ -671,8 +710,8 @@ impl AdminCommand {
 
         conn.buffer.resize_buffer(1024)?;
         conn.buffer.reset_offset();
-        AdminCommand::write_header(&mut conn, GRANT_ROLES, 2);
-        AdminCommand::write_field_str(&mut conn, USER, user);
+        AdminCommand::write_header(&mut conn.buffer, GRANT_ROLES, 2);
+        AdminCommand::write_field_str(&mut conn.buffer, USER, user);
         AdminCommand::write_roles(&mut conn, roles);
 
         AdminCommand::execute(policy, conn).await
@@ -689,8 +728,8 @@ impl AdminCommand {
 
         conn.buffer.resize_buffer(1024)?;
         conn.buffer.reset_offset();
-        AdminCommand::write_header(&mut conn, REVOKE_ROLES, 2);
-        AdminCommand::write_field_str(&mut conn, USER, user);
+        AdminCommand::write_header(&mut conn.buffer, REVOKE_ROLES, 2);
+        AdminCommand::write_field_str(&mut conn.buffer, USER, user);
         AdminCommand::write_roles(&mut conn, roles);
 
         AdminCommand::execute(policy, conn).await
@@ -708,10 +747,10 @@ impl AdminCommand {
         conn.buffer.reset_offset();
 
         if let Some(user) = user {
-            AdminCommand::write_header(&mut conn, QUERY_USERS, 1);
-            AdminCommand::write_field_str(&mut conn, USER, user);
+            AdminCommand::write_header(&mut conn.buffer, QUERY_USERS, 1);
+            AdminCommand::write_field_str(&mut conn.buffer, USER, user);
         } else {
-            AdminCommand::write_header(&mut conn, QUERY_USERS, 0);
+            AdminCommand::write_header(&mut conn.buffer, QUERY_USERS, 0);
         }
 
         AdminCommand::read_users(policy, conn).await
@@ -729,10 +768,10 @@ impl AdminCommand {
         conn.buffer.reset_offset();
 
         if let Some(role) = role {
-            AdminCommand::write_header(&mut conn, QUERY_ROLES, 1);
-            AdminCommand::write_field_str(&mut conn, ROLE, role);
+            AdminCommand::write_header(&mut conn.buffer, QUERY_ROLES, 1);
+            AdminCommand::write_field_str(&mut conn.buffer, ROLE, role);
         } else {
-            AdminCommand::write_header(&mut conn, QUERY_ROLES, 0);
+            AdminCommand::write_header(&mut conn.buffer, QUERY_ROLES, 0);
         }
 
         AdminCommand::read_roles(policy, conn).await
@@ -740,43 +779,43 @@ impl AdminCommand {
 
     // Utility methods
 
-    fn write_size(conn: &mut Connection, size: i64) {
+    fn write_size(buffer: &mut Buffer, size: i64) {
         // Write total size of message which is the current offset.
         let size = (size - 8) | (MSG_VERSION << 56) | (MSG_TYPE << 48);
-        conn.buffer.write_u64(size as u64);
+        buffer.write_u64(size as u64);
     }
 
-    fn write_header(conn: &mut Connection, command: u8, field_count: u8) {
-        conn.buffer.data_offset = 8;
-        conn.buffer.write_u8(0);
-        conn.buffer.write_u8(0);
-        conn.buffer.write_u8(command);
-        conn.buffer.write_u8(field_count);
+    fn write_header(buffer: &mut Buffer, command: u8, field_count: u8) {
+        buffer.data_offset = 8;
+        buffer.write_u8(0);
+        buffer.write_u8(0);
+        buffer.write_u8(command);
+        buffer.write_u8(field_count);
 
         // Authenticate header is almost all zeros
         for _ in 0..(16 - 4) {
-            conn.buffer.write_u8(0);
+            buffer.write_u8(0);
         }
     }
 
-    fn write_field_header(conn: &mut Connection, id: u8, size: usize) {
-        conn.buffer.write_u32(size as u32 + 1);
-        conn.buffer.write_u8(id);
+    fn write_field_header(buffer: &mut Buffer, id: u8, size: usize) {
+        buffer.write_u32(size as u32 + 1);
+        buffer.write_u8(id);
     }
 
-    fn write_field_str(conn: &mut Connection, id: u8, s: &str) {
-        AdminCommand::write_field_header(conn, id, s.len());
-        conn.buffer.write_str(s);
+    fn write_field_str(buffer: &mut Buffer, id: u8, s: &str) {
+        AdminCommand::write_field_header(buffer, id, s.len());
+        buffer.write_str(s);
     }
 
-    fn write_field_u32(conn: &mut Connection, id: u8, v: u32) {
-        AdminCommand::write_field_header(conn, id, 4);
-        conn.buffer.write_u32(v);
+    fn write_field_u32(buffer: &mut Buffer, id: u8, v: u32) {
+        AdminCommand::write_field_header(buffer, id, 4);
+        buffer.write_u32(v);
     }
 
-    fn write_field_bytes(conn: &mut Connection, id: u8, b: &[u8]) {
-        AdminCommand::write_field_header(conn, id, b.len());
-        conn.buffer.write_bytes(b);
+    fn write_field_bytes(buffer: &mut Buffer, id: u8, b: &[u8]) {
+        AdminCommand::write_field_header(buffer, id, b.len());
+        buffer.write_bytes(b);
     }
 
     fn write_roles(conn: &mut Connection, roles: &[&str]) {
@@ -785,7 +824,7 @@ impl AdminCommand {
             size += role.len() + 1; // size + len
         }
 
-        AdminCommand::write_field_header(conn, ROLES, size);
+        AdminCommand::write_field_header(&mut conn.buffer, ROLES, size);
         conn.buffer.write_u8(roles.len() as u8);
         for role in roles {
             conn.buffer.write_u8(role.len() as u8);
@@ -827,7 +866,7 @@ impl AdminCommand {
             }
         }
 
-        AdminCommand::write_field_header(conn, PRIVILEGES, size);
+        AdminCommand::write_field_header(&mut conn.buffer, PRIVILEGES, size);
         conn.buffer.write_u8(privileges.len() as u8);
 
         for prev in privileges {
@@ -865,7 +904,7 @@ impl AdminCommand {
             size += address.len();
         }
 
-        AdminCommand::write_field_header(conn, SET_ALLOWLIST, size);
+        AdminCommand::write_field_header(&mut conn.buffer, SET_ALLOWLIST, size);
 
         for address in allowlist {
             if comma {
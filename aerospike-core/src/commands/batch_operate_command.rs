@@ -13,45 +13,74 @@
 // limitations under the License.
 
 use aerospike_rt::time::Instant;
-use std::collections::hash_map::Entry::{Occupied, Vacant};
-use std::collections::HashMap;
 use std::sync::Arc;
 
 use crate::batch::BatchOperation;
 use crate::batch::BatchRecordIndex;
 use crate::cluster::partition::Partition;
 use crate::cluster::{Cluster, Node};
+use crate::codec::BinCodecs;
 use crate::commands::StreamCommand;
 use crate::commands::{self};
 use crate::errors::{Error, Result};
 use crate::net::{BufferedConn, Connection};
 use crate::policy::{BatchPolicy, Policy, Replica};
-use crate::{value, Record, ResultCode, Value};
+use crate::trace;
+use crate::{BinView, FromBins, Record, RecordMeta, ResultCode};
 use aerospike_rt::sleep;
 use aerospike_rt::time::Duration;
 
-#[derive(Clone)]
-pub struct BatchOperateCommand {
+// Outcome of sending a group of batch operations to a node.
+#[derive(Debug, PartialEq, Eq)]
+enum GroupStatus {
+    Done,
+    // The node is inactive or a new connection to it failed; it may have left the cluster.
+    NodeUnavailable,
+    // The request could not be sent to the node, or no connection to it was free.
+    SendFailed,
+}
+
+pub struct BatchOperateCommand<T: FromBins = Record> {
     policy: BatchPolicy,
     pub node: Arc<Node>,
     pub batch_ops: Vec<(BatchOperation, usize)>,
+    // Decoded records, in the same order as `batch_ops`.
+    pub records: Vec<Option<T>>,
 }
 
-impl BatchOperateCommand {
-    pub const fn new(
+impl<T: FromBins> BatchOperateCommand<T> {
+    pub fn new(
         policy: BatchPolicy,
         node: Arc<Node>,
         batch_ops: Vec<(BatchOperation, usize)>,
-    ) -> BatchOperateCommand {
+    ) -> Self {
+        let records = batch_ops.iter().map(|_| None).collect();
         BatchOperateCommand {
             policy,
             node,
             batch_ops,
+            records,
         }
     }
 
-    #[allow(clippy::option_if_let_else)]
     pub async fn execute(self, cluster: Arc<Cluster>) -> Result<Self> {
+        let span = self.trace_span();
+        let res = trace::instrument(span.clone(), self.execute_with_timeout(cluster)).await;
+        trace::record_result(&span, &res);
+        res
+    }
+
+    fn trace_span(&self) -> trace::Span {
+        let namespace = self
+            .batch_ops
+            .first()
+            .map(|(op, _)| op.key().namespace)
+            .unwrap_or_default();
+        trace::command_span("batch", &namespace, "", None)
+    }
+
+    #[allow(clippy::option_if_let_else)]
+    async fn execute_with_timeout(self, cluster: Arc<Cluster>) -> Result<Self> {
         if self.policy.total_timeout() > 0 {
             let res = aerospike_rt::timeout(
                 Duration::from_millis(u64::from(self.policy.total_timeout())),
@@ -72,22 +101,31 @@ impl BatchOperateCommand {
 
         // set timeout outside the loop
         let deadline = self.policy.deadline();
+        let codecs = cluster.codecs();
 
         // Execute command until successful, timed out or maximum iterations have been reached.
         loop {
-            let success = if iterations & 1 == 0 || matches!(self.policy.replica, Replica::Master) {
+            if iterations > 0 {
+                trace::retry(iterations + 1);
+            }
+
+            let status = if iterations & 1 == 0 || matches!(self.policy.replica, Replica::Master) {
                 // For even iterations, we request all keys from the same node for efficiency.
                 Self::request_group(
                     &mut self.batch_ops,
+                    &mut self.records,
                     &self.policy,
+                    &codecs,
                     deadline,
                     self.node.clone(),
+                    iterations + 1,
                 )
                 .await?
             } else {
                 // However, for odd iterations try the second choice for each. Instead of re-sharding the batch (as the second choice may not correspond to the first), just try each by itself.
-                let mut all_successful = true;
-                for individual_op in self.batch_ops.chunks_mut(1) {
+                let mut status = GroupStatus::Done;
+                let individual_ops = self.batch_ops.chunks_mut(1);
+                for (individual_op, record) in individual_ops.zip(self.records.chunks_mut(1)) {
                     let key = individual_op[0].0.key();
                     // Find somewhere else to try.
                     let partition = Partition::new_by_key(&key);
@@ -97,15 +135,24 @@ impl BatchOperateCommand {
                         Some(self.node.clone()),
                     )?;
 
-                    if !Self::request_group(individual_op, &self.policy, deadline, node).await? {
-                        all_successful = false;
+                    status = Self::request_group(
+                        individual_op,
+                        record,
+                        &self.policy,
+                        &codecs,
+                        deadline,
+                        node,
+                        iterations + 1,
+                    )
+                    .await?;
+                    if status != GroupStatus::Done {
                         break;
                     }
                 }
-                all_successful
+                status
             };
 
-            if success {
+            if status == GroupStatus::Done {
                 // command has completed successfully. Exit method.
                 return Ok(self);
             }
@@ -117,8 +164,12 @@ impl BatchOperateCommand {
                 return Err(Error::Timeout(format!("Timeout after {iterations} tries")));
             }
 
-            // Sleep before trying again, after the first iteration
-            if let Some(sleep_between_retries) = self.policy.sleep_between_retries() {
+            // A node that cannot be reached may have left the cluster or given up its partitions,
+            // so waiting for a fresh partition map replaces the sleep before the retry re-resolves
+            // each key's node.
+            if status == GroupStatus::NodeUnavailable {
+                cluster.request_tend(deadline).await;
+            } else if let Some(sleep_between_retries) = self.policy.sleep_between_retries() {
                 sleep(sleep_between_retries).await;
             }
 
@@ -135,15 +186,27 @@ impl BatchOperateCommand {
 
     async fn request_group(
         batch_ops: &mut [(BatchOperation, usize)],
+        records: &mut [Option<T>],
         policy: &BatchPolicy,
+        codecs: &BinCodecs,
         deadline: Option<Instant>,
         node: Arc<Node>,
-    ) -> Result<bool> {
-        let mut conn = match node.get_connection(0).await {
+        attempt: usize,
+    ) -> Result<GroupStatus> {
+        let span = trace::current();
+        trace::record_attempt(&span, &node, attempt);
+
+        let stopwatch = trace::Stopwatch::start();
+        let conn = node.get_connection(0).await;
+        trace::connection_acquired(&node, stopwatch, &conn);
+        let mut conn = match conn {
             Ok(conn) => conn,
             Err(err) => {
                 warn!("Node {node}: {err}");
-                return Ok(false);
+                if commands::is_node_unavailable(&err) {
+                    return Ok(GroupStatus::NodeUnavailable);
+                }
+                return Ok(GroupStatus::SendFailed);
             }
         };
 
@@ -152,6 +215,7 @@ impl BatchOperateCommand {
             .map_err(|_| Error::ClientError("Failed to prepare send buffer".into()))?;
 
         conn.buffer.write_timeout(policy.server_timeout());
+        trace::record_bytes_sent(&span, conn.buffer.data_buffer.len());
 
         conn.set_socket_timeout(deadline, policy.socket_timeout());
         conn.set_timeout_delay(true, policy.timeout_delay());
@@ -162,11 +226,14 @@ impl BatchOperateCommand {
             // Close socket to flush out possible garbage. Do not put back in pool.
             conn.invalidate();
             warn!("Node {node}: {err}");
-            return Ok(false);
+            return Ok(GroupStatus::SendFailed);
         }
 
         // Parse results.
-        if let Err(err) = Self::parse_result(batch_ops, &mut conn).await {
+        let received = conn.bytes_received();
+        let res = Self::parse_result(batch_ops, records, codecs, &mut conn).await;
+        trace::record_bytes_received(&span, conn.bytes_received() - received);
+        if let Err(err) = res {
             // close the connection
             // cancelling/closing the batch/multi commands will return an error, which will
             // close the connection to throw away its data and signal the server about the
@@ -176,26 +243,28 @@ impl BatchOperateCommand {
             }
             Err(err)
         } else {
-            Ok(true)
+            Ok(GroupStatus::Done)
         }
     }
 
     async fn parse_group(
         batch_ops: &mut [(BatchOperation, usize)],
+        records: &mut [Option<T>],
+        codecs: &BinCodecs,
         conn: &mut BufferedConn<'_>,
         size: usize,
     ) -> Result<bool> {
         while conn.bytes_read() < size {
             conn.read_buffer(commands::buffer::MSG_REMAINING_HEADER_SIZE as usize)
                 .await?;
-            match Self::parse_record(conn).await {
+            match Self::parse_record(batch_ops, codecs, conn).await {
                 Ok(None) => return Ok(false),
                 Ok(Some(batch_record)) => {
                     let batch_op = batch_ops
                         .get_mut(batch_record.batch_index)
                         .expect("Invalid batch index");
-                    batch_op.0.set_record(batch_record.record);
                     batch_op.0.set_result_code(batch_record.result_code, false);
+                    records[batch_record.batch_index] = batch_record.record;
                 }
                 Err(Error::BatchLastError(batch_index, rc, in_doubt, ref msg)) => {
                     let batch_op = batch_ops
@@ -216,7 +285,11 @@ impl BatchOperateCommand {
         Ok(true)
     }
 
-    async fn parse_record(conn: &mut BufferedConn<'_>) -> Result<Option<BatchRecordIndex>> {
+    async fn parse_record(
+        batch_ops: &[(BatchOperation, usize)],
+        codecs: &BinCodecs,
+        conn: &mut BufferedConn<'_>,
+    ) -> Result<Option<BatchRecordIndex<T>>> {
         // if cmd is the end marker of the response, do not proceed further
         let info3 = conn.buffer().read_u8(Some(3));
         let last_record = info3 & commands::buffer::INFO3_LAST == commands::buffer::INFO3_LAST;
@@ -269,7 +342,10 @@ impl BatchOperateCommand {
         let (key, _) = StreamCommand::parse_key(conn, field_count).await?;
 
         let record = if found_key {
-            let mut bins: HashMap<String, Value> = HashMap::with_capacity(op_count);
+            let op_key = batch_ops
+                .get(batch_index as usize)
+                .map_or(&key, |(batch_op, _)| batch_op.key_ref());
+            let mut bins = T::Builder::default();
 
             for _ in 0..op_count {
                 conn.read_buffer(8).await?;
@@ -278,28 +354,22 @@ impl BatchOperateCommand {
                 let particle_type = conn.buffer().read_u8(None);
                 conn.buffer().skip(1);
                 let name_size = conn.buffer().read_u8(None) as usize;
-                conn.read_buffer(name_size).await?;
-                let name = conn.buffer().read_str(name_size)?;
-                let particle_bytes_size = op_size - (4 + name_size);
-                conn.read_buffer(particle_bytes_size).await?;
-                let value =
-                    value::bytes_to_particle(particle_type, conn.buffer(), particle_bytes_size)?;
-
-                // list/map operations may return multiple values for the same bin.
-                match bins.entry(name) {
-                    Vacant(entry) => {
-                        entry.insert(value);
-                    }
-                    Occupied(entry) => match *entry.into_mut() {
-                        Value::MultiResult(ref mut list) => list.push(value),
-                        ref mut prev => {
-                            *prev = Value::MultiResult(vec![prev.clone(), value]);
-                        }
-                    },
-                }
+                let bin_size = op_size - 4;
+                conn.read_buffer(bin_size).await?;
+                let data = conn.buffer().read_slice(bin_size);
+                codecs.decode_bin::<T>(
+                    op_key,
+                    &mut bins,
+                    &BinView::from_op(particle_type, data, name_size)?,
+                )?;
             }
 
-            Some(Record::new(Some(key), bins, generation, expiration))
+            let meta = RecordMeta {
+                key: Some(key),
+                generation,
+                expiration,
+            };
+            Some(T::build(bins, meta)?)
         } else {
             None
         };
@@ -316,6 +386,8 @@ impl BatchOperateCommand {
 
     async fn parse_result(
         batch_ops: &mut [(BatchOperation, usize)],
+        records: &mut [Option<T>],
+        codecs: &BinCodecs,
         conn: &mut Connection,
     ) -> Result<()> {
         let mut status = true;
@@ -331,7 +403,7 @@ impl BatchOperateCommand {
             status = false;
             if size > 0 {
                 conn.set_limit_body(size)?;
-                match Self::parse_group(batch_ops, &mut conn, size).await {
+                match Self::parse_group(batch_ops, records, codecs, &mut conn, size).await {
                     Ok(stat) => status = stat,
                     Err(e @ Error::ServerError(_, _, _)) => {
                         conn.drain(conn.conn.deadline()).await?;
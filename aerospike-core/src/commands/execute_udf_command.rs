@@ -15,11 +15,15 @@
 use std::str;
 use std::sync::Arc;
 
+use aerospike_rt::time::Instant;
+
 use crate::cluster::{Cluster, Node};
 use crate::commands::{Command, ReadCommand, SingleCommand};
 use crate::errors::Result;
+use crate::interceptor::Interception;
 use crate::net::Connection;
 use crate::policy::WritePolicy;
+use crate::trace;
 use crate::{Bins, Key, Policy, Value};
 
 pub struct ExecuteUDFCommand<'a> {
@@ -54,8 +58,8 @@ impl<'a> ExecuteUDFCommand<'a> {
         }
     }
 
-    pub async fn execute(&mut self) -> Result<()> {
-        SingleCommand::execute(self.policy, self).await
+    pub async fn execute(&mut self, interception: &Interception<'_>) -> Result<()> {
+        SingleCommand::execute_intercepted(self.policy, self, interception).await
     }
 }
 
@@ -88,10 +92,18 @@ impl Command for ExecuteUDFCommand<'_> {
         true
     }
 
+    fn trace_span(&self) -> trace::Span {
+        trace::key_span("execute_udf", self.read_command.single_command.key)
+    }
+
     async fn get_node(&mut self) -> Result<Arc<Node>> {
         self.read_command.get_node().await
     }
 
+    async fn refresh_partitions(&mut self, deadline: Option<Instant>) {
+        self.read_command.refresh_partitions(deadline).await;
+    }
+
     fn hint(&self) -> u8 {
         self.read_command.single_command.hint()
     }
@@ -14,11 +14,15 @@
 
 use std::sync::Arc;
 
+use aerospike_rt::time::Instant;
+
 use crate::cluster::{Cluster, Node};
 use crate::commands::{Command, SingleCommand};
 use crate::errors::{Error, Result};
+use crate::interceptor::Interception;
 use crate::net::Connection;
 use crate::policy::ReadPolicy;
+use crate::trace;
 use crate::{Key, Policy, ResultCode};
 
 pub struct ExistsCommand<'a> {
@@ -36,8 +40,8 @@ impl<'a> ExistsCommand<'a> {
         }
     }
 
-    pub async fn execute(&mut self) -> Result<()> {
-        SingleCommand::execute(self.policy, self).await
+    pub async fn execute(&mut self, interception: &Interception<'_>) -> Result<()> {
+        SingleCommand::execute_intercepted(self.policy, self, interception).await
     }
 }
 
@@ -60,6 +64,10 @@ impl Command for ExistsCommand<'_> {
         self.single_command.get_node()
     }
 
+    async fn refresh_partitions(&mut self, deadline: Option<Instant>) {
+        self.single_command.refresh_partitions(deadline).await;
+    }
+
     fn hint(&self) -> u8 {
         self.single_command.hint()
     }
@@ -72,6 +80,10 @@ impl Command for ExistsCommand<'_> {
         true
     }
 
+    fn trace_span(&self) -> trace::Span {
+        trace::key_span("exists", self.single_command.key)
+    }
+
     async fn parse_result(&mut self, conn: &mut Connection) -> Result<()> {
         // Read header.
         if let Err(err) = conn.read_header().await {
@@ -41,6 +41,24 @@ impl Message {
         msg.parse_response()
     }
 
+    #[cfg(feature = "blocking")]
+    pub(crate) fn info_blocking(
+        policy: AdminPolicy,
+        conn: &mut crate::blocking::Connection,
+        commands: &[&str],
+    ) -> Result<HashMap<String, String>> {
+        let cmd = commands.join("\n") + "\n";
+        let mut msg = Message::new(&cmd.into_bytes())?;
+
+        conn.set_timeout(None, policy.timeout());
+        conn.write_all(&msg.buf)?;
+        conn.read_all(msg.buf[..8].as_mut())?;
+        msg.resize_for_data()?;
+        conn.read_all(msg.buf.as_mut())?;
+
+        msg.parse_response()
+    }
+
     fn new(data: &[u8]) -> Result<Self> {
         let mut len = Vec::with_capacity(8);
         len.write_u64::<NetworkEndian>(data.len() as u64).unwrap();
@@ -68,7 +86,17 @@ impl Message {
         // read the header
         conn.read_all(self.buf[..8].as_mut()).await?;
 
-        // figure our message size and grow the buffer if necessary
+        self.resize_for_data()?;
+
+        // read the message content
+        conn.read_all(self.buf.as_mut()).await?;
+        conn.reset_state();
+
+        Ok(())
+    }
+
+    // figure our message size and grow the buffer if necessary
+    fn resize_for_data(&mut self) -> Result<()> {
         let data_len = self.data_len() as usize;
 
         // Corrupted data streams can result in a huge length.
@@ -79,11 +107,6 @@ impl Message {
             )));
         }
         self.buf.resize(data_len, 0);
-
-        // read the message content
-        conn.read_all(self.buf.as_mut()).await?;
-        conn.reset_state();
-
         Ok(())
     }
 
@@ -0,0 +1,84 @@
+// Copyright 2015-2018 Aerospike, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::sync::Arc;
+
+use aerospike_rt::time::Instant;
+
+use crate::cluster::Node;
+use crate::commands::Command;
+use crate::errors::{Error, Result};
+use crate::interceptor::Interception;
+use crate::net::Connection;
+use crate::trace;
+
+// Wraps a single record command to call the interceptors' retry hooks.
+pub struct InterceptedCommand<'a, 'b> {
+    cmd: &'a mut (dyn Command + Send),
+    interception: &'a Interception<'b>,
+}
+
+impl<'a, 'b> InterceptedCommand<'a, 'b> {
+    pub fn new(cmd: &'a mut (dyn Command + Send), interception: &'a Interception<'b>) -> Self {
+        InterceptedCommand { cmd, interception }
+    }
+}
+
+#[async_trait::async_trait]
+impl Command for InterceptedCommand<'_, '_> {
+    async fn write_timeout(&mut self, conn: &mut Connection) -> Result<()> {
+        self.cmd.write_timeout(conn).await
+    }
+
+    async fn write_buffer(&mut self, conn: &mut Connection) -> Result<()> {
+        self.cmd.write_buffer(conn).await
+    }
+
+    async fn prepare_buffer(&mut self, conn: &mut Connection) -> Result<()> {
+        self.cmd.prepare_buffer(conn).await
+    }
+
+    async fn get_node(&mut self) -> Result<Arc<Node>> {
+        self.cmd.get_node().await
+    }
+
+    async fn refresh_partitions(&mut self, deadline: Option<Instant>) {
+        self.cmd.refresh_partitions(deadline).await;
+    }
+
+    fn hint(&self) -> u8 {
+        self.cmd.hint()
+    }
+
+    fn can_recover_connection(&mut self) -> bool {
+        self.cmd.can_recover_connection()
+    }
+
+    fn trace_span(&self) -> trace::Span {
+        self.cmd.trace_span()
+    }
+
+    fn can_retry(&mut self) -> bool {
+        self.cmd.can_retry()
+    }
+
+    fn on_retry(&mut self, attempt: usize, err: &Error) {
+        self.interception.retry(attempt, err);
+        self.cmd.on_retry(attempt, err);
+    }
+
+    async fn parse_result(&mut self, conn: &mut Connection) -> Result<()> {
+        self.cmd.parse_result(conn).await
+    }
+}
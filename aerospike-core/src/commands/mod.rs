@@ -20,6 +20,7 @@ pub mod delete_command;
 pub mod execute_udf_command;
 pub mod exists_command;
 pub mod info_command;
+pub mod intercepted_command;
 pub mod operate_command;
 pub mod particle_type;
 pub mod query_command;
@@ -35,12 +36,15 @@ mod field_type;
 
 use std::sync::Arc;
 
+use aerospike_rt::time::Instant;
+
 pub use self::batch_attr::BatchAttr;
 pub use self::batch_operate_command::BatchOperateCommand;
 pub use self::delete_command::DeleteCommand;
 pub use self::execute_udf_command::ExecuteUDFCommand;
 pub use self::exists_command::ExistsCommand;
 pub use self::info_command::Message;
+pub use self::intercepted_command::InterceptedCommand;
 pub use self::operate_command::OperateCommand;
 pub use self::particle_type::ParticleType;
 pub use self::query_command::QueryCommand;
@@ -55,6 +59,8 @@ pub use self::write_command::WriteCommand;
 use crate::cluster::Node;
 use crate::errors::{Error, Result};
 use crate::net::Connection;
+use crate::trace;
+use crate::ResultCode;
 
 // Command interface describes all commands available
 #[async_trait::async_trait]
@@ -67,6 +73,18 @@ pub trait Command {
     async fn write_buffer(&mut self, conn: &mut Connection) -> Result<()>;
     fn can_retry(&mut self) -> bool;
     fn can_recover_connection(&mut self) -> bool;
+
+    // Called before the command is retried after a failed attempt.
+    fn on_retry(&mut self, _attempt: usize, _err: &Error) {}
+
+    // Called before a retry when the previous attempt could not reach the partition's node,
+    // so that key-routed commands are re-routed with a fresh partition map.
+    async fn refresh_partitions(&mut self, _deadline: Option<Instant>) {}
+
+    // Span the command is executed in when the `tracing` feature is enabled.
+    fn trace_span(&self) -> trace::Span {
+        trace::command_span("command", "", "", None)
+    }
 }
 
 pub const fn keep_connection(err: &Error) -> bool {
@@ -76,3 +94,18 @@ pub const fn keep_connection(err: &Error) -> bool {
 pub const fn is_network_error(err: &Error) -> bool {
     matches!(err, Error::Connection(_) | Error::Timeout(_))
 }
+
+// No connection to the node could be opened, or the node is no longer active, so it may have
+// left the cluster. Running out of pooled connections does not count: the node is still there.
+pub const fn is_node_unavailable(err: &Error) -> bool {
+    matches!(err, Error::Connection(_) | Error::InvalidNode(_))
+}
+
+// The node that was sent the command does not own the key's partition, e.g. because the
+// partition migrated away from it. The command can be retried against the new owner.
+pub const fn is_routing_error(err: &Error) -> bool {
+    matches!(
+        err,
+        Error::ServerError(ResultCode::PartitionUnavailable, _, _)
+    )
+}
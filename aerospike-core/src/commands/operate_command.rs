@@ -14,12 +14,16 @@
 
 use std::sync::Arc;
 
+use aerospike_rt::time::Instant;
+
 use crate::cluster::{Cluster, Node};
 use crate::commands::{Command, ReadCommand, SingleCommand};
 use crate::errors::Result;
+use crate::interceptor::Interception;
 use crate::net::Connection;
 use crate::operations::Operation;
 use crate::policy::{Policy, WritePolicy};
+use crate::trace;
 use crate::{Bins, Key};
 
 pub struct OperateCommand<'a> {
@@ -48,8 +52,8 @@ impl<'a> OperateCommand<'a> {
         }
     }
 
-    pub async fn execute(&mut self) -> Result<()> {
-        SingleCommand::execute(self.policy, self).await
+    pub async fn execute(&mut self, interception: &Interception<'_>) -> Result<()> {
+        SingleCommand::execute_intercepted(self.policy, self, interception).await
     }
 }
 
@@ -80,10 +84,18 @@ impl Command for OperateCommand<'_> {
         true
     }
 
+    fn trace_span(&self) -> trace::Span {
+        trace::key_span("operate", self.read_command.single_command.key)
+    }
+
     async fn get_node(&mut self) -> Result<Arc<Node>> {
         self.read_command.get_node().await
     }
 
+    async fn refresh_partitions(&mut self, deadline: Option<Instant>) {
+        self.read_command.refresh_partitions(deadline).await;
+    }
+
     fn hint(&self) -> u8 {
         self.read_command.single_command.hint()
     }
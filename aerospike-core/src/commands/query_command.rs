@@ -15,27 +15,30 @@
 use std::sync::Arc;
 
 use crate::cluster::Node;
+use crate::codec::BinCodecs;
 use crate::commands::{Command, SingleCommand, StreamCommand};
 use crate::errors::Result;
 use crate::net::Connection;
 use crate::policy::QueryPolicy;
 use crate::query::NodePartitions;
-use crate::{Recordset, Statement};
+use crate::trace;
+use crate::{FromBins, Record, Recordset, Statement};
 
 use aerospike_rt::Mutex;
 
-pub struct QueryCommand<'a> {
-    stream_command: StreamCommand,
+pub struct QueryCommand<'a, T: FromBins = Record> {
+    stream_command: StreamCommand<T>,
     policy: &'a QueryPolicy,
     statement: Arc<Statement>,
 }
 
-impl<'a> QueryCommand<'a> {
+impl<'a, T: FromBins> QueryCommand<'a, T> {
     pub async fn new(
         policy: &'a QueryPolicy,
         statement: Arc<Statement>,
-        recordset: Arc<Recordset>,
+        recordset: Arc<Recordset<T>>,
         node_partitions: Arc<Mutex<NodePartitions>>,
+        codecs: BinCodecs,
     ) -> Self {
         let node = {
             let node_partitions = node_partitions.lock().await;
@@ -43,7 +46,7 @@ impl<'a> QueryCommand<'a> {
         };
 
         QueryCommand {
-            stream_command: StreamCommand::new(node, recordset, node_partitions, false),
+            stream_command: StreamCommand::new(node, recordset, node_partitions, codecs, false),
             policy,
             statement,
         }
@@ -55,7 +58,7 @@ impl<'a> QueryCommand<'a> {
 }
 
 #[async_trait::async_trait]
-impl Command for QueryCommand<'_> {
+impl<T: FromBins> Command for QueryCommand<'_, T> {
     async fn write_timeout(&mut self, conn: &mut Connection) -> Result<()> {
         let server_timeout = self
             .stream_command
@@ -102,6 +105,15 @@ impl Command for QueryCommand<'_> {
         false
     }
 
+    fn trace_span(&self) -> trace::Span {
+        trace::command_span(
+            "query",
+            &self.statement.namespace,
+            &self.statement.set_name,
+            None,
+        )
+    }
+
     async fn parse_result(&mut self, conn: &mut Connection) -> Result<()> {
         StreamCommand::parse_result(&mut self.stream_command, conn).await
     }
@@ -16,19 +16,30 @@ use std::collections::hash_map::Entry::{Occupied, Vacant};
 use std::collections::HashMap;
 use std::sync::Arc;
 
+use aerospike_rt::time::Instant;
+
 use crate::cluster::{Cluster, Node};
+use crate::codec::BinCodecs;
+use crate::commands::buffer::Buffer;
 use crate::commands::{Command, SingleCommand};
 use crate::errors::{Error, Result};
+use crate::interceptor::Interception;
 use crate::net::Connection;
 use crate::policy::{BasePolicy, Policy, Replica};
+use crate::trace;
 use crate::value::bytes_to_particle;
-use crate::{Bins, Key, Record, ResultCode, Value};
+use crate::{Bins, Key, Record, RecordView, ResultCode, Value};
 
 pub struct ReadCommand<'a> {
     pub single_command: SingleCommand<'a>,
     pub record: Option<Record>,
+    // The result of every operation in order, if requested by the operate command.
+    pub op_results: Option<Vec<(String, Value)>>,
+    // Called with a view of the record instead of parsing it into `record`, if set.
+    pub view_fn: Option<&'a mut (dyn FnMut(RecordView<'_>) + Send)>,
     policy: &'a BasePolicy,
     bins: Bins,
+    codecs: BinCodecs,
 }
 
 impl<'a> ReadCommand<'a> {
@@ -40,44 +51,73 @@ impl<'a> ReadCommand<'a> {
         replica: Replica,
     ) -> Self {
         ReadCommand {
+            codecs: cluster.codecs(),
             single_command: SingleCommand::new(cluster, key, replica),
             bins,
             policy,
             record: None,
+            op_results: None,
+            view_fn: None,
         }
     }
 
-    pub async fn execute(&mut self) -> Result<()> {
-        SingleCommand::execute(self.policy, self).await
+    pub async fn execute(&mut self, interception: &Interception<'_>) -> Result<()> {
+        SingleCommand::execute_intercepted(self.policy, self, interception).await
     }
 
-    fn parse_record(
-        &self,
-        conn: &mut Connection,
+    pub(crate) fn parse_record(
+        buffer: &mut Buffer,
         op_count: usize,
         field_count: usize,
         generation: u32,
         expiration: u32,
+    ) -> Result<Record> {
+        Self::parse_record_with_op_results(
+            buffer,
+            op_count,
+            field_count,
+            generation,
+            expiration,
+            None,
+        )
+    }
+
+    // Like `parse_record`, but also collects the result of every operation in order, including
+    // the nil results that are not kept in the record.
+    pub(crate) fn parse_record_with_op_results(
+        buffer: &mut Buffer,
+        op_count: usize,
+        field_count: usize,
+        generation: u32,
+        expiration: u32,
+        mut op_results: Option<&mut Vec<(String, Value)>>,
     ) -> Result<Record> {
         let mut bins: HashMap<String, Value> = HashMap::with_capacity(op_count);
+        if let Some(op_results) = op_results.as_deref_mut() {
+            op_results.clear();
+        }
 
         // There can be fields in the response (setname etc). For now, ignore them. Expose them to
         // the API if needed in the future.
         for _ in 0..field_count {
-            let field_size = conn.buffer.read_u32(None) as usize;
-            conn.buffer.skip(4 + field_size);
+            let field_size = buffer.read_u32(None) as usize;
+            buffer.skip(4 + field_size);
         }
 
         for _ in 0..op_count {
-            let op_size = conn.buffer.read_u32(None) as usize;
-            conn.buffer.skip(1);
-            let particle_type = conn.buffer.read_u8(None);
-            conn.buffer.skip(1);
-            let name_size = conn.buffer.read_u8(None) as usize;
-            let name: String = conn.buffer.read_str(name_size)?;
+            let op_size = buffer.read_u32(None) as usize;
+            buffer.skip(1);
+            let particle_type = buffer.read_u8(None);
+            buffer.skip(1);
+            let name_size = buffer.read_u8(None) as usize;
+            let name: String = buffer.read_str(name_size)?;
 
             let particle_bytes_size = op_size - (4 + name_size);
-            let value = bytes_to_particle(particle_type, &mut conn.buffer, particle_bytes_size)?;
+            let value = bytes_to_particle(particle_type, buffer, particle_bytes_size)?;
+
+            if let Some(op_results) = op_results.as_deref_mut() {
+                op_results.push((name.clone(), value.clone()));
+            }
 
             if !value.is_nil() {
                 // list/map operations may return multiple values for the same bin.
@@ -119,6 +159,10 @@ impl Command for ReadCommand<'_> {
         self.single_command.get_node()
     }
 
+    async fn refresh_partitions(&mut self, deadline: Option<Instant>) {
+        self.single_command.refresh_partitions(deadline).await;
+    }
+
     fn hint(&self) -> u8 {
         self.single_command.hint()
     }
@@ -131,6 +175,10 @@ impl Command for ReadCommand<'_> {
         true
     }
 
+    fn trace_span(&self) -> trace::Span {
+        trace::key_span("get", self.single_command.key)
+    }
+
     async fn parse_result(&mut self, conn: &mut Connection) -> Result<()> {
         if let Err(err) = conn.read_header().await {
             warn!("Parse result error: {err}");
@@ -157,18 +205,46 @@ impl Command for ReadCommand<'_> {
 
         match ResultCode::from(result_code) {
             ResultCode::Ok => {
+                if let Some(view_fn) = self.view_fn.as_mut() {
+                    let data = &conn.buffer.data_buffer[conn.buffer.data_offset()..];
+                    view_fn(RecordView::parse(
+                        data,
+                        field_count,
+                        op_count,
+                        generation,
+                        expiration,
+                    )?);
+                    return Ok(());
+                }
+
                 let record = if self.bins.is_none() {
                     Record::new(None, HashMap::new(), generation, expiration)
                 } else {
-                    self.parse_record(conn, op_count, field_count, generation, expiration)?
+                    let mut record = Self::parse_record_with_op_results(
+                        &mut conn.buffer,
+                        op_count,
+                        field_count,
+                        generation,
+                        expiration,
+                        self.op_results.as_mut(),
+                    )?;
+                    let key = self.single_command.key;
+                    self.codecs
+                        .decode_record(key, &mut record, self.op_results.as_mut())?;
+                    record
                 };
                 self.record = Some(record);
                 Ok(())
             }
             ResultCode::UdfBadResponse => {
                 // record bin "FAILURE" contains details about the UDF error
-                let record =
-                    self.parse_record(conn, op_count, field_count, generation, expiration)?;
+                let record = Self::parse_record(
+                    &mut conn.buffer,
+                    op_count,
+                    field_count,
+                    generation,
+                    expiration,
+                )?;
                 let reason = record
                     .bins
                     .get("FAILURE")
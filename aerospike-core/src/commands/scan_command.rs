@@ -18,29 +18,32 @@ use std::sync::Arc;
 use aerospike_rt::Mutex;
 
 use crate::cluster::Node;
+use crate::codec::BinCodecs;
 use crate::commands::{Command, SingleCommand, StreamCommand};
 use crate::errors::Result;
 use crate::net::Connection;
 use crate::policy::QueryPolicy;
 use crate::query::NodePartitions;
-use crate::{Bins, Recordset};
+use crate::trace;
+use crate::{Bins, FromBins, Record, Recordset};
 
-pub struct ScanCommand<'a> {
-    stream_command: StreamCommand,
+pub struct ScanCommand<'a, T: FromBins = Record> {
+    stream_command: StreamCommand<T>,
     policy: &'a QueryPolicy,
     namespace: &'a str,
     set_name: &'a str,
     bins: Bins,
 }
 
-impl<'a> ScanCommand<'a> {
+impl<'a, T: FromBins> ScanCommand<'a, T> {
     pub async fn new(
         policy: &'a QueryPolicy,
         namespace: &'a str,
         set_name: &'a str,
         bins: Bins,
-        recordset: Arc<Recordset>,
+        recordset: Arc<Recordset<T>>,
         node_partitions: Arc<Mutex<NodePartitions>>,
+        codecs: BinCodecs,
     ) -> Self {
         let node = {
             let node_partitions = node_partitions.lock().await;
@@ -48,7 +51,7 @@ impl<'a> ScanCommand<'a> {
         };
 
         ScanCommand {
-            stream_command: StreamCommand::new(node, recordset, node_partitions, true),
+            stream_command: StreamCommand::new(node, recordset, node_partitions, codecs, true),
             policy,
             namespace,
             set_name,
@@ -62,7 +65,7 @@ impl<'a> ScanCommand<'a> {
 }
 
 #[async_trait::async_trait]
-impl Command for ScanCommand<'_> {
+impl<T: FromBins> Command for ScanCommand<'_, T> {
     async fn write_timeout(&mut self, conn: &mut Connection) -> Result<()> {
         let server_timeout = self
             .stream_command
@@ -110,6 +113,10 @@ impl Command for ScanCommand<'_> {
         false
     }
 
+    fn trace_span(&self) -> trace::Span {
+        trace::command_span("scan", self.namespace, self.set_name, None)
+    }
+
     async fn parse_result(&mut self, conn: &mut Connection) -> Result<()> {
         StreamCommand::parse_result(&mut self.stream_command, conn).await
     }
@@ -21,6 +21,7 @@ use crate::errors::{Error, Result};
 use crate::net::{BufferedConn, Connection};
 use crate::operations::Operation;
 use crate::policy::{Policy, WritePolicy};
+use crate::trace;
 use crate::{ResultCode, Statement};
 
 /// Payload type for background server commands.
@@ -121,6 +122,15 @@ impl Command for ServerCommand<'_> {
         false
     }
 
+    fn trace_span(&self) -> trace::Span {
+        trace::command_span(
+            "query_background",
+            &self.statement.namespace,
+            &self.statement.set_name,
+            None,
+        )
+    }
+
     async fn parse_result(&mut self, conn: &mut Connection) -> Result<()> {
         // Server commands should only send back a return code.
         // Still parse the response to drain the socket.
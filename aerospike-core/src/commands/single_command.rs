@@ -16,10 +16,12 @@ use std::sync::Arc;
 
 use crate::cluster::partition::Partition;
 use crate::cluster::{Cluster, Node};
-use crate::commands::{self};
+use crate::commands::{self, InterceptedCommand};
 use crate::errors::{Error, Result};
+use crate::interceptor::Interception;
 use crate::net::Connection;
 use crate::policy::Policy;
+use crate::trace;
 use crate::Key;
 use aerospike_rt::sleep;
 use aerospike_rt::time::{Duration, Instant};
@@ -44,6 +46,10 @@ impl<'a> SingleCommand<'a> {
         }
     }
 
+    pub async fn refresh_partitions(&self, deadline: Option<Instant>) {
+        self.cluster.request_tend(deadline).await;
+    }
+
     pub const fn hint(&self) -> u8 {
         self.key.digest[0]
     }
@@ -76,10 +82,32 @@ impl<'a> SingleCommand<'a> {
     // EXECUTE
     //
 
-    #[allow(clippy::option_if_let_else)]
     pub async fn execute(
         policy: &(dyn Policy + Send + Sync),
         cmd: &'a mut (dyn commands::Command + Send),
+    ) -> Result<()> {
+        let span = cmd.trace_span();
+        let res = trace::instrument(span.clone(), Self::execute_with_timeout(policy, cmd)).await;
+        trace::record_result(&span, &res);
+        res
+    }
+
+    // Executes the command, calling the interceptors' retry hooks before each retry.
+    pub async fn execute_intercepted(
+        policy: &(dyn Policy + Send + Sync),
+        cmd: &mut (dyn commands::Command + Send),
+        interception: &Interception<'_>,
+    ) -> Result<()> {
+        if interception.is_empty() {
+            return SingleCommand::execute(policy, cmd).await;
+        }
+        SingleCommand::execute(policy, &mut InterceptedCommand::new(cmd, interception)).await
+    }
+
+    #[allow(clippy::option_if_let_else)]
+    async fn execute_with_timeout(
+        policy: &(dyn Policy + Send + Sync),
+        cmd: &'a mut (dyn commands::Command + Send),
     ) -> Result<()> {
         if policy.total_timeout() > 0 {
             match aerospike_rt::timeout(
@@ -100,7 +128,10 @@ impl<'a> SingleCommand<'a> {
         policy: &(dyn Policy + Send + Sync),
         cmd: &'a mut (dyn commands::Command + Send),
     ) -> Result<()> {
+        let span = trace::current();
         let mut iterations = 0;
+        let mut last_err = None;
+        let mut routing_error = false;
 
         // set timeout outside the loop
         let deadline = policy.deadline();
@@ -112,6 +143,10 @@ impl<'a> SingleCommand<'a> {
 
             // check for max retries
             if iterations > effective_attempt {
+                // surface server-side routing errors as-is once retries are exhausted
+                if let Some(err) = last_err.filter(commands::is_routing_error) {
+                    return Err(err);
+                }
                 // first attempt isn't a retry
                 return Err(Error::Timeout(format!("Timeout after {iterations} tries")));
             }
@@ -124,7 +159,16 @@ impl<'a> SingleCommand<'a> {
                     return Err(Error::Timeout("Timeout".to_string()));
                 }
 
-                if let Some(sleep_between_retries) = policy.sleep_between_retries() {
+                trace::retry(iterations);
+                if let Some(err) = last_err.take() {
+                    cmd.on_retry(iterations, &err);
+                }
+
+                // Waiting for a tend replaces the sleep when the partition's node couldn't be
+                // reached, as the partition map is likely stale.
+                if std::mem::take(&mut routing_error) {
+                    cmd.refresh_partitions(deadline).await;
+                } else if let Some(sleep_between_retries) = policy.sleep_between_retries() {
                     sleep(sleep_between_retries).await;
                 }
             }
@@ -143,14 +187,23 @@ impl<'a> SingleCommand<'a> {
                 e @ Err(Error::InvalidArgument(_)) => e?,
                 Err(e) => {
                     warn!("Error selecting node from the partition table: {e}");
+                    last_err = Some(e);
+                    routing_error = true;
                     continue;
                 } // Node is currently inactive. Retry.
             };
 
-            let mut conn = match node.get_connection(cmd.hint()).await {
+            trace::record_attempt(&span, &node, iterations);
+
+            let stopwatch = trace::Stopwatch::start();
+            let conn = node.get_connection(cmd.hint()).await;
+            trace::connection_acquired(&node, stopwatch, &conn);
+            let mut conn = match conn {
                 Ok(conn) => conn,
                 Err(err) => {
                     warn!("Node {node}: {err}");
+                    routing_error = commands::is_node_unavailable(&err);
+                    last_err = Some(err);
                     continue;
                 }
             };
@@ -164,6 +217,7 @@ impl<'a> SingleCommand<'a> {
             cmd.write_timeout(&mut conn)
                 .await
                 .map_err(|e| e.chain_error("Failed to set timeout for send buffer"))?;
+            trace::record_bytes_sent(&span, conn.buffer.data_buffer.len());
 
             // Send command.
             if let Err(err) = cmd.write_buffer(&mut conn).await {
@@ -171,11 +225,15 @@ impl<'a> SingleCommand<'a> {
                 // Close socket to flush out possible garbage. Do not put back in pool.
                 conn.invalidate();
                 warn!("Node {node}: {err}");
+                last_err = Some(err);
                 continue;
             }
 
             // Parse results.
-            if let Err(err) = cmd.parse_result(&mut conn).await {
+            let received = conn.bytes_received();
+            let res = cmd.parse_result(&mut conn).await;
+            trace::record_bytes_received(&span, conn.bytes_received() - received);
+            if let Err(err) = res {
                 // close the connection
                 // cancelling/closing the batch/multi commands will return an error, which will
                 // close the connection to throw away its data and signal the server about the
@@ -185,6 +243,13 @@ impl<'a> SingleCommand<'a> {
                 }
 
                 if commands::is_network_error(&err) {
+                    last_err = Some(err);
+                    continue;
+                }
+                if commands::is_routing_error(&err) {
+                    warn!("Node {node}: {err}");
+                    last_err = Some(err);
+                    routing_error = true;
                     continue;
                 }
                 return Err(err);
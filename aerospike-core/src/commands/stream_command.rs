@@ -12,46 +12,48 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use std::collections::hash_map::Entry::{Occupied, Vacant};
-use std::collections::HashMap;
 use std::sync::Arc;
 
 use aerospike_rt::Mutex;
 
 use crate::cluster::Node;
+use crate::codec::BinCodecs;
 use crate::commands::buffer;
 use crate::commands::field_type::FieldType;
-use crate::commands::Command;
+use crate::commands::{Command, ParticleType};
 use crate::errors::{Error, Result};
 use crate::net::{BufferedConn, Connection};
 use crate::query::{NodePartitions, Recordset};
 use crate::value::bytes_to_particle;
-use crate::{Key, Record, ResultCode, Value};
+use crate::{BinView, FromBins, Key, Record, RecordMeta, ResultCode, Value};
 
-pub struct StreamCommand {
+pub struct StreamCommand<T: FromBins = Record> {
     is_scan: bool,
     node: Arc<Node>,
-    pub(crate) recordset: Arc<Recordset>,
+    codecs: BinCodecs,
+    pub(crate) recordset: Arc<Recordset<T>>,
     pub(crate) node_partitions: Arc<Mutex<NodePartitions>>,
 }
 
-impl Drop for StreamCommand {
+impl<T: FromBins> Drop for StreamCommand<T> {
     fn drop(&mut self) {
         // signal_end
         self.recordset.signal_end();
     }
 }
 
-impl StreamCommand {
+impl<T: FromBins> StreamCommand<T> {
     pub const fn new(
         node: Arc<Node>,
-        recordset: Arc<Recordset>,
+        recordset: Arc<Recordset<T>>,
         node_partitions: Arc<Mutex<NodePartitions>>,
+        codecs: BinCodecs,
         is_scan: bool,
     ) -> Self {
         StreamCommand {
             is_scan,
             node,
+            codecs,
             recordset,
             node_partitions,
         }
@@ -61,7 +63,7 @@ impl StreamCommand {
         &self,
         conn: &mut BufferedConn<'_>,
         _size: usize,
-    ) -> Result<(Option<Record>, Option<u64>, bool)> {
+    ) -> Result<(Option<(Key, Result<T>)>, Option<u64>, bool)> {
         let result_code = ResultCode::from(conn.buffer().read_u8(Some(5)));
         if result_code != ResultCode::Ok {
             match result_code {
@@ -109,8 +111,9 @@ impl StreamCommand {
             return Ok((None, None, true));
         }
 
-        let mut bins: HashMap<String, Value> = HashMap::with_capacity(op_count);
-
+        // A bin that fails to decode fails the record, but the remaining bins still have to be
+        // read to keep the stream in sync.
+        let mut builder = Ok(T::Builder::default());
         for _ in 0..op_count {
             conn.read_buffer(8).await?;
             let op_size = conn.buffer().read_u32(None) as usize;
@@ -118,31 +121,27 @@ impl StreamCommand {
             let particle_type = conn.buffer().read_u8(None);
             conn.buffer().skip(1);
             let name_size = conn.buffer().read_u8(None) as usize;
-            conn.read_buffer(name_size).await?;
-            let name: String = conn.buffer().read_str(name_size)?;
-
-            let particle_bytes_size = op_size - (4 + name_size);
-            conn.read_buffer(particle_bytes_size).await?;
-            let value = bytes_to_particle(particle_type, conn.buffer(), particle_bytes_size)?;
-
-            if !value.is_nil() {
-                // list/map operations may return multiple values for the same bin.
-                match bins.entry(name) {
-                    Vacant(entry) => {
-                        entry.insert(value);
+
+            let bin_size = op_size - 4;
+            conn.read_buffer(bin_size).await?;
+            if let Ok(ref mut bins) = builder {
+                if particle_type != ParticleType::NULL as u8 {
+                    let data = conn.buffer().read_slice(bin_size);
+                    let bin = BinView::from_op(particle_type, data, name_size)?;
+                    if let Err(err) = self.codecs.decode_bin::<T>(&key, bins, &bin) {
+                        builder = Err(err);
                     }
-                    Occupied(entry) => match *entry.into_mut() {
-                        Value::MultiResult(ref mut list) => list.push(value),
-                        ref mut prev => {
-                            *prev = Value::MultiResult(vec![prev.clone(), value]);
-                        }
-                    },
                 }
             }
         }
 
-        let record = Record::new(Some(key), bins, generation, expiration);
-        Ok((Some(record), bval, true))
+        let meta = RecordMeta {
+            key: Some(key.clone()),
+            generation,
+            expiration,
+        };
+        let record = builder.and_then(|bins| T::build(bins, meta));
+        Ok((Some((key, record)), bval, true))
     }
 
     async fn parse_stream(&self, conn: &mut BufferedConn<'_>, size: usize) -> Result<bool> {
@@ -158,19 +157,18 @@ impl StreamCommand {
 
             let res = self.parse_record(conn, size).await;
             match res {
-                Ok((Some(rec), bval, _)) => {
+                Ok((Some((key, rec)), bval, _)) => {
                     let tracker = self.recordset.tracker.lock().await;
                     let mut node_partitions = self.node_partitions.lock().await;
                     if !tracker.allow_record(&mut node_partitions) {
                         continue 'outer;
                     }
-                    let key = &rec.key.clone().unwrap();
-                    self.recordset.push(Ok(rec)).await?;
+                    self.recordset.push(rec).await?;
 
                     if self.is_scan {
-                        tracker.set_digest(&mut node_partitions, key).await?;
+                        tracker.set_digest(&mut node_partitions, &key).await?;
                     } else {
-                        tracker.set_last(&mut node_partitions, key, bval).await?;
+                        tracker.set_last(&mut node_partitions, &key, bval).await?;
                     }
                     drop(tracker);
                     drop(node_partitions);
@@ -186,7 +184,9 @@ impl StreamCommand {
 
         Ok(true)
     }
+}
 
+impl StreamCommand {
     pub async fn parse_key(
         conn: &mut BufferedConn<'_>,
         field_count: usize,
@@ -242,7 +242,7 @@ impl StreamCommand {
 }
 
 #[async_trait::async_trait]
-impl Command for StreamCommand {
+impl<T: FromBins> Command for StreamCommand<T> {
     async fn write_timeout(&mut self, _conn: &mut Connection) -> Result<()> {
         // should be implemented downstream
         unreachable!()
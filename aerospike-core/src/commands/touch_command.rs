@@ -14,11 +14,15 @@
 
 use std::sync::Arc;
 
+use aerospike_rt::time::Instant;
+
 use crate::cluster::{Cluster, Node};
 use crate::commands::{Command, SingleCommand};
 use crate::errors::{Error, Result};
+use crate::interceptor::Interception;
 use crate::net::Connection;
 use crate::policy::{Policy, WritePolicy};
+use crate::trace;
 use crate::{Key, ResultCode};
 
 pub struct TouchCommand<'a> {
@@ -34,8 +38,8 @@ impl<'a> TouchCommand<'a> {
         }
     }
 
-    pub async fn execute(&mut self) -> Result<()> {
-        SingleCommand::execute(self.policy, self).await
+    pub async fn execute(&mut self, interception: &Interception<'_>) -> Result<()> {
+        SingleCommand::execute_intercepted(self.policy, self, interception).await
     }
 }
 
@@ -58,6 +62,10 @@ impl Command for TouchCommand<'_> {
         self.single_command.get_node()
     }
 
+    async fn refresh_partitions(&mut self, deadline: Option<Instant>) {
+        self.single_command.refresh_partitions(deadline).await;
+    }
+
     fn hint(&self) -> u8 {
         self.single_command.hint()
     }
@@ -70,6 +78,10 @@ impl Command for TouchCommand<'_> {
         true
     }
 
+    fn trace_span(&self) -> trace::Span {
+        trace::key_span("touch", self.single_command.key)
+    }
+
     async fn parse_result(&mut self, conn: &mut Connection) -> Result<()> {
         // Read header.
         if let Err(err) = conn.read_header().await {
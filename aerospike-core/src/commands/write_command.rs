@@ -14,12 +14,16 @@
 
 use std::sync::Arc;
 
+use aerospike_rt::time::Instant;
+
 use crate::cluster::{Cluster, Node};
 use crate::commands::{Command, SingleCommand};
 use crate::errors::{Error, Result};
+use crate::interceptor::Interception;
 use crate::net::Connection;
 use crate::operations::OperationType;
 use crate::policy::{Policy, WritePolicy};
+use crate::trace;
 use crate::{Bin, Key, ResultCode};
 
 pub struct WriteCommand<'a> {
@@ -45,8 +49,8 @@ impl<'a> WriteCommand<'a> {
         }
     }
 
-    pub async fn execute(&mut self) -> Result<()> {
-        SingleCommand::execute(self.policy, self).await
+    pub async fn execute(&mut self, interception: &Interception<'_>) -> Result<()> {
+        SingleCommand::execute_intercepted(self.policy, self, interception).await
     }
 }
 
@@ -74,6 +78,10 @@ impl Command for WriteCommand<'_> {
         self.single_command.get_node()
     }
 
+    async fn refresh_partitions(&mut self, deadline: Option<Instant>) {
+        self.single_command.refresh_partitions(deadline).await;
+    }
+
     fn hint(&self) -> u8 {
         self.single_command.hint()
     }
@@ -86,6 +94,16 @@ impl Command for WriteCommand<'_> {
         true
     }
 
+    fn trace_span(&self) -> trace::Span {
+        let operation = match self.operation {
+            OperationType::Incr => "add",
+            OperationType::Append => "append",
+            OperationType::Prepend => "prepend",
+            _ => "put",
+        };
+        trace::key_span(operation, self.single_command.key)
+    }
+
     async fn parse_result(&mut self, conn: &mut Connection) -> Result<()> {
         // Read header.
         if let Err(err) = conn.read_header().await {
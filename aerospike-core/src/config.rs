@@ -0,0 +1,548 @@
+// Copyright 2015-2020 Aerospike, Inc.
+//
+// Portions may be licensed to Aerospike, Inc. under one or more contributor
+// license agreements.
+//
+// Licensed under the Apache License, Version 2.0 (the "License"); you may not
+// use this file except in compliance with the License. You may obtain a copy of
+// the License at http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS, WITHOUT
+// WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied. See the
+// License for the specific language governing permissions and limitations under
+// the License.
+
+//! Client configuration loaded from a file, and reloaded while the client is running. Requires
+//! the `config-toml` or `config-yaml` feature.
+//!
+//! A configuration file sets the reloadable `ClientPolicy` values, and the defaults of the
+//! read, write, batch and query policies. Every value is optional, and uses the name and unit
+//! of the policy field it sets; all timeouts are in milliseconds:
+//!
+//! ```toml
+//! [client]
+//! timeout = 1000
+//! idle_timeout = 55000
+//! min_conns_per_node = 0
+//! max_conns_per_node = 256
+//! tend_interval = 1000
+//! tend_timeout = 1000
+//!
+//! [read]
+//! total_timeout = 1000
+//! socket_timeout = 500
+//! max_retries = 2
+//! sleep_between_retries = 0
+//! timeout_delay = 0
+//!
+//! [write]
+//! total_timeout = 1000
+//! send_key = true
+//! durable_delete = false
+//!
+//! [batch]
+//! total_timeout = 5000
+//!
+//! [query]
+//! socket_timeout = 30000
+//! ```
+//!
+//! A [`ConfigWatcher`] applies the file to a running [`Client`] without reconnecting, and
+//! applies it again every time the file changes. The client policy is replaced on the cluster
+//! and its nodes; open connections are kept, and the new values take effect for new
+//! connections, commands and tend cycles. Commands take their policy as an argument, so the
+//! default policies are read from the watcher:
+//!
+//! ```rust,no_run
+//! # use std::time::Duration;
+//! # use aerospike::{as_key, Bins, Client, ClientPolicy};
+//! # use aerospike::config::ConfigWatcher;
+//! # #[tokio::main]
+//! # async fn main() {
+//! # let hosts = std::env::var("AEROSPIKE_HOSTS").unwrap_or_else(|_| "127.0.0.1:3000".to_string());
+//! let client = Client::new(&ClientPolicy::default(), &hosts).await.unwrap();
+//! let watcher = ConfigWatcher::start(&client, "aerospike.toml", Duration::from_secs(5)).unwrap();
+//!
+//! let key = as_key!("test", "test", 1);
+//! let record = client.get(&watcher.defaults().read, &key, Bins::All).await;
+//! # }
+//! ```
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, Weak};
+use std::time::Duration;
+
+use hazarc::AtomicArc;
+use serde::Deserialize;
+
+use crate::cluster::Cluster;
+use crate::errors::{Error, Result};
+use crate::policy::{BasePolicy, BatchPolicy, QueryPolicy, ReadPolicy, WritePolicy};
+use crate::{Client, ClientPolicy};
+
+/// Contents of a configuration file. Values that are not set keep the value of the initial
+/// client policy, or of the default policies.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ClientConfig {
+    /// Reloadable values of the `ClientPolicy`.
+    pub client: ClientPolicyConfig,
+    /// Values of the default `ReadPolicy`.
+    pub read: BasePolicyConfig,
+    /// Values of the default `WritePolicy`.
+    pub write: WritePolicyConfig,
+    /// Values of the default `BatchPolicy`.
+    pub batch: BasePolicyConfig,
+    /// Values of the default `QueryPolicy`.
+    pub query: BasePolicyConfig,
+}
+
+/// Reloadable values of the `ClientPolicy`. See [`ClientPolicy`] for their meaning.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+#[allow(missing_docs)]
+pub struct ClientPolicyConfig {
+    pub timeout: Option<u32>,
+    pub idle_timeout: Option<u32>,
+    pub min_conns_per_node: Option<usize>,
+    pub max_conns_per_node: Option<usize>,
+    pub tend_interval: Option<u32>,
+    pub tend_timeout: Option<u32>,
+}
+
+/// Values of a default policy. See [`BasePolicy`] for their meaning.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+#[allow(missing_docs)]
+pub struct BasePolicyConfig {
+    pub total_timeout: Option<u32>,
+    pub socket_timeout: Option<u32>,
+    pub timeout_delay: Option<u32>,
+    pub max_retries: Option<usize>,
+    pub sleep_between_retries: Option<u32>,
+}
+
+/// Values of the default write policy. See [`WritePolicy`] for their meaning.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+#[allow(missing_docs)]
+pub struct WritePolicyConfig {
+    pub total_timeout: Option<u32>,
+    pub socket_timeout: Option<u32>,
+    pub timeout_delay: Option<u32>,
+    pub max_retries: Option<usize>,
+    pub sleep_between_retries: Option<u32>,
+    pub send_key: Option<bool>,
+    pub durable_delete: Option<bool>,
+}
+
+/// Default policies for commands, as set by a configuration file.
+#[derive(Debug, Clone, Default)]
+pub struct PolicyDefaults {
+    /// Default policy for reads.
+    pub read: ReadPolicy,
+    /// Default policy for writes, deletes and operations.
+    pub write: WritePolicy,
+    /// Default policy for batch commands.
+    pub batch: BatchPolicy,
+    /// Default policy for queries and scans.
+    pub query: QueryPolicy,
+}
+
+impl ClientConfig {
+    /// Parses a TOML configuration.
+    #[cfg(feature = "config-toml")]
+    pub fn from_toml(content: &str) -> Result<Self> {
+        let config: Self = toml::from_str(content)
+            .map_err(|err| Error::InvalidArgument(format!("Invalid TOML configuration: {err}")))?;
+        config.validate()?;
+        Ok(config)
+    }
+
+    /// Parses a YAML configuration. An empty document is an empty configuration.
+    #[cfg(feature = "config-yaml")]
+    pub fn from_yaml(content: &str) -> Result<Self> {
+        let config: Option<Self> = serde_yaml::from_str(content)
+            .map_err(|err| Error::InvalidArgument(format!("Invalid YAML configuration: {err}")))?;
+        let config = config.unwrap_or_default();
+        config.validate()?;
+        Ok(config)
+    }
+
+    /// Reads a configuration file. The format is chosen by the file's extension: `.toml`, or
+    /// `.yaml` and `.yml`.
+    pub fn from_file(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        let content = fs::read_to_string(path).map_err(|err| {
+            Error::from(err).chain_error(&format!("Could not read {}", path.display()))
+        })?;
+        ClientConfig::parse(path, &content)
+    }
+
+    fn parse(path: &Path, content: &str) -> Result<Self> {
+        match path.extension().and_then(|ext| ext.to_str()) {
+            #[cfg(feature = "config-toml")]
+            Some("toml") => ClientConfig::from_toml(content),
+            #[cfg(feature = "config-yaml")]
+            Some("yaml" | "yml") => ClientConfig::from_yaml(content),
+            _ => Err(Error::InvalidArgument(format!(
+                "Unsupported configuration file format: {}",
+                path.display()
+            ))),
+        }
+    }
+
+    fn validate(&self) -> Result<()> {
+        let client = &self.client;
+        if client.max_conns_per_node == Some(0) {
+            return Err(Error::InvalidArgument(
+                "max_conns_per_node must be greater than 0".to_string(),
+            ));
+        }
+        if client.tend_interval == Some(0) {
+            return Err(Error::InvalidArgument(
+                "tend_interval must be greater than 0".to_string(),
+            ));
+        }
+        if let (Some(min), Some(max)) = (client.min_conns_per_node, client.max_conns_per_node) {
+            if min > max {
+                return Err(Error::InvalidArgument(format!(
+                    "min_conns_per_node ({min}) must not exceed max_conns_per_node ({max})"
+                )));
+            }
+        }
+        Ok(())
+    }
+
+    /// Returns the initial client policy with the values of the configuration applied. Fails if
+    /// the resulting policy is invalid, e.g. if `max_conns_per_node` is less than the initial
+    /// policy's `conn_pools_per_node`.
+    pub fn client_policy(&self, initial: &ClientPolicy) -> Result<ClientPolicy> {
+        let config = &self.client;
+        let mut policy = initial.clone();
+        policy.timeout = config.timeout.unwrap_or(policy.timeout);
+        policy.idle_timeout = config.idle_timeout.unwrap_or(policy.idle_timeout);
+        policy.min_conns_per_node = config
+            .min_conns_per_node
+            .unwrap_or(policy.min_conns_per_node);
+        policy.max_conns_per_node = config
+            .max_conns_per_node
+            .unwrap_or(policy.max_conns_per_node);
+        policy.tend_interval = config.tend_interval.unwrap_or(policy.tend_interval);
+        policy.tend_timeout = config.tend_timeout.unwrap_or(policy.tend_timeout);
+        policy.validate().map_err(|err| {
+            Error::InvalidArgument(format!("Invalid client configuration: {err}"))
+        })?;
+        Ok(policy)
+    }
+
+    /// Returns the default policies with the values of the configuration applied.
+    pub fn policy_defaults(&self) -> PolicyDefaults {
+        let mut defaults = PolicyDefaults::default();
+        self.read.apply(&mut defaults.read.base_policy);
+        self.write.base().apply(&mut defaults.write.base_policy);
+        if let Some(send_key) = self.write.send_key {
+            defaults.write.send_key = send_key;
+        }
+        if let Some(durable_delete) = self.write.durable_delete {
+            defaults.write.durable_delete = durable_delete;
+        }
+        self.batch.apply(&mut defaults.batch.base_policy);
+        self.query.apply(&mut defaults.query.base_policy);
+        defaults
+    }
+}
+
+impl BasePolicyConfig {
+    fn apply(&self, policy: &mut BasePolicy) {
+        policy.total_timeout = self.total_timeout.unwrap_or(policy.total_timeout);
+        policy.socket_timeout = self.socket_timeout.unwrap_or(policy.socket_timeout);
+        policy.timeout_delay = self.timeout_delay.unwrap_or(policy.timeout_delay);
+        policy.max_retries = self.max_retries.unwrap_or(policy.max_retries);
+        policy.sleep_between_retries = self
+            .sleep_between_retries
+            .unwrap_or(policy.sleep_between_retries);
+    }
+}
+
+impl WritePolicyConfig {
+    const fn base(&self) -> BasePolicyConfig {
+        BasePolicyConfig {
+            total_timeout: self.total_timeout,
+            socket_timeout: self.socket_timeout,
+            timeout_delay: self.timeout_delay,
+            max_retries: self.max_retries,
+            sleep_between_retries: self.sleep_between_retries,
+        }
+    }
+}
+
+/// Watches a configuration file, and applies it to a running [`Client`] every time it
+/// changes.
+///
+/// The file is polled at a fixed interval and applied when its content changed. A file that
+/// can not be read or parsed is logged and ignored, keeping the current settings. Values that
+/// are removed from the file revert to the client policy the client was created with, or to
+/// the default policies.
+///
+/// The watcher stops when it is dropped, or when the client is closed.
+#[derive(Debug)]
+pub struct ConfigWatcher {
+    state: Arc<WatcherState>,
+}
+
+#[derive(Debug)]
+struct WatcherState {
+    path: PathBuf,
+    cluster: Arc<Cluster>,
+    initial: ClientPolicy,
+    defaults: AtomicArc<PolicyDefaults>,
+    // content of the last applied file
+    content: Mutex<String>,
+}
+
+impl ConfigWatcher {
+    /// Applies the configuration file to the client, and starts watching it for changes every
+    /// `poll_interval`. Fails if the file can not be read or is invalid.
+    pub fn start(client: &Client, path: impl AsRef<Path>, poll_interval: Duration) -> Result<Self> {
+        let path = path.as_ref().to_path_buf();
+        let state = Arc::new(WatcherState {
+            path,
+            cluster: client.cluster.clone(),
+            initial: client.cluster.client_policy(),
+            defaults: AtomicArc::from(PolicyDefaults::default()),
+            content: Mutex::new(String::new()),
+        });
+        state.reload(true)?;
+
+        let weak = Arc::downgrade(&state);
+        let _res = aerospike_rt::spawn(ConfigWatcher::watch(weak, poll_interval));
+        Ok(ConfigWatcher { state })
+    }
+
+    async fn watch(state: Weak<WatcherState>, poll_interval: Duration) {
+        loop {
+            aerospike_rt::sleep(poll_interval).await;
+            let Some(state) = state.upgrade() else {
+                break;
+            };
+            if state.cluster.is_closed() {
+                break;
+            }
+            let content = WatcherState::read_async(state.path.clone()).await;
+            if let Err(err) = content.and_then(|content| state.apply(content, false)) {
+                log_error_chain!(
+                    err,
+                    "Could not reload the client configuration from {}",
+                    state.path.display()
+                );
+            }
+        }
+    }
+
+    /// Reads the configuration file and applies it if it changed since it was last applied.
+    /// Returns true if it was applied.
+    pub fn reload(&self) -> Result<bool> {
+        self.state.reload(false)
+    }
+
+    /// Returns the current default policies.
+    pub fn defaults(&self) -> Arc<PolicyDefaults> {
+        self.state.defaults.load_owned()
+    }
+
+    /// Returns the path of the watched configuration file.
+    pub fn path(&self) -> &Path {
+        &self.state.path
+    }
+}
+
+impl WatcherState {
+    fn read(path: &Path) -> Result<String> {
+        fs::read_to_string(path).map_err(|err| {
+            Error::from(err).chain_error(&format!("Could not read {}", path.display()))
+        })
+    }
+
+    // Same as `read`, but reads the file on a blocking task so that polling does not stall the
+    // async runtime.
+    async fn read_async(path: PathBuf) -> Result<String> {
+        #[cfg(all(feature = "rt-tokio", not(feature = "rt-async-std")))]
+        return aerospike_rt::task::spawn_blocking(move || WatcherState::read(&path))
+            .await
+            .unwrap_or_else(|err| {
+                Err(Error::ClientError(format!(
+                    "Reading the configuration file failed: {err}"
+                )))
+            });
+        #[cfg(all(feature = "rt-async-std", not(feature = "rt-tokio")))]
+        return aerospike_rt::task::spawn_blocking(move || WatcherState::read(&path)).await;
+    }
+
+    fn reload(&self, force: bool) -> Result<bool> {
+        let content = WatcherState::read(&self.path)?;
+        self.apply(content, force)
+    }
+
+    // Applies the content of the file, unless it is the content that was last applied.
+    fn apply(&self, content: String, force: bool) -> Result<bool> {
+        let mut applied = self
+            .content
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner);
+        if !force && *applied == content {
+            return Ok(false);
+        }
+
+        let config = ClientConfig::parse(&self.path, &content)?;
+        self.cluster
+            .set_client_policy(config.client_policy(&self.initial)?);
+        self.defaults.store(Arc::new(config.policy_defaults()));
+        *applied = content;
+        drop(applied);
+
+        info!("Applied client configuration from {}", self.path.display());
+        Ok(true)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ClientConfig;
+    use crate::ClientPolicy;
+
+    #[test]
+    #[cfg(feature = "config-toml")]
+    fn parse_toml() {
+        let config = ClientConfig::from_toml(
+            r"
+            [client]
+            timeout = 2000
+            tend_interval = 500
+
+            [read]
+            total_timeout = 100
+            max_retries = 5
+
+            [write]
+            socket_timeout = 50
+            send_key = true
+            ",
+        )
+        .unwrap();
+        assert_eq!(config.client.timeout, Some(2000));
+        assert_eq!(config.client.tend_interval, Some(500));
+        assert_eq!(config.client.max_conns_per_node, None);
+        assert_eq!(config.read.max_retries, Some(5));
+        assert_eq!(config.write.socket_timeout, Some(50));
+        assert_eq!(config.write.send_key, Some(true));
+        assert_eq!(config.batch, super::BasePolicyConfig::default());
+
+        assert_eq!(
+            ClientConfig::from_toml("").unwrap(),
+            ClientConfig::default()
+        );
+        assert!(ClientConfig::from_toml("[client]\ntimeout = \"fast\"").is_err());
+        assert!(ClientConfig::from_toml("[read]\nsend_key = true").is_err());
+        assert!(ClientConfig::from_toml("[client]\nmax_conns_per_node = 0").is_err());
+        assert!(ClientConfig::from_toml(
+            "[client]\nmin_conns_per_node = 10\nmax_conns_per_node = 5"
+        )
+        .is_err());
+    }
+
+    #[test]
+    #[cfg(feature = "config-yaml")]
+    fn parse_yaml() {
+        use std::path::Path;
+
+        let config = ClientConfig::from_yaml(
+            "
+            client:
+              idle_timeout: 1000
+            query:
+              socket_timeout: 30000
+            write:
+              durable_delete: true
+            ",
+        )
+        .unwrap();
+        assert_eq!(config.client.idle_timeout, Some(1000));
+        assert_eq!(config.query.socket_timeout, Some(30000));
+        assert_eq!(config.write.durable_delete, Some(true));
+
+        assert_eq!(
+            ClientConfig::from_yaml("").unwrap(),
+            ClientConfig::default()
+        );
+        assert!(ClientConfig::from_yaml("clients:\n  timeout: 1").is_err());
+        assert!(ClientConfig::parse(Path::new("aerospike.yml"), "read: {}").is_ok());
+        assert!(ClientConfig::parse(Path::new("aerospike.json"), "{}").is_err());
+    }
+
+    #[test]
+    fn apply() {
+        let mut config = ClientConfig::default();
+        config.client.timeout = Some(2000);
+        config.client.max_conns_per_node = Some(10);
+        config.read.total_timeout = Some(100);
+        config.write.max_retries = Some(0);
+        config.write.send_key = Some(true);
+        config.query.sleep_between_retries = Some(7);
+
+        let initial = ClientPolicy {
+            idle_timeout: 1234,
+            application_id: Some("app".to_string()),
+            ..ClientPolicy::default()
+        };
+        let policy = config.client_policy(&initial).unwrap();
+        assert_eq!(policy.timeout, 2000);
+        assert_eq!(policy.max_conns_per_node, 10);
+        assert_eq!(policy.idle_timeout, 1234);
+        assert_eq!(policy.tend_interval, initial.tend_interval);
+        assert_eq!(policy.application_id.as_deref(), Some("app"));
+
+        let defaults = config.policy_defaults();
+        assert_eq!(defaults.read.base_policy.total_timeout, 100);
+        assert_eq!(defaults.write.base_policy.max_retries, 0);
+        assert!(defaults.write.send_key);
+        assert_eq!(defaults.query.base_policy.sleep_between_retries, 7);
+        let batch = crate::BatchPolicy::default();
+        assert_eq!(
+            defaults.batch.base_policy.total_timeout,
+            batch.base_policy.total_timeout
+        );
+
+        // values removed from the file revert to the initial ones
+        let policy = ClientConfig::default().client_policy(&initial).unwrap();
+        assert_eq!(policy.timeout, initial.timeout);
+        assert!(!ClientConfig::default().policy_defaults().write.send_key);
+    }
+
+    #[test]
+    fn apply_invalid() {
+        let initial = ClientPolicy {
+            conn_pools_per_node: 4,
+            ..ClientPolicy::default()
+        };
+        let mut config = ClientConfig::default();
+        config.client.max_conns_per_node = Some(3);
+        assert!(config.client_policy(&initial).is_err());
+
+        config.client.max_conns_per_node = Some(4);
+        assert_eq!(
+            config.client_policy(&initial).unwrap().max_conns_per_node,
+            4
+        );
+
+        // the initial minimum may exceed a maximum that is set by the file alone
+        config.client.max_conns_per_node = Some(8);
+        let initial = ClientPolicy {
+            min_conns_per_node: 10,
+            ..initial
+        };
+        assert!(config.client_policy(&initial).is_err());
+    }
+}
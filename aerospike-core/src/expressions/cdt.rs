@@ -0,0 +1,109 @@
+// Copyright 2015-2020 Aerospike, Inc.
+//
+// Portions may be licensed to Aerospike, Inc. under one or more contributor
+// license agreements.
+//
+// Licensed under the Apache License, Version 2.0 (the "License"); you may not
+// use this file except in compliance with the License. You may obtain a copy of
+// the License at http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS, WITHOUT
+// WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied. See the
+// License for the specific language governing permissions and limitations under
+// the License.
+
+//! Path Cdt Aerospike Filter Expressions, which apply to both lists and maps.
+//!
+//! ```
+//! use aerospike::expressions::cdt::select_by_path;
+//! use aerospike::expressions::{gt, int_val, loop_var, map_bin};
+//! use aerospike::expressions::{ExpType, LoopVarPart};
+//! use aerospike::operations::cdt_context::{ctx_all_children, ctx_all_children_with_filter};
+//! use aerospike::operations::SelectFlags;
+//!
+//! // Prices of all items in all orders that are above 100.
+//! let prices = select_by_path(
+//!     ExpType::LIST,
+//!     SelectFlags::Values,
+//!     map_bin("orders".to_string()),
+//!     &[
+//!         ctx_all_children(),
+//!         ctx_all_children_with_filter(&gt(
+//!             loop_var(ExpType::INT, LoopVarPart::Value),
+//!             int_val(100),
+//!         ))
+//!         .unwrap(),
+//!     ],
+//! );
+//! ```
+
+use crate::expressions::{ExpOp, ExpType, Expression, ExpressionArgument, MODIFY};
+use crate::operations::cdt::{
+    ToModifyFlagsBitmask, ToSelectFlagsBitmask, CDT_SELECT, SELECT_APPLY,
+};
+use crate::operations::cdt_context::CdtContext;
+use crate::Value;
+
+const MODULE: i64 = 0;
+
+/// Creates expression that selects the elements of the list or map bin that match the context.
+///
+/// The context may contain [`ctx_all_children`] and [`ctx_all_children_with_filter`] steps at any
+/// level. The flags determine what is returned for the selected elements.
+/// Requires server version 8.1+.
+///
+/// [`ctx_all_children`]: crate::operations::cdt_context::ctx_all_children
+/// [`ctx_all_children_with_filter`]: crate::operations::cdt_context::ctx_all_children_with_filter
+pub fn select_by_path<TSF: ToSelectFlagsBitmask>(
+    return_type: ExpType,
+    flags: TSF,
+    bin: Expression,
+    ctx: &[CdtContext],
+) -> Expression {
+    let args = vec![
+        ExpressionArgument::Value(Value::from(CDT_SELECT)),
+        ExpressionArgument::PathContext(ctx.to_vec()),
+        ExpressionArgument::Value(Value::from(flags.to_bitmask())),
+    ];
+    add_call(bin, return_type, MODULE, args)
+}
+
+/// Creates expression that modifies the elements of the list or map bin that match the context.
+///
+/// Each element is replaced with the result of the modify expression, in which the element is
+/// available through [`loop_var`](crate::expressions::loop_var). Returns the modified bin.
+/// Requires server version 8.1+.
+pub fn modify_by_path<TMF: ToModifyFlagsBitmask>(
+    return_type: ExpType,
+    flags: TMF,
+    modify_exp: Expression,
+    bin: Expression,
+    ctx: &[CdtContext],
+) -> Expression {
+    let args = vec![
+        ExpressionArgument::Value(Value::from(CDT_SELECT)),
+        ExpressionArgument::PathContext(ctx.to_vec()),
+        ExpressionArgument::Value(Value::from(flags.to_bitmask() | SELECT_APPLY)),
+        ExpressionArgument::FilterExpression(modify_exp),
+    ];
+    add_call(bin, return_type, MODULE | MODIFY, args)
+}
+
+fn add_call(
+    bin: Expression,
+    return_type: ExpType,
+    flags: i64,
+    arguments: Vec<ExpressionArgument>,
+) -> Expression {
+    Expression {
+        cmd: Some(ExpOp::Call),
+        val: None,
+        bin: Some(Box::new(bin)),
+        flags: Some(flags),
+        module: Some(return_type),
+        exps: None,
+        arguments: Some(arguments),
+        bytes: None,
+    }
+}
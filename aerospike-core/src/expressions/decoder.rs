@@ -0,0 +1,719 @@
+// Copyright 2015-2020 Aerospike, Inc.
+//
+// Portions may be licensed to Aerospike, Inc. under one or more contributor
+// license agreements.
+//
+// Licensed under the Apache License, Version 2.0 (the "License"); you may not
+// use this file except in compliance with the License. You may obtain a copy of
+// the License at http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS, WITHOUT
+// WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied. See the
+// License for the specific language governing permissions and limitations under
+// the License.
+
+// Decoder for the msgpack wire format of filter expressions.
+//
+// This is the inverse of `Expression::pack`. The input usually comes from configuration rather
+// than from the server, so every read is bounds checked and malformed input results in an
+// error instead of a panic.
+
+use std::collections::{BTreeMap, HashMap};
+use std::convert::TryFrom;
+
+use crate::commands::ParticleType;
+use crate::expressions::{ExpOp, ExpType, Expression, ExpressionArgument};
+use crate::operations::cdt::CDT_SELECT;
+use crate::operations::cdt_context::{CdtContext, CtxType};
+use crate::{Error, Result, Value};
+
+// Bits of a packed context id that hold the create flags of the context.
+const CTX_FLAGS: u8 = 0xc0;
+
+pub fn decode(bytes: &[u8]) -> Result<Expression> {
+    let mut decoder = Decoder { buf: bytes, pos: 0 };
+    let exp = decoder.expression()?;
+    if decoder.pos != bytes.len() {
+        return Err(decoder.error("trailing bytes after expression"));
+    }
+    Ok(exp)
+}
+
+const fn exp_op(code: i64) -> Option<ExpOp> {
+    let op = match code {
+        0 => ExpOp::Unknown,
+        1 => ExpOp::EQ,
+        2 => ExpOp::NE,
+        3 => ExpOp::GT,
+        4 => ExpOp::GE,
+        5 => ExpOp::LT,
+        6 => ExpOp::LE,
+        7 => ExpOp::Regex,
+        8 => ExpOp::Geo,
+        16 => ExpOp::And,
+        17 => ExpOp::Or,
+        18 => ExpOp::Not,
+        19 => ExpOp::Xor,
+        20 => ExpOp::Add,
+        21 => ExpOp::Sub,
+        22 => ExpOp::Mul,
+        23 => ExpOp::Div,
+        24 => ExpOp::Pow,
+        25 => ExpOp::Log,
+        26 => ExpOp::Mod,
+        27 => ExpOp::Abs,
+        28 => ExpOp::Floor,
+        29 => ExpOp::Ceil,
+        30 => ExpOp::ToInt,
+        31 => ExpOp::ToFloat,
+        32 => ExpOp::IntAnd,
+        33 => ExpOp::IntOr,
+        34 => ExpOp::IntXor,
+        35 => ExpOp::IntNot,
+        36 => ExpOp::IntLshift,
+        37 => ExpOp::IntRshift,
+        38 => ExpOp::IntARshift,
+        39 => ExpOp::IntCount,
+        40 => ExpOp::IntLscan,
+        41 => ExpOp::IntRscan,
+        50 => ExpOp::Min,
+        51 => ExpOp::Max,
+        64 => ExpOp::DigestModulo,
+        65 => ExpOp::DeviceSize,
+        66 => ExpOp::LastUpdate,
+        67 => ExpOp::SinceUpdate,
+        68 => ExpOp::VoidTime,
+        69 => ExpOp::TTL,
+        70 => ExpOp::SetName,
+        71 => ExpOp::KeyExists,
+        72 => ExpOp::IsTombstone,
+        73 => ExpOp::MemorySize,
+        74 => ExpOp::RecordSize,
+        80 => ExpOp::Key,
+        81 => ExpOp::Bin,
+        82 => ExpOp::BinType,
+        122 => ExpOp::VarBuiltin,
+        123 => ExpOp::Cond,
+        124 => ExpOp::Var,
+        125 => ExpOp::Let,
+        126 => ExpOp::Quoted,
+        127 => ExpOp::Call,
+        _ => return None,
+    };
+    Some(op)
+}
+
+pub const fn exp_type(code: i64) -> Option<ExpType> {
+    let exp_type = match code {
+        0 => ExpType::NIL,
+        1 => ExpType::BOOL,
+        2 => ExpType::INT,
+        3 => ExpType::STRING,
+        4 => ExpType::LIST,
+        5 => ExpType::MAP,
+        6 => ExpType::BLOB,
+        7 => ExpType::FLOAT,
+        8 => ExpType::GEO,
+        9 => ExpType::HLL,
+        _ => return None,
+    };
+    Some(exp_type)
+}
+
+struct Decoder<'a> {
+    buf: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Decoder<'a> {
+    fn error(&self, msg: &str) -> Error {
+        Error::BadResponse(format!("Invalid expression at byte {}: {}", self.pos, msg))
+    }
+
+    fn peek(&self) -> Result<u8> {
+        self.buf
+            .get(self.pos)
+            .copied()
+            .ok_or_else(|| self.error("unexpected end of input"))
+    }
+
+    fn take(&mut self, n: usize) -> Result<&'a [u8]> {
+        let end = self.pos.checked_add(n).filter(|end| *end <= self.buf.len());
+        match end {
+            Some(end) => {
+                let bytes = &self.buf[self.pos..end];
+                self.pos = end;
+                Ok(bytes)
+            }
+            None => Err(self.error("unexpected end of input")),
+        }
+    }
+
+    fn read_u8(&mut self) -> Result<u8> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn read_be<const N: usize>(&mut self) -> Result<[u8; N]> {
+        let mut bytes = [0; N];
+        bytes.copy_from_slice(self.take(N)?);
+        Ok(bytes)
+    }
+
+    fn read_len(&mut self, marker: u8) -> Result<usize> {
+        let len = match marker {
+            0xc4 | 0xd9 => usize::from(self.read_u8()?),
+            0xc5 | 0xda | 0xdc | 0xde => usize::from(u16::from_be_bytes(self.read_be()?)),
+            0xc6 | 0xdb | 0xdd | 0xdf => u32::from_be_bytes(self.read_be()?) as usize,
+            _ => unreachable!(),
+        };
+        Ok(len)
+    }
+
+    // Reads an array header if the next item is an array.
+    fn array_begin(&mut self) -> Result<Option<usize>> {
+        let marker = self.peek()?;
+        match marker {
+            0x90..=0x9f => {
+                self.pos += 1;
+                Ok(Some(usize::from(marker & 0x0f)))
+            }
+            0xdc | 0xdd => {
+                self.pos += 1;
+                self.read_len(marker).map(Some)
+            }
+            _ => Ok(None),
+        }
+    }
+
+    fn expect_array(&mut self) -> Result<usize> {
+        self.array_begin()?
+            .ok_or_else(|| self.error("expected an array"))
+    }
+
+    fn read_integer(&mut self) -> Result<i64> {
+        let marker = self.read_u8()?;
+        let val = match marker {
+            0x00..=0x7f => i64::from(marker),
+            0xe0..=0xff => i64::from(marker as i8),
+            0xcc => i64::from(self.read_u8()?),
+            0xcd => i64::from(u16::from_be_bytes(self.read_be()?)),
+            0xce => i64::from(u32::from_be_bytes(self.read_be()?)),
+            0xcf => u64::from_be_bytes(self.read_be()?) as i64,
+            0xd0 => i64::from(i8::from_be_bytes(self.read_be()?)),
+            0xd1 => i64::from(i16::from_be_bytes(self.read_be()?)),
+            0xd2 => i64::from(i32::from_be_bytes(self.read_be()?)),
+            0xd3 => i64::from_be_bytes(self.read_be()?),
+            _ => {
+                self.pos -= 1;
+                return Err(self.error("expected an integer"));
+            }
+        };
+        Ok(val)
+    }
+
+    fn read_bytes(&mut self) -> Result<&'a [u8]> {
+        let marker = self.read_u8()?;
+        let len = match marker {
+            0xa0..=0xbf => usize::from(marker & 0x1f),
+            0xc4..=0xc6 | 0xd9..=0xdb => self.read_len(marker)?,
+            _ => {
+                self.pos -= 1;
+                return Err(self.error("expected a string"));
+            }
+        };
+        self.take(len)
+    }
+
+    // Names of bins and variables are packed without a particle type prefix.
+    fn read_raw_string(&mut self) -> Result<String> {
+        let bytes = self.read_bytes()?;
+        String::from_utf8(bytes.to_vec()).map_err(|_| self.error("invalid UTF-8 in string"))
+    }
+
+    fn read_particle(&mut self) -> Result<Value> {
+        let bytes = self.read_bytes()?;
+        let (ptype, data) = match bytes.split_first() {
+            Some((ptype, data)) => (*ptype, data),
+            None => return Ok(Value::String(String::new())),
+        };
+        let string =
+            || String::from_utf8(data.to_vec()).map_err(|_| self.error("invalid UTF-8 in string"));
+        match ParticleType::from(ptype) {
+            ParticleType::STRING => Ok(Value::String(string()?)),
+            ParticleType::BLOB => Ok(Value::Blob(data.to_vec())),
+            ParticleType::GEOJSON => Ok(Value::GeoJSON(string()?)),
+            _ => Err(self.error(&format!("unsupported particle type {ptype}"))),
+        }
+    }
+
+    fn read_value(&mut self) -> Result<Value> {
+        let marker = self.peek()?;
+        match marker {
+            0x00..=0x7f | 0xcc..=0xd3 | 0xe0..=0xff => self.read_integer().map(Value::Int),
+            0xc0 => {
+                self.pos += 1;
+                Ok(Value::Nil)
+            }
+            0xc2 | 0xc3 => {
+                self.pos += 1;
+                Ok(Value::Bool(marker == 0xc3))
+            }
+            0xca => {
+                self.pos += 1;
+                Ok(Value::from(f32::from_be_bytes(self.read_be()?)))
+            }
+            0xcb => {
+                self.pos += 1;
+                Ok(Value::from(f64::from_be_bytes(self.read_be()?)))
+            }
+            0xa0..=0xbf | 0xc4..=0xc6 | 0xd9..=0xdb => self.read_particle(),
+            0x90..=0x9f | 0xdc | 0xdd => {
+                let count = self.expect_array()?;
+                let mut list = Vec::with_capacity(count.min(self.buf.len()));
+                for _ in 0..count {
+                    list.push(self.read_value()?);
+                }
+                Ok(Value::List(list))
+            }
+            0x80..=0x8f | 0xde | 0xdf => self.read_map(),
+            0xd4 => {
+                self.pos += 1;
+                match self.take(2)? {
+                    [0xff, 0x01] => Ok(Value::Infinity),
+                    [0xff, 0x00] => Ok(Value::Wildcard),
+                    _ => Err(self.error("unsupported extension type")),
+                }
+            }
+            _ => Err(self.error(&format!("unsupported msgpack type {marker:#x}"))),
+        }
+    }
+
+    fn read_map(&mut self) -> Result<Value> {
+        let marker = self.read_u8()?;
+        let mut count = match marker {
+            0x80..=0x8f => usize::from(marker & 0x0f),
+            _ => self.read_len(marker)?,
+        };
+
+        // Ordered maps start with an extension entry that carries the map order.
+        let mut ordered = false;
+        if count > 0 && self.peek()? == 0xc7 {
+            self.pos += 1;
+            let len = usize::from(self.read_u8()?);
+            ordered = self.read_u8()? & 0x01 != 0;
+            self.take(len)?;
+            self.read_value()?;
+            count -= 1;
+        }
+
+        if ordered {
+            let mut map = BTreeMap::new();
+            for _ in 0..count {
+                let key = self.read_value()?;
+                map.insert(key, self.read_value()?);
+            }
+            Ok(Value::OrderedMap(map))
+        } else {
+            let mut map = HashMap::with_capacity(count.min(self.buf.len()));
+            for _ in 0..count {
+                let key = self.read_value()?;
+                map.insert(key, self.read_value()?);
+            }
+            Ok(Value::HashMap(map))
+        }
+    }
+
+    fn expression(&mut self) -> Result<Expression> {
+        let Some(count) = self.array_begin()? else {
+            let val = self.read_value()?;
+            return Ok(Expression::new(None, Some(val), None, None, None, None));
+        };
+        if count == 0 {
+            return Err(self.error("empty expression"));
+        }
+
+        let code = self.read_integer()?;
+        let op = exp_op(code).ok_or_else(|| self.error(&format!("unknown operation {code}")))?;
+        let exp = match op {
+            ExpOp::Bin => {
+                self.expect_count(op, count, 3)?;
+                let module = self.read_exp_type()?;
+                let name = self.read_raw_string()?;
+                Expression::new(
+                    Some(op),
+                    Some(Value::from(name)),
+                    None,
+                    None,
+                    Some(module),
+                    None,
+                )
+            }
+            ExpOp::VarBuiltin => {
+                self.expect_count(op, count, 3)?;
+                let module = self.read_exp_type()?;
+                let part = self.read_integer()?;
+                Expression::new(
+                    Some(op),
+                    Some(Value::Int(part)),
+                    None,
+                    None,
+                    Some(module),
+                    None,
+                )
+            }
+            ExpOp::BinType | ExpOp::Var => {
+                self.expect_count(op, count, 2)?;
+                let name = self.read_raw_string()?;
+                Expression::new(Some(op), Some(Value::from(name)), None, None, None, None)
+            }
+            ExpOp::Regex => {
+                self.expect_count(op, count, 4)?;
+                let flags = self.read_integer()?;
+                let regex = self.read_raw_string()?;
+                let bin = self.expression()?;
+                Expression::new(
+                    Some(op),
+                    Some(Value::from(regex)),
+                    Some(bin),
+                    Some(flags),
+                    None,
+                    None,
+                )
+            }
+            ExpOp::Call => {
+                self.expect_count(op, count, 5)?;
+                let module = self.read_exp_type()?;
+                let flags = self.read_integer()?;
+                let arguments = self.call_arguments()?;
+                let bin = self.expression()?;
+                let mut exp =
+                    Expression::new(Some(op), None, Some(bin), Some(flags), Some(module), None);
+                exp.arguments = Some(arguments);
+                exp
+            }
+            ExpOp::Let => {
+                if count % 2 != 0 {
+                    return Err(self.error("let expects pairs of definitions and a scope"));
+                }
+                let mut exps = Vec::with_capacity(count / 2);
+                for _ in 0..(count - 2) / 2 {
+                    let name = self.read_raw_string()?;
+                    let value = self.expression()?;
+                    exps.push(super::def(name, value));
+                }
+                exps.push(self.expression()?);
+                super::exp_let(exps)
+            }
+            ExpOp::Key | ExpOp::DigestModulo | ExpOp::Quoted => {
+                self.expect_count(op, count, 2)?;
+                let val = self.read_value()?;
+                Expression::new(Some(op), Some(val), None, None, None, None)
+            }
+            _ if count == 1 => Expression::new(Some(op), None, None, None, None, None),
+            _ => {
+                let mut exps = Vec::with_capacity(count - 1);
+                for _ in 1..count {
+                    exps.push(self.expression()?);
+                }
+                Expression::new(Some(op), None, None, None, None, Some(exps))
+            }
+        };
+        Ok(exp)
+    }
+
+    fn expect_count(&self, op: ExpOp, count: usize, expected: usize) -> Result<()> {
+        if count == expected {
+            Ok(())
+        } else {
+            Err(self.error(&format!(
+                "{op:?} expects {} arguments, found {}",
+                expected - 1,
+                count - 1
+            )))
+        }
+    }
+
+    fn read_exp_type(&mut self) -> Result<ExpType> {
+        let code = self.read_integer()?;
+        exp_type(code).ok_or_else(|| self.error(&format!("unknown expression type {code}")))
+    }
+
+    // Module calls are packed either as `[op, args...]` or, with a CDT context, as
+    // `[0xff, [ctx_id, ctx_value, ...], [op, args...]]`. Path calls carry their context in place
+    // as `[0xfe, [ctx_id, ctx_value, ...], flags, modify_exp?]`.
+    fn call_arguments(&mut self) -> Result<Vec<ExpressionArgument>> {
+        let mut count = self.expect_array()?;
+        let mut ctx = vec![];
+        if count == 3 && self.peek_integer() == Some(0xff) {
+            self.read_integer()?;
+            ctx = self.read_ctx()?;
+            count = self.expect_array()?;
+        }
+
+        let mut args = Vec::with_capacity(count + 1);
+        for i in 0..count {
+            let path = matches!(
+                args.first(),
+                Some(ExpressionArgument::Value(Value::Int(CDT_SELECT)))
+            );
+            // Nested expressions are always arrays; everything else is a plain value.
+            if i == 1 && path {
+                args.push(ExpressionArgument::PathContext(self.read_ctx()?));
+            } else if i > 0 && self.array_begin_peek()? {
+                args.push(ExpressionArgument::FilterExpression(self.expression()?));
+            } else {
+                args.push(ExpressionArgument::Value(self.read_value()?));
+            }
+        }
+        if !ctx.is_empty() {
+            args.push(ExpressionArgument::Context(ctx));
+        }
+        Ok(args)
+    }
+
+    // Context ids carry the create flags of the context in their high bits. Expression contexts
+    // hold a packed expression, which is kept as is.
+    fn read_ctx(&mut self) -> Result<Vec<CdtContext>> {
+        let len = self.expect_array()?;
+        if len % 2 != 0 {
+            return Err(self.error("context expects pairs of ids and values"));
+        }
+        let mut ctx = Vec::with_capacity(len / 2);
+        for _ in 0..len / 2 {
+            let id = self.read_integer()?;
+            let id = u8::try_from(id).map_err(|_| self.error("invalid context id"))?;
+            let (id, flags) = (id & !CTX_FLAGS, id & CTX_FLAGS);
+            let value = if id == CtxType::Exp as u8 {
+                let start = self.pos;
+                self.expression()?;
+                Value::Blob(self.buf[start..self.pos].to_vec())
+            } else {
+                self.read_value()?
+            };
+            ctx.push(CdtContext { id, flags, value });
+        }
+        Ok(ctx)
+    }
+
+    fn peek_integer(&mut self) -> Option<i64> {
+        let pos = self.pos;
+        let val = self.read_integer().ok();
+        self.pos = pos;
+        val
+    }
+
+    fn array_begin_peek(&self) -> Result<bool> {
+        Ok(matches!(self.peek()?, 0x90..=0x9f | 0xdc | 0xdd))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::decode;
+    use crate::expressions::*;
+    use crate::operations::cdt_context::{
+        ctx_all_children, ctx_all_children_with_filter, ctx_list_index, ctx_list_index_create,
+        ctx_map_key, ctx_map_key_create,
+    };
+    use crate::operations::lists::{ListOrderType, ListReturnType};
+    use crate::operations::maps::MapReturnType;
+    use crate::operations::MapOrder;
+    use crate::operations::{ModifyFlags, SelectFlags};
+    use crate::{Record, Value};
+
+    fn roundtrip(exp: &Expression) -> Expression {
+        let bytes = exp.to_bytes().unwrap();
+        let decoded = decode(&bytes).unwrap();
+        assert_eq!(bytes, decoded.to_bytes().unwrap());
+        decoded
+    }
+
+    #[test]
+    fn decode_scalar_expressions() {
+        let exp = and(vec![
+            ge(int_bin("a".to_string()), int_val(-100_000)),
+            ne(string_bin("s".to_string()), string_val("x".to_string())),
+            eq(blob_bin("b".to_string()), blob_val(vec![1, 2, 3])),
+            lt(float_bin("f".to_string()), float_val(1.5)),
+            not(bool_bin("t".to_string())),
+            geo_compare(
+                geo_bin("g".to_string()),
+                geo_val(r#"{"type":"Point","coordinates":[1,2]}"#.to_string()),
+            ),
+            eq(key(ExpType::STRING), nil()),
+            eq(digest_modulo(3), int_val(1)),
+            lt(ttl(), int_val(3600)),
+            bin_exists("e".to_string()),
+            regex_compare("^a.*".to_string(), 1, string_bin("s".to_string())),
+        ]);
+        assert_eq!(roundtrip(&exp), exp);
+    }
+
+    #[test]
+    fn decode_variables_and_conditions() {
+        let exp = exp_let(vec![
+            def(
+                "x".to_string(),
+                num_add(vec![int_bin("a".to_string()), int_val(1)]),
+            ),
+            def("y".to_string(), float_val(2.0)),
+            cond(vec![
+                gt(var("x".to_string()), int_val(10)),
+                to_float(var("x".to_string())),
+                var("y".to_string()),
+            ]),
+        ]);
+        assert_eq!(roundtrip(&exp), exp);
+        assert_eq!(roundtrip(&unknown()), unknown());
+    }
+
+    #[test]
+    fn decode_cdt_expressions() {
+        let exp = eq(
+            lists::get_by_index(
+                ListReturnType::Values,
+                ExpType::INT,
+                int_val(0),
+                list_bin("l".to_string()),
+                &[ctx_list_index(1), ctx_map_key(Value::from("k"))],
+            ),
+            int_val(5),
+        );
+        let decoded = roundtrip(&exp);
+        assert_eq!(decoded.to_string(), exp.to_string());
+
+        let exp = maps::get_by_key_range(
+            MapReturnType::Count,
+            Some(string_val("a".to_string())),
+            Some(infinity()),
+            map_bin("m".to_string()),
+            &[],
+        );
+        roundtrip(&exp);
+
+        let exp = list_val(vec![Value::from(1), Value::from("a"), Value::Wildcard]);
+        assert_eq!(roundtrip(&exp), exp);
+    }
+
+    #[test]
+    fn decode_path_expressions() {
+        let ctx = [
+            ctx_map_key(Value::from("orders")),
+            ctx_all_children(),
+            ctx_all_children_with_filter(&gt(
+                loop_var(ExpType::INT, LoopVarPart::Value),
+                int_val(100),
+            ))
+            .unwrap(),
+        ];
+        let exp = cdt::select_by_path(
+            ExpType::LIST,
+            SelectFlags::Values,
+            map_bin("m".to_string()),
+            &ctx,
+        );
+        assert_eq!(
+            exp.to_bytes().unwrap(),
+            [
+                0x95, 0x7f, 0x04, 0x00, 0x93, 0xcc, 0xfe, 0x96, 0x22, 0xa7, 0x03, b'o', b'r', b'd',
+                b'e', b'r', b's', 0x04, 0xc3, 0x04, 0x93, 0x03, 0x93, 0x7a, 0x02, 0x01, 0x64, 0x01,
+                0x93, 0x51, 0x05, 0xa1, b'm'
+            ]
+        );
+        assert_eq!(roundtrip(&exp), exp);
+        assert_eq!(
+            exp.to_string(),
+            r#"cdt::select_by_path[LIST](ctx[ctx_map_key("orders"), ctx_all_children(), ctx_all_children_with_filter(gt(loop_var(INT, Value), 100))], 1, map_bin("m"))"#
+        );
+
+        let exp = cdt::modify_by_path(
+            ExpType::MAP,
+            ModifyFlags::NoFail,
+            num_mul(vec![loop_var(ExpType::INT, LoopVarPart::Value), int_val(2)]),
+            map_bin("m".to_string()),
+            &ctx,
+        );
+        assert_eq!(roundtrip(&exp), exp);
+        assert_eq!(
+            exp.to_string(),
+            r#"cdt::modify_by_path[MAP](ctx[ctx_map_key("orders"), ctx_all_children(), ctx_all_children_with_filter(gt(loop_var(INT, Value), 100))], 20, num_mul(loop_var(INT, Value), 2), map_bin("m"))"#
+        );
+    }
+
+    #[test]
+    fn decode_context_flags() {
+        let ctx = [
+            ctx_map_key_create(Value::from("orders"), MapOrder::KeyOrdered),
+            ctx_list_index_create(-1, ListOrderType::Ordered, false),
+            ctx_list_index_create(0, ListOrderType::Unordered, true),
+            ctx_all_children(),
+        ];
+        let exp = cdt::select_by_path(
+            ExpType::LIST,
+            SelectFlags::Values,
+            map_bin("m".to_string()),
+            &ctx,
+        );
+        let decoded = roundtrip(&exp);
+        assert_eq!(decoded, exp);
+        let Some(ExpressionArgument::PathContext(decoded_ctx)) =
+            decoded.arguments.as_ref().and_then(|args| args.get(1))
+        else {
+            panic!("path context was not decoded");
+        };
+        assert_eq!(
+            decoded_ctx
+                .iter()
+                .map(|c| (c.id, c.flags))
+                .collect::<Vec<_>>(),
+            [(0x22, 0x80), (0x10, 0xc0), (0x10, 0x80), (0x04, 0x00)]
+        );
+        assert_eq!(
+            decoded.to_string(),
+            r#"cdt::select_by_path[LIST](ctx[ctx_map_key("orders", flags = 0x80), ctx_list_index(-1, flags = 0xc0), ctx_list_index(0, flags = 0x80), ctx_all_children()], 1, map_bin("m"))"#
+        );
+    }
+
+    #[test]
+    fn decode_unknown_operations() {
+        // `[0x60, [0x51, 2, "a"]]`: an operation unknown to the client around a bin
+        let bytes = [0x92, 0x60, 0x93, 0x51, 0x02, 0xa1, b'a'];
+        assert!(decode(&bytes).is_err());
+
+        let exp = from_bytes(&bytes).unwrap();
+        assert_eq!(exp.to_bytes().unwrap(), bytes);
+        assert_eq!(exp.to_string(), r#"raw("kmCTUQKhYQ==")"#);
+        let record = Record::new(None, HashMap::new(), 1, 0);
+        assert!(exp.evaluate(&record).is_err());
+
+        // undecodable expressions are kept as they are inside other expressions
+        let exp = and(vec![exp, bool_val(true)]);
+        let packed = exp.to_bytes().unwrap();
+        assert_eq!(packed[..2], [0x93, 0x10]);
+        assert_eq!(packed[2..9], bytes);
+        assert_eq!(
+            from_base64(&exp.to_base64().unwrap()).unwrap(),
+            from_bytes(&packed).unwrap()
+        );
+    }
+
+    #[test]
+    fn decode_invalid_input() {
+        let bytes = eq(int_bin("a".to_string()), int_val(5)).to_bytes().unwrap();
+        for len in 0..bytes.len() {
+            assert!(decode(&bytes[..len]).is_err());
+        }
+
+        let mut trailing = bytes;
+        trailing.push(0xc0);
+        assert!(decode(&trailing).is_err());
+
+        // Unknown operation code
+        assert!(decode(&[0x91, 0x60]).is_err());
+        // Bin without a name
+        assert!(decode(&[0x92, 0x51, 0x02]).is_err());
+    }
+}
@@ -0,0 +1,552 @@
+// Copyright 2015-2020 Aerospike, Inc.
+//
+// Portions may be licensed to Aerospike, Inc. under one or more contributor
+// license agreements.
+//
+// Licensed under the Apache License, Version 2.0 (the "License"); you may not
+// use this file except in compliance with the License. You may obtain a copy of
+// the License at http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS, WITHOUT
+// WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied. See the
+// License for the specific language governing permissions and limitations under
+// the License.
+
+// Human readable rendering of filter expressions.
+//
+// Expressions are printed in prefix form, using the names of the functions in this module that
+// build them, e.g. `and(gt(int_bin("a"), 5), eq(var("x"), "y"))`. Module calls are printed as
+// `<module>::<operation>[<return type>](<arguments>, <bin>)`, with the CDT context, if any, as
+// the first argument.
+
+use std::fmt;
+
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine as _};
+
+use crate::expressions::{ExpOp, ExpType, Expression, ExpressionArgument, MODIFY};
+use crate::operations::cdt::CDT_SELECT;
+use crate::operations::cdt_context::CdtContext;
+use crate::value::FloatValue;
+use crate::Value;
+
+impl fmt::Display for Expression {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if let Some(bytes) = &self.bytes {
+            return write!(f, "raw({:?})", BASE64.encode(bytes));
+        }
+        let Some(cmd) = self.cmd else {
+            if let Some(exps) = &self.exps {
+                // DEF expression
+                f.write_str("def(")?;
+                write_name(f, self.val.as_ref())?;
+                return write_args(f, exps.iter(), true);
+            }
+            return match &self.val {
+                Some(val) => write_value(f, val),
+                None => f.write_str("nil"),
+            };
+        };
+
+        match cmd {
+            ExpOp::Bin => {
+                let module = self.module.map_or("nil", exp_type_name);
+                write!(f, "{module}_bin(")?;
+                write_name(f, self.val.as_ref())?;
+                f.write_str(")")
+            }
+            ExpOp::BinType | ExpOp::Var => {
+                write!(f, "{}(", op_name(cmd))?;
+                write_name(f, self.val.as_ref())?;
+                f.write_str(")")
+            }
+            ExpOp::VarBuiltin => {
+                let module = self.module.map_or("nil", exp_type_name);
+                let part = match self.val {
+                    Some(Value::Int(0)) => "MapKey",
+                    Some(Value::Int(1)) => "Value",
+                    Some(Value::Int(2)) => "Index",
+                    _ => "?",
+                };
+                write!(f, "loop_var({}, {part})", module.to_uppercase())
+            }
+            ExpOp::Key => match &self.val {
+                Some(Value::Int(code)) => {
+                    let name = super::decoder::exp_type(*code).map_or("?", exp_type_name);
+                    write!(f, "key({})", name.to_uppercase())
+                }
+                _ => f.write_str("key()"),
+            },
+            ExpOp::Regex => {
+                f.write_str("regex_compare(")?;
+                write_name(f, self.val.as_ref())?;
+                write!(f, ", {}, ", self.flags.unwrap_or_default())?;
+                write_bin(f, self.bin.as_deref())?;
+                f.write_str(")")
+            }
+            ExpOp::Call => self.fmt_call(f),
+            ExpOp::Quoted => {
+                f.write_str("list_val(")?;
+                if let Some(val) = &self.val {
+                    write_value(f, val)?;
+                }
+                f.write_str(")")
+            }
+            _ => {
+                f.write_str(op_name(cmd))?;
+                if let Some(exps) = &self.exps {
+                    write_args(f, exps.iter(), false)
+                } else if let Some(val) = &self.val {
+                    f.write_str("(")?;
+                    write_value(f, val)?;
+                    f.write_str(")")
+                } else {
+                    f.write_str("()")
+                }
+            }
+        }
+    }
+}
+
+impl Expression {
+    fn fmt_call(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let flags = self.flags.unwrap_or_default();
+        let mut args = self.arguments.iter().flatten();
+        let op = match args.next() {
+            Some(ExpressionArgument::Value(Value::Int(op))) => Some(*op),
+            _ => None,
+        };
+        let (module, name) = call_name(flags, op);
+        match name {
+            Some(name) => write!(f, "{module}::{name}")?,
+            None => write!(f, "{module}::op_{}", op.unwrap_or_default())?,
+        }
+        if let Some(module) = self.module {
+            write!(f, "[{}]", exp_type_name(module).to_uppercase())?;
+        }
+
+        f.write_str("(")?;
+        let mut first = true;
+        let mut sep = |f: &mut fmt::Formatter| {
+            let res = if first { Ok(()) } else { f.write_str(", ") };
+            first = false;
+            res
+        };
+        let ctx = self.arguments.iter().flatten().find_map(|arg| match arg {
+            ExpressionArgument::Context(ctx) if !ctx.is_empty() => Some(ctx),
+            _ => None,
+        });
+        if let Some(ctx) = ctx {
+            sep(f)?;
+            write_ctx(f, ctx)?;
+        }
+        for arg in args {
+            match arg {
+                ExpressionArgument::Value(val) => {
+                    sep(f)?;
+                    write_value(f, val)?;
+                }
+                ExpressionArgument::FilterExpression(exp) => {
+                    sep(f)?;
+                    write!(f, "{exp}")?;
+                }
+                ExpressionArgument::PathContext(ctx) => {
+                    sep(f)?;
+                    write_ctx(f, ctx)?;
+                }
+                ExpressionArgument::Context(_) => {}
+            }
+        }
+        sep(f)?;
+        write_bin(f, self.bin.as_deref())?;
+        f.write_str(")")
+    }
+}
+
+fn write_args<'a>(
+    f: &mut fmt::Formatter,
+    exps: impl Iterator<Item = &'a Expression>,
+    open: bool,
+) -> fmt::Result {
+    let mut first = !open;
+    if !open {
+        f.write_str("(")?;
+    }
+    for exp in exps {
+        if !first {
+            f.write_str(", ")?;
+        }
+        first = false;
+        write!(f, "{exp}")?;
+    }
+    f.write_str(")")
+}
+
+fn write_name(f: &mut fmt::Formatter, val: Option<&Value>) -> fmt::Result {
+    match val {
+        Some(Value::String(name)) => write!(f, "{name:?}"),
+        Some(val) => write!(f, "{:?}", val.to_string()),
+        None => f.write_str("\"\""),
+    }
+}
+
+fn write_bin(f: &mut fmt::Formatter, bin: Option<&Expression>) -> fmt::Result {
+    match bin {
+        Some(bin) => write!(f, "{bin}"),
+        None => f.write_str("nil"),
+    }
+}
+
+fn write_ctx(f: &mut fmt::Formatter, ctx: &[CdtContext]) -> fmt::Result {
+    f.write_str("ctx[")?;
+    for (i, c) in ctx.iter().enumerate() {
+        if i > 0 {
+            f.write_str(", ")?;
+        }
+        match (c.id, &c.value) {
+            (0x04, Value::Blob(exp)) if exp[..] == [0xc3] => f.write_str("ctx_all_children()")?,
+            (0x04, Value::Blob(exp)) => match super::decoder::decode(exp) {
+                Ok(exp) => write!(f, "ctx_all_children_with_filter({exp})")?,
+                Err(_) => write!(f, "ctx_all_children_with_filter(blob_val({exp:?}))")?,
+            },
+            _ => {
+                match ctx_name(c.id) {
+                    Some(name) => write!(f, "{name}(")?,
+                    None => write!(f, "ctx_{:#x}(", c.id)?,
+                }
+                write_value(f, &c.value)?;
+                if c.flags != 0 {
+                    write!(f, ", flags = {:#x}", c.flags)?;
+                }
+                f.write_str(")")?;
+            }
+        }
+    }
+    f.write_str("]")
+}
+
+fn write_value(f: &mut fmt::Formatter, val: &Value) -> fmt::Result {
+    match val {
+        Value::Nil => f.write_str("nil"),
+        Value::Bool(val) => write!(f, "{val}"),
+        Value::Int(val) => write!(f, "{val}"),
+        Value::Float(FloatValue::F64(bits)) => write!(f, "{:?}", f64::from_bits(*bits)),
+        Value::Float(FloatValue::F32(bits)) => write!(f, "{:?}", f32::from_bits(*bits)),
+        Value::String(val) => write!(f, "{val:?}"),
+        Value::GeoJSON(val) => write!(f, "geo_val({val:?})"),
+        Value::Blob(val) | Value::HLL(val) => write!(f, "blob_val({val:?})"),
+        Value::List(list) | Value::MultiResult(list) => {
+            f.write_str("[")?;
+            for (i, val) in list.iter().enumerate() {
+                if i > 0 {
+                    f.write_str(", ")?;
+                }
+                write_value(f, val)?;
+            }
+            f.write_str("]")
+        }
+        Value::HashMap(map) => write_map(f, map.iter()),
+        Value::OrderedMap(map) => write_map(f, map.iter()),
+        Value::KeyValueList(list) => write_map(f, list.iter().map(|(k, v)| (k, v))),
+        Value::Infinity => f.write_str("infinity()"),
+        Value::Wildcard => f.write_str("wildcard()"),
+    }
+}
+
+fn write_map<'a>(
+    f: &mut fmt::Formatter,
+    entries: impl Iterator<Item = (&'a Value, &'a Value)>,
+) -> fmt::Result {
+    f.write_str("{")?;
+    for (i, (key, val)) in entries.enumerate() {
+        if i > 0 {
+            f.write_str(", ")?;
+        }
+        write_value(f, key)?;
+        f.write_str(": ")?;
+        write_value(f, val)?;
+    }
+    f.write_str("}")
+}
+
+const fn exp_type_name(exp_type: ExpType) -> &'static str {
+    match exp_type {
+        ExpType::NIL => "nil",
+        ExpType::BOOL => "bool",
+        ExpType::INT => "int",
+        ExpType::STRING => "string",
+        ExpType::LIST => "list",
+        ExpType::MAP => "map",
+        ExpType::BLOB => "blob",
+        ExpType::FLOAT => "float",
+        ExpType::GEO => "geo",
+        ExpType::HLL => "hll",
+    }
+}
+
+pub(super) const fn op_name(op: ExpOp) -> &'static str {
+    match op {
+        ExpOp::Unknown => "unknown",
+        ExpOp::EQ => "eq",
+        ExpOp::NE => "ne",
+        ExpOp::GT => "gt",
+        ExpOp::GE => "ge",
+        ExpOp::LT => "lt",
+        ExpOp::LE => "le",
+        ExpOp::Regex => "regex_compare",
+        ExpOp::Geo => "geo_compare",
+        ExpOp::And => "and",
+        ExpOp::Or => "or",
+        ExpOp::Not => "not",
+        ExpOp::Xor => "xor",
+        ExpOp::Add => "num_add",
+        ExpOp::Sub => "num_sub",
+        ExpOp::Mul => "num_mul",
+        ExpOp::Div => "num_div",
+        ExpOp::Pow => "num_pow",
+        ExpOp::Log => "num_log",
+        ExpOp::Mod => "num_mod",
+        ExpOp::Abs => "num_abs",
+        ExpOp::Floor => "num_floor",
+        ExpOp::Ceil => "num_ceil",
+        ExpOp::ToInt => "to_int",
+        ExpOp::ToFloat => "to_float",
+        ExpOp::IntAnd => "int_and",
+        ExpOp::IntOr => "int_or",
+        ExpOp::IntXor => "int_xor",
+        ExpOp::IntNot => "int_not",
+        ExpOp::IntLshift => "int_lshift",
+        ExpOp::IntRshift => "int_rshift",
+        ExpOp::IntARshift => "int_arshift",
+        ExpOp::IntCount => "int_count",
+        ExpOp::IntLscan => "int_lscan",
+        ExpOp::IntRscan => "int_rscan",
+        ExpOp::Min => "min",
+        ExpOp::Max => "max",
+        ExpOp::DigestModulo => "digest_modulo",
+        ExpOp::DeviceSize => "device_size",
+        ExpOp::LastUpdate => "last_update",
+        ExpOp::SinceUpdate => "since_update",
+        ExpOp::VoidTime => "void_time",
+        ExpOp::TTL => "ttl",
+        ExpOp::SetName => "set_name",
+        ExpOp::KeyExists => "key_exists",
+        ExpOp::IsTombstone => "is_tombstone",
+        ExpOp::MemorySize => "memory_size",
+        ExpOp::RecordSize => "record_size",
+        ExpOp::Key => "key",
+        ExpOp::Bin => "bin",
+        ExpOp::BinType => "bin_type",
+        ExpOp::Cond => "cond",
+        ExpOp::VarBuiltin => "loop_var",
+        ExpOp::Var => "var",
+        ExpOp::Let => "exp_let",
+        ExpOp::Quoted => "list_val",
+        ExpOp::Call => "call",
+    }
+}
+
+const fn ctx_name(id: u8) -> Option<&'static str> {
+    let name = match id {
+        0x10 => "ctx_list_index",
+        0x11 => "ctx_list_rank",
+        0x13 => "ctx_list_value",
+        0x20 => "ctx_map_index",
+        0x21 => "ctx_map_rank",
+        0x22 => "ctx_map_key",
+        0x23 => "ctx_map_value",
+        _ => return None,
+    };
+    Some(name)
+}
+
+// Maps a module call to the module name and the name of the function that builds it. Lists and
+// maps share a module id; map operation codes start at 64. Path calls apply to both.
+const fn call_name(flags: i64, op: Option<i64>) -> (&'static str, Option<&'static str>) {
+    let Some(op) = op else {
+        return ("call", None);
+    };
+    match flags & !MODIFY {
+        0 if op == CDT_SELECT && flags & MODIFY != 0 => ("cdt", Some("modify_by_path")),
+        0 if op == CDT_SELECT => ("cdt", Some("select_by_path")),
+        0 if op < 64 => ("lists", list_op_name(op)),
+        0 => ("maps", map_op_name(op)),
+        1 => ("bitwise", bit_op_name(op)),
+        2 => ("hll", hll_op_name(op)),
+        _ => ("call", None),
+    }
+}
+
+const fn list_op_name(op: i64) -> Option<&'static str> {
+    let name = match op {
+        1 => "append",
+        2 => "append_items",
+        3 => "insert",
+        4 => "insert_items",
+        9 => "set",
+        11 => "clear",
+        12 => "increment",
+        13 => "sort",
+        16 => "size",
+        19 => "get_by_index",
+        21 => "get_by_rank",
+        22 => "get_by_value",
+        23 => "get_by_value_list",
+        24 => "get_by_index_range",
+        25 => "get_by_value_range",
+        26 => "get_by_rank_range",
+        27 => "get_by_value_relative_rank_range",
+        32 => "remove_by_index",
+        34 => "remove_by_rank",
+        35 => "remove_by_value",
+        36 => "remove_by_value_list",
+        37 => "remove_by_index_range",
+        38 => "remove_by_value_range",
+        39 => "remove_by_rank_range",
+        40 => "remove_by_value_relative_rank_range",
+        _ => return None,
+    };
+    Some(name)
+}
+
+const fn map_op_name(op: i64) -> Option<&'static str> {
+    let name = match op {
+        64 => "set_type",
+        65 => "add",
+        66 => "add_items",
+        67 => "put",
+        68 => "put_items",
+        69 => "replace",
+        70 => "replace_items",
+        73 => "increment",
+        74 => "decrement",
+        75 => "clear",
+        76 => "remove_by_key",
+        77 => "remove_by_index",
+        79 => "remove_by_rank",
+        81 => "remove_by_key_list",
+        82 => "remove_by_value",
+        83 => "remove_by_value_list",
+        84 => "remove_by_key_range",
+        85 => "remove_by_index_range",
+        86 => "remove_by_value_range",
+        87 => "remove_by_rank_range",
+        88 => "remove_by_key_relative_index_range",
+        89 => "remove_by_value_relative_rank_range",
+        96 => "size",
+        97 => "get_by_key",
+        98 => "get_by_index",
+        100 => "get_by_rank",
+        102 => "get_by_value",
+        103 => "get_by_key_range",
+        104 => "get_by_index_range",
+        105 => "get_by_value_range",
+        106 => "get_by_rank_range",
+        107 => "get_by_key_list",
+        108 => "get_by_value_list",
+        109 => "get_by_key_relative_index_range",
+        110 => "get_by_value_relative_rank_range",
+        _ => return None,
+    };
+    Some(name)
+}
+
+const fn bit_op_name(op: i64) -> Option<&'static str> {
+    let name = match op {
+        0 => "resize",
+        1 => "insert",
+        2 => "remove",
+        3 => "set",
+        4 => "or",
+        5 => "xor",
+        6 => "and",
+        7 => "not",
+        8 => "lshift",
+        9 => "rshift",
+        10 => "add",
+        11 => "subtract",
+        12 => "set_int",
+        50 => "get",
+        51 => "count",
+        52 => "lscan",
+        53 => "rscan",
+        54 => "get_int",
+        _ => return None,
+    };
+    Some(name)
+}
+
+const fn hll_op_name(op: i64) -> Option<&'static str> {
+    let name = match op {
+        0 => "init",
+        1 => "add",
+        50 => "get_count",
+        51 => "get_union",
+        52 => "get_union_count",
+        53 => "get_intersect_count",
+        54 => "get_similarity",
+        55 => "describe",
+        56 => "may_contain",
+        _ => return None,
+    };
+    Some(name)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::expressions::*;
+    use crate::operations::cdt_context::ctx_map_key;
+    use crate::operations::lists::ListReturnType;
+    use crate::Value;
+
+    #[test]
+    fn display_scalar_expressions() {
+        let exp = or(vec![
+            and(vec![
+                gt(int_bin("age".to_string()), int_val(18)),
+                eq(string_bin("name".to_string()), string_val("x".to_string())),
+            ]),
+            lt(ttl(), float_val(3600.0)),
+            eq(key(ExpType::INT), digest_modulo(3)),
+        ]);
+        assert_eq!(
+            exp.to_string(),
+            r#"or(and(gt(int_bin("age"), 18), eq(string_bin("name"), "x")), lt(ttl(), 3600.0), eq(key(INT), digest_modulo(3)))"#
+        );
+    }
+
+    #[test]
+    fn display_variables() {
+        let exp = exp_let(vec![
+            def("x".to_string(), bin_type("a".to_string())),
+            cond(vec![
+                eq(var("x".to_string()), int_val(1)),
+                list_val(vec![Value::from(1), Value::from("a")]),
+                unknown(),
+            ]),
+        ]);
+        assert_eq!(
+            exp.to_string(),
+            r#"exp_let(def("x", bin_type("a")), cond(eq(var("x"), 1), list_val([1, "a"]), unknown()))"#
+        );
+        assert_eq!(
+            regex_compare("^a".to_string(), 2, string_bin("s".to_string())).to_string(),
+            r#"regex_compare("^a", 2, string_bin("s"))"#
+        );
+    }
+
+    #[test]
+    fn display_module_calls() {
+        let exp = lists::get_by_index(
+            ListReturnType::Values,
+            ExpType::INT,
+            int_val(0),
+            list_bin("l".to_string()),
+            &[ctx_map_key(Value::from("k"))],
+        );
+        assert_eq!(
+            exp.to_string(),
+            r#"lists::get_by_index[INT](ctx[ctx_map_key("k")], 7, 0, list_bin("l"))"#
+        );
+    }
+}
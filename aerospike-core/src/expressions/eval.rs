@@ -0,0 +1,1599 @@
+// Copyright 2015-2020 Aerospike, Inc.
+//
+// Portions may be licensed to Aerospike, Inc. under one or more contributor
+// license agreements.
+//
+// Licensed under the Apache License, Version 2.0 (the "License"); you may not
+// use this file except in compliance with the License. You may obtain a copy of
+// the License at http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS, WITHOUT
+// WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied. See the
+// License for the specific language governing permissions and limitations under
+// the License.
+
+// Local evaluation of filter expressions against records held in memory.
+//
+// The interpreter follows the server's rules: an expression evaluates to unknown if a bin it
+// reads is missing or holds a value of a different type than the bin expression declares, and
+// unknown propagates through every operation except `and`/`or`, which can still be decided by
+// their other arguments. Arguments of mismatched types are rejected with an error, like the
+// server rejects such expressions when it parses them.
+
+use std::cmp::Ordering;
+use std::collections::{BTreeMap, HashMap};
+use std::convert::TryFrom;
+use std::ops::Range;
+use std::time::{Duration, UNIX_EPOCH};
+
+use regex::RegexBuilder;
+
+use super::display::op_name;
+use crate::expressions::{ExpOp, ExpType, Expression, ExpressionArgument, MODIFY};
+use crate::operations::cdt_context::CdtContext;
+use crate::record::CITRUSLEAF_EPOCH;
+use crate::{Error, Record, RegexFlag, Result, Value};
+
+// Unwraps an evaluated value, or returns unknown from the enclosing function.
+macro_rules! known {
+    ($val:expr) => {
+        match $val {
+            Some(val) => val,
+            None => return Ok(None),
+        }
+    };
+}
+
+const INVERTED: i64 = 0x10000;
+
+impl Expression {
+    /// Evaluates the expression against a record held in memory.
+    ///
+    /// Returns `None` if the expression evaluates to unknown, e.g. because it reads a bin that
+    /// does not exist or that holds a value of a different type than the bin expression declares.
+    /// Expressions the server would reject, e.g. because they compare an integer with a string,
+    /// return an error.
+    ///
+    /// Expressions that depend on server-side state, i.e. `last_update()`, `since_update()`,
+    /// `record_size()`, `device_size()`, `memory_size()` and `digest_modulo()`, as well as
+    /// `geo_compare()`, HLL expressions, bitwise expressions that modify a bin and expressions
+    /// that [`from_bytes`](crate::expressions::from_bytes) could not decode, can not be evaluated
+    /// locally and return an error.
+    ///
+    /// ```
+    /// use std::collections::HashMap;
+    /// use aerospike::expressions::{and, gt, int_bin, int_val, lt, num_add};
+    /// use aerospike::{Record, Value};
+    ///
+    /// let mut bins = HashMap::new();
+    /// bins.insert("a".to_string(), Value::from(5));
+    /// let record = Record::new(None, bins, 1, 0);
+    ///
+    /// let exp = num_add(vec![int_bin("a".to_string()), int_val(1)]);
+    /// assert_eq!(exp.evaluate(&record).unwrap(), Some(Value::from(6)));
+    ///
+    /// // Bin "b" does not exist, so the comparison is unknown and the record is filtered out.
+    /// let exp = and(vec![gt(int_bin("a".to_string()), int_val(1)), lt(int_bin("b".to_string()), int_val(1))]);
+    /// assert_eq!(exp.evaluate(&record).unwrap(), None);
+    /// assert!(!exp.matches(&record).unwrap());
+    /// ```
+    pub fn evaluate(&self, record: &Record) -> Result<Option<Value>> {
+        Evaluator {
+            record,
+            vars: vec![],
+        }
+        .eval(self)
+    }
+
+    /// Returns whether the record passes the expression when it is used as a filter, i.e.
+    /// whether the expression evaluates to `true`. Like on the server, a record for which the
+    /// expression evaluates to unknown does not pass.
+    pub fn matches(&self, record: &Record) -> Result<bool> {
+        match self.evaluate(record)? {
+            Some(Value::Bool(val)) => Ok(val),
+            None => Ok(false),
+            Some(val) => Err(Error::InvalidArgument(format!(
+                "Filter expression must return a boolean, found {:?}",
+                exp_type_of(&val)
+            ))),
+        }
+    }
+}
+
+struct Evaluator<'a> {
+    record: &'a Record,
+    vars: Vec<(String, Option<Value>)>,
+}
+
+impl Evaluator<'_> {
+    fn eval(&mut self, exp: &Expression) -> Result<Option<Value>> {
+        if exp.bytes.is_some() {
+            return Err(invalid(
+                "Undecoded expressions can not be evaluated locally",
+            ));
+        }
+        let Some(op) = exp.cmd else {
+            if exp.exps.is_some() {
+                return Err(invalid(
+                    "def() can only be used as an argument of exp_let()",
+                ));
+            }
+            return Ok(Some(exp.val.clone().unwrap_or(Value::Nil)));
+        };
+
+        let exps = exp.exps.as_deref().unwrap_or(&[]);
+        match op {
+            ExpOp::Unknown => Ok(None),
+            ExpOp::EQ | ExpOp::NE | ExpOp::GT | ExpOp::GE | ExpOp::LT | ExpOp::LE => {
+                let [left, right] = arity(op, exps)?;
+                let left = known!(self.eval(left)?);
+                let right = known!(self.eval(right)?);
+                compare(op, &left, &right).map(|res| Some(Value::Bool(res)))
+            }
+            ExpOp::Regex => self.regex(exp),
+            ExpOp::And => self.and_or(exps, false),
+            ExpOp::Or => self.and_or(exps, true),
+            ExpOp::Not => {
+                let [arg] = arity(op, exps)?;
+                let val = known!(self.eval_bool(arg)?);
+                Ok(Some(Value::Bool(!val)))
+            }
+            ExpOp::Xor => {
+                let mut count = 0;
+                for exp in exps {
+                    if known!(self.eval_bool(exp)?) {
+                        count += 1;
+                    }
+                }
+                Ok(Some(Value::Bool(count == 1)))
+            }
+            ExpOp::Add
+            | ExpOp::Sub
+            | ExpOp::Mul
+            | ExpOp::Div
+            | ExpOp::Min
+            | ExpOp::Max
+            | ExpOp::IntAnd
+            | ExpOp::IntOr
+            | ExpOp::IntXor => self.fold(op, exps),
+            ExpOp::Abs
+            | ExpOp::Floor
+            | ExpOp::Ceil
+            | ExpOp::ToInt
+            | ExpOp::ToFloat
+            | ExpOp::IntNot
+            | ExpOp::IntCount => {
+                let [arg] = arity(op, exps)?;
+                let val = known!(self.eval(arg)?);
+                unary(op, val)
+            }
+            ExpOp::Pow
+            | ExpOp::Log
+            | ExpOp::Mod
+            | ExpOp::IntLshift
+            | ExpOp::IntRshift
+            | ExpOp::IntARshift
+            | ExpOp::IntLscan
+            | ExpOp::IntRscan => {
+                let [left, right] = arity(op, exps)?;
+                let left = known!(self.eval(left)?);
+                let right = known!(self.eval(right)?);
+                binary(op, left, right)
+            }
+            ExpOp::Cond => self.cond(exps),
+            ExpOp::Let => self.exp_let(exps),
+            ExpOp::Var => {
+                let name = name(exp)?;
+                match self.vars.iter().rev().find(|(var, _)| var == name) {
+                    Some((_, val)) => Ok(val.clone()),
+                    None => Err(invalid(&format!("Undefined variable {name:?}"))),
+                }
+            }
+            ExpOp::Quoted => Ok(exp.val.clone()),
+            ExpOp::Bin => {
+                let val = self.record.bins.get(name(exp)?);
+                Ok(val
+                    .filter(|val| Some(exp_type_of(val)) == exp.module)
+                    .cloned())
+            }
+            ExpOp::BinType => {
+                let val = self.record.bins.get(name(exp)?);
+                Ok(Some(Value::Int(
+                    val.map_or(0, |val| val.particle_type() as i64),
+                )))
+            }
+            ExpOp::Key => {
+                let exp_type = match &exp.val {
+                    Some(Value::Int(code)) => super::decoder::exp_type(*code),
+                    _ => None,
+                };
+                let key = self
+                    .record
+                    .key
+                    .as_ref()
+                    .and_then(|key| key.user_key.as_ref());
+                Ok(key
+                    .filter(|key| Some(exp_type_of(key)) == exp_type)
+                    .cloned())
+            }
+            ExpOp::KeyExists => {
+                let key = self
+                    .record
+                    .key
+                    .as_ref()
+                    .and_then(|key| key.user_key.as_ref());
+                Ok(Some(Value::Bool(key.is_some())))
+            }
+            ExpOp::SetName => Ok(self
+                .record
+                .key
+                .as_ref()
+                .map(|key| Value::from(key.set_name.as_str()))),
+            ExpOp::TTL => {
+                let ttl = self.record.time_to_live();
+                Ok(Some(Value::Int(ttl.map_or(-1, |ttl| ttl.as_secs() as i64))))
+            }
+            ExpOp::VoidTime => {
+                let void_time = match self.record.expiration() {
+                    0 => -1,
+                    secs => {
+                        let void_time = *CITRUSLEAF_EPOCH + Duration::from_secs(u64::from(secs));
+                        let since_epoch = void_time.duration_since(UNIX_EPOCH).unwrap_or_default();
+                        since_epoch.as_nanos() as i64
+                    }
+                };
+                Ok(Some(Value::Int(void_time)))
+            }
+            ExpOp::IsTombstone => Ok(Some(Value::Bool(false))),
+            ExpOp::Call => self.call(exp),
+            ExpOp::Geo
+            | ExpOp::DigestModulo
+            | ExpOp::DeviceSize
+            | ExpOp::MemorySize
+            | ExpOp::RecordSize
+            | ExpOp::LastUpdate
+            | ExpOp::SinceUpdate
+            | ExpOp::VarBuiltin => Err(invalid(&format!(
+                "{}() can not be evaluated locally",
+                op_name(op)
+            ))),
+        }
+    }
+
+    fn eval_bool(&mut self, exp: &Expression) -> Result<Option<bool>> {
+        match self.eval(exp)? {
+            Some(Value::Bool(val)) => Ok(Some(val)),
+            Some(val) => Err(invalid(&format!(
+                "Expected a boolean, found {:?}",
+                exp_type_of(&val)
+            ))),
+            None => Ok(None),
+        }
+    }
+
+    // `and` returns false as soon as one argument is false, and `or` returns true as soon as one
+    // argument is true; otherwise the result is unknown if any argument is unknown.
+    fn and_or(&mut self, exps: &[Expression], decisive: bool) -> Result<Option<Value>> {
+        let mut unknown = false;
+        for exp in exps {
+            match self.eval_bool(exp)? {
+                Some(val) if val == decisive => return Ok(Some(Value::Bool(decisive))),
+                Some(_) => {}
+                None => unknown = true,
+            }
+        }
+        Ok((!unknown).then_some(Value::Bool(!decisive)))
+    }
+
+    fn fold(&mut self, op: ExpOp, exps: &[Expression]) -> Result<Option<Value>> {
+        let mut vals = Vec::with_capacity(exps.len());
+        for exp in exps {
+            vals.push(known!(self.eval(exp)?));
+        }
+
+        if let Some(ints) = vals.iter().map(as_int).collect::<Option<Vec<_>>>() {
+            let Some((&first, rest)) = ints.split_first() else {
+                return Err(invalid(&format!("{}() requires arguments", op_name(op))));
+            };
+            let res = match op {
+                ExpOp::Add => rest.iter().fold(first, |acc, val| acc.wrapping_add(*val)),
+                ExpOp::Sub if rest.is_empty() => first.wrapping_neg(),
+                ExpOp::Sub => rest.iter().fold(first, |acc, val| acc.wrapping_sub(*val)),
+                ExpOp::Mul => rest.iter().fold(first, |acc, val| acc.wrapping_mul(*val)),
+                ExpOp::Div if rest.is_empty() => known!(1i64.checked_div(first)),
+                ExpOp::Div => {
+                    let mut acc = first;
+                    for val in rest {
+                        acc = known!(acc.checked_div(*val));
+                    }
+                    acc
+                }
+                ExpOp::Min => rest.iter().fold(first, |acc, val| acc.min(*val)),
+                ExpOp::Max => rest.iter().fold(first, |acc, val| acc.max(*val)),
+                ExpOp::IntAnd => rest.iter().fold(first, |acc, val| acc & val),
+                ExpOp::IntOr => rest.iter().fold(first, |acc, val| acc | val),
+                ExpOp::IntXor => rest.iter().fold(first, |acc, val| acc ^ val),
+                _ => unreachable!(),
+            };
+            return Ok(Some(Value::Int(res)));
+        }
+
+        let floats = vals.iter().map(as_float).collect::<Option<Vec<_>>>();
+        let floats = match floats {
+            Some(floats) if !matches!(op, ExpOp::IntAnd | ExpOp::IntOr | ExpOp::IntXor) => floats,
+            _ => {
+                return Err(invalid(&format!(
+                    "{}() arguments must all be integers or all be floats",
+                    op_name(op)
+                )))
+            }
+        };
+        let Some((&first, rest)) = floats.split_first() else {
+            return Err(invalid(&format!("{}() requires arguments", op_name(op))));
+        };
+        let res = match op {
+            ExpOp::Add => rest.iter().fold(first, |acc, val| acc + val),
+            ExpOp::Sub if rest.is_empty() => -first,
+            ExpOp::Sub => rest.iter().fold(first, |acc, val| acc - val),
+            ExpOp::Mul => rest.iter().fold(first, |acc, val| acc * val),
+            ExpOp::Div if rest.is_empty() => 1.0 / first,
+            ExpOp::Div => rest.iter().fold(first, |acc, val| acc / val),
+            ExpOp::Min => rest.iter().fold(first, |acc, val| acc.min(*val)),
+            ExpOp::Max => rest.iter().fold(first, |acc, val| acc.max(*val)),
+            _ => unreachable!(),
+        };
+        Ok(Some(Value::from(res)))
+    }
+
+    fn regex(&mut self, exp: &Expression) -> Result<Option<Value>> {
+        let pattern = name(exp)?;
+        let flags = exp.flags.unwrap_or_default();
+        let bin = exp
+            .bin
+            .as_deref()
+            .ok_or_else(|| invalid("regex_compare() requires a string expression"))?;
+        let val = match known!(self.eval(bin)?) {
+            Value::String(val) => val,
+            val => {
+                return Err(invalid(&format!(
+                    "regex_compare() requires a string expression, found {:?}",
+                    exp_type_of(&val)
+                )))
+            }
+        };
+
+        let newline = flags & RegexFlag::NEWLINE as i64 != 0;
+        let regex = RegexBuilder::new(pattern)
+            .case_insensitive(flags & RegexFlag::ICASE as i64 != 0)
+            .multi_line(newline)
+            .dot_matches_new_line(!newline)
+            .build()
+            .map_err(|err| invalid(&format!("Invalid regular expression: {err}")))?;
+        Ok(Some(Value::Bool(regex.is_match(&val))))
+    }
+
+    fn cond(&mut self, exps: &[Expression]) -> Result<Option<Value>> {
+        let Some((default, pairs)) = exps.split_last() else {
+            return Err(invalid("cond() requires a default action"));
+        };
+        if pairs.is_empty() || pairs.len() % 2 != 0 {
+            return Err(invalid(
+                "cond() requires pairs of conditions and actions followed by a default action",
+            ));
+        }
+
+        for pair in pairs.chunks(2) {
+            if known!(self.eval_bool(&pair[0])?) {
+                return self.eval(&pair[1]);
+            }
+        }
+        self.eval(default)
+    }
+
+    fn exp_let(&mut self, exps: &[Expression]) -> Result<Option<Value>> {
+        let Some((scope, defs)) = exps.split_last() else {
+            return Err(invalid("exp_let() requires a scope expression"));
+        };
+
+        let depth = self.vars.len();
+        for def in defs {
+            let (None, Some(Value::String(name)), Some([value])) =
+                (def.cmd, &def.val, def.exps.as_deref())
+            else {
+                return Err(invalid(
+                    "exp_let() arguments must be def() expressions followed by a scope expression",
+                ));
+            };
+            let value = self.eval(value)?;
+            self.vars.push((name.clone(), value));
+        }
+        let res = self.eval(scope);
+        self.vars.truncate(depth);
+        res
+    }
+
+    fn call(&mut self, exp: &Expression) -> Result<Option<Value>> {
+        let flags = exp.flags.unwrap_or_default();
+        let bin = exp
+            .bin
+            .as_deref()
+            .ok_or_else(|| invalid("Module expression without a bin"))?;
+
+        let mut params = vec![];
+        let mut ctx: &[CdtContext] = &[];
+        for arg in exp.arguments.iter().flatten() {
+            match arg {
+                ExpressionArgument::Value(val) => params.push(val.clone()),
+                ExpressionArgument::FilterExpression(exp) => params.push(known!(self.eval(exp)?)),
+                ExpressionArgument::Context(arg) => ctx = arg,
+                ExpressionArgument::PathContext(_) => {
+                    return Err(invalid("Path expressions can not be evaluated locally"))
+                }
+            }
+        }
+        let target = known!(self.eval(bin)?);
+        let Some((op, params)) = params.split_first() else {
+            return Err(invalid("Module expression without an operation"));
+        };
+        let op = as_int(op).ok_or_else(|| invalid("Invalid module operation"))?;
+
+        match flags & !MODIFY {
+            0 => cdt_call(op, params, ctx, target, flags & MODIFY != 0),
+            1 if flags & MODIFY == 0 => bit_read(op, params, &target),
+            1 => Err(invalid(
+                "Bitwise expressions that modify the bin can not be evaluated locally",
+            )),
+            _ => Err(invalid("HLL expressions can not be evaluated locally")),
+        }
+    }
+}
+
+fn invalid(msg: &str) -> Error {
+    Error::InvalidArgument(msg.to_string())
+}
+
+fn arity<const N: usize>(op: ExpOp, exps: &[Expression]) -> Result<&[Expression; N]> {
+    <&[Expression; N]>::try_from(exps).map_err(|_| {
+        invalid(&format!(
+            "{}() requires {} arguments, found {}",
+            op_name(op),
+            N,
+            exps.len()
+        ))
+    })
+}
+
+fn name(exp: &Expression) -> Result<&str> {
+    match &exp.val {
+        Some(Value::String(name)) => Ok(name),
+        _ => Err(invalid(&format!(
+            "{}() requires a name",
+            exp.cmd.map_or("def", op_name)
+        ))),
+    }
+}
+
+const fn exp_type_of(val: &Value) -> ExpType {
+    match val {
+        Value::Nil | Value::Infinity | Value::Wildcard => ExpType::NIL,
+        Value::Bool(_) => ExpType::BOOL,
+        Value::Int(_) => ExpType::INT,
+        Value::Float(_) => ExpType::FLOAT,
+        Value::String(_) => ExpType::STRING,
+        Value::Blob(_) => ExpType::BLOB,
+        Value::List(_) | Value::MultiResult(_) => ExpType::LIST,
+        Value::HashMap(_) | Value::OrderedMap(_) | Value::KeyValueList(_) => ExpType::MAP,
+        Value::GeoJSON(_) => ExpType::GEO,
+        Value::HLL(_) => ExpType::HLL,
+    }
+}
+
+const fn as_int(val: &Value) -> Option<i64> {
+    match val {
+        Value::Int(val) => Some(*val),
+        _ => None,
+    }
+}
+
+fn as_float(val: &Value) -> Option<f64> {
+    match val {
+        Value::Float(val) => Some(f64::from(val)),
+        _ => None,
+    }
+}
+
+fn compare(op: ExpOp, left: &Value, right: &Value) -> Result<bool> {
+    let (left_type, right_type) = (exp_type_of(left), exp_type_of(right));
+    if left_type != right_type && left_type != ExpType::NIL && right_type != ExpType::NIL {
+        return Err(invalid(&format!(
+            "{}() can not compare {:?} with {:?}",
+            op_name(op),
+            left_type,
+            right_type
+        )));
+    }
+
+    let ord = cmp(left, right);
+    let res = match op {
+        ExpOp::EQ => ord == Ordering::Equal,
+        ExpOp::NE => ord != Ordering::Equal,
+        ExpOp::GT => ord == Ordering::Greater,
+        ExpOp::GE => ord != Ordering::Less,
+        ExpOp::LT => ord == Ordering::Less,
+        ExpOp::LE => ord != Ordering::Greater,
+        _ => unreachable!(),
+    };
+    Ok(res)
+}
+
+#[allow(clippy::cast_precision_loss)]
+fn unary(op: ExpOp, val: Value) -> Result<Option<Value>> {
+    let res = match (op, val) {
+        (ExpOp::Abs, Value::Int(val)) => Value::Int(val.wrapping_abs()),
+        (ExpOp::Abs, Value::Float(val)) => Value::from(f64::from(val).abs()),
+        (ExpOp::Floor, Value::Float(val)) => Value::from(f64::from(val).floor()),
+        (ExpOp::Ceil, Value::Float(val)) => Value::from(f64::from(val).ceil()),
+        (ExpOp::ToInt, Value::Float(val)) => Value::Int(f64::from(val) as i64),
+        (ExpOp::ToFloat, Value::Int(val)) => Value::from(val as f64),
+        (ExpOp::IntNot, Value::Int(val)) => Value::Int(!val),
+        (ExpOp::IntCount, Value::Int(val)) => Value::Int(i64::from(val.count_ones())),
+        (op, val) => {
+            return Err(invalid(&format!(
+                "{}() does not accept {:?}",
+                op_name(op),
+                exp_type_of(&val)
+            )))
+        }
+    };
+    Ok(Some(res))
+}
+
+fn binary(op: ExpOp, left: Value, right: Value) -> Result<Option<Value>> {
+    let res = match (op, left, right) {
+        (ExpOp::Pow, Value::Float(base), Value::Float(exp)) => {
+            Value::from(f64::from(base).powf(f64::from(exp)))
+        }
+        (ExpOp::Log, Value::Float(num), Value::Float(base)) => {
+            Value::from(f64::from(num).log(f64::from(base)))
+        }
+        (ExpOp::Mod, Value::Int(num), Value::Int(denom)) => {
+            Value::Int(known!(num.checked_rem(denom)))
+        }
+        (ExpOp::IntLshift, Value::Int(val), Value::Int(shift)) => {
+            let shift = known!(u32::try_from(shift).ok());
+            Value::Int(val.checked_shl(shift).unwrap_or(0))
+        }
+        (ExpOp::IntRshift, Value::Int(val), Value::Int(shift)) => {
+            let shift = known!(u32::try_from(shift).ok());
+            Value::Int((val as u64).checked_shr(shift).unwrap_or(0) as i64)
+        }
+        (ExpOp::IntARshift, Value::Int(val), Value::Int(shift)) => {
+            let shift = known!(u32::try_from(shift).ok());
+            Value::Int(val >> shift.min(63))
+        }
+        (ExpOp::IntLscan | ExpOp::IntRscan, Value::Int(val), Value::Bool(search)) => {
+            let bit = |i: u32| (val as u64 >> (63 - i)) & 1 == u64::from(search);
+            let pos = if op == ExpOp::IntLscan {
+                (0..64).find(|i| bit(*i))
+            } else {
+                (0..64).rev().find(|i| bit(*i))
+            };
+            Value::Int(pos.map_or(-1, i64::from))
+        }
+        (op, left, right) => {
+            return Err(invalid(&format!(
+                "{}() does not accept {:?} and {:?}",
+                op_name(op),
+                exp_type_of(&left),
+                exp_type_of(&right)
+            )))
+        }
+    };
+    Ok(Some(res))
+}
+
+// Orders values like the server orders CDT elements: first by type, then by value.
+fn cmp(left: &Value, right: &Value) -> Ordering {
+    const fn type_order(val: &Value) -> u8 {
+        match val {
+            Value::Nil => 1,
+            Value::Bool(_) => 2,
+            Value::Int(_) => 3,
+            Value::String(_) => 4,
+            Value::List(_) | Value::MultiResult(_) => 5,
+            Value::HashMap(_) | Value::OrderedMap(_) | Value::KeyValueList(_) => 6,
+            Value::Blob(_) | Value::HLL(_) => 7,
+            Value::Float(_) => 8,
+            Value::GeoJSON(_) => 9,
+            Value::Infinity => 10,
+            Value::Wildcard => 0,
+        }
+    }
+
+    match (left, right) {
+        (Value::Wildcard, _) | (_, Value::Wildcard) => Ordering::Equal,
+        (Value::Bool(left), Value::Bool(right)) => left.cmp(right),
+        (Value::Int(left), Value::Int(right)) => left.cmp(right),
+        (Value::String(left), Value::String(right))
+        | (Value::GeoJSON(left), Value::GeoJSON(right)) => left.cmp(right),
+        (Value::Blob(left) | Value::HLL(left), Value::Blob(right) | Value::HLL(right)) => {
+            left.cmp(right)
+        }
+        (Value::Float(left), Value::Float(right)) => {
+            let (left, right) = (f64::from(left), f64::from(right));
+            left.partial_cmp(&right)
+                .unwrap_or_else(|| left.total_cmp(&right))
+        }
+        (
+            Value::List(left) | Value::MultiResult(left),
+            Value::List(right) | Value::MultiResult(right),
+        ) => {
+            for (left, right) in left.iter().zip(right) {
+                if matches!(left, Value::Wildcard) || matches!(right, Value::Wildcard) {
+                    return Ordering::Equal;
+                }
+                match cmp(left, right) {
+                    Ordering::Equal => {}
+                    ord => return ord,
+                }
+            }
+            left.len().cmp(&right.len())
+        }
+        (left, right) if type_order(left) == 6 && type_order(right) == 6 => {
+            let (left, right) = (sorted_entries(left), sorted_entries(right));
+            left.len().cmp(&right.len()).then_with(|| {
+                left.iter()
+                    .zip(&right)
+                    .map(|((lk, lv), (rk, rv))| cmp(lk, rk).then_with(|| cmp(lv, rv)))
+                    .find(|ord| *ord != Ordering::Equal)
+                    .unwrap_or(Ordering::Equal)
+            })
+        }
+        (left, right) => type_order(left).cmp(&type_order(right)),
+    }
+}
+
+fn values_eq(left: &Value, right: &Value) -> bool {
+    cmp(left, right) == Ordering::Equal
+}
+
+// Returns the entries of a map in key order, which is also the index order the server uses.
+fn sorted_entries(map: &Value) -> Vec<(&Value, &Value)> {
+    let mut entries: Vec<(&Value, &Value)> = match map {
+        Value::HashMap(map) => map.iter().collect(),
+        Value::OrderedMap(map) => map.iter().collect(),
+        Value::KeyValueList(list) => list.iter().map(|(key, val)| (key, val)).collect(),
+        _ => vec![],
+    };
+    entries.sort_by(|(left, _), (right, _)| cmp(left, right));
+    entries
+}
+
+fn rank_order(values: &[&Value]) -> Vec<usize> {
+    let mut order: Vec<usize> = (0..values.len()).collect();
+    order.sort_by(|left, right| cmp(values[*left], values[*right]));
+    order
+}
+
+fn resolve_index(index: i64, len: usize) -> Option<usize> {
+    let index = if index < 0 {
+        index.checked_add(len as i64)?
+    } else {
+        index
+    };
+    usize::try_from(index).ok().filter(|index| *index < len)
+}
+
+// Resolves a start position and an optional count to a range of positions. A start before the
+// first position reduces the count accordingly.
+fn resolve_range(start: i64, count: Option<i64>, len: usize) -> Range<usize> {
+    let mut count = count.unwrap_or(i64::MAX);
+    if count < 0 {
+        return 0..0;
+    }
+    let mut start = start;
+    if start < 0 {
+        count = count.saturating_add(start);
+        start = 0;
+    }
+    let start = (start as usize).min(len);
+    let end = start.saturating_add(count.max(0) as usize).min(len);
+    start..end
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum Selector {
+    Index,
+    IndexRange,
+    Rank,
+    RankRange,
+    Value,
+    ValueList,
+    ValueInterval,
+    ValueRelRankRange,
+    Key,
+    KeyList,
+    KeyInterval,
+    KeyRelIndexRange,
+}
+
+// Get and remove operations share the selector; whether elements are removed depends on the
+// modify flag of the call.
+const fn list_selector(op: i64) -> Option<Selector> {
+    let selector = match op {
+        19 | 32 => Selector::Index,
+        21 | 34 => Selector::Rank,
+        22 | 35 => Selector::Value,
+        23 | 36 => Selector::ValueList,
+        24 | 37 => Selector::IndexRange,
+        25 | 38 => Selector::ValueInterval,
+        26 | 39 => Selector::RankRange,
+        27 | 40 => Selector::ValueRelRankRange,
+        _ => return None,
+    };
+    Some(selector)
+}
+
+const fn map_selector(op: i64) -> Option<Selector> {
+    let selector = match op {
+        76 | 97 => Selector::Key,
+        77 | 98 => Selector::Index,
+        79 | 100 => Selector::Rank,
+        81 | 107 => Selector::KeyList,
+        82 | 102 => Selector::Value,
+        83 | 108 => Selector::ValueList,
+        84 | 103 => Selector::KeyInterval,
+        85 | 104 => Selector::IndexRange,
+        86 | 105 => Selector::ValueInterval,
+        87 | 106 => Selector::RankRange,
+        88 | 109 => Selector::KeyRelIndexRange,
+        89 | 110 => Selector::ValueRelRankRange,
+        _ => return None,
+    };
+    Some(selector)
+}
+
+// A list or map flattened into entries in index order. List entries have no keys.
+struct Entries<'v> {
+    keys: Option<Vec<&'v Value>>,
+    values: Vec<&'v Value>,
+}
+
+impl<'v> Entries<'v> {
+    fn new(val: &'v Value) -> Option<Self> {
+        match val {
+            Value::List(list) => Some(Entries {
+                keys: None,
+                values: list.iter().collect(),
+            }),
+            Value::HashMap(_) | Value::OrderedMap(_) | Value::KeyValueList(_) => {
+                let (keys, values) = sorted_entries(val).into_iter().unzip();
+                Some(Entries {
+                    keys: Some(keys),
+                    values,
+                })
+            }
+            _ => None,
+        }
+    }
+
+    const fn len(&self) -> usize {
+        self.values.len()
+    }
+
+    // Returns the selected positions, in the order the server returns them, or `None` if a
+    // single element was selected by an index or rank that is out of range.
+    fn select(&self, selector: Selector, params: &[Value]) -> Result<Option<Vec<usize>>> {
+        let int = |i: usize| {
+            params
+                .get(i)
+                .and_then(as_int)
+                .ok_or_else(|| invalid("Expected an integer argument"))
+        };
+        let opt_int = |i: usize| {
+            params
+                .get(i)
+                .map(|val| as_int(val).ok_or_else(|| invalid("Expected an integer argument")))
+                .transpose()
+        };
+        let param = |i: usize| params.get(i).ok_or_else(|| invalid("Missing argument"));
+        let list = |i: usize| match param(i)? {
+            Value::List(list) => Ok(list),
+            _ => Err(invalid("Expected a list argument")),
+        };
+        let keys = || {
+            self.keys
+                .as_ref()
+                .ok_or_else(|| invalid("Key selectors require a map"))
+        };
+        let len = self.len();
+        let all = 0..len;
+
+        let positions = match selector {
+            Selector::Index => match resolve_index(int(0)?, len) {
+                Some(index) => vec![index],
+                None => return Ok(None),
+            },
+            Selector::IndexRange => {
+                resolve_range(resolve_start(int(0)?, len), opt_int(1)?, len).collect()
+            }
+            Selector::Rank => match resolve_index(int(0)?, len) {
+                Some(rank) => vec![rank_order(&self.values)[rank]],
+                None => return Ok(None),
+            },
+            Selector::RankRange => {
+                let order = rank_order(&self.values);
+                order[resolve_range(resolve_start(int(0)?, len), opt_int(1)?, len)].to_vec()
+            }
+            Selector::Value => {
+                let val = param(0)?;
+                all.filter(|i| values_eq(self.values[*i], val)).collect()
+            }
+            Selector::ValueList => {
+                let list = list(0)?;
+                all.filter(|i| list.iter().any(|val| values_eq(self.values[*i], val)))
+                    .collect()
+            }
+            Selector::ValueInterval => {
+                let (begin, end) = (param(0)?, params.get(1));
+                all.filter(|i| in_interval(self.values[*i], begin, end))
+                    .collect()
+            }
+            Selector::ValueRelRankRange => {
+                let (val, rank) = (param(0)?, int(1)?);
+                let order = rank_order(&self.values);
+                let lower = order
+                    .iter()
+                    .take_while(|i| cmp(self.values[**i], val) == Ordering::Less)
+                    .count();
+                let start = (lower as i64).saturating_add(rank);
+                order[resolve_range(start, opt_int(2)?, len)].to_vec()
+            }
+            Selector::Key => {
+                let (keys, key) = (keys()?, param(0)?);
+                all.filter(|i| values_eq(keys[*i], key)).collect()
+            }
+            Selector::KeyList => {
+                let (keys, list) = (keys()?, list(0)?);
+                all.filter(|i| list.iter().any(|key| values_eq(keys[*i], key)))
+                    .collect()
+            }
+            Selector::KeyInterval => {
+                let (keys, begin, end) = (keys()?, param(0)?, params.get(1));
+                all.filter(|i| in_interval(keys[*i], begin, end)).collect()
+            }
+            Selector::KeyRelIndexRange => {
+                let (keys, key, index) = (keys()?, param(0)?, int(1)?);
+                let lower = keys
+                    .iter()
+                    .take_while(|k| cmp(k, key) == Ordering::Less)
+                    .count();
+                let start = (lower as i64).saturating_add(index);
+                resolve_range(start, opt_int(2)?, len).collect()
+            }
+        };
+        Ok(Some(positions))
+    }
+
+    fn result(&self, positions: &[usize], return_type: i64, single: bool) -> Result<Value> {
+        let len = self.len();
+        let ranks = || {
+            let mut ranks = vec![0; len];
+            for (rank, i) in rank_order(&self.values).into_iter().enumerate() {
+                ranks[i] = rank;
+            }
+            ranks
+        };
+        let collect = |vals: Vec<Value>| {
+            if single {
+                vals.into_iter().next().unwrap_or(Value::Nil)
+            } else {
+                Value::List(vals)
+            }
+        };
+        let ints = |f: &dyn Fn(usize) -> usize| {
+            collect(positions.iter().map(|i| Value::Int(f(*i) as i64)).collect())
+        };
+
+        let res = match return_type & !INVERTED {
+            0 => Value::Nil,
+            1 => ints(&|i| i),
+            2 => ints(&|i| len - 1 - i),
+            3 => {
+                let ranks = ranks();
+                ints(&|i| ranks[i])
+            }
+            4 => {
+                let ranks = ranks();
+                ints(&|i| len - 1 - ranks[i])
+            }
+            5 => Value::Int(positions.len() as i64),
+            6 => {
+                let keys = self
+                    .keys
+                    .as_ref()
+                    .ok_or_else(|| invalid("Lists have no keys"))?;
+                collect(positions.iter().map(|i| keys[*i].clone()).collect())
+            }
+            7 => collect(positions.iter().map(|i| self.values[*i].clone()).collect()),
+            8 | 16 | 17 => {
+                let keys = self
+                    .keys
+                    .as_ref()
+                    .ok_or_else(|| invalid("Lists have no keys"))?;
+                let entries = positions
+                    .iter()
+                    .map(|i| (keys[*i].clone(), self.values[*i].clone()));
+                if return_type & !INVERTED == 17 {
+                    Value::OrderedMap(entries.collect::<BTreeMap<_, _>>())
+                } else {
+                    Value::HashMap(entries.collect::<HashMap<_, _>>())
+                }
+            }
+            13 => Value::Bool(!positions.is_empty()),
+            rt => return Err(invalid(&format!("Unsupported return type {rt}"))),
+        };
+        Ok(res)
+    }
+}
+
+const fn resolve_start(start: i64, len: usize) -> i64 {
+    if start < 0 {
+        start.saturating_add(len as i64)
+    } else {
+        start
+    }
+}
+
+// Intervals include the begin value and exclude the end value; a missing end is unbounded.
+fn in_interval(val: &Value, begin: &Value, end: Option<&Value>) -> bool {
+    cmp(val, begin) != Ordering::Less && end.is_none_or(|end| cmp(val, end) == Ordering::Less)
+}
+
+fn cdt_call(
+    op: i64,
+    params: &[Value],
+    ctx: &[CdtContext],
+    mut target: Value,
+    modify: bool,
+) -> Result<Option<Value>> {
+    let Some(node) = navigate(&mut target, ctx) else {
+        return Ok(None);
+    };
+
+    let is_list = matches!(node, Value::List(_));
+    if !is_list && exp_type_of(node) != ExpType::MAP {
+        return Ok(None);
+    }
+    // Lists and maps share the module; map operation codes start at 64.
+    if is_list != (op < 64) {
+        return Ok(None);
+    }
+
+    let selector = if is_list {
+        list_selector(op)
+    } else {
+        map_selector(op)
+    };
+
+    if !modify {
+        let entries = Entries::new(node).unwrap_or_else(|| unreachable!());
+        if op == 16 || op == 96 {
+            return Ok(Some(Value::Int(entries.len() as i64)));
+        }
+        let Some(selector) = selector else {
+            return Err(invalid(&format!("Unsupported CDT read operation {op}")));
+        };
+        let (return_type, params) = split_return_type(params)?;
+        let Some(mut positions) = entries.select(selector, params)? else {
+            return Ok(None);
+        };
+        if return_type & INVERTED != 0 {
+            positions = invert(&positions, entries.len());
+        }
+        let single = return_type & INVERTED == 0
+            && matches!(selector, Selector::Index | Selector::Rank | Selector::Key);
+        return entries.result(&positions, return_type, single).map(Some);
+    }
+
+    if let Some(selector) = selector {
+        let (return_type, params) = split_return_type(params)?;
+        let entries = Entries::new(node).unwrap_or_else(|| unreachable!());
+        let Some(mut positions) = entries.select(selector, params)? else {
+            return Ok(None);
+        };
+        if return_type & INVERTED != 0 {
+            positions = invert(&positions, entries.len());
+        }
+        remove_positions(node, positions);
+    } else if is_list {
+        known!(list_modify(op, params, node)?);
+    } else {
+        known!(map_modify(op, params, node)?);
+    }
+    Ok(Some(target))
+}
+
+fn split_return_type(params: &[Value]) -> Result<(i64, &[Value])> {
+    match params.split_first() {
+        Some((Value::Int(return_type), params)) => Ok((*return_type, params)),
+        _ => Err(invalid("Missing return type")),
+    }
+}
+
+fn invert(positions: &[usize], len: usize) -> Vec<usize> {
+    (0..len).filter(|i| !positions.contains(i)).collect()
+}
+
+fn remove_positions(node: &mut Value, mut positions: Vec<usize>) {
+    positions.sort_unstable();
+    positions.dedup();
+    if let Value::List(list) = node {
+        for i in positions.into_iter().rev() {
+            list.remove(i);
+        }
+        return;
+    }
+
+    let keys: Vec<Value> = {
+        let entries = sorted_entries(node);
+        positions.iter().map(|i| entries[*i].0.clone()).collect()
+    };
+    for key in &keys {
+        map_remove(node, key);
+    }
+}
+
+fn navigate<'v>(node: &'v mut Value, ctx: &[CdtContext]) -> Option<&'v mut Value> {
+    let Some((first, rest)) = ctx.split_first() else {
+        return Some(node);
+    };
+
+    let next = match (first.id, node) {
+        (0x10, Value::List(list)) => {
+            let index = resolve_index(as_int(&first.value)?, list.len())?;
+            list.get_mut(index)
+        }
+        (0x11, Value::List(list)) => {
+            let index = resolve_index(as_int(&first.value)?, list.len())?;
+            let index = rank_order(&list.iter().collect::<Vec<_>>())[index];
+            list.get_mut(index)
+        }
+        (0x13, Value::List(list)) => list.iter_mut().find(|val| values_eq(val, &first.value)),
+        (id @ 0x20..=0x23, node) if exp_type_of(node) == ExpType::MAP => {
+            let key = {
+                let entries = sorted_entries(node);
+                let index = match id {
+                    0x20 => resolve_index(as_int(&first.value)?, entries.len())?,
+                    0x21 => {
+                        let values: Vec<&Value> = entries.iter().map(|(_, val)| *val).collect();
+                        rank_order(&values)[resolve_index(as_int(&first.value)?, values.len())?]
+                    }
+                    0x22 => entries
+                        .iter()
+                        .position(|(key, _)| values_eq(key, &first.value))?,
+                    _ => entries
+                        .iter()
+                        .position(|(_, val)| values_eq(val, &first.value))?,
+                };
+                entries[index].0.clone()
+            };
+            map_get_mut(node, &key)
+        }
+        _ => None,
+    }?;
+    navigate(next, rest)
+}
+
+fn map_get_mut<'v>(map: &'v mut Value, key: &Value) -> Option<&'v mut Value> {
+    match map {
+        Value::HashMap(map) => map.get_mut(key),
+        Value::OrderedMap(map) => map.get_mut(key),
+        Value::KeyValueList(list) => list
+            .iter_mut()
+            .find(|(k, _)| values_eq(k, key))
+            .map(|(_, val)| val),
+        _ => None,
+    }
+}
+
+fn map_remove(map: &mut Value, key: &Value) {
+    match map {
+        Value::HashMap(map) => {
+            map.remove(key);
+        }
+        Value::OrderedMap(map) => {
+            map.remove(key);
+        }
+        Value::KeyValueList(list) => list.retain(|(k, _)| !values_eq(k, key)),
+        _ => {}
+    }
+}
+
+fn map_insert(map: &mut Value, key: Value, val: Value) {
+    match map {
+        Value::HashMap(map) => {
+            map.insert(key, val);
+        }
+        Value::OrderedMap(map) => {
+            map.insert(key, val);
+        }
+        Value::KeyValueList(list) => {
+            list.retain(|(k, _)| !values_eq(k, &key));
+            list.push((key, val));
+        }
+        _ => {}
+    }
+}
+
+fn increment(current: Option<&Value>, incr: &Value) -> Option<Value> {
+    match (current, incr) {
+        (None | Some(Value::Nil), incr) => Some(incr.clone()),
+        (Some(Value::Int(val)), Value::Int(incr)) => Some(Value::Int(val.wrapping_add(*incr))),
+        (Some(Value::Float(val)), Value::Float(incr)) => {
+            Some(Value::from(f64::from(val) + f64::from(incr)))
+        }
+        _ => None,
+    }
+}
+
+// Applies a list write operation. Returns `None` if the server would fail the operation.
+fn list_modify(op: i64, params: &[Value], node: &mut Value) -> Result<Option<()>> {
+    let Value::List(list) = node else {
+        return Ok(None);
+    };
+    let param = |i: usize| params.get(i).ok_or_else(|| invalid("Missing argument"));
+    let items = |i: usize| match param(i)? {
+        Value::List(items) => Ok(items.clone()),
+        _ => Err(invalid("Expected a list argument")),
+    };
+    // Inserting past the end of a list pads it with nil values.
+    let insert_index = |index: &Value, len: usize| {
+        let index = as_int(index).ok_or_else(|| invalid("Expected an integer argument"))?;
+        let index = if index < 0 {
+            index.checked_add(len as i64).filter(|index| *index >= 0)
+        } else {
+            Some(index)
+        };
+        Ok::<_, Error>(index.map(|index| index as usize))
+    };
+    let ordered = |i: usize| params.get(i).and_then(as_int).unwrap_or_default() & 1 != 0;
+    let append = |list: &mut Vec<Value>, val: Value, ordered: bool| {
+        if ordered {
+            let pos = list
+                .iter()
+                .take_while(|item| cmp(item, &val) != Ordering::Greater)
+                .count();
+            list.insert(pos, val);
+        } else {
+            list.push(val);
+        }
+    };
+
+    match op {
+        1 => append(list, param(0)?.clone(), ordered(1)),
+        2 => {
+            let ordered = ordered(1);
+            for val in items(0)? {
+                append(list, val, ordered);
+            }
+        }
+        3 | 4 => {
+            let index = known!(insert_index(param(0)?, list.len())?);
+            let vals = if op == 3 {
+                vec![param(1)?.clone()]
+            } else {
+                items(1)?
+            };
+            if index > list.len() {
+                list.resize(index, Value::Nil);
+            }
+            list.splice(index..index, vals);
+        }
+        9 => {
+            let index = known!(insert_index(param(0)?, list.len())?);
+            if index >= list.len() {
+                list.resize(index + 1, Value::Nil);
+            }
+            list[index] = param(1)?.clone();
+        }
+        11 => list.clear(),
+        12 => {
+            let index = known!(insert_index(param(0)?, list.len())?);
+            if index >= list.len() {
+                list.resize(index + 1, Value::Nil);
+            }
+            list[index] = known!(increment(Some(&list[index]), param(1)?));
+        }
+        13 => {
+            let flags = params.first().and_then(as_int).unwrap_or_default();
+            list.sort_by(cmp);
+            if flags & 2 != 0 {
+                list.dedup_by(|left, right| values_eq(left, right));
+            }
+            if flags & 1 != 0 {
+                list.reverse();
+            }
+        }
+        _ => return Err(invalid(&format!("Unsupported list write operation {op}"))),
+    }
+    Ok(Some(()))
+}
+
+// Applies a map write operation. Returns `None` if the server would fail the operation.
+fn map_modify(op: i64, params: &[Value], node: &mut Value) -> Result<Option<()>> {
+    let param = |i: usize| params.get(i).ok_or_else(|| invalid("Missing argument"));
+    match op {
+        // add, put and replace differ in whether the key must be absent or present.
+        65..=70 => {
+            let items: Vec<(Value, Value)> = if matches!(op, 65 | 67 | 69) {
+                vec![(param(0)?.clone(), param(1)?.clone())]
+            } else {
+                sorted_entries(param(0)?)
+                    .into_iter()
+                    .map(|(key, val)| (key.clone(), val.clone()))
+                    .collect()
+            };
+            for (key, val) in items {
+                let exists = map_get_mut(node, &key).is_some();
+                match op {
+                    65 | 66 if exists => return Ok(None),
+                    69 | 70 if !exists => return Ok(None),
+                    _ => map_insert(node, key, val),
+                }
+            }
+        }
+        73 => {
+            let key = param(0)?.clone();
+            let current = map_get_mut(node, &key).map(|val| val.clone());
+            let val = known!(increment(current.as_ref(), param(1)?));
+            map_insert(node, key, val);
+        }
+        75 => match node {
+            Value::HashMap(map) => map.clear(),
+            Value::OrderedMap(map) => map.clear(),
+            Value::KeyValueList(list) => list.clear(),
+            _ => {}
+        },
+        _ => return Err(invalid(&format!("Unsupported map write operation {op}"))),
+    }
+    Ok(Some(()))
+}
+
+fn bit_read(op: i64, params: &[Value], target: &Value) -> Result<Option<Value>> {
+    let Value::Blob(bytes) = target else {
+        return Ok(None);
+    };
+    let int = |i: usize| {
+        params
+            .get(i)
+            .and_then(as_int)
+            .ok_or_else(|| invalid("Expected an integer argument"))
+    };
+
+    let total = bytes.len() as i64 * 8;
+    let offset = resolve_start(int(0)?, bytes.len() * 8);
+    let size = int(1)?;
+    if offset < 0 || size < 0 || offset.saturating_add(size) > total {
+        return Ok(None);
+    }
+    let (offset, size) = (offset as usize, size as usize);
+    let bit = |i: usize| (bytes[(offset + i) / 8] >> (7 - (offset + i) % 8)) & 1 == 1;
+
+    let res = match op {
+        50 => {
+            let mut out = vec![0u8; size.div_ceil(8)];
+            for i in (0..size).filter(|i| bit(*i)) {
+                out[i / 8] |= 0x80 >> (i % 8);
+            }
+            Value::Blob(out)
+        }
+        51 => Value::Int((0..size).filter(|i| bit(*i)).count() as i64),
+        52 | 53 => {
+            let search = match params.get(2) {
+                Some(Value::Bool(search)) => *search,
+                Some(Value::Int(search)) => *search != 0,
+                _ => return Err(invalid("Expected a boolean argument")),
+            };
+            let pos = if op == 52 {
+                (0..size).find(|i| bit(*i) == search)
+            } else {
+                (0..size).rev().find(|i| bit(*i) == search)
+            };
+            Value::Int(pos.map_or(-1, |pos| pos as i64))
+        }
+        54 => {
+            if size == 0 || size > 64 {
+                return Ok(None);
+            }
+            let val = (0..size).fold(0u64, |acc, i| (acc << 1) | u64::from(bit(i)));
+            let signed = params.get(2).and_then(as_int) == Some(1);
+            if signed && size < 64 && val >> (size - 1) & 1 == 1 {
+                Value::Int((val as i64).wrapping_sub(1 << size))
+            } else {
+                Value::Int(val as i64)
+            }
+        }
+        _ => return Err(invalid(&format!("Unsupported bitwise read operation {op}"))),
+    };
+    Ok(Some(res))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use crate::expressions::*;
+    use crate::operations::cdt_context::ctx_map_key;
+    use crate::operations::lists::{ListPolicy, ListReturnType, ListSortFlags};
+    use crate::operations::maps::{MapPolicy, MapReturnType};
+    use crate::{Key, Record, Value};
+
+    fn record() -> Record {
+        let mut bins = HashMap::new();
+        bins.insert("i".to_string(), Value::from(10));
+        bins.insert("f".to_string(), Value::from(2.5));
+        bins.insert("s".to_string(), Value::from("Hello"));
+        bins.insert(
+            "b".to_string(),
+            Value::from(vec![0b0000_0001u8, 0b0100_0010]),
+        );
+        bins.insert("l".to_string(), as_list!(5, 1, 3, "a"));
+        bins.insert(
+            "m".to_string(),
+            as_map!("a" => 1, "b" => 2, "c" => as_map!("x" => 10)),
+        );
+        let key = as_key!("test", "demo", "k");
+        Record::new(Some(key), bins, 1, 0)
+    }
+
+    fn eval(exp: &Expression) -> Option<Value> {
+        exp.evaluate(&record()).unwrap()
+    }
+
+    #[test]
+    fn comparisons_and_unknown() {
+        let rec = record();
+        assert!(gt(int_bin("i".to_string()), int_val(5))
+            .matches(&rec)
+            .unwrap());
+        assert!(
+            eq(string_bin("s".to_string()), string_val("Hello".to_string()))
+                .matches(&rec)
+                .unwrap()
+        );
+
+        // Missing bins and bins of a different type are unknown.
+        let missing = gt(int_bin("x".to_string()), int_val(5));
+        let wrong_type = eq(int_bin("s".to_string()), int_val(5));
+        assert_eq!(eval(&missing), None);
+        assert_eq!(eval(&wrong_type), None);
+        assert!(!missing.matches(&rec).unwrap());
+
+        let truthy = gt(int_bin("i".to_string()), int_val(5));
+        let falsy = lt(int_bin("i".to_string()), int_val(5));
+        assert_eq!(
+            eval(&and(vec![falsy.clone(), missing.clone()])),
+            Some(Value::from(false))
+        );
+        assert_eq!(eval(&and(vec![truthy.clone(), missing.clone()])), None);
+        assert_eq!(
+            eval(&or(vec![missing.clone(), truthy])),
+            Some(Value::from(true))
+        );
+        assert_eq!(eval(&or(vec![missing.clone(), falsy])), None);
+        assert_eq!(eval(&not(missing)), None);
+        assert_eq!(eval(&unknown()), None);
+
+        // Comparing values of different types is rejected like on the server.
+        let exp = eq(int_bin("i".to_string()), string_val("10".to_string()));
+        assert!(exp.evaluate(&rec).is_err());
+    }
+
+    #[test]
+    fn arithmetic() {
+        let exp = num_add(vec![int_bin("i".to_string()), int_val(5)]);
+        assert_eq!(eval(&exp), Some(Value::from(15)));
+        assert_eq!(eval(&num_sub(vec![int_val(5)])), Some(Value::from(-5)));
+        assert_eq!(
+            eval(&num_div(vec![int_val(7), int_val(2)])),
+            Some(Value::from(3))
+        );
+        assert_eq!(eval(&num_div(vec![int_val(7), int_val(0)])), None);
+        assert_eq!(eval(&num_mod(int_val(7), int_val(3))), Some(Value::from(1)));
+        assert_eq!(
+            eval(&num_pow(float_bin("f".to_string()), float_val(2.0))),
+            Some(Value::from(6.25))
+        );
+        assert_eq!(eval(&to_int(float_val(2.9))), Some(Value::from(2)));
+        assert_eq!(
+            eval(&max(vec![int_val(1), int_val(9), int_val(4)])),
+            Some(Value::from(9))
+        );
+        assert_eq!(eval(&int_count(int_val(0b1011))), Some(Value::from(3)));
+        assert_eq!(
+            eval(&int_lscan(int_val(1), bool_val(true))),
+            Some(Value::from(63))
+        );
+        assert_eq!(
+            eval(&int_arshift(int_val(-8), int_val(1))),
+            Some(Value::from(-4))
+        );
+
+        let mixed = num_add(vec![int_val(1), float_val(1.0)]);
+        assert!(mixed.evaluate(&record()).is_err());
+    }
+
+    #[test]
+    fn cond_and_variables() {
+        let exp = exp_let(vec![
+            def(
+                "x".to_string(),
+                num_mul(vec![int_bin("i".to_string()), int_val(2)]),
+            ),
+            cond(vec![
+                gt(var("x".to_string()), int_val(100)),
+                string_val("big".to_string()),
+                gt(var("x".to_string()), int_val(10)),
+                string_val("medium".to_string()),
+                string_val("small".to_string()),
+            ]),
+        ]);
+        assert_eq!(eval(&exp), Some(Value::from("medium")));
+        assert!(var("x".to_string()).evaluate(&record()).is_err());
+    }
+
+    #[test]
+    fn metadata() {
+        let rec = record();
+        assert_eq!(eval(&ttl()), Some(Value::from(-1)));
+        assert_eq!(eval(&void_time()), Some(Value::from(-1)));
+        assert_eq!(eval(&key_exists()), Some(Value::from(true)));
+        assert_eq!(eval(&set_name()), Some(Value::from("demo")));
+        assert_eq!(eval(&key(ExpType::STRING)), Some(Value::from("k")));
+        assert_eq!(eval(&key(ExpType::INT)), None);
+        assert!(bin_exists("s".to_string()).matches(&rec).unwrap());
+        assert!(!bin_exists("x".to_string()).matches(&rec).unwrap());
+        assert!(last_update().evaluate(&rec).is_err());
+
+        let mut rec = rec;
+        rec.key = Some(Key::new("test", "demo", Value::from(1)).unwrap());
+        assert_eq!(
+            key(ExpType::INT).evaluate(&rec).unwrap(),
+            Some(Value::from(1))
+        );
+    }
+
+    #[test]
+    fn regex() {
+        let exp = regex_compare("^hel".to_string(), 2, string_bin("s".to_string()));
+        assert_eq!(eval(&exp), Some(Value::from(true)));
+        let exp = regex_compare("^hel".to_string(), 0, string_bin("s".to_string()));
+        assert_eq!(eval(&exp), Some(Value::from(false)));
+    }
+
+    #[test]
+    fn lists() {
+        let bin = || list_bin("l".to_string());
+        let exp = lists::get_by_index(
+            ListReturnType::Values,
+            ExpType::INT,
+            int_val(-2),
+            bin(),
+            &[],
+        );
+        assert_eq!(eval(&exp), Some(Value::from(3)));
+        let exp = lists::get_by_rank(ListReturnType::Values, ExpType::INT, int_val(0), bin(), &[]);
+        assert_eq!(eval(&exp), Some(Value::from(1)));
+        let exp = lists::get_by_value_range(
+            ListReturnType::Count,
+            Some(int_val(2)),
+            Some(int_val(6)),
+            bin(),
+            &[],
+        );
+        assert_eq!(eval(&exp), Some(Value::from(2)));
+        let exp = lists::get_by_rank_range_count(
+            ListReturnType::Values,
+            int_val(0),
+            int_val(2),
+            bin(),
+            &[],
+        );
+        assert_eq!(eval(&exp), Some(as_list!(1, 3)));
+        let exp = lists::get_by_index(
+            ListReturnType::Values,
+            ExpType::INT,
+            int_val(10),
+            bin(),
+            &[],
+        );
+        assert_eq!(eval(&exp), None);
+
+        let exp = lists::append(ListPolicy::default(), int_val(7), bin(), &[]);
+        assert_eq!(eval(&lists::size(exp, &[])), Some(Value::from(5)));
+        let exp = lists::remove_by_value(
+            ListReturnType::None,
+            string_val("a".to_string()),
+            bin(),
+            &[],
+        );
+        assert_eq!(eval(&exp), Some(as_list!(5, 1, 3)));
+        let exp = lists::sort(
+            ListSortFlags::Default,
+            lists::remove_by_index(ListReturnType::None, int_val(3), bin(), &[]),
+            &[],
+        );
+        assert_eq!(eval(&exp), Some(as_list!(1, 3, 5)));
+    }
+
+    #[test]
+    fn maps() {
+        let bin = || map_bin("m".to_string());
+        let exp = maps::get_by_key(
+            MapReturnType::Value,
+            ExpType::INT,
+            string_val("b".to_string()),
+            bin(),
+            &[],
+        );
+        assert_eq!(eval(&exp), Some(Value::from(2)));
+        let exp = maps::get_by_key(
+            MapReturnType::Value,
+            ExpType::INT,
+            string_val("x".to_string()),
+            bin(),
+            &[ctx_map_key(Value::from("c"))],
+        );
+        assert_eq!(eval(&exp), Some(Value::from(10)));
+        let exp = maps::get_by_index_range(MapReturnType::Key, int_val(0), bin(), &[]);
+        assert_eq!(eval(&exp), Some(as_list!("a", "b", "c")));
+        let exp = maps::get_by_key(
+            MapReturnType::Exists,
+            ExpType::BOOL,
+            string_val("z".to_string()),
+            bin(),
+            &[],
+        );
+        assert_eq!(eval(&exp), Some(Value::from(false)));
+
+        let exp = maps::put(
+            &MapPolicy::default(),
+            string_val("d".to_string()),
+            int_val(4),
+            bin(),
+            &[],
+        );
+        assert_eq!(eval(&maps::size(exp, &[])), Some(Value::from(4)));
+        let exp = maps::remove_by_key(MapReturnType::None, string_val("c".to_string()), bin(), &[]);
+        assert_eq!(eval(&exp), Some(as_map!("a" => 1, "b" => 2)));
+    }
+
+    #[test]
+    fn bitwise() {
+        let bin = || blob_bin("b".to_string());
+        assert_eq!(
+            eval(&bitwise::count(int_val(0), int_val(16), bin())),
+            Some(Value::from(3))
+        );
+        assert_eq!(
+            eval(&bitwise::get_int(int_val(8), int_val(8), false, bin())),
+            Some(Value::from(0b0100_0010))
+        );
+        assert_eq!(
+            eval(&bitwise::lscan(
+                int_val(8),
+                int_val(8),
+                bool_val(true),
+                bin()
+            )),
+            Some(Value::from(1))
+        );
+        assert_eq!(
+            eval(&bitwise::get(int_val(7), int_val(3), bin())),
+            Some(Value::from(vec![0b1010_0000u8]))
+        );
+        assert_eq!(eval(&bitwise::count(int_val(8), int_val(16), bin())), None);
+    }
+}
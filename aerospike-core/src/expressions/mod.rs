@@ -16,6 +16,10 @@
 //! Functions used for Filter Expressions. This module requires Aerospike Server version >= 5.2
 
 pub mod bitwise;
+pub mod cdt;
+mod decoder;
+mod display;
+mod eval;
 pub mod hll;
 pub mod lists;
 pub mod maps;
@@ -23,12 +27,70 @@ pub mod regex_flag;
 use base64::{engine::general_purpose::STANDARD as BASE64, Engine as _};
 
 use crate::commands::buffer::Buffer;
-use crate::msgpack::encoder::{pack_array_begin, pack_integer, pack_raw_string, pack_value};
+use crate::msgpack::encoder::{
+    pack_array_begin, pack_ctx_list, pack_ctx_value, pack_integer, pack_raw_string, pack_value,
+};
 use crate::operations::cdt_context::CdtContext;
 use crate::value::MapLike;
+use crate::{EpochNanos, Geometry, ParticleType, ToGeoJson, Value};
 use crate::{Error, Result};
-use crate::{ParticleType, Value};
 use std::fmt::Debug;
+use std::time::SystemTime;
+
+#[doc(hidden)]
+pub use aerospike_macro::exp as __exp;
+
+/// Builds a filter expression from Rust-like syntax.
+///
+/// The macro expands to calls of the builder functions in this module and checks the types of
+/// the expression at compile time.
+///
+/// - Bins are written `$name`, or `$"name"` for names that are not identifiers. The type of a bin
+///   is inferred from the literals and operators it is used with, or can be given explicitly as
+///   `$name: int`. Supported types are `int`, `float`, `string`, `bool`, `blob`, `list`, `map`,
+///   `geo` and `hll`.
+/// - Record metadata is written `$.ttl`, `$.void_time`, `$.last_update`, `$.since_update`,
+///   `$.set_name`, `$.key_exists`, `$.is_tombstone`, `$.record_size`, `$.device_size`,
+///   `$.memory_size`, `$.digest_modulo(3)` and `$.key`, which is typed like a bin.
+/// - Integer, float, string and boolean literals are supported. Other Rust expressions of type
+///   [`Expression`] can be spliced in with `{...}` and annotated like bins.
+/// - The operators `||`, `&&`, `!`, `==`, `!=`, `<`, `<=`, `>`, `>=`, `+`, `-`, `*`, `/`, `%`,
+///   `&`, `|`, `^`, `<<` and `>>` have the same precedence as in Rust. `&`, `|`, `^` and `!` are
+///   bitwise on integers and logical on booleans, `>>` is an arithmetic shift.
+/// - The functions `abs`, `ceil`, `floor`, `pow`, `log`, `min`, `max`, `to_int`, `to_float`
+///   and `regex(bin, "pattern", flags)` map to the corresponding builder functions.
+///
+/// ```
+/// use aerospike::exp;
+/// use aerospike::expressions::{and, gt, int_bin, int_val, lt, or, string_bin, string_val, eq, ttl};
+///
+/// let limit = 3600;
+/// let exp = exp!($a > 5 && $name == "x" || $.ttl < {int_val(limit)});
+/// assert_eq!(
+///     exp,
+///     or(vec![
+///         and(vec![
+///             gt(int_bin("a".to_string()), int_val(5)),
+///             eq(string_bin("name".to_string()), string_val("x".to_string())),
+///         ]),
+///         lt(ttl(), int_val(3600)),
+///     ])
+/// );
+/// ```
+///
+/// Comparing values of different types does not compile:
+///
+/// ```compile_fail
+/// use aerospike::exp;
+///
+/// let exp = exp!($a > 5 && $a == "x");
+/// ```
+#[macro_export]
+macro_rules! exp {
+    ($($tokens:tt)+) => {
+        $crate::expressions::__exp!([$crate] $($tokens)+)
+    };
+}
 
 /// Expression data types for use in filter expressions on Map and List operations.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -109,6 +171,7 @@ pub(crate) enum ExpOp {
     Key = 80,
     Bin = 81,
     BinType = 82,
+    VarBuiltin = 122,
     Cond = 123,
     Var = 124,
     Let = 125,
@@ -123,6 +186,8 @@ pub(crate) enum ExpressionArgument {
     Value(Value),
     FilterExpression(Expression),
     Context(Vec<CdtContext>),
+    // Context of a path expression, which is packed in place as a flat list of ids and values.
+    PathContext(Vec<CdtContext>),
 }
 
 /// Filter expression, which can be applied to most commands to control which records are affected.
@@ -145,7 +210,7 @@ pub struct Expression {
     exps: Option<Vec<Expression>>,
     /// Optional Arguments (CDT)
     arguments: Option<Vec<ExpressionArgument>>,
-    /// Pre-packed expression bytes (used by [`from_base64`]).
+    /// Pre-packed expression bytes that could not be decoded (used by [`from_bytes`]).
     bytes: Option<Vec<u8>>,
 }
 
@@ -228,7 +293,8 @@ impl Expression {
                         // First match to estimate the Size and write the Context
                         match arg {
                             ExpressionArgument::Value(_)
-                            | ExpressionArgument::FilterExpression(_) => len += 1,
+                            | ExpressionArgument::FilterExpression(_)
+                            | ExpressionArgument::PathContext(_) => len += 1,
                             ExpressionArgument::Context(ctx) => {
                                 if !ctx.is_empty() {
                                     size += pack_array_begin(buf, 3);
@@ -237,7 +303,7 @@ impl Expression {
 
                                     for c in ctx {
                                         size += pack_integer(buf, i64::from(c.id));
-                                        size += pack_value(buf, &c.value)?;
+                                        size += pack_ctx_value(buf, c)?;
                                     }
                                 }
                             }
@@ -253,6 +319,9 @@ impl Expression {
                             ExpressionArgument::FilterExpression(cmd) => {
                                 size += cmd.pack(buf)?;
                             }
+                            ExpressionArgument::PathContext(ctx) => {
+                                size += pack_ctx_list(buf, ctx)?;
+                            }
                             ExpressionArgument::Context(_) => {}
                         }
                     }
@@ -273,6 +342,15 @@ impl Expression {
                 // The name - Raw String is needed instead of the msgpack String that the pack_value method would use.
                 size += pack_raw_string(buf, &self.val.clone().unwrap().to_string());
             }
+            ExpOp::VarBuiltin => {
+                // Loop variable encoder
+                size += pack_array_begin(buf, 3);
+                size += pack_integer(buf, cmd as i64);
+                // The type of the variable
+                size += pack_integer(buf, self.module.unwrap() as i64);
+                // The part of the element (map key, value or index)
+                size += self.pack_value(buf)?;
+            }
             ExpOp::BinType | ExpOp::Var => {
                 // BinType/Var encoder
                 size += pack_array_begin(buf, 2);
@@ -333,13 +411,26 @@ impl Expression {
         Ok(size)
     }
 
-    /// Encode the expression to a base64 string.
-    pub fn base64(&self) -> Result<String> {
+    /// Encode the expression to its msgpack wire format, as sent to the server.
+    pub fn to_bytes(&self) -> Result<Vec<u8>> {
         let sz = self.size()?;
         let mut buf = Buffer::new(sz);
         buf.resize_buffer(sz)?;
         self.pack(&mut Some(&mut buf))?;
-        Ok(BASE64.encode(&buf.data_buffer[..buf.data_offset]))
+        buf.data_buffer.truncate(buf.data_offset);
+        Ok(buf.data_buffer)
+    }
+
+    /// Encode the expression to a base64 string.
+    pub fn to_base64(&self) -> Result<String> {
+        Ok(BASE64.encode(self.to_bytes()?))
+    }
+
+    /// Encode the expression to a base64 string.
+    ///
+    /// This is an alias for [`to_base64`](Expression::to_base64).
+    pub fn base64(&self) -> Result<String> {
+        self.to_base64()
     }
 }
 
@@ -361,11 +452,32 @@ pub fn key(exp_type: ExpType) -> Expression {
 }
 
 /// Creates an expression from a base64-encoded expression string.
+///
+/// The expression tree is decoded, so the result can be inspected, printed or combined with other
+/// expressions like any expression built in code. See [`from_bytes`] for expressions that cannot
+/// be decoded.
+/// ```
+/// use aerospike::expressions::{eq, from_base64, int_bin, int_val};
+/// let exp = eq(int_bin("a".to_string()), int_val(5));
+/// let decoded = from_base64(&exp.to_base64().unwrap()).unwrap();
+/// assert_eq!(decoded, exp);
+/// assert_eq!(decoded.to_string(), r#"eq(int_bin("a"), 5)"#);
+/// ```
 pub fn from_base64(b64: &str) -> Result<Expression> {
     let bytes = BASE64
         .decode(b64)
         .map_err(|e| Error::BadResponse(format!("Invalid base64 expression: {e}")))?;
-    Ok(Expression {
+    from_bytes(&bytes)
+}
+
+/// Creates an expression from its msgpack wire format, as produced by
+/// [`Expression::to_bytes`].
+///
+/// Expressions that cannot be decoded, e.g. because they use operations of a newer server
+/// version, are kept as packed bytes and sent to the server as they are. They are displayed as
+/// `raw("<base64>")`.
+pub fn from_bytes(bytes: &[u8]) -> Result<Expression> {
+    Ok(decoder::decode(bytes).unwrap_or_else(|_| Expression {
         cmd: None,
         val: None,
         bin: None,
@@ -373,8 +485,8 @@ pub fn from_base64(b64: &str) -> Result<Expression> {
         module: None,
         exps: None,
         arguments: None,
-        bytes: Some(bytes),
-    })
+        bytes: Some(bytes.to_vec()),
+    }))
 }
 
 /// Creates an expression that returns whether the primary key is stored in the record meta data.
@@ -692,6 +804,69 @@ pub fn ttl() -> Expression {
     Expression::new(Some(ExpOp::TTL), None, None, None, None, None)
 }
 
+/// Creates an integer value holding the point in time as nanoseconds since 1970-01-01 epoch, the
+/// unit of [`last_update()`] and [`void_time()`].
+///
+/// Points in time after the year 2262 or before 1677 are saturated to `i64::MAX` or `i64::MIN`.
+/// ```
+/// // Record last updated before 2020-08-01
+/// use std::time::{Duration, UNIX_EPOCH};
+/// use aerospike::expressions::{last_update, lt, time_val};
+/// lt(last_update(), time_val(UNIX_EPOCH + Duration::from_secs(1_596_240_000)));
+/// ```
+pub fn time_val(time: SystemTime) -> Expression {
+    Expression::new(
+        None,
+        Some(Value::from(EpochNanos(time))),
+        None,
+        None,
+        None,
+        None,
+    )
+}
+
+/// Creates an expression that returns true if the record was last updated before the given
+/// point in time.
+/// ```
+/// // Records not updated in the last day
+/// use std::time::{Duration, SystemTime};
+/// use aerospike::expressions::last_update_before;
+/// last_update_before(SystemTime::now() - Duration::from_secs(24 * 60 * 60));
+/// ```
+pub fn last_update_before(time: SystemTime) -> Expression {
+    lt(last_update(), time_val(time))
+}
+
+/// Creates an expression that returns true if the record was last updated at or after the given
+/// point in time.
+pub fn last_update_since(time: SystemTime) -> Expression {
+    ge(last_update(), time_val(time))
+}
+
+/// Creates an expression that returns true if the record expires before the given point in
+/// time. Records that never expire do not match.
+/// ```
+/// // Records that expire within the next hour
+/// use std::time::{Duration, SystemTime};
+/// use aerospike::expressions::void_time_before;
+/// void_time_before(SystemTime::now() + Duration::from_secs(60 * 60));
+/// ```
+pub fn void_time_before(time: SystemTime) -> Expression {
+    and(vec![
+        ne(void_time(), int_val(-1)),
+        lt(void_time(), time_val(time)),
+    ])
+}
+
+/// Creates an expression that returns true if the record expires after the given point in time.
+/// Records that never expire match.
+pub fn void_time_after(time: SystemTime) -> Expression {
+    or(vec![
+        eq(void_time(), int_val(-1)),
+        gt(void_time(), time_val(time)),
+    ])
+}
+
 /// Creates expression that returns if record has been deleted and is still in tombstone state.
 /// This expression usually evaluates quickly because record meta data is cached in memory.
 ///
@@ -741,10 +916,14 @@ pub fn regex_compare(regex: String, flags: i64, bin: Expression) -> Expression {
 
 /// Creates a geospatial comparison expression.
 /// ```
-/// use aerospike::expressions::{geo_compare, geo_bin, geo_val};
+/// use aerospike::expressions::{geo_compare, geo_bin, geo_geometry_val, geo_val};
+/// use aerospike::{AeroCircle, GeoPoint};
 /// // Query region within coordinates.
 /// let region = "{\"type\": \"Polygon\", \"coordinates\": [ [[-122.500000, 37.000000],[-121.000000, 37.000000], [-121.000000, 38.080000],[-122.500000, 38.080000], [-122.500000, 37.000000]] ] }";
 /// geo_compare(geo_bin("a".to_string()), geo_val(region.to_string()));
+/// // Points within 50km.
+/// let circle = AeroCircle::new(GeoPoint::new(-122.0, 37.5).unwrap(), 50_000.0).unwrap();
+/// geo_compare(geo_bin("a".to_string()), geo_geometry_val(circle));
 /// ```
 pub fn geo_compare(left: Expression, right: Expression) -> Expression {
     Expression::new(
@@ -810,6 +989,12 @@ pub fn geo_val(val: String) -> Expression {
     Expression::new(None, Some(Value::GeoJSON(val)), None, None, None, None)
 }
 
+/// Creates a geospatial value from one of the geometry types, like
+/// [`GeoPolygon`](crate::GeoPolygon).
+pub fn geo_geometry_val(val: impl Into<Geometry>) -> Expression {
+    geo_val(val.into().to_geojson())
+}
+
 /// Creates a nil value.
 pub fn nil() -> Expression {
     Expression::new(None, Some(Value::Nil), None, None, None, None)
@@ -1633,6 +1818,48 @@ pub fn var(name: String) -> Expression {
     }
 }
 
+/// Part of the current element that [`loop_var`] retrieves.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LoopVarPart {
+    /// The map key of the element.
+    MapKey = 0,
+    /// The value of the element.
+    Value = 1,
+    /// The index of the element in its list or map.
+    Index = 2,
+}
+
+/// Retrieve a part of the element that is currently visited by a path expression or operation.
+///
+/// Used in the filter of [`ctx_all_children_with_filter`] or in the modify expression of
+/// [`modify_by_path`](crate::operations::modify_by_path).
+/// Requires server version 8.1+.
+///
+/// ```
+/// use aerospike::expressions::{gt, int_val, loop_var, ExpType, LoopVarPart};
+/// use aerospike::operations::cdt_context::ctx_all_children_with_filter;
+///
+/// // Children of the bin with an integer value greater than 10.
+/// let ctx = ctx_all_children_with_filter(&gt(
+///     loop_var(ExpType::INT, LoopVarPart::Value),
+///     int_val(10),
+/// ));
+/// ```
+///
+/// [`ctx_all_children_with_filter`]: crate::operations::cdt_context::ctx_all_children_with_filter
+pub fn loop_var(exp_type: ExpType, part: LoopVarPart) -> Expression {
+    Expression {
+        cmd: Some(ExpOp::VarBuiltin),
+        val: Some(Value::from(part as i64)),
+        bin: None,
+        flags: None,
+        module: Some(exp_type),
+        exps: None,
+        arguments: None,
+        bytes: None,
+    }
+}
+
 /// Creates unknown value. Used to intentionally fail an expression.
 ///
 /// The failure can be ignored with [`ExpWriteFlags::EvalNoFail`](crate::operations::exp::ExpWriteFlags::EvalNoFail)
@@ -1727,4 +1954,95 @@ mod tests {
         let re_encoded = decoded.base64().unwrap();
         assert_eq!(b64, re_encoded);
     }
+
+    #[test]
+    fn time_val_bounds() {
+        use std::time::{Duration, UNIX_EPOCH};
+
+        let time = UNIX_EPOCH + Duration::from_secs(1_596_240_000);
+        assert_eq!(time_val(time), int_val(1_596_240_000_000_000_000));
+        let far_future = UNIX_EPOCH + Duration::from_secs(10_000_000_000);
+        assert_eq!(time_val(far_future), int_val(i64::MAX));
+        let far_past = UNIX_EPOCH - Duration::from_secs(10_000_000_000);
+        assert_eq!(time_val(far_past), int_val(i64::MIN));
+        assert_eq!(
+            last_update_before(far_future),
+            lt(last_update(), int_val(i64::MAX))
+        );
+        assert_eq!(
+            void_time_before(far_past),
+            and(vec![
+                ne(void_time(), int_val(-1)),
+                lt(void_time(), int_val(i64::MIN)),
+            ])
+        );
+    }
+
+    #[test]
+    fn exp_macro_infers_bin_types() {
+        let exp = exp!($a + 1 > $b && $name == "x" || !$flag);
+        let expected = or(vec![
+            and(vec![
+                gt(
+                    num_add(vec![int_bin("a".to_string()), int_val(1)]),
+                    int_bin("b".to_string()),
+                ),
+                eq(string_bin("name".to_string()), string_val("x".to_string())),
+            ]),
+            not(bool_bin("flag".to_string())),
+        ]);
+        assert_eq!(exp, expected);
+
+        let exp = exp!($"my-bin": float * 2.0 >= -1.5);
+        let expected = ge(
+            num_mul(vec![float_bin("my-bin".to_string()), float_val(2.0)]),
+            float_val(-1.5),
+        );
+        assert_eq!(exp, expected);
+    }
+
+    #[test]
+    fn exp_macro_operators() {
+        assert_eq!(
+            exp!($a - $b - 1 == ($a % 3) << 2),
+            eq(
+                num_sub(vec![
+                    int_bin("a".to_string()),
+                    int_bin("b".to_string()),
+                    int_val(1)
+                ]),
+                int_lshift(num_mod(int_bin("a".to_string()), int_val(3)), int_val(2)),
+            )
+        );
+        assert_eq!(
+            exp!(($a & 0xff != 0) ^ $b: bool),
+            xor(vec![
+                ne(
+                    int_and(vec![int_bin("a".to_string()), int_val(0xff)]),
+                    int_val(0)
+                ),
+                bool_bin("b".to_string()),
+            ])
+        );
+        assert_eq!(
+            exp!(max($f, pow(2.0, 3.0)) < to_float($.ttl) && regex($s, "^a", 2)),
+            and(vec![
+                lt(
+                    max(vec![
+                        float_bin("f".to_string()),
+                        num_pow(float_val(2.0), float_val(3.0))
+                    ]),
+                    to_float(ttl()),
+                ),
+                regex_compare("^a".to_string(), 2, string_bin("s".to_string())),
+            ])
+        );
+        assert_eq!(
+            exp!($.key == "k" && $.digest_modulo(3) == {int_val(1)}),
+            and(vec![
+                eq(key(ExpType::STRING), string_val("k".to_string())),
+                eq(digest_modulo(3), int_val(1)),
+            ])
+        );
+    }
 }
@@ -0,0 +1,449 @@
+// Copyright 2015-2020 Aerospike, Inc.
+//
+// Portions may be licensed to Aerospike, Inc. under one or more contributor
+// license agreements.
+//
+// Licensed under the Apache License, Version 2.0 (the "License"); you may not
+// use this file except in compliance with the License. You may obtain a copy of
+// the License at http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS, WITHOUT
+// WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied. See the
+// License for the specific language governing permissions and limitations under
+// the License.
+
+use std::collections::hash_map::Entry::{Occupied, Vacant};
+use std::collections::{BTreeMap, HashMap};
+use std::convert::TryFrom;
+use std::hash::{BuildHasher, Hash};
+
+use crate::errors::{Error, Result};
+use crate::{BinView, Bins, Key, ParticleType, Record, Value, ValueView};
+
+/// Types that records can be decoded into directly, without building a [`Record`] first.
+///
+/// The response parsers of [`Client::get_as`](crate::Client::get_as),
+/// [`Client::batch_get_as`](crate::Client::batch_get_as) and
+/// [`Client::query_as`](crate::Client::query_as) pass each bin to
+/// [`decode_bin`](Self::decode_bin) as soon as it has been read from the connection, and call
+/// [`build`](Self::build) once all bins of a record have been decoded. Strings, blobs, lists and
+/// maps are decoded straight from the response buffer into the fields of the type.
+///
+/// The trait is usually derived. Each named field is read from the bin of the same name, and
+/// decoded with [`FromValueView`]. Fields of type `Option<T>` are `None` if the bin is missing
+/// or nil; other fields fail to decode unless they have a default.
+///
+/// ```
+/// use aerospike::{FromBins, Key};
+///
+/// #[derive(FromBins)]
+/// struct User {
+///     name: String,
+///     #[aerospike(rename = "mail")]
+///     email: Option<String>,
+///     #[aerospike(default)]
+///     logins: i64,
+///     tags: Vec<String>,
+///     avatar: Vec<u8>,
+///     #[aerospike(key)]
+///     key: Option<Key>,
+///     #[aerospike(generation)]
+///     generation: u32,
+/// }
+/// ```
+///
+/// Field attributes:
+///
+/// * `#[aerospike(rename = "bin")]` reads the field from a bin with a different name.
+/// * `#[aerospike(default)]` uses `Default::default()` if the bin is missing or nil.
+/// * `#[aerospike(key)]` sets an `Option<Key>` field to the key of the record, which is only
+///   known for batch and query results.
+/// * `#[aerospike(generation)]` sets a `u32` field to the generation of the record.
+///
+/// The derived code refers to the `aerospike` crate. Use `#[aerospike(crate = "path")]` on the
+/// struct if the client is imported under another name, e.g. `aerospike_sync`.
+pub trait FromBins: Sized + Send + 'static {
+    /// Partially decoded record, filled in bin by bin.
+    type Builder: Default + Send;
+
+    /// Returns the bins to read from the server. Defaults to all bins.
+    fn bins() -> Bins {
+        Bins::All
+    }
+
+    /// Decodes a bin of the record into the builder. Bins that the type does not use should be
+    /// ignored.
+    ///
+    /// Bins with nil values are passed as well, as returned by the server. Batch commands return
+    /// a nil value for every write operation, single record and query commands do not return nil
+    /// bins at all.
+    fn decode_bin(builder: &mut Self::Builder, bin: &BinView<'_>) -> Result<()>;
+
+    /// Completes the decoding after all bins of the record have been passed to
+    /// [`decode_bin`](Self::decode_bin).
+    fn build(builder: Self::Builder, meta: RecordMeta) -> Result<Self>;
+}
+
+/// Record metadata passed to [`FromBins::build`].
+#[derive(Debug, Clone)]
+pub struct RecordMeta {
+    /// Key of the record. Only set for batch and query results.
+    pub key: Option<Key>,
+
+    /// Record modification count.
+    pub generation: u32,
+
+    /// Date record will expire, in seconds from Jan 01 2010, 00:00:00 UTC, or 0 if the record
+    /// never expires.
+    pub expiration: u32,
+}
+
+/// Records decode into themselves, with the same bins as returned by the other read commands.
+impl FromBins for Record {
+    type Builder = HashMap<String, Value>;
+
+    fn decode_bin(bins: &mut Self::Builder, bin: &BinView<'_>) -> Result<()> {
+        let value = bin.to_value()?;
+
+        // list/map operations may return multiple values for the same bin.
+        match bins.entry(bin.name().to_string()) {
+            Vacant(entry) => {
+                entry.insert(value);
+            }
+            Occupied(entry) => match *entry.into_mut() {
+                Value::MultiResult(ref mut list) => list.push(value),
+                ref mut prev => {
+                    *prev = Value::MultiResult(vec![prev.clone(), value]);
+                }
+            },
+        }
+        Ok(())
+    }
+
+    fn build(bins: Self::Builder, meta: RecordMeta) -> Result<Self> {
+        Ok(Record::new(
+            meta.key,
+            bins,
+            meta.generation,
+            meta.expiration,
+        ))
+    }
+}
+
+/// Types that bin values, and the elements of list and map bins, can be decoded into.
+pub trait FromValueView: Sized {
+    /// Decodes the value.
+    fn from_value_view(value: ValueView<'_>) -> Result<Self>;
+
+    /// Returns the value for a bin that is missing or nil, if the type has one.
+    fn missing() -> Option<Self> {
+        None
+    }
+}
+
+pub fn type_error(value: &ValueView<'_>, expected: &str) -> Error {
+    let found = match value {
+        ValueView::Nil => "nil",
+        ValueView::Bool(_) => "bool",
+        ValueView::Int(_) => "integer",
+        ValueView::Float(_) => "float",
+        ValueView::String(_) => "string",
+        ValueView::Blob(_) => "blob",
+        ValueView::GeoJSON(_) => "GeoJSON",
+        ValueView::HLL(_) => "HLL",
+        ValueView::List(_) => "list",
+        ValueView::Map(_) => "map",
+    };
+    Error::ClientError(format!(
+        "Invalid type conversion from {found} value to {expected}"
+    ))
+}
+
+macro_rules! from_int_view {
+    ($($ty:ty),*) => {
+        $(
+            impl FromValueView for $ty {
+                fn from_value_view(value: ValueView<'_>) -> Result<Self> {
+                    match value {
+                        ValueView::Int(val) => <$ty>::try_from(val).map_err(|_| {
+                            Error::ClientError(format!(
+                                "Integer {val} is out of the range of {}",
+                                stringify!($ty)
+                            ))
+                        }),
+                        _ => Err(type_error(&value, stringify!($ty))),
+                    }
+                }
+            }
+        )*
+    };
+}
+
+from_int_view!(i8, i16, i32, i64, isize, u16, u32, u64, usize);
+
+impl FromValueView for f64 {
+    fn from_value_view(value: ValueView<'_>) -> Result<Self> {
+        match value {
+            ValueView::Float(val) => Ok(val),
+            _ => Err(type_error(&value, "f64")),
+        }
+    }
+}
+
+impl FromValueView for f32 {
+    #[allow(clippy::cast_possible_truncation)]
+    fn from_value_view(value: ValueView<'_>) -> Result<Self> {
+        match value {
+            ValueView::Float(val) => Ok(val as f32),
+            _ => Err(type_error(&value, "f32")),
+        }
+    }
+}
+
+impl FromValueView for bool {
+    fn from_value_view(value: ValueView<'_>) -> Result<Self> {
+        match value {
+            ValueView::Bool(val) => Ok(val),
+            _ => Err(type_error(&value, "bool")),
+        }
+    }
+}
+
+/// Decodes string and `GeoJSON` values.
+impl FromValueView for String {
+    fn from_value_view(value: ValueView<'_>) -> Result<Self> {
+        match value {
+            ValueView::String(val) | ValueView::GeoJSON(val) => Ok(val.to_string()),
+            _ => Err(type_error(&value, "String")),
+        }
+    }
+}
+
+/// Decodes blob and HLL values. Lists of integers decode into other `Vec` types.
+impl FromValueView for Vec<u8> {
+    fn from_value_view(value: ValueView<'_>) -> Result<Self> {
+        match value {
+            ValueView::Blob(val) | ValueView::HLL(val) => Ok(val.to_vec()),
+            _ => Err(type_error(&value, "Vec<u8>")),
+        }
+    }
+}
+
+impl<T: FromValueView> FromValueView for Vec<T> {
+    fn from_value_view(value: ValueView<'_>) -> Result<Self> {
+        match value {
+            ValueView::List(list) => list.map(|item| T::from_value_view(item?)).collect(),
+            _ => Err(type_error(&value, "Vec")),
+        }
+    }
+}
+
+impl<K, V, S> FromValueView for HashMap<K, V, S>
+where
+    K: FromValueView + Eq + Hash,
+    V: FromValueView,
+    S: BuildHasher + Default,
+{
+    fn from_value_view(value: ValueView<'_>) -> Result<Self> {
+        match value {
+            ValueView::Map(map) => map
+                .map(|entry| {
+                    let (key, val) = entry?;
+                    Ok((K::from_value_view(key)?, V::from_value_view(val)?))
+                })
+                .collect(),
+            _ => Err(type_error(&value, "HashMap")),
+        }
+    }
+}
+
+impl<K, V> FromValueView for BTreeMap<K, V>
+where
+    K: FromValueView + Ord,
+    V: FromValueView,
+{
+    fn from_value_view(value: ValueView<'_>) -> Result<Self> {
+        match value {
+            ValueView::Map(map) => map
+                .map(|entry| {
+                    let (key, val) = entry?;
+                    Ok((K::from_value_view(key)?, V::from_value_view(val)?))
+                })
+                .collect(),
+            _ => Err(type_error(&value, "BTreeMap")),
+        }
+    }
+}
+
+/// Nil values decode into `None`.
+impl<T: FromValueView> FromValueView for Option<T> {
+    fn from_value_view(value: ValueView<'_>) -> Result<Self> {
+        match value {
+            ValueView::Nil => Ok(None),
+            value => T::from_value_view(value).map(Some),
+        }
+    }
+
+    fn missing() -> Option<Self> {
+        Some(None)
+    }
+}
+
+/// Decodes any value, e.g. for bins of varying type.
+impl FromValueView for Value {
+    fn from_value_view(value: ValueView<'_>) -> Result<Self> {
+        value.to_value()
+    }
+
+    fn missing() -> Option<Self> {
+        Some(Value::Nil)
+    }
+}
+
+// Decodes a bin into a field of a derived `FromBins` builder. Nil values leave the field unset.
+#[doc(hidden)]
+pub fn __decode_field<T: FromValueView>(field: &mut Option<T>, bin: &BinView<'_>) -> Result<()> {
+    if matches!(bin.particle_type(), ParticleType::NULL) {
+        return Ok(());
+    }
+    let value = bin
+        .value()
+        .and_then(T::from_value_view)
+        .map_err(|err| err.chain_error(&format!("Failed to decode bin {}", bin.name())))?;
+    *field = Some(value);
+    Ok(())
+}
+
+// Returns the decoded value of a field of a derived `FromBins` builder, falling back to the
+// value for missing bins.
+#[doc(hidden)]
+pub fn __field_value<T: FromValueView>(field: Option<T>, name: &str) -> Result<T> {
+    field
+        .or_else(T::missing)
+        .ok_or_else(|| Error::ClientError(format!("Bin {name} is missing")))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::{FromBins, RecordMeta};
+    use crate::record_view::push_op;
+    use crate::{FromValueView, Key, Record, RecordView, Value, ValueView};
+
+    #[derive(crate::FromBins, Debug, PartialEq)]
+    #[aerospike(crate = "crate")]
+    struct User {
+        name: String,
+        #[aerospike(rename = "mail")]
+        email: Option<String>,
+        #[aerospike(default)]
+        logins: i64,
+        tags: Vec<String>,
+        avatar: Vec<u8>,
+        scores: HashMap<String, f64>,
+        #[aerospike(key)]
+        key: Option<Key>,
+        #[aerospike(generation)]
+        generation: u32,
+    }
+
+    fn decode<T: FromBins>(data: &[u8], op_count: usize) -> crate::Result<T> {
+        let record = RecordView::parse(data, 0, op_count, 7, 0)?;
+        let mut builder = T::Builder::default();
+        for bin in record.bins() {
+            T::decode_bin(&mut builder, bin)?;
+        }
+        T::build(
+            builder,
+            RecordMeta {
+                key: None,
+                generation: 7,
+                expiration: 0,
+            },
+        )
+    }
+
+    #[test]
+    fn derived() {
+        assert_eq!(
+            User::bins(),
+            crate::Bins::from(["name", "mail", "logins", "tags", "avatar", "scores"])
+        );
+
+        let mut data = vec![];
+        push_op(&mut data, "name", 3, b"Ann");
+        push_op(&mut data, "other", 1, &5_i64.to_be_bytes());
+        push_op(
+            &mut data,
+            "tags",
+            20,
+            &[0x92, 0xa2, 0x03, b'a', 0xa2, 0x03, b'b'],
+        );
+        push_op(&mut data, "avatar", 4, &[1, 2]);
+        // {"x": 1.5}
+        let mut scores = vec![0x81, 0xa2, 0x03, b'x', 0xcb];
+        scores.extend_from_slice(&1.5_f64.to_be_bytes());
+        push_op(&mut data, "scores", 19, &scores);
+        push_op(&mut data, "mail", 0, &[]);
+
+        let user: User = decode(&data, 6).unwrap();
+        assert_eq!(
+            user,
+            User {
+                name: "Ann".to_string(),
+                email: None,
+                logins: 0,
+                tags: vec!["a".to_string(), "b".to_string()],
+                avatar: vec![1, 2],
+                scores: vec![("x".to_string(), 1.5)].into_iter().collect(),
+                key: None,
+                generation: 7,
+            }
+        );
+    }
+
+    #[test]
+    fn derived_errors() {
+        let mut data = vec![];
+        push_op(&mut data, "name", 1, &5_i64.to_be_bytes());
+        let err = decode::<User>(&data, 1).unwrap_err();
+        assert!(err.to_string().contains("Failed to decode bin name"));
+
+        let mut data = vec![];
+        push_op(&mut data, "name", 3, b"Ann");
+        let err = decode::<User>(&data, 1).unwrap_err();
+        assert!(err.to_string().contains("Bin tags is missing"));
+    }
+
+    #[test]
+    fn record() {
+        let mut data = vec![];
+        push_op(&mut data, "a", 1, &1_i64.to_be_bytes());
+        push_op(&mut data, "a", 1, &2_i64.to_be_bytes());
+        push_op(&mut data, "b", 0, &[]);
+        let record: Record = decode(&data, 3).unwrap();
+        assert_eq!(record.generation, 7);
+        assert_eq!(
+            record.bins.get("a"),
+            Some(&Value::MultiResult(vec![Value::from(1), Value::from(2)]))
+        );
+        assert_eq!(record.bins.get("b"), Some(&Value::Nil));
+    }
+
+    #[test]
+    fn value_views() {
+        assert!(i8::from_value_view(ValueView::Int(300)).is_err());
+        assert_eq!(u16::from_value_view(ValueView::Int(300)).unwrap(), 300);
+        assert!(i64::from_value_view(ValueView::String("1")).is_err());
+        assert_eq!(
+            Option::<i64>::from_value_view(ValueView::Nil).unwrap(),
+            None
+        );
+        assert_eq!(
+            Value::from_value_view(ValueView::String("x")).unwrap(),
+            Value::from("x")
+        );
+    }
+}
@@ -0,0 +1,741 @@
+// Copyright 2015-2020 Aerospike, Inc.
+//
+// Portions may be licensed to Aerospike, Inc. under one or more contributor
+// license agreements.
+//
+// Licensed under the Apache License, Version 2.0 (the "License"); you may not
+// use this file except in compliance with the License. You may obtain a copy of
+// the License at http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS, WITHOUT
+// WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied. See the
+// License for the specific language governing permissions and limitations under
+// the License.
+
+//! Typed geometries in the `GeoJSON` dialect of the server.
+
+use std::convert::TryFrom;
+use std::fmt;
+use std::str::FromStr;
+
+use crate::errors::{Error, Result};
+use crate::from_bins::{type_error, FromValueView};
+use crate::{Value, ValueView};
+
+/// Types that can be written as `GeoJSON`, i.e. the geometry types of this module and strings
+/// holding `GeoJSON`.
+pub trait ToGeoJson {
+    /// Returns the `GeoJSON` text.
+    fn to_geojson(&self) -> String;
+}
+
+impl<T: ToGeoJson + ?Sized> ToGeoJson for &T {
+    fn to_geojson(&self) -> String {
+        (**self).to_geojson()
+    }
+}
+
+impl ToGeoJson for str {
+    fn to_geojson(&self) -> String {
+        self.to_owned()
+    }
+}
+
+impl ToGeoJson for String {
+    fn to_geojson(&self) -> String {
+        self.clone()
+    }
+}
+
+/// Point with a longitude and latitude in degrees.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct GeoPoint {
+    lng: f64,
+    lat: f64,
+}
+
+impl GeoPoint {
+    /// Creates a point. The longitude must be between -180 and 180 degrees, and the latitude
+    /// between -90 and 90 degrees.
+    pub fn new(lng: f64, lat: f64) -> Result<Self> {
+        if !(-180.0..=180.0).contains(&lng) {
+            return Err(invalid(format!(
+                "Longitude {lng} is not between -180 and 180"
+            )));
+        }
+        if !(-90.0..=90.0).contains(&lat) {
+            return Err(invalid(format!("Latitude {lat} is not between -90 and 90")));
+        }
+        Ok(GeoPoint { lng, lat })
+    }
+
+    /// Returns the longitude in degrees.
+    pub const fn lng(&self) -> f64 {
+        self.lng
+    }
+
+    /// Returns the latitude in degrees.
+    pub const fn lat(&self) -> f64 {
+        self.lat
+    }
+}
+
+/// Polygon with an exterior ring and optional holes.
+///
+/// Rings are closed automatically, i.e. the first point is appended to a ring whose last point
+/// differs from it. A closed ring needs at least four points.
+#[derive(Debug, Clone, PartialEq)]
+pub struct GeoPolygon {
+    rings: Vec<Vec<GeoPoint>>,
+}
+
+impl GeoPolygon {
+    /// Creates a polygon with the given exterior ring.
+    pub fn new(exterior: Vec<GeoPoint>) -> Result<Self> {
+        Ok(GeoPolygon {
+            rings: vec![ring(exterior)?],
+        })
+    }
+
+    /// Adds a hole to the polygon.
+    pub fn with_hole(mut self, hole: Vec<GeoPoint>) -> Result<Self> {
+        self.rings.push(ring(hole)?);
+        Ok(self)
+    }
+
+    /// Returns the closed exterior ring.
+    pub fn exterior(&self) -> &[GeoPoint] {
+        &self.rings[0]
+    }
+
+    /// Returns the closed rings of the holes.
+    pub fn holes(&self) -> &[Vec<GeoPoint>] {
+        &self.rings[1..]
+    }
+
+    fn from_rings(rings: Vec<Vec<GeoPoint>>) -> Result<Self> {
+        let mut rings = rings.into_iter();
+        let exterior = rings
+            .next()
+            .ok_or_else(|| invalid("Polygon has no rings".to_string()))?;
+        rings.try_fold(GeoPolygon::new(exterior)?, GeoPolygon::with_hole)
+    }
+}
+
+fn ring(mut points: Vec<GeoPoint>) -> Result<Vec<GeoPoint>> {
+    if let (Some(first), Some(last)) = (points.first(), points.last()) {
+        if first != last {
+            points.push(*first);
+        }
+    }
+    if points.len() < 4 {
+        return Err(invalid(format!(
+            "Polygon ring needs at least 3 distinct points, found {}",
+            points.len().saturating_sub(1)
+        )));
+    }
+    Ok(points)
+}
+
+/// Collection of polygons.
+#[derive(Debug, Clone, PartialEq)]
+pub struct GeoMultiPolygon {
+    polygons: Vec<GeoPolygon>,
+}
+
+impl GeoMultiPolygon {
+    /// Creates a multi-polygon. At least one polygon is required.
+    pub fn new(polygons: Vec<GeoPolygon>) -> Result<Self> {
+        if polygons.is_empty() {
+            return Err(invalid("MultiPolygon has no polygons".to_string()));
+        }
+        Ok(GeoMultiPolygon { polygons })
+    }
+
+    /// Returns the polygons.
+    pub fn polygons(&self) -> &[GeoPolygon] {
+        &self.polygons
+    }
+}
+
+/// Circle around a point, an extension of `GeoJSON` by the server for "points within radius"
+/// queries.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct AeroCircle {
+    center: GeoPoint,
+    radius: f64,
+}
+
+impl AeroCircle {
+    /// Creates a circle with the given radius in meters, which must be positive.
+    pub fn new(center: GeoPoint, radius: f64) -> Result<Self> {
+        if !(radius.is_finite() && radius > 0.0) {
+            return Err(invalid(format!("Radius {radius} is not a positive number")));
+        }
+        Ok(AeroCircle { center, radius })
+    }
+
+    /// Returns the center of the circle.
+    pub const fn center(&self) -> GeoPoint {
+        self.center
+    }
+
+    /// Returns the radius in meters.
+    pub const fn radius(&self) -> f64 {
+        self.radius
+    }
+}
+
+/// Any of the geometries supported by the server, e.g. as read from a `GeoJSON` bin.
+///
+/// ```
+/// use aerospike::{GeoPoint, Geometry, Value};
+/// use std::convert::TryFrom;
+///
+/// let value = Value::GeoJSON(r#"{"type": "Point", "coordinates": [13.4, 52.5]}"#.to_string());
+/// match Geometry::try_from(value).unwrap() {
+///     Geometry::Point(point) => assert_eq!(point, GeoPoint::new(13.4, 52.5).unwrap()),
+///     other => panic!("unexpected geometry {}", other),
+/// }
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub enum Geometry {
+    /// `Point` geometry.
+    Point(GeoPoint),
+    /// `Polygon` geometry.
+    Polygon(GeoPolygon),
+    /// `MultiPolygon` geometry.
+    MultiPolygon(GeoMultiPolygon),
+    /// `AeroCircle` geometry.
+    Circle(AeroCircle),
+}
+
+impl Geometry {
+    const fn type_name(&self) -> &'static str {
+        match self {
+            Geometry::Point(_) => "Point",
+            Geometry::Polygon(_) => "Polygon",
+            Geometry::MultiPolygon(_) => "MultiPolygon",
+            Geometry::Circle(_) => "AeroCircle",
+        }
+    }
+}
+
+const fn invalid(msg: String) -> Error {
+    Error::InvalidArgument(msg)
+}
+
+// GeoJSON output
+
+struct Position<'a>(&'a GeoPoint);
+
+impl fmt::Display for Position<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "[{},{}]", self.0.lng, self.0.lat)
+    }
+}
+
+fn write_list<T>(
+    f: &mut fmt::Formatter<'_>,
+    items: &[T],
+    mut write: impl FnMut(&mut fmt::Formatter<'_>, &T) -> fmt::Result,
+) -> fmt::Result {
+    f.write_str("[")?;
+    for (i, item) in items.iter().enumerate() {
+        if i > 0 {
+            f.write_str(",")?;
+        }
+        write(f, item)?;
+    }
+    f.write_str("]")
+}
+
+fn write_rings(f: &mut fmt::Formatter<'_>, polygon: &GeoPolygon) -> fmt::Result {
+    write_list(f, &polygon.rings, |f, ring| {
+        write_list(f, ring, |f, point| write!(f, "{}", Position(point)))
+    })
+}
+
+impl fmt::Display for GeoPoint {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, r#"{{"type":"Point","coordinates":{}}}"#, Position(self))
+    }
+}
+
+impl fmt::Display for GeoPolygon {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(r#"{"type":"Polygon","coordinates":"#)?;
+        write_rings(f, self)?;
+        f.write_str("}")
+    }
+}
+
+impl fmt::Display for GeoMultiPolygon {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(r#"{"type":"MultiPolygon","coordinates":"#)?;
+        write_list(f, &self.polygons, write_rings)?;
+        f.write_str("}")
+    }
+}
+
+impl fmt::Display for AeroCircle {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            r#"{{"type":"AeroCircle","coordinates":[{},{}]}}"#,
+            Position(&self.center),
+            self.radius
+        )
+    }
+}
+
+impl fmt::Display for Geometry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Geometry::Point(point) => point.fmt(f),
+            Geometry::Polygon(polygon) => polygon.fmt(f),
+            Geometry::MultiPolygon(polygons) => polygons.fmt(f),
+            Geometry::Circle(circle) => circle.fmt(f),
+        }
+    }
+}
+
+// GeoJSON input
+
+// Minimal JSON document model, sufficient to read geometries.
+#[derive(Debug)]
+enum Json {
+    Number(f64),
+    String(String),
+    Array(Vec<Json>),
+    Object(Vec<(String, Json)>),
+    Other,
+}
+
+struct Parser<'a> {
+    input: &'a [u8],
+    pos: usize,
+}
+
+impl Parser<'_> {
+    fn parse(input: &str) -> Result<Json> {
+        let mut parser = Parser {
+            input: input.as_bytes(),
+            pos: 0,
+        };
+        let json = parser.value()?;
+        parser.skip_whitespace();
+        if parser.pos < parser.input.len() {
+            return Err(parser.error("trailing characters"));
+        }
+        Ok(json)
+    }
+
+    fn error(&self, msg: &str) -> Error {
+        invalid(format!("Invalid GeoJSON at offset {}: {msg}", self.pos))
+    }
+
+    fn skip_whitespace(&mut self) {
+        while matches!(self.peek(), Some(b' ' | b'\t' | b'\n' | b'\r')) {
+            self.pos += 1;
+        }
+    }
+
+    fn peek(&self) -> Option<u8> {
+        self.input.get(self.pos).copied()
+    }
+
+    fn expect(&mut self, byte: u8) -> Result<()> {
+        self.skip_whitespace();
+        if self.peek() != Some(byte) {
+            return Err(self.error(&format!("expected '{}'", byte as char)));
+        }
+        self.pos += 1;
+        Ok(())
+    }
+
+    // Consumes `byte` if it is the next non-whitespace character.
+    fn next_is(&mut self, byte: u8) -> bool {
+        self.skip_whitespace();
+        let found = self.peek() == Some(byte);
+        if found {
+            self.pos += 1;
+        }
+        found
+    }
+
+    fn value(&mut self) -> Result<Json> {
+        self.skip_whitespace();
+        match self.peek() {
+            Some(b'{') => self.object(),
+            Some(b'[') => self.array(),
+            Some(b'"') => Ok(Json::String(self.string()?)),
+            Some(b'-' | b'0'..=b'9') => self.number(),
+            Some(b't' | b'f' | b'n') => self.literal(),
+            _ => Err(self.error("expected a value")),
+        }
+    }
+
+    fn object(&mut self) -> Result<Json> {
+        self.expect(b'{')?;
+        let mut members = vec![];
+        if self.next_is(b'}') {
+            return Ok(Json::Object(members));
+        }
+        loop {
+            self.skip_whitespace();
+            let name = self.string()?;
+            self.expect(b':')?;
+            members.push((name, self.value()?));
+            if !self.next_is(b',') {
+                self.expect(b'}')?;
+                return Ok(Json::Object(members));
+            }
+        }
+    }
+
+    fn array(&mut self) -> Result<Json> {
+        self.expect(b'[')?;
+        let mut items = vec![];
+        if self.next_is(b']') {
+            return Ok(Json::Array(items));
+        }
+        loop {
+            items.push(self.value()?);
+            if !self.next_is(b',') {
+                self.expect(b']')?;
+                return Ok(Json::Array(items));
+            }
+        }
+    }
+
+    fn string(&mut self) -> Result<String> {
+        self.expect(b'"')?;
+        let mut out = Vec::new();
+        loop {
+            let byte = self
+                .peek()
+                .ok_or_else(|| self.error("unterminated string"))?;
+            self.pos += 1;
+            match byte {
+                b'"' => break,
+                b'\\' => {
+                    let escaped = self
+                        .peek()
+                        .ok_or_else(|| self.error("unterminated string"))?;
+                    self.pos += 1;
+                    let unescaped = match escaped {
+                        b'"' | b'\\' | b'/' => escaped as char,
+                        b'b' => '\u{8}',
+                        b'f' => '\u{c}',
+                        b'n' => '\n',
+                        b'r' => '\r',
+                        b't' => '\t',
+                        b'u' => self.unicode_escape()?,
+                        _ => return Err(self.error("invalid escape")),
+                    };
+                    let mut buf = [0; 4];
+                    out.extend_from_slice(unescaped.encode_utf8(&mut buf).as_bytes());
+                }
+                _ => out.push(byte),
+            }
+        }
+        String::from_utf8(out).map_err(|_| self.error("invalid UTF-8 in string"))
+    }
+
+    fn unicode_escape(&mut self) -> Result<char> {
+        let hex = self
+            .input
+            .get(self.pos..self.pos + 4)
+            .and_then(|hex| std::str::from_utf8(hex).ok())
+            .and_then(|hex| u32::from_str_radix(hex, 16).ok())
+            .ok_or_else(|| self.error("invalid unicode escape"))?;
+        self.pos += 4;
+        // Surrogate pairs are not needed for geometries.
+        Ok(char::from_u32(hex).unwrap_or(char::REPLACEMENT_CHARACTER))
+    }
+
+    fn number(&mut self) -> Result<Json> {
+        let start = self.pos;
+        while matches!(
+            self.peek(),
+            Some(b'-' | b'+' | b'.' | b'e' | b'E' | b'0'..=b'9')
+        ) {
+            self.pos += 1;
+        }
+        std::str::from_utf8(&self.input[start..self.pos])
+            .ok()
+            .and_then(|num| num.parse().ok())
+            .map(Json::Number)
+            .ok_or_else(|| self.error("invalid number"))
+    }
+
+    fn literal(&mut self) -> Result<Json> {
+        for literal in [&b"true"[..], b"false", b"null"] {
+            if self.input[self.pos..].starts_with(literal) {
+                self.pos += literal.len();
+                return Ok(Json::Other);
+            }
+        }
+        Err(self.error("expected a value"))
+    }
+}
+
+fn coordinates_error(type_name: &str) -> Error {
+    invalid(format!("Invalid coordinates for GeoJSON {type_name}"))
+}
+
+fn array<'a>(json: &'a Json, type_name: &str) -> Result<&'a [Json]> {
+    match json {
+        Json::Array(items) => Ok(items),
+        _ => Err(coordinates_error(type_name)),
+    }
+}
+
+fn position(json: &Json, type_name: &str) -> Result<GeoPoint> {
+    match json {
+        Json::Array(items) => match items.as_slice() {
+            [Json::Number(lng), Json::Number(lat)] => GeoPoint::new(*lng, *lat),
+            _ => Err(coordinates_error(type_name)),
+        },
+        _ => Err(coordinates_error(type_name)),
+    }
+}
+
+fn rings(json: &Json, type_name: &str) -> Result<GeoPolygon> {
+    let rings = array(json, type_name)?
+        .iter()
+        .map(|ring| {
+            array(ring, type_name)?
+                .iter()
+                .map(|pos| position(pos, type_name))
+                .collect()
+        })
+        .collect::<Result<Vec<_>>>()?;
+    GeoPolygon::from_rings(rings)
+}
+
+impl FromStr for Geometry {
+    type Err = Error;
+
+    /// Parses a `GeoJSON` geometry of one of the types supported by the server.
+    fn from_str(s: &str) -> Result<Self> {
+        let Json::Object(members) = Parser::parse(s)? else {
+            return Err(invalid("GeoJSON is not an object".to_string()));
+        };
+        let member = |name: &str| {
+            members
+                .iter()
+                .find(|(member, _)| member == name)
+                .map(|(_, value)| value)
+        };
+        let type_name = match member("type") {
+            Some(Json::String(type_name)) => type_name.as_str(),
+            _ => return Err(invalid("GeoJSON has no type".to_string())),
+        };
+        let coordinates = member("coordinates").ok_or_else(|| coordinates_error(type_name))?;
+
+        match type_name {
+            "Point" => position(coordinates, type_name).map(Geometry::Point),
+            "Polygon" => rings(coordinates, type_name).map(Geometry::Polygon),
+            "MultiPolygon" => {
+                let polygons = array(coordinates, type_name)?
+                    .iter()
+                    .map(|polygon| rings(polygon, type_name))
+                    .collect::<Result<_>>()?;
+                GeoMultiPolygon::new(polygons).map(Geometry::MultiPolygon)
+            }
+            "AeroCircle" => match array(coordinates, type_name)? {
+                [center, Json::Number(radius)] => {
+                    AeroCircle::new(position(center, type_name)?, *radius).map(Geometry::Circle)
+                }
+                _ => Err(coordinates_error(type_name)),
+            },
+            _ => Err(invalid(format!("Unsupported GeoJSON type {type_name}"))),
+        }
+    }
+}
+
+impl TryFrom<Value> for Geometry {
+    type Error = String;
+    fn try_from(val: Value) -> std::result::Result<Self, Self::Error> {
+        match val {
+            Value::GeoJSON(ref json) => json.parse().map_err(|err: Error| err.to_string()),
+            _ => Err(format!(
+                "Invalid type conversion from Value::{} to {}",
+                val.particle_type(),
+                std::any::type_name::<Self>()
+            )),
+        }
+    }
+}
+
+impl FromValueView for Geometry {
+    fn from_value_view(value: ValueView<'_>) -> Result<Self> {
+        match value {
+            ValueView::GeoJSON(json) => json.parse(),
+            _ => Err(type_error(&value, "Geometry")),
+        }
+    }
+}
+
+impl ToGeoJson for Geometry {
+    fn to_geojson(&self) -> String {
+        self.to_string()
+    }
+}
+
+impl From<Geometry> for Value {
+    fn from(geometry: Geometry) -> Value {
+        Value::GeoJSON(geometry.to_string())
+    }
+}
+
+macro_rules! geometry_impls {
+    ($($ty:ident => $variant:ident),*) => {
+        $(
+            impl From<$ty> for Geometry {
+                fn from(geometry: $ty) -> Geometry {
+                    Geometry::$variant(geometry)
+                }
+            }
+
+            impl From<$ty> for Value {
+                fn from(geometry: $ty) -> Value {
+                    Value::GeoJSON(geometry.to_string())
+                }
+            }
+
+            impl ToGeoJson for $ty {
+                fn to_geojson(&self) -> String {
+                    self.to_string()
+                }
+            }
+
+            impl FromStr for $ty {
+                type Err = Error;
+
+                fn from_str(s: &str) -> Result<Self> {
+                    match s.parse()? {
+                        Geometry::$variant(geometry) => Ok(geometry),
+                        other => Err(invalid(format!(
+                            "Expected GeoJSON {}, found {}",
+                            stringify!($variant),
+                            other.type_name()
+                        ))),
+                    }
+                }
+            }
+
+            impl TryFrom<Value> for $ty {
+                type Error = String;
+                fn try_from(val: Value) -> std::result::Result<Self, Self::Error> {
+                    match val {
+                        Value::GeoJSON(ref json) => {
+                            json.parse().map_err(|err: Error| err.to_string())
+                        }
+                        _ => Err(format!(
+                            "Invalid type conversion from Value::{} to {}",
+                            val.particle_type(),
+                            std::any::type_name::<Self>()
+                        )),
+                    }
+                }
+            }
+
+            impl FromValueView for $ty {
+                fn from_value_view(value: ValueView<'_>) -> Result<Self> {
+                    match value {
+                        ValueView::GeoJSON(json) => json.parse(),
+                        _ => Err(type_error(&value, stringify!($ty))),
+                    }
+                }
+            }
+        )*
+    };
+}
+
+geometry_impls!(
+    GeoPoint => Point,
+    GeoPolygon => Polygon,
+    GeoMultiPolygon => MultiPolygon,
+    AeroCircle => Circle
+);
+
+#[cfg(test)]
+mod tests {
+    use std::convert::TryFrom;
+
+    use super::{AeroCircle, GeoMultiPolygon, GeoPoint, GeoPolygon, Geometry};
+    use crate::Value;
+
+    fn point(lng: f64, lat: f64) -> GeoPoint {
+        GeoPoint::new(lng, lat).unwrap()
+    }
+
+    #[test]
+    fn validation() {
+        assert!(GeoPoint::new(180.5, 0.0).is_err());
+        assert!(GeoPoint::new(0.0, -91.0).is_err());
+        assert!(GeoPoint::new(f64::NAN, 0.0).is_err());
+        assert!(AeroCircle::new(point(0.0, 0.0), 0.0).is_err());
+        assert!(GeoPolygon::new(vec![point(0.0, 0.0), point(1.0, 0.0)]).is_err());
+        assert!(GeoMultiPolygon::new(vec![]).is_err());
+
+        // rings are closed automatically
+        let polygon =
+            GeoPolygon::new(vec![point(0.0, 0.0), point(1.0, 0.0), point(1.0, 1.0)]).unwrap();
+        assert_eq!(polygon.exterior().len(), 4);
+    }
+
+    #[test]
+    fn geojson() {
+        let polygon = GeoPolygon::new(vec![point(0.0, 0.0), point(2.0, 0.0), point(2.0, 2.0)])
+            .unwrap()
+            .with_hole(vec![point(0.5, 0.5), point(1.0, 0.5), point(1.0, 1.0)])
+            .unwrap();
+        let circle = AeroCircle::new(point(-122.25, 37.5), 50000.0).unwrap();
+        assert_eq!(
+            point(13.4, -52.5).to_string(),
+            r#"{"type":"Point","coordinates":[13.4,-52.5]}"#
+        );
+        assert_eq!(
+            polygon.to_string(),
+            r#"{"type":"Polygon","coordinates":[[[0,0],[2,0],[2,2],[0,0]],[[0.5,0.5],[1,0.5],[1,1],[0.5,0.5]]]}"#
+        );
+        assert_eq!(
+            circle.to_string(),
+            r#"{"type":"AeroCircle","coordinates":[[-122.25,37.5],50000]}"#
+        );
+
+        let multi = GeoMultiPolygon::new(vec![polygon.clone(), polygon]).unwrap();
+        for geometry in [
+            Geometry::from(point(13.4, -52.5)),
+            Geometry::from(circle),
+            Geometry::from(multi),
+        ] {
+            let value = Value::from(geometry.clone());
+            assert_eq!(Geometry::try_from(value).unwrap(), geometry);
+        }
+    }
+
+    #[test]
+    fn parse() {
+        let json = r#" { "type" : "AeroCircle", "coordinates": [[-122.0, 37.5], 5e4] } "#;
+        let circle: AeroCircle = json.parse().unwrap();
+        assert!((circle.radius() - 50_000.0).abs() < f64::EPSILON);
+        assert_eq!(circle.center(), point(-122.0, 37.5));
+
+        let json = r#"{"type": "Point", "coordinates": [1, 2], "name": "a\"bé", "x": null}"#;
+        assert_eq!(json.parse::<GeoPoint>().unwrap(), point(1.0, 2.0));
+        assert!(json.parse::<GeoPolygon>().is_err());
+
+        assert!(r#"{"type": "Point", "coordinates": [1, 2]"#.parse::<Geometry>().is_err());
+        assert!(r#"{"type": "Point", "coordinates": [200, 2]}"#.parse::<Geometry>().is_err());
+        assert!(r#"{"type": "LineString", "coordinates": [[1, 2], [3, 4]]}"#
+            .parse::<Geometry>()
+            .is_err());
+        assert!(GeoPoint::try_from(Value::from("x")).is_err());
+    }
+}
@@ -0,0 +1,609 @@
+// Copyright 2015-2020 Aerospike, Inc.
+//
+// Portions may be licensed to Aerospike, Inc. under one or more contributor
+// license agreements.
+//
+// Licensed under the Apache License, Version 2.0 (the "License"); you may not
+// use this file except in compliance with the License. You may obtain a copy of
+// the License at http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS, WITHOUT
+// WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied. See the
+// License for the specific language governing permissions and limitations under
+// the License.
+
+use std::convert::TryFrom;
+
+use crate::commands::buffer::Buffer;
+use crate::errors::{Error, Result};
+use crate::from_bins::{type_error, FromValueView};
+use crate::msgpack::encoder;
+use crate::{Value, ValueView};
+
+// Bits of the HLL value in each register; the minhash bits follow.
+const HLL_BITS: u8 = 6;
+const MAX_HLL_VALUE: u8 = (1 << HLL_BITS) - 1;
+const MIN_INDEX_BITS: u8 = 4;
+const MAX_INDEX_BITS: u8 = 16;
+const MIN_MINHASH_BITS: u8 = 4;
+const MAX_MINHASH_BITS: u8 = 51;
+const MAX_INDEX_AND_MINHASH_BITS: u8 = 64;
+
+// Flags, index bits, minhash bits and the cached count of the server.
+const HEADER_SIZE: usize = 11;
+
+/// `HyperLogLog` sketch in the format of the server's HLL bins.
+///
+/// Sketches are read from [`Value::HLL`] values, e.g. the bins of a batch read, and can be merged
+/// and queried locally, like the server does for the `hll::get_union_count`,
+/// `hll::get_intersect_count` and `hll::get_similarity` operations. Sketches built on the client
+/// are written with [`hll::set_union`](crate::operations::hll::set_union).
+///
+/// Estimates are computed on the client and can differ slightly from the estimates of the server.
+///
+/// ```
+/// use aerospike::operations::hll::{self, HLLPolicy};
+/// use aerospike::{as_val, HllSketch, Value};
+///
+/// let mut sketch = HllSketch::new(12, 0).unwrap();
+/// for user in ["ann", "bob", "ann"] {
+///     sketch.add(&as_val!(user)).unwrap();
+/// }
+/// assert_eq!(sketch.cardinality(), 2);
+///
+/// let op = hll::set_union(&HLLPolicy::default(), "visitors", vec![Value::from(sketch)]);
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HllSketch {
+    index_bits: u8,
+    minhash_bits: u8,
+    // HLL value in the high bits, minhash value in the low `minhash_bits` bits.
+    registers: Vec<u64>,
+}
+
+impl HllSketch {
+    /// Creates an empty sketch with 2^`index_bits` registers. Index bits must be between 4 and 16,
+    /// and minhash bits either 0 or between 4 and 51, with at most 64 bits in total. Minhash bits
+    /// improve the accuracy of intersection counts and similarity.
+    pub fn new(index_bits: u8, minhash_bits: u8) -> Result<Self> {
+        if !(MIN_INDEX_BITS..=MAX_INDEX_BITS).contains(&index_bits) {
+            return Err(Error::InvalidArgument(format!(
+                "HLL index bits {index_bits} not between {MIN_INDEX_BITS} and {MAX_INDEX_BITS}"
+            )));
+        }
+        if minhash_bits != 0 && !(MIN_MINHASH_BITS..=MAX_MINHASH_BITS).contains(&minhash_bits) {
+            return Err(Error::InvalidArgument(format!(
+                "HLL minhash bits {minhash_bits} not 0 or between {MIN_MINHASH_BITS} and \
+                 {MAX_MINHASH_BITS}"
+            )));
+        }
+        if index_bits + minhash_bits > MAX_INDEX_AND_MINHASH_BITS {
+            return Err(Error::InvalidArgument(format!(
+                "HLL index bits {index_bits} and minhash bits {minhash_bits} exceed \
+                 {MAX_INDEX_AND_MINHASH_BITS} bits"
+            )));
+        }
+        Ok(HllSketch {
+            index_bits,
+            minhash_bits,
+            registers: vec![0; 1 << index_bits],
+        })
+    }
+
+    /// Decodes a sketch from the bytes of an HLL bin.
+    pub fn from_bytes(data: &[u8]) -> Result<Self> {
+        if data.len() < HEADER_SIZE {
+            return Err(Error::InvalidArgument("HLL data is too short".to_string()));
+        }
+        let mut sketch = HllSketch::new(data[1], data[2])?;
+        let register_bits = usize::from(sketch.register_bits());
+        let expected = HEADER_SIZE + (sketch.registers.len() * register_bits).div_ceil(8);
+        if data.len() != expected {
+            return Err(Error::InvalidArgument(format!(
+                "HLL data has {} bytes, expected {expected}",
+                data.len()
+            )));
+        }
+
+        let bits = &data[HEADER_SIZE..];
+        for i in 0..sketch.registers.len() {
+            let register = read_bits(bits, i * register_bits, register_bits);
+            if register != 0 && sketch.hll_value(register) == 0 {
+                return Err(Error::InvalidArgument(format!(
+                    "HLL register {i} has a minhash value but no HLL value"
+                )));
+            }
+            sketch.registers[i] = register;
+        }
+        Ok(sketch)
+    }
+
+    /// Encodes the sketch in the format of an HLL bin.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let register_bits = usize::from(self.register_bits());
+        let mut data = vec![0; HEADER_SIZE + (self.registers.len() * register_bits).div_ceil(8)];
+        data[1] = self.index_bits;
+        data[2] = self.minhash_bits;
+
+        let bits = &mut data[HEADER_SIZE..];
+        for (i, register) in self.registers.iter().enumerate() {
+            write_bits(bits, i * register_bits, register_bits, *register);
+        }
+        data
+    }
+
+    /// Returns the number of index bits.
+    pub const fn index_bits(&self) -> u8 {
+        self.index_bits
+    }
+
+    /// Returns the number of minhash bits.
+    pub const fn minhash_bits(&self) -> u8 {
+        self.minhash_bits
+    }
+
+    /// Returns whether no elements have been added to the sketch.
+    pub fn is_empty(&self) -> bool {
+        self.registers.iter().all(|register| *register == 0)
+    }
+
+    /// Adds an element to the sketch. Elements are hashed with the 128-bit `MurmurHash3` of their
+    /// msgpack encoding, like the server does for `hll::add`.
+    pub fn add(&mut self, value: &Value) -> Result<()> {
+        let size = encoder::pack_value(&mut None, value)?;
+        let mut buf = Buffer::new(size);
+        buf.resize_buffer(size)?;
+        encoder::pack_value(&mut Some(&mut buf), value)?;
+        self.add_hash(murmur3_x64_128(&buf.data_buffer));
+        Ok(())
+    }
+
+    /// Adds all elements to the sketch.
+    pub fn add_all<'a>(&mut self, values: impl IntoIterator<Item = &'a Value>) -> Result<()> {
+        values.into_iter().try_for_each(|value| self.add(value))
+    }
+
+    // Like the server, the index is taken from the low bits of the first hash and the minhash
+    // value from its high bits. The HLL value is the number of trailing zeros of the second hash
+    // plus one.
+    fn add_hash(&mut self, (h1, h2): (u64, u64)) {
+        let index = (h1 & ((1 << self.index_bits) - 1)) as usize;
+        let hll = u64::from(h2.trailing_zeros() + 1).min(u64::from(MAX_HLL_VALUE));
+        let minhash = if self.minhash_bits == 0 {
+            0
+        } else {
+            h1 >> (64 - self.minhash_bits)
+        };
+        let register = (hll << self.minhash_bits) | minhash;
+        self.registers[index] = self.registers[index].max(register);
+    }
+
+    /// Merges another sketch into this one, so that it estimates the union of both. If the sketches
+    /// have different index or minhash bits, the result has the smaller number of bits of each.
+    pub fn merge(&mut self, other: &HllSketch) {
+        let index_bits = self.index_bits.min(other.index_bits);
+        let minhash_bits = self.minhash_bits.min(other.minhash_bits);
+        self.fold(index_bits, minhash_bits);
+        let other = other.folded(index_bits, minhash_bits);
+        for (register, other) in self.registers.iter_mut().zip(other.registers.iter()) {
+            *register = (*register).max(*other);
+        }
+    }
+
+    /// Returns a sketch of the union of the given sketches.
+    pub fn union(sketches: &[HllSketch]) -> Result<HllSketch> {
+        let (first, rest) = sketches
+            .split_first()
+            .ok_or_else(|| Error::InvalidArgument("No HLL sketches given".to_string()))?;
+        Ok(rest.iter().fold(first.clone(), |mut union, sketch| {
+            union.merge(sketch);
+            union
+        }))
+    }
+
+    /// Returns the estimated number of distinct elements.
+    pub fn cardinality(&self) -> u64 {
+        let mut counts = vec![0_u32; usize::from(MAX_HLL_VALUE) + 1];
+        for register in &self.registers {
+            counts[self.hll_value(*register) as usize] += 1;
+        }
+        estimate(&counts, self.registers.len())
+    }
+
+    /// Returns the estimated number of distinct elements in the union of the sketches.
+    pub fn union_count(sketches: &[HllSketch]) -> Result<u64> {
+        Ok(HllSketch::union(sketches)?.cardinality())
+    }
+
+    /// Returns the estimated number of distinct elements in the intersection of the sketches.
+    ///
+    /// The intersection of more than two sketches requires minhash bits in all of them.
+    #[allow(clippy::cast_precision_loss)]
+    pub fn intersect_count(sketches: &[HllSketch]) -> Result<u64> {
+        if let [a, b] = sketches {
+            if a.minhash_bits == 0 || b.minhash_bits == 0 {
+                let union = HllSketch::union(sketches)?.cardinality();
+                return Ok((a.cardinality() + b.cardinality()).saturating_sub(union));
+            }
+        }
+        let union = HllSketch::union_count(sketches)?;
+        Ok((HllSketch::similarity(sketches)? * union as f64).round() as u64)
+    }
+
+    /// Returns the estimated Jaccard similarity of the sketches, i.e. the size of their
+    /// intersection divided by the size of their union.
+    ///
+    /// The similarity of more than two sketches requires minhash bits in all of them.
+    #[allow(clippy::cast_precision_loss)]
+    pub fn similarity(sketches: &[HllSketch]) -> Result<f64> {
+        let union = HllSketch::union(sketches)?;
+        if union.minhash_bits == 0 {
+            if let [a, b] = sketches {
+                let union = union.cardinality();
+                if union == 0 {
+                    return Ok(0.0);
+                }
+                let intersection = (a.cardinality() + b.cardinality()).saturating_sub(union);
+                return Ok(intersection as f64 / union as f64);
+            }
+            return Err(Error::InvalidArgument(
+                "Similarity of more than two HLL sketches requires minhash bits".to_string(),
+            ));
+        }
+
+        let sketches: Vec<HllSketch> = sketches
+            .iter()
+            .map(|sketch| sketch.folded(union.index_bits, union.minhash_bits))
+            .collect();
+        let mut matching = 0_u32;
+        let mut non_empty = 0_u32;
+        for (i, register) in union.registers.iter().enumerate() {
+            if *register == 0 {
+                continue;
+            }
+            non_empty += 1;
+            if sketches
+                .iter()
+                .all(|sketch| sketch.registers[i] == *register)
+            {
+                matching += 1;
+            }
+        }
+        if non_empty == 0 {
+            return Ok(0.0);
+        }
+        Ok(f64::from(matching) / f64::from(non_empty))
+    }
+
+    const fn register_bits(&self) -> u8 {
+        HLL_BITS + self.minhash_bits
+    }
+
+    const fn hll_value(&self, register: u64) -> u64 {
+        register >> self.minhash_bits
+    }
+
+    fn folded(&self, index_bits: u8, minhash_bits: u8) -> HllSketch {
+        let mut sketch = self.clone();
+        sketch.fold(index_bits, minhash_bits);
+        sketch
+    }
+
+    // Reduces the number of index and minhash bits. Index bits that are dropped are the high bits
+    // of the index, which do not affect the HLL value, and minhash bits that are dropped are the
+    // low bits of the minhash value.
+    fn fold(&mut self, index_bits: u8, minhash_bits: u8) {
+        if index_bits == self.index_bits && minhash_bits == self.minhash_bits {
+            return;
+        }
+        let dropped_minhash_bits = self.minhash_bits - minhash_bits;
+        let index_mask = (1 << index_bits) - 1;
+        let mut registers = vec![0; 1 << index_bits];
+        for (index, register) in self.registers.iter().enumerate() {
+            let hll = self.hll_value(*register);
+            if hll == 0 {
+                continue;
+            }
+            let minhash = (*register & ((1 << self.minhash_bits) - 1)) >> dropped_minhash_bits;
+            let register = (hll << minhash_bits) | minhash;
+            let index = index & index_mask;
+            registers[index] = registers[index].max(register);
+        }
+        self.index_bits = index_bits;
+        self.minhash_bits = minhash_bits;
+        self.registers = registers;
+    }
+}
+
+// Improved raw estimator of Otmar Ertl, "New cardinality estimation algorithms for HyperLogLog
+// sketches", which needs neither bias correction nor linear counting.
+#[allow(clippy::cast_precision_loss)]
+fn estimate(counts: &[u32], registers: usize) -> u64 {
+    let m = registers as f64;
+    let q = counts.len() - 2;
+    let mut z = m * tau(1.0 - f64::from(counts[q + 1]) / m);
+    for count in counts[1..=q].iter().rev() {
+        z = 0.5 * (z + f64::from(*count));
+    }
+    z += m * sigma(f64::from(counts[0]) / m);
+    (0.5 / std::f64::consts::LN_2 * m * m / z).round() as u64
+}
+
+// Both series are summed until they no longer change.
+#[allow(clippy::float_cmp)]
+fn sigma(mut x: f64) -> f64 {
+    if x >= 1.0 {
+        return f64::INFINITY;
+    }
+    let mut y = 1.0;
+    let mut z = x;
+    loop {
+        x *= x;
+        let prev = z;
+        z += x * y;
+        y += y;
+        if z == prev {
+            return z;
+        }
+    }
+}
+
+#[allow(clippy::float_cmp)]
+fn tau(mut x: f64) -> f64 {
+    if x == 0.0 || x >= 1.0 {
+        return 0.0;
+    }
+    let mut y = 1.0;
+    let mut z = 1.0 - x;
+    loop {
+        x = x.sqrt();
+        let prev = z;
+        y *= 0.5;
+        z -= (1.0 - x).powi(2) * y;
+        if z == prev {
+            return z / 3.0;
+        }
+    }
+}
+
+// Registers are packed most significant bit first.
+fn read_bits(data: &[u8], offset: usize, count: usize) -> u64 {
+    (offset..offset + count).fold(0, |value, bit| {
+        (value << 1) | u64::from((data[bit / 8] >> (7 - bit % 8)) & 1)
+    })
+}
+
+fn write_bits(data: &mut [u8], offset: usize, count: usize, value: u64) {
+    for i in 0..count {
+        if (value >> (count - 1 - i)) & 1 == 1 {
+            let bit = offset + i;
+            data[bit / 8] |= 1 << (7 - bit % 8);
+        }
+    }
+}
+
+fn murmur3_x64_128(data: &[u8]) -> (u64, u64) {
+    const C1: u64 = 0x87c3_7b91_1142_53d5;
+    const C2: u64 = 0x4cf5_ad43_2745_937f;
+
+    let mix_k1 = |k1: u64| k1.wrapping_mul(C1).rotate_left(31).wrapping_mul(C2);
+    let mix_k2 = |k2: u64| k2.wrapping_mul(C2).rotate_left(33).wrapping_mul(C1);
+    let fmix = |mut k: u64| {
+        k ^= k >> 33;
+        k = k.wrapping_mul(0xff51_afd7_ed55_8ccd);
+        k ^= k >> 33;
+        k = k.wrapping_mul(0xc4ce_b9fe_1a85_ec53);
+        k ^ (k >> 33)
+    };
+    let le_u64 = |bytes: &[u8]| {
+        bytes
+            .iter()
+            .rev()
+            .fold(0_u64, |value, byte| (value << 8) | u64::from(*byte))
+    };
+
+    let (mut h1, mut h2) = (0_u64, 0_u64);
+    let mut blocks = data.chunks_exact(16);
+    for block in &mut blocks {
+        h1 ^= mix_k1(le_u64(&block[..8]));
+        h1 = h1
+            .rotate_left(27)
+            .wrapping_add(h2)
+            .wrapping_mul(5)
+            .wrapping_add(0x52dc_e729);
+        h2 ^= mix_k2(le_u64(&block[8..]));
+        h2 = h2
+            .rotate_left(31)
+            .wrapping_add(h1)
+            .wrapping_mul(5)
+            .wrapping_add(0x3849_5ab5);
+    }
+
+    let tail = blocks.remainder();
+    if tail.len() > 8 {
+        h2 ^= mix_k2(le_u64(&tail[8..]));
+    }
+    if !tail.is_empty() {
+        h1 ^= mix_k1(le_u64(&tail[..tail.len().min(8)]));
+    }
+
+    h1 ^= data.len() as u64;
+    h2 ^= data.len() as u64;
+    h1 = h1.wrapping_add(h2);
+    h2 = h2.wrapping_add(h1);
+    h1 = fmix(h1);
+    h2 = fmix(h2);
+    h1 = h1.wrapping_add(h2);
+    h2 = h2.wrapping_add(h1);
+    (h1, h2)
+}
+
+impl From<HllSketch> for Value {
+    fn from(sketch: HllSketch) -> Value {
+        Value::HLL(sketch.to_bytes())
+    }
+}
+
+impl From<&HllSketch> for Value {
+    fn from(sketch: &HllSketch) -> Value {
+        Value::HLL(sketch.to_bytes())
+    }
+}
+
+impl TryFrom<Value> for HllSketch {
+    type Error = String;
+    fn try_from(val: Value) -> std::result::Result<Self, Self::Error> {
+        match val {
+            Value::HLL(ref data) => HllSketch::from_bytes(data).map_err(|err| err.to_string()),
+            _ => Err(format!(
+                "Invalid type conversion from Value::{} to {}",
+                val.particle_type(),
+                std::any::type_name::<Self>()
+            )),
+        }
+    }
+}
+
+impl FromValueView for HllSketch {
+    fn from_value_view(value: ValueView<'_>) -> Result<Self> {
+        match value {
+            ValueView::HLL(data) => HllSketch::from_bytes(data),
+            _ => Err(type_error(&value, "HllSketch")),
+        }
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::cast_precision_loss)]
+mod tests {
+    use super::{murmur3_x64_128, HllSketch};
+    use crate::Value;
+
+    fn sketch(index_bits: u8, minhash_bits: u8, values: std::ops::Range<i64>) -> HllSketch {
+        let mut sketch = HllSketch::new(index_bits, minhash_bits).unwrap();
+        for value in values {
+            sketch.add(&Value::from(value)).unwrap();
+        }
+        sketch
+    }
+
+    fn assert_close(actual: f64, expected: f64, tolerance: f64) {
+        assert!(
+            (actual - expected).abs() <= expected * tolerance,
+            "{} is not within {} of {}",
+            actual,
+            tolerance,
+            expected
+        );
+    }
+
+    #[test]
+    fn murmur3() {
+        assert_eq!(murmur3_x64_128(b""), (0, 0));
+        assert_eq!(
+            murmur3_x64_128(b"hello"),
+            (0xcbd8_a7b3_41bd_9b02, 0x5b1e_906a_48ae_1d19)
+        );
+    }
+
+    #[test]
+    fn validation() {
+        assert!(HllSketch::new(3, 0).is_err());
+        assert!(HllSketch::new(17, 0).is_err());
+        assert!(HllSketch::new(8, 2).is_err());
+        assert!(HllSketch::new(16, 50).is_err());
+        assert!(HllSketch::new(16, 48).is_ok());
+        assert!(HllSketch::from_bytes(&[0, 4, 0]).is_err());
+        assert!(HllSketch::from_bytes(&[0; 20]).is_err());
+    }
+
+    #[test]
+    fn invalid_registers() {
+        // registers with the largest HLL value
+        let mut data = HllSketch::new(16, 0).unwrap().to_bytes();
+        data[11] = 0xff;
+        assert!(HllSketch::from_bytes(&data).unwrap().cardinality() > 0);
+
+        // a register with a minhash value, but no HLL value
+        let mut data = HllSketch::new(4, 4).unwrap().to_bytes();
+        data[11] = 0x01;
+        assert!(HllSketch::from_bytes(&data).is_err());
+        data[11] = 0x05;
+        assert!(HllSketch::from_bytes(&data).is_ok());
+    }
+
+    #[test]
+    fn server_union() {
+        // `hll::get_union` of "asd123" in a sketch with 4 index bits and "123asd" in a sketch
+        // with 6 index bits, as returned by the server
+        let server = [
+            0, 4, 0, 0, 0, 0, 0, 0, 0, 0, 0, 8, 0, 2, 0, 0, 0, 0, 0, 0, 0, 0, 0,
+        ];
+        let mut a = HllSketch::new(4, 0).unwrap();
+        a.add(&Value::from("asd123")).unwrap();
+        let mut b = HllSketch::new(6, 0).unwrap();
+        b.add(&Value::from("123asd")).unwrap();
+        let union = HllSketch::union(&[a, b]).unwrap();
+        assert_eq!(union.to_bytes(), server.to_vec());
+        assert_eq!(HllSketch::from_bytes(&server).unwrap().cardinality(), 2);
+    }
+
+    #[test]
+    fn bytes_round_trip() {
+        let sketch = sketch(8, 12, 0..1000);
+        let data = sketch.to_bytes();
+        assert_eq!(data.len(), 11 + 256 * 18 / 8);
+        assert_eq!(HllSketch::from_bytes(&data).unwrap(), sketch);
+        assert!(!sketch.is_empty());
+        assert!(HllSketch::new(8, 0).unwrap().is_empty());
+    }
+
+    #[test]
+    fn cardinality() {
+        assert_eq!(HllSketch::new(12, 0).unwrap().cardinality(), 0);
+        assert_eq!(sketch(12, 0, 0..10).cardinality(), 10);
+        assert_close(
+            sketch(12, 0, 0..100_000).cardinality() as f64,
+            100_000.0,
+            0.05,
+        );
+    }
+
+    #[test]
+    fn union_and_intersection() {
+        for minhash_bits in [0, 16] {
+            let a = sketch(12, minhash_bits, 0..20_000);
+            let b = sketch(12, minhash_bits, 10_000..30_000);
+            let sketches = [a, b];
+            assert_close(
+                HllSketch::union_count(&sketches).unwrap() as f64,
+                30_000.0,
+                0.05,
+            );
+            assert_close(
+                HllSketch::intersect_count(&sketches).unwrap() as f64,
+                10_000.0,
+                0.2,
+            );
+            assert_close(HllSketch::similarity(&sketches).unwrap(), 1.0 / 3.0, 0.2);
+        }
+
+        let c = sketch(12, 0, 0..10);
+        assert!(HllSketch::similarity(&[c.clone(), c.clone(), c]).is_err());
+    }
+
+    #[test]
+    fn merge_folds() {
+        let mut a = sketch(12, 16, 0..20_000);
+        let b = sketch(10, 8, 20_000..40_000);
+        a.merge(&b);
+        assert_eq!((a.index_bits(), a.minhash_bits()), (10, 8));
+        assert_close(a.cardinality() as f64, 40_000.0, 0.1);
+        assert_close(
+            sketch(12, 0, 0..20_000).folded(8, 0).cardinality() as f64,
+            20_000.0,
+            0.2,
+        );
+    }
+}
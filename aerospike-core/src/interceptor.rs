@@ -0,0 +1,456 @@
+// Copyright 2015-2020 Aerospike, Inc.
+//
+// Portions may be licensed to Aerospike, Inc. under one or more contributor
+// license agreements.
+//
+// Licensed under the Apache License, Version 2.0 (the "License"); you may not
+// use this file except in compliance with the License. You may obtain a copy of
+// the License at http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS, WITHOUT
+// WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied. See the
+// License for the specific language governing permissions and limitations under
+// the License.
+
+use std::borrow::Cow;
+use std::fmt;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use crate::errors::{Error, Result};
+use crate::expressions::Expression;
+use crate::operations::Operation;
+use crate::policy::{BasePolicy, BatchPolicy, QueryPolicy, ReadPolicy, WritePolicy};
+use crate::{BatchOperation, Bin, Key, Record, Statement};
+
+/// Hooks that are called around the commands executed by the client.
+///
+/// Interceptors are configured through `ClientPolicy::interceptors`. They can be used to
+/// implement cross-cutting concerns such as tenant isolation checks, audit logging or metrics
+/// without wrapping every client method.
+///
+/// All hooks have empty default implementations. Interceptors are called in the order in
+/// which they are listed in the client policy.
+///
+/// Single record commands, batches, queries and scans are intercepted. Info, admin, UDF
+/// registration and index management commands are not.
+///
+/// # Examples
+///
+/// Reject commands on namespaces other than `"tenant"`, and hide records that do not belong to
+/// the tenant from reads:
+///
+/// ```rust
+/// use std::sync::Arc;
+/// use aerospike::expressions::{eq, int_bin, int_val};
+/// use aerospike::{ClientPolicy, CommandContext, CommandInterceptor, CommandPolicy, Error};
+///
+/// #[derive(Debug)]
+/// struct TenantIsolation;
+///
+/// impl CommandInterceptor for TenantIsolation {
+///     fn before_command(
+///         &self,
+///         command: &CommandContext<'_>,
+///         policy: &mut CommandPolicy<'_>,
+///     ) -> aerospike::Result<()> {
+///         if command.namespace() != "tenant" {
+///             return Err(Error::InvalidArgument(format!(
+///                 "{} on namespace {} is not allowed",
+///                 command.command(),
+///                 command.namespace()
+///             )));
+///         }
+///         *policy.filter_expression() = Some(eq(int_bin("tenant_id".into()), int_val(42)));
+///         Ok(())
+///     }
+/// }
+///
+/// let mut policy = ClientPolicy::default();
+/// policy.interceptors.push(Arc::new(TenantIsolation));
+/// ```
+pub trait CommandInterceptor: fmt::Debug + Send + Sync {
+    /// Called before the command is sent to the server. Returning an error rejects the
+    /// command: the error is returned to the caller, the server is not contacted and no other
+    /// hooks are called for the command. The policy the command is executed with can be
+    /// modified; the caller's policy is left unchanged.
+    fn before_command(
+        &self,
+        _command: &CommandContext<'_>,
+        _policy: &mut CommandPolicy<'_>,
+    ) -> Result<()> {
+        Ok(())
+    }
+
+    /// Called once the command completed, with the record returned by the server, if any, or
+    /// the error the command failed with, and the time the command took including retries.
+    /// Queries and scans complete once they have been dispatched to the cluster nodes.
+    fn after_command(
+        &self,
+        _command: &CommandContext<'_>,
+        _result: std::result::Result<Option<&Record>, &Error>,
+        _latency: Duration,
+    ) {
+    }
+
+    /// Called before a single record command is retried after a failed attempt, with the
+    /// number of the attempt about to be made and the error of the failed attempt.
+    fn on_retry(&self, _command: &CommandContext<'_>, _attempt: usize, _error: &Error) {}
+}
+
+/// Describes a command passed to a [`CommandInterceptor`].
+#[derive(Debug, Clone, Copy)]
+pub struct CommandContext<'a> {
+    command: &'static str,
+    namespace: &'a str,
+    set_name: &'a str,
+    key: Option<&'a Key>,
+    bins: Option<&'a [Bin]>,
+    operations: Option<&'a [Operation]>,
+    batch: Option<&'a [BatchOperation]>,
+    statement: Option<&'a Statement>,
+}
+
+impl<'a> CommandContext<'a> {
+    pub(crate) const fn new(command: &'static str, namespace: &'a str, set_name: &'a str) -> Self {
+        CommandContext {
+            command,
+            namespace,
+            set_name,
+            key: None,
+            bins: None,
+            operations: None,
+            batch: None,
+            statement: None,
+        }
+    }
+
+    pub(crate) fn for_key(command: &'static str, key: &'a Key) -> Self {
+        CommandContext {
+            key: Some(key),
+            ..Self::new(command, &key.namespace, &key.set_name)
+        }
+    }
+
+    pub(crate) fn for_statement(command: &'static str, statement: &'a Statement) -> Self {
+        CommandContext {
+            statement: Some(statement),
+            ..Self::new(command, &statement.namespace, &statement.set_name)
+        }
+    }
+
+    pub(crate) const fn with_bins(self, bins: &'a [Bin]) -> Self {
+        CommandContext {
+            bins: Some(bins),
+            ..self
+        }
+    }
+
+    pub(crate) const fn with_operations(self, operations: &'a [Operation]) -> Self {
+        CommandContext {
+            operations: Some(operations),
+            ..self
+        }
+    }
+
+    pub(crate) const fn with_batch(self, batch: &'a [BatchOperation]) -> Self {
+        CommandContext {
+            batch: Some(batch),
+            ..self
+        }
+    }
+
+    /// Name of the client method that issued the command, e.g. `"get"`, `"put"` or `"query"`.
+    pub const fn command(&self) -> &'static str {
+        self.command
+    }
+
+    /// Namespace the command is executed on. For batches, this is the namespace of the first
+    /// key in the batch; use [`batch`](Self::batch) to inspect all keys.
+    pub const fn namespace(&self) -> &'a str {
+        self.namespace
+    }
+
+    /// Set name the command is executed on, or an empty string for batches and queries or
+    /// scans of a whole namespace.
+    pub const fn set_name(&self) -> &'a str {
+        self.set_name
+    }
+
+    /// Key of single record commands.
+    pub const fn key(&self) -> Option<&'a Key> {
+        self.key
+    }
+
+    /// Bins written by `put`, `add`, `append` and `prepend`.
+    pub const fn bins(&self) -> Option<&'a [Bin]> {
+        self.bins
+    }
+
+    /// Operations of `operate` and background query commands.
+    pub const fn operations(&self) -> Option<&'a [Operation]> {
+        self.operations
+    }
+
+    /// Operations of batch commands.
+    pub const fn batch(&self) -> Option<&'a [BatchOperation]> {
+        self.batch
+    }
+
+    /// Statement of query and scan commands.
+    pub const fn statement(&self) -> Option<&'a Statement> {
+        self.statement
+    }
+}
+
+/// Mutable reference to the policy a command is executed with, passed to
+/// [`CommandInterceptor::before_command`].
+#[derive(Debug)]
+pub enum CommandPolicy<'a> {
+    /// Policy of `get` and `exists`.
+    Read(&'a mut ReadPolicy),
+
+    /// Policy of single record write commands, `operate` and `execute_udf`, and of background
+    /// query commands.
+    Write(&'a mut WritePolicy),
+
+    /// Policy of batch commands.
+    Batch(&'a mut BatchPolicy),
+
+    /// Policy of queries and scans.
+    Query(&'a mut QueryPolicy),
+}
+
+impl CommandPolicy<'_> {
+    /// Returns the base policy, which holds the timeouts and retry settings of the command.
+    pub const fn base_policy(&mut self) -> &mut BasePolicy {
+        match self {
+            CommandPolicy::Read(policy) => &mut policy.base_policy,
+            CommandPolicy::Write(policy) => &mut policy.base_policy,
+            CommandPolicy::Batch(policy) => &mut policy.base_policy,
+            CommandPolicy::Query(policy) => &mut policy.base_policy,
+        }
+    }
+
+    /// Returns the filter expression sent with the command. For batch commands, this is
+    /// `BatchPolicy::filter_expression`; for all other commands it is the filter expression of
+    /// the base policy.
+    pub const fn filter_expression(&mut self) -> &mut Option<Expression> {
+        match self {
+            CommandPolicy::Batch(policy) => &mut policy.filter_expression,
+            _ => &mut self.base_policy().filter_expression,
+        }
+    }
+}
+
+// Policies that can be passed to interceptors.
+pub trait InterceptedPolicy: Clone {
+    fn command_policy(&mut self) -> CommandPolicy<'_>;
+}
+
+impl InterceptedPolicy for ReadPolicy {
+    fn command_policy(&mut self) -> CommandPolicy<'_> {
+        CommandPolicy::Read(self)
+    }
+}
+
+impl InterceptedPolicy for WritePolicy {
+    fn command_policy(&mut self) -> CommandPolicy<'_> {
+        CommandPolicy::Write(self)
+    }
+}
+
+impl InterceptedPolicy for BatchPolicy {
+    fn command_policy(&mut self) -> CommandPolicy<'_> {
+        CommandPolicy::Batch(self)
+    }
+}
+
+impl InterceptedPolicy for QueryPolicy {
+    fn command_policy(&mut self) -> CommandPolicy<'_> {
+        CommandPolicy::Query(self)
+    }
+}
+
+// Runs the interceptors configured in the client policy for a single command.
+pub struct Interception<'a> {
+    interceptors: Vec<Arc<dyn CommandInterceptor>>,
+    context: CommandContext<'a>,
+    started: Instant,
+}
+
+impl<'a> Interception<'a> {
+    pub fn new(
+        interceptors: Vec<Arc<dyn CommandInterceptor>>,
+        context: CommandContext<'a>,
+    ) -> Self {
+        Interception {
+            interceptors,
+            context,
+            started: Instant::now(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.interceptors.is_empty()
+    }
+
+    // Calls the `before_command` hooks. The policy is only cloned if there are interceptors
+    // that may modify it.
+    pub fn before<'p, P: InterceptedPolicy>(&mut self, policy: &'p P) -> Result<Cow<'p, P>> {
+        if self.is_empty() {
+            return Ok(Cow::Borrowed(policy));
+        }
+
+        let mut policy = policy.clone();
+        {
+            let mut command_policy = policy.command_policy();
+            for interceptor in &self.interceptors {
+                interceptor.before_command(&self.context, &mut command_policy)?;
+            }
+        }
+        self.started = Instant::now();
+        Ok(Cow::Owned(policy))
+    }
+
+    pub fn retry(&self, attempt: usize, error: &Error) {
+        for interceptor in &self.interceptors {
+            interceptor.on_retry(&self.context, attempt, error);
+        }
+    }
+
+    pub fn after(&self, result: std::result::Result<Option<&Record>, &Error>) {
+        if self.is_empty() {
+            return;
+        }
+
+        let latency = self.started.elapsed();
+        for interceptor in &self.interceptors {
+            interceptor.after_command(&self.context, result, latency);
+        }
+    }
+
+    // Calls the `after_command` hooks for commands that do not return a record.
+    pub fn complete<T>(&self, result: Result<T>) -> Result<T> {
+        self.after(result.as_ref().map(|_| None));
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, Mutex};
+
+    use super::*;
+    use crate::expressions::{eq, int_bin, int_val};
+
+    #[derive(Debug, Default)]
+    struct Recorder {
+        calls: Mutex<Vec<String>>,
+        reject: bool,
+    }
+
+    impl CommandInterceptor for Recorder {
+        fn before_command(
+            &self,
+            command: &CommandContext<'_>,
+            policy: &mut CommandPolicy<'_>,
+        ) -> Result<()> {
+            self.calls.lock().unwrap().push(format!(
+                "before {} {}",
+                command.command(),
+                command.namespace()
+            ));
+            if self.reject {
+                return Err(Error::InvalidArgument("rejected".into()));
+            }
+            *policy.filter_expression() = Some(eq(int_bin("tenant".into()), int_val(1)));
+            policy.base_policy().max_retries = 7;
+            Ok(())
+        }
+
+        fn after_command(
+            &self,
+            command: &CommandContext<'_>,
+            result: std::result::Result<Option<&Record>, &Error>,
+            _latency: Duration,
+        ) {
+            self.calls.lock().unwrap().push(format!(
+                "after {} {}",
+                command.command(),
+                result.is_ok()
+            ));
+        }
+
+        fn on_retry(&self, command: &CommandContext<'_>, attempt: usize, _error: &Error) {
+            self.calls
+                .lock()
+                .unwrap()
+                .push(format!("retry {} {attempt}", command.command()));
+        }
+    }
+
+    #[test]
+    fn without_interceptors_policy_is_borrowed() {
+        let key = as_key!("test", "test", 1);
+        let policy = ReadPolicy::default();
+        let mut interception = Interception::new(vec![], CommandContext::for_key("get", &key));
+        assert!(matches!(interception.before(&policy), Ok(Cow::Borrowed(_))));
+    }
+
+    #[test]
+    fn hooks_are_called_and_policy_is_modified() {
+        let recorder = Arc::new(Recorder::default());
+        let key = as_key!("ns", "set", 1);
+        let bins = [as_bin!("a", 1)];
+        let policy = WritePolicy::default();
+        let mut interception = Interception::new(
+            vec![recorder.clone()],
+            CommandContext::for_key("put", &key).with_bins(&bins),
+        );
+
+        let modified = interception.before(&policy).unwrap();
+        assert_eq!(modified.base_policy.max_retries, 7);
+        assert!(modified.base_policy.filter_expression.is_some());
+        assert_eq!(
+            policy.base_policy.max_retries,
+            WritePolicy::default().base_policy.max_retries
+        );
+        assert!(policy.base_policy.filter_expression.is_none());
+
+        interception.retry(2, &Error::Timeout("timeout".into()));
+        let res = interception.complete(Ok(()));
+        assert!(res.is_ok());
+
+        assert_eq!(
+            *recorder.calls.lock().unwrap(),
+            vec!["before put ns", "retry put 2", "after put true"]
+        );
+    }
+
+    #[test]
+    fn batch_filter_expression() {
+        let mut policy = BatchPolicy::default();
+        *policy.command_policy().filter_expression() = Some(int_val(1));
+        assert!(policy.filter_expression.is_some());
+        assert!(policy.base_policy.filter_expression.is_none());
+    }
+
+    #[test]
+    fn rejected_command() {
+        let recorder = Arc::new(Recorder {
+            reject: true,
+            ..Recorder::default()
+        });
+        let key = as_key!("ns", "set", 1);
+        let policy = ReadPolicy::default();
+        let mut interception =
+            Interception::new(vec![recorder.clone()], CommandContext::for_key("get", &key));
+        assert!(matches!(
+            interception.before(&policy),
+            Err(Error::InvalidArgument(_))
+        ));
+        assert_eq!(*recorder.calls.lock().unwrap(), vec!["before get ns"]);
+    }
+}
@@ -0,0 +1,426 @@
+// Copyright 2015-2020 Aerospike, Inc.
+//
+// Portions may be licensed to Aerospike, Inc. under one or more contributor
+// license agreements.
+//
+// Licensed under the Apache License, Version 2.0 (the "License"); you may not
+// use this file except in compliance with the License. You may obtain a copy of
+// the License at http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS, WITHOUT
+// WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied. See the
+// License for the specific language governing permissions and limitations under
+// the License.
+
+//! Conversion between JSON documents and Aerospike values. Requires the `json` feature.
+//!
+//! With the default [`JsonPolicy`], values are converted as follows:
+//!
+//! * JSON objects become key-ordered maps, and maps with string keys become JSON objects.
+//! * Blobs and HLL values are written as `{"$blob": "<base64>"}` and `{"$hll": "<base64>"}`
+//!   objects, which are converted back to blobs and HLL values.
+//! * JSON objects that look like `GeoJSON` geometries become `GeoJSON` values, and `GeoJSON`
+//!   values become the JSON objects they contain.
+//! * Integers outside the range of `i64` and floats that are not finite can not be converted
+//!   and result in an error, as do maps with keys that are not strings.
+//!
+//! A JSON document that is converted to a value and back is unchanged. The other direction is
+//! not lossless: objects with a single `$blob` or `$hll` key and a string value, and objects with
+//! a `GeoJSON` geometry `type` and `coordinates`, are reserved for blobs, HLL values and `GeoJSON`
+//! values. Maps of that shape are written to JSON as they are, but read back as blobs, HLL values
+//! and `GeoJSON` values. Use [`BlobEncoding::Base64`] or disable
+//! [`detect_geojson`](JsonPolicy::detect_geojson) if such maps have to be read back as maps.
+//!
+//! ```
+//! use std::convert::TryFrom;
+//! use aerospike::Value;
+//!
+//! let json = serde_json::json!({"name": "Ann", "tags": ["a", "b"], "age": 42});
+//! let value = Value::try_from(json.clone()).unwrap();
+//! assert_eq!(serde_json::Value::try_from(value).unwrap(), json);
+//! ```
+
+use std::collections::{BTreeMap, HashMap};
+use std::convert::TryFrom;
+
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine as _};
+use serde_json::{Map, Number};
+
+use crate::errors::{Error, Result};
+use crate::value::FloatValue;
+use crate::{Bin, Bins, Record, Value};
+
+const BLOB_TAG: &str = "$blob";
+const HLL_TAG: &str = "$hll";
+const GEO_TYPES: [&str; 4] = ["Point", "Polygon", "MultiPolygon", "AeroCircle"];
+
+/// How blob and HLL values are represented in JSON.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BlobEncoding {
+    /// Write blobs as `{"$blob": "<base64>"}` and HLL values as `{"$hll": "<base64>"}` objects,
+    /// and convert such objects back when reading JSON.
+    Tagged,
+    /// Write blobs and HLL values as base64 strings. The strings are read back as strings.
+    Base64,
+}
+
+/// `JsonPolicy` determines how JSON documents are converted to Aerospike values and back.
+#[derive(Debug, Clone)]
+pub struct JsonPolicy {
+    /// Convert JSON objects to key-ordered maps (`Value::OrderedMap`) instead of unordered maps
+    /// (`Value::HashMap`). Default: true.
+    pub ordered_maps: bool,
+
+    /// Representation of blob and HLL values. Default: `BlobEncoding::Tagged`.
+    pub blob_encoding: BlobEncoding,
+
+    /// Convert JSON objects with a `GeoJSON` geometry `type` and `coordinates` to `GeoJSON`
+    /// values. Default: true.
+    pub detect_geojson: bool,
+}
+
+impl Default for JsonPolicy {
+    fn default() -> Self {
+        JsonPolicy {
+            ordered_maps: true,
+            blob_encoding: BlobEncoding::Tagged,
+            detect_geojson: true,
+        }
+    }
+}
+
+/// Where a JSON document is stored in a record by
+/// [`Client::put_json`](crate::Client::put_json).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum JsonLayout {
+    /// Store each top-level field of the document, which must be a JSON object, in its own bin.
+    Bins,
+    /// Store the whole document in the bin with the given name.
+    Bin(String),
+}
+
+/// Converts a JSON value to an Aerospike value.
+pub fn from_json(json: &serde_json::Value, policy: &JsonPolicy) -> Result<Value> {
+    let value = match json {
+        serde_json::Value::Null => Value::Nil,
+        serde_json::Value::Bool(val) => Value::Bool(*val),
+        serde_json::Value::Number(num) => from_number(num)?,
+        serde_json::Value::String(val) => Value::String(val.clone()),
+        serde_json::Value::Array(list) => Value::List(
+            list.iter()
+                .map(|val| from_json(val, policy))
+                .collect::<Result<_>>()?,
+        ),
+        serde_json::Value::Object(map) => from_object(map, policy)?,
+    };
+    Ok(value)
+}
+
+fn from_number(num: &Number) -> Result<Value> {
+    if let Some(val) = num.as_i64() {
+        return Ok(Value::Int(val));
+    }
+    if num.is_u64() {
+        return Err(Error::InvalidArgument(format!(
+            "JSON number {num} is out of the range of Aerospike integers"
+        )));
+    }
+    num.as_f64()
+        .map(Value::from)
+        .ok_or_else(|| Error::InvalidArgument(format!("Invalid JSON number {num}")))
+}
+
+fn from_object(map: &Map<String, serde_json::Value>, policy: &JsonPolicy) -> Result<Value> {
+    if policy.blob_encoding == BlobEncoding::Tagged && map.len() == 1 {
+        match map.iter().next() {
+            Some((tag, serde_json::Value::String(data))) if tag == BLOB_TAG => {
+                return decode_base64(data).map(Value::Blob);
+            }
+            Some((tag, serde_json::Value::String(data))) if tag == HLL_TAG => {
+                return decode_base64(data).map(Value::HLL);
+            }
+            _ => {}
+        }
+    }
+
+    if policy.detect_geojson && is_geojson(map) {
+        return Ok(Value::GeoJSON(
+            serde_json::Value::from(map.clone()).to_string(),
+        ));
+    }
+
+    let entries = map
+        .iter()
+        .map(|(key, val)| Ok((Value::String(key.clone()), from_json(val, policy)?)));
+    if policy.ordered_maps {
+        Ok(Value::OrderedMap(
+            entries.collect::<Result<BTreeMap<_, _>>>()?,
+        ))
+    } else {
+        Ok(Value::HashMap(entries.collect::<Result<HashMap<_, _>>>()?))
+    }
+}
+
+fn is_geojson(map: &Map<String, serde_json::Value>) -> bool {
+    let Some(serde_json::Value::String(geo_type)) = map.get("type") else {
+        return false;
+    };
+    GEO_TYPES.contains(&geo_type.as_str()) && map.contains_key("coordinates")
+}
+
+fn decode_base64(data: &str) -> Result<Vec<u8>> {
+    BASE64
+        .decode(data)
+        .map_err(|err| Error::InvalidArgument(format!("Invalid base64 encoded blob: {err}")))
+}
+
+/// Converts an Aerospike value to a JSON value.
+pub fn to_json(value: &Value, policy: &JsonPolicy) -> Result<serde_json::Value> {
+    let json = match value {
+        Value::Nil => serde_json::Value::Null,
+        Value::Bool(val) => serde_json::Value::Bool(*val),
+        Value::Int(val) => serde_json::Value::from(*val),
+        Value::Float(val) => {
+            let val = match val {
+                FloatValue::F64(bits) => f64::from_bits(*bits),
+                FloatValue::F32(bits) => f64::from(f32::from_bits(*bits)),
+            };
+            Number::from_f64(val)
+                .map(serde_json::Value::Number)
+                .ok_or_else(|| {
+                    Error::InvalidArgument(format!("Float {val} can not be represented in JSON"))
+                })?
+        }
+        Value::String(val) => serde_json::Value::String(val.clone()),
+        Value::Blob(val) => to_blob(val, BLOB_TAG, policy),
+        Value::HLL(val) => to_blob(val, HLL_TAG, policy),
+        Value::List(list) | Value::MultiResult(list) => serde_json::Value::Array(
+            list.iter()
+                .map(|val| to_json(val, policy))
+                .collect::<Result<_>>()?,
+        ),
+        Value::HashMap(map) => to_object(map.iter(), policy)?,
+        Value::OrderedMap(map) => to_object(map.iter(), policy)?,
+        Value::KeyValueList(list) => to_object(list.iter().map(|(k, v)| (k, v)), policy)?,
+        Value::GeoJSON(val) => serde_json::from_str(val).map_err(|err| {
+            Error::InvalidArgument(format!("Invalid GeoJSON value {val:?}: {err}"))
+        })?,
+        Value::Infinity | Value::Wildcard => {
+            return Err(Error::InvalidArgument(format!(
+                "Value {value} can not be represented in JSON"
+            )))
+        }
+    };
+    Ok(json)
+}
+
+fn to_blob(val: &[u8], tag: &str, policy: &JsonPolicy) -> serde_json::Value {
+    let data = serde_json::Value::String(BASE64.encode(val));
+    match policy.blob_encoding {
+        BlobEncoding::Tagged => {
+            let mut map = Map::with_capacity(1);
+            map.insert(tag.to_string(), data);
+            serde_json::Value::Object(map)
+        }
+        BlobEncoding::Base64 => data,
+    }
+}
+
+fn to_object<'a>(
+    entries: impl Iterator<Item = (&'a Value, &'a Value)>,
+    policy: &JsonPolicy,
+) -> Result<serde_json::Value> {
+    let mut map = Map::new();
+    for (key, val) in entries {
+        let Value::String(key) = key else {
+            return Err(Error::InvalidArgument(format!(
+                "Map key {key} can not be represented in JSON, only string keys are supported"
+            )));
+        };
+        map.insert(key.clone(), to_json(val, policy)?);
+    }
+    Ok(serde_json::Value::Object(map))
+}
+
+// Converts a document to the bins that store it with the given layout.
+pub(crate) fn document_bins(layout: &JsonLayout, doc: &serde_json::Value) -> Result<Vec<Bin>> {
+    let policy = JsonPolicy::default();
+    match layout {
+        JsonLayout::Bins => {
+            let serde_json::Value::Object(map) = doc else {
+                return Err(Error::InvalidArgument(
+                    "Only JSON objects can be stored as bins".to_string(),
+                ));
+            };
+            map.iter()
+                .map(|(name, val)| Ok(Bin::new(name.clone(), from_json(val, &policy)?)))
+                .collect()
+        }
+        JsonLayout::Bin(name) => Ok(vec![Bin::new(name.clone(), from_json(doc, &policy)?)]),
+    }
+}
+
+// Returns the bins to read for a document stored with the given layout.
+pub(crate) fn layout_bins(layout: &JsonLayout) -> Bins {
+    match layout {
+        JsonLayout::Bins => Bins::All,
+        JsonLayout::Bin(name) => Bins::Some(vec![name.clone()]),
+    }
+}
+
+// Converts a record read with `layout_bins` back to the document.
+pub(crate) fn record_document(layout: &JsonLayout, record: &Record) -> Result<serde_json::Value> {
+    let policy = JsonPolicy::default();
+    match layout {
+        JsonLayout::Bins => {
+            let mut map = Map::with_capacity(record.bins.len());
+            for (name, val) in &record.bins {
+                map.insert(name.clone(), to_json(val, &policy)?);
+            }
+            Ok(serde_json::Value::Object(map))
+        }
+        JsonLayout::Bin(name) => record
+            .bins
+            .get(name)
+            .map_or(Ok(serde_json::Value::Null), |val| to_json(val, &policy)),
+    }
+}
+
+impl TryFrom<serde_json::Value> for Value {
+    type Error = Error;
+
+    /// Converts a JSON value using the default [`JsonPolicy`].
+    fn try_from(json: serde_json::Value) -> Result<Self> {
+        from_json(&json, &JsonPolicy::default())
+    }
+}
+
+impl TryFrom<Value> for serde_json::Value {
+    type Error = Error;
+
+    /// Converts a value using the default [`JsonPolicy`].
+    fn try_from(value: Value) -> Result<Self> {
+        to_json(&value, &JsonPolicy::default())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::{BTreeMap, HashMap};
+    use std::convert::TryFrom;
+
+    use serde_json::json;
+
+    use super::{from_json, to_json, BlobEncoding, JsonPolicy};
+    use crate::Value;
+
+    fn roundtrip(json: &serde_json::Value) -> Value {
+        let value = Value::try_from(json.clone()).unwrap();
+        assert_eq!(&serde_json::Value::try_from(value.clone()).unwrap(), json);
+        value
+    }
+
+    #[test]
+    fn json_scalars() {
+        assert_eq!(roundtrip(&json!(null)), Value::Nil);
+        assert_eq!(roundtrip(&json!(true)), Value::Bool(true));
+        assert_eq!(roundtrip(&json!(-42)), Value::Int(-42));
+        assert_eq!(roundtrip(&json!(i64::MAX)), Value::Int(i64::MAX));
+        assert_eq!(roundtrip(&json!(1.5)), Value::from(1.5));
+        assert_eq!(roundtrip(&json!("a")), Value::from("a"));
+
+        let err = Value::try_from(json!(u64::MAX)).unwrap_err();
+        assert!(err.to_string().contains("out of the range"), "{:?}", err);
+        assert!(serde_json::Value::try_from(Value::from(f64::NAN)).is_err());
+    }
+
+    #[test]
+    fn json_collections() {
+        let json = json!({"b": [1, "x", {"c": null}], "a": {"d": 2.5}});
+        let value = roundtrip(&json);
+        assert!(matches!(value, Value::OrderedMap(_)), "{:?}", value);
+
+        let policy = JsonPolicy {
+            ordered_maps: false,
+            ..JsonPolicy::default()
+        };
+        let value = from_json(&json, &policy).unwrap();
+        assert!(matches!(value, Value::HashMap(_)), "{:?}", value);
+        assert_eq!(to_json(&value, &policy).unwrap(), json);
+
+        let mut map = HashMap::new();
+        map.insert(Value::from(1), Value::from(2));
+        assert!(serde_json::Value::try_from(Value::HashMap(map)).is_err());
+    }
+
+    #[test]
+    fn json_blobs() {
+        let json = json!({"data": {"$blob": "AQID"}, "hll": {"$hll": "BA=="}});
+        let value = roundtrip(&json);
+        let Value::OrderedMap(map) = value else {
+            panic!("{:?}", value);
+        };
+        assert_eq!(map[&Value::from("data")], Value::Blob(vec![1, 2, 3]));
+        assert_eq!(map[&Value::from("hll")], Value::HLL(vec![4]));
+        assert!(Value::try_from(json!({"$blob": "!"})).is_err());
+
+        let policy = JsonPolicy {
+            blob_encoding: BlobEncoding::Base64,
+            ..JsonPolicy::default()
+        };
+        assert_eq!(
+            to_json(&Value::Blob(vec![1, 2, 3]), &policy).unwrap(),
+            json!("AQID")
+        );
+        assert!(matches!(
+            from_json(&json!({"$blob": "AQID"}), &policy).unwrap(),
+            Value::OrderedMap(_)
+        ));
+    }
+
+    #[test]
+    fn json_reserved_objects() {
+        // maps shaped like tagged blobs are read back as blobs
+        let mut map = BTreeMap::new();
+        map.insert(Value::from("$blob"), Value::from("AQID"));
+        let json = serde_json::Value::try_from(Value::OrderedMap(map.clone())).unwrap();
+        assert_eq!(json, json!({"$blob": "AQID"}));
+        assert_eq!(Value::try_from(json).unwrap(), Value::Blob(vec![1, 2, 3]));
+
+        let policy = JsonPolicy {
+            blob_encoding: BlobEncoding::Base64,
+            ..JsonPolicy::default()
+        };
+        let json = to_json(&Value::OrderedMap(map.clone()), &policy).unwrap();
+        assert_eq!(from_json(&json, &policy).unwrap(), Value::OrderedMap(map));
+
+        // other objects with reserved keys are plain maps
+        for json in [json!({"$blob": 1}), json!({"$hll": "BA==", "x": 1})] {
+            assert!(matches!(roundtrip(&json), Value::OrderedMap(_)));
+        }
+    }
+
+    #[test]
+    fn json_geojson() {
+        let json = json!({"type": "Point", "coordinates": [-122.0, 37.5]});
+        let value = roundtrip(&json);
+        assert_eq!(
+            value,
+            Value::GeoJSON(r#"{"coordinates":[-122.0,37.5],"type":"Point"}"#.to_string())
+        );
+
+        let policy = JsonPolicy {
+            detect_geojson: false,
+            ..JsonPolicy::default()
+        };
+        assert!(matches!(
+            from_json(&json, &policy).unwrap(),
+            Value::OrderedMap(_)
+        ));
+        assert!(matches!(
+            roundtrip(&json!({"type": "Point"})),
+            Value::OrderedMap(_)
+        ));
+    }
+}
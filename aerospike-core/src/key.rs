@@ -13,13 +13,21 @@
 // License for the specific language governing permissions and limitations under
 // the License.
 
+use std::convert::TryInto;
 use std::fmt;
+use std::fmt::Write;
 use std::io::Cursor;
 use std::result::Result as StdResult;
+use std::str::FromStr;
+use std::sync::Arc;
+
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine as _};
 
 use crate::cluster::node;
-use crate::errors::Result;
-use crate::Value;
+use crate::cluster::partition::Partition;
+use crate::errors::{Error, Result};
+use crate::policy::Replica;
+use crate::{Client, Node, Value};
 
 use byteorder::{LittleEndian, ReadBytesExt};
 use ripemd::digest::Digest;
@@ -73,15 +81,12 @@ impl Key {
     /// Construct a new key from namespace, optional set name, user key and digest.
     /// The server handles record identifiers by digest only.
     /// The digest will be set to the provided value and not validated.
-    pub fn key_with_digest<S>(
+    pub fn key_with_digest(
         namespace: String,
         set_name: Option<String>,
         key: Option<Value>,
         digest: [u8; 20],
-    ) -> Result<Self>
-    where
-        S: Into<String>,
-    {
+    ) -> Result<Self> {
         Ok(Self {
             namespace,
             set_name: set_name.unwrap_or_default(),
@@ -90,6 +95,88 @@ impl Key {
         })
     }
 
+    /// Construct a new key from namespace, set name and a digest in hexadecimal form, e.g. as
+    /// printed in the server logs.
+    pub fn from_digest_hex<S>(namespace: S, set_name: S, digest: &str) -> Result<Self>
+    where
+        S: Into<String>,
+    {
+        let invalid = || Error::InvalidArgument(format!("Invalid hex digest: {digest}"));
+        if digest.len() != 40 || !digest.bytes().all(|b| b.is_ascii_hexdigit()) {
+            return Err(invalid());
+        }
+        let mut bytes = [0; 20];
+        for (i, byte) in bytes.iter_mut().enumerate() {
+            *byte = u8::from_str_radix(&digest[2 * i..2 * i + 2], 16).map_err(|_| invalid())?;
+        }
+        Key::key_with_digest(namespace.into(), Some(set_name.into()), None, bytes)
+    }
+
+    /// Construct a new key from namespace, set name and a base64 encoded digest.
+    pub fn from_digest_base64<S>(namespace: S, set_name: S, digest: &str) -> Result<Self>
+    where
+        S: Into<String>,
+    {
+        let bytes = BASE64
+            .decode(digest)?
+            .try_into()
+            .map_err(|_| Error::InvalidArgument(format!("Invalid base64 digest: {digest}")))?;
+        Key::key_with_digest(namespace.into(), Some(set_name.into()), None, bytes)
+    }
+
+    /// Returns the digest in hexadecimal form.
+    pub fn digest_hex(&self) -> String {
+        self.digest
+            .iter()
+            .fold(String::with_capacity(40), |mut s, b| {
+                let _ = write!(s, "{b:02x}");
+                s
+            })
+    }
+
+    /// Returns the digest in base64 encoding.
+    pub fn digest_base64(&self) -> String {
+        BASE64.encode(self.digest)
+    }
+
+    /// Returns the node that a command for this key is sent to with the given replica policy,
+    /// according to the current partition map of the client.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the namespace is unknown or no node holds the partition of the key.
+    pub fn node_for(&self, client: &Client, replica: Replica) -> Result<Arc<Node>> {
+        client
+            .cluster
+            .get_node(&Partition::new_by_key(self), replica, None)
+    }
+
+    /// Returns an endless iterator of keys with integer user keys that hash to the given
+    /// partition, e.g. to test the routing of commands to a particular node.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the partition id is not below 4096.
+    pub fn partition_keys<S>(
+        namespace: S,
+        set_name: S,
+        partition_id: usize,
+    ) -> Result<impl Iterator<Item = Key>>
+    where
+        S: Into<String>,
+    {
+        if partition_id >= node::PARTITIONS {
+            return Err(Error::InvalidArgument(format!(
+                "Invalid partition id {partition_id}"
+            )));
+        }
+        let namespace = namespace.into();
+        let set_name = set_name.into();
+        Ok((0..=i64::MAX)
+            .filter_map(move |i| Key::new(namespace.clone(), set_name.clone(), Value::Int(i)).ok())
+            .filter(move |key| key.partition_id() == partition_id))
+    }
+
     fn compute_digest(&mut self) -> Result<()> {
         let mut hash = Ripemd160::new();
         hash.update(self.set_name.as_bytes());
@@ -123,20 +210,34 @@ impl Key {
     }
 }
 
+/// Formats the key as `namespace:set:user_key:digest`, with the digest in hexadecimal form. The
+/// user key is left out if the key does not have one: `namespace:set:digest`.
 impl fmt::Display for Key {
     fn fmt(&self, f: &mut fmt::Formatter) -> StdResult<(), fmt::Error> {
-        match self.user_key {
-            Some(ref value) => write!(
-                f,
-                "<Key: ns=\"{}\", set=\"{}\", key=\"{}\">",
-                &self.namespace, &self.set_name, value
-            ),
-            None => write!(
-                f,
-                "<Key: ns=\"{}\", set=\"{}\", digest=\"{:?}\">",
-                &self.namespace, &self.set_name, &self.digest
-            ),
+        write!(f, "{}:{}:", &self.namespace, &self.set_name)?;
+        if let Some(ref user_key) = self.user_key {
+            write!(f, "{user_key}:")?;
         }
+        write!(f, "{}", self.digest_hex())
+    }
+}
+
+/// Parses a key in the `namespace:set:digest` or `namespace:set:user_key:digest` form of its
+/// `Display` implementation. The set name may be empty. The user key is not parsed, since its
+/// type is not known; the record is identified by the digest.
+impl FromStr for Key {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let (namespace, rest) = s
+            .split_once(':')
+            .ok_or_else(|| Error::InvalidArgument(format!("Invalid key: {s}")))?;
+        let (rest, digest) = rest
+            .rsplit_once(':')
+            .ok_or_else(|| Error::InvalidArgument(format!("Invalid key: {s}")))?;
+        // set names can not contain colons, user keys can
+        let set_name = rest.split_once(':').map_or(rest, |(set_name, _)| set_name);
+        Key::from_digest_hex(namespace, set_name, digest)
     }
 }
 
@@ -157,6 +258,9 @@ macro_rules! as_key {
 mod tests {
     use std::str;
 
+    use crate::cluster::partition::Partition;
+    use crate::Key;
+
     macro_rules! digest {
         ($x:expr) => {
             hex::encode(as_key!("namespace", "set", $x).digest)
@@ -309,6 +413,68 @@ mod tests {
         );
     }
 
+    #[test]
+    fn digest_encodings() {
+        let key = as_key!("namespace", "set", 1);
+        let hex = key.digest_hex();
+        assert_eq!(hex, digest!(1));
+
+        let parsed = Key::from_digest_hex("namespace", "set", &hex).unwrap();
+        assert_eq!(parsed.digest, key.digest);
+        assert_eq!(parsed.user_key, None);
+        let parsed = Key::from_digest_hex("namespace", "set", &hex.to_uppercase()).unwrap();
+        assert_eq!(parsed.digest, key.digest);
+        let parsed = Key::from_digest_base64("namespace", "set", &key.digest_base64()).unwrap();
+        assert_eq!(parsed.digest, key.digest);
+
+        assert!(Key::from_digest_hex("namespace", "set", &hex[1..]).is_err());
+        assert!(Key::from_digest_hex("namespace", "set", &format!("+{}", &hex[1..])).is_err());
+        assert!(Key::from_digest_base64("namespace", "set", "AAAA").is_err());
+        assert!(Key::from_digest_base64("namespace", "set", "not base64!").is_err());
+    }
+
+    #[test]
+    fn display_from_str() {
+        let key = as_key!("namespace", "set", "key");
+        let s = key.to_string();
+        assert_eq!(s, format!("namespace:set:key:{}", digest!("key")));
+        let parsed: Key = s.parse().unwrap();
+        assert_eq!(parsed.namespace, "namespace");
+        assert_eq!(parsed.set_name, "set");
+        assert_eq!(parsed.user_key, None);
+        assert_eq!(parsed.digest, key.digest);
+
+        let key = as_key!("namespace", "set", "a:b");
+        let parsed: Key = key.to_string().parse().unwrap();
+        assert_eq!(parsed.set_name, "set");
+        assert_eq!(parsed.digest, key.digest);
+        let key = as_key!("namespace", "", 42);
+        assert_eq!(
+            key.to_string(),
+            format!("namespace::42:{}", key.digest_hex())
+        );
+        assert_eq!(key.to_string().parse::<Key>().unwrap().digest, key.digest);
+
+        let key = Key::key_with_digest("namespace".into(), None, None, key.digest).unwrap();
+        assert_eq!(key.to_string().parse::<Key>().unwrap(), key);
+        assert!("namespace:set".parse::<Key>().is_err());
+        assert!("namespace".parse::<Key>().is_err());
+    }
+
+    #[test]
+    fn partition_keys() {
+        let keys: Vec<Key> = Key::partition_keys("namespace", "set", 1234)
+            .unwrap()
+            .take(3)
+            .collect();
+        assert_eq!(keys.len(), 3);
+        for key in keys {
+            assert_eq!(key.partition_id(), 1234);
+            assert_eq!(Partition::new_by_key(&key).partition_id, 1234);
+        }
+        assert!(Key::partition_keys("namespace", "set", 4096).is_err());
+    }
+
     #[test]
     #[should_panic(expected = "Data type is not supported as Key value.")]
     fn unsupported_float_key() {
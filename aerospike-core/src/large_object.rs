@@ -0,0 +1,733 @@
+// Copyright 2015-2020 Aerospike, Inc.
+//
+// Portions may be licensed to Aerospike, Inc. under one or more contributor
+// license agreements.
+//
+// Licensed under the Apache License, Version 2.0 (the "License"); you may not
+// use this file except in compliance with the License. You may obtain a copy of
+// the License at http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS, WITHOUT
+// WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied. See the
+// License for the specific language governing permissions and limitations under
+// the License.
+
+use std::collections::VecDeque;
+use std::convert::TryFrom;
+use std::fmt;
+use std::future::Future;
+use std::io;
+use std::mem;
+use std::ops::Range;
+use std::pin::Pin;
+use std::task::{ready, Context, Poll};
+
+use futures::future::{self, BoxFuture};
+use futures::io::{AsyncRead, AsyncWrite};
+use futures::{AsyncReadExt, AsyncWriteExt};
+use ripemd::{Digest, Ripemd160};
+
+use crate::errors::{Error, Result};
+use crate::policy::{GenerationPolicy, LargeObjectPolicy, RecordExistsAction, WritePolicy};
+use crate::{
+    BatchDeletePolicy, BatchOperation, BatchReadPolicy, Bin, Bins, Client, Key, Record, ResultCode,
+    Value,
+};
+
+const SIZE_BIN: &str = "size";
+const CHUNKS_BIN: &str = "chunks";
+const CHUNK_SIZE_BIN: &str = "chunk_size";
+const VERSION_BIN: &str = "version";
+const CHECKSUM_BIN: &str = "checksum";
+const DATA_BIN: &str = "data";
+
+/// Manifest record of a [`LargeObject`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LargeObjectManifest {
+    /// Size of the object in bytes.
+    pub size: u64,
+
+    /// Number of chunk records.
+    pub chunks: u32,
+
+    /// Size of all chunks but the last one, in bytes.
+    pub chunk_size: u32,
+
+    /// RIPEMD-160 digest of the object.
+    pub checksum: [u8; 20],
+
+    /// Generation of the manifest record.
+    pub generation: u32,
+
+    // Random version that the chunk keys are derived from, so that a new version of the object
+    // can be written next to the current one.
+    version: i64,
+}
+
+impl LargeObjectManifest {
+    fn from_record(record: &Record) -> Result<Self> {
+        let invalid =
+            |bin: &str| Error::BadResponse(format!("Invalid large object manifest bin {bin}"));
+        let int = |bin: &str| match record.bins.get(bin) {
+            Some(Value::Int(value)) => Ok(*value),
+            _ => Err(invalid(bin)),
+        };
+        let checksum = match record.bins.get(CHECKSUM_BIN) {
+            Some(Value::Blob(checksum)) => {
+                <[u8; 20]>::try_from(checksum.as_slice()).map_err(|_| invalid(CHECKSUM_BIN))?
+            }
+            _ => return Err(invalid(CHECKSUM_BIN)),
+        };
+        Ok(LargeObjectManifest {
+            size: u64::try_from(int(SIZE_BIN)?).map_err(|_| invalid(SIZE_BIN))?,
+            chunks: u32::try_from(int(CHUNKS_BIN)?).map_err(|_| invalid(CHUNKS_BIN))?,
+            chunk_size: u32::try_from(int(CHUNK_SIZE_BIN)?).map_err(|_| invalid(CHUNK_SIZE_BIN))?,
+            checksum,
+            generation: record.generation,
+            version: int(VERSION_BIN)?,
+        })
+    }
+
+    fn bins(&self) -> Vec<Bin> {
+        vec![
+            as_bin!(SIZE_BIN, self.size),
+            as_bin!(CHUNKS_BIN, self.chunks),
+            as_bin!(CHUNK_SIZE_BIN, self.chunk_size),
+            as_bin!(VERSION_BIN, self.version),
+            as_bin!(CHECKSUM_BIN, self.checksum.to_vec()),
+        ]
+    }
+}
+
+/// Blob stored across multiple records, for values larger than the write-block-size of the
+/// namespace. Created with [`Client::large_object`].
+///
+/// The object is split into chunk records of [`LargeObjectPolicy::chunk_size`] bytes, keyed by
+/// digests derived from the key of the object, and a manifest record under the key itself that
+/// holds the size, the number of chunks and a checksum of the object. Chunks are written
+/// concurrently and read with batch requests.
+///
+/// An object is overwritten by writing the chunks of the new version next to the current ones and
+/// then swapping the manifest, so that readers see either the old or the new version. The chunks
+/// of the old version are deleted afterwards; a reader that is still streaming the old version
+/// then fails. The checksum is verified when a reader reaches the end of the object.
+///
+/// ```rust,edition2021
+/// # use aerospike::*;
+/// # #[tokio::main]
+/// # async fn main() -> Result<()> {
+/// # let hosts = std::env::var("AEROSPIKE_HOSTS").unwrap();
+/// # let client = Client::new(&ClientPolicy::default(), &hosts).await?;
+/// let key = as_key!("test", "models", "resnet-50");
+/// let object = client.large_object(&LargeObjectPolicy::default(), &key);
+/// object.put(&vec![0; 20 * 1024 * 1024]).await?;
+///
+/// if let Some(manifest) = object.manifest().await? {
+///     println!("{} bytes in {} chunks", manifest.size, manifest.chunks);
+/// }
+/// let data = object.get().await?;
+/// # Ok(())
+/// # }
+/// ```
+pub struct LargeObject<'a> {
+    client: &'a Client,
+    policy: LargeObjectPolicy,
+    key: Key,
+}
+
+impl<'a> LargeObject<'a> {
+    pub(crate) fn new(client: &'a Client, policy: &LargeObjectPolicy, key: &Key) -> Self {
+        LargeObject {
+            client,
+            policy: policy.clone(),
+            key: key.clone(),
+        }
+    }
+
+    /// Returns the key of the manifest record.
+    pub const fn key(&self) -> &Key {
+        &self.key
+    }
+
+    /// Reads the manifest of the object, or `None` if the object does not exist.
+    pub async fn manifest(&self) -> Result<Option<LargeObjectManifest>> {
+        read_manifest(self.client, &self.policy, &self.key).await
+    }
+
+    /// Reads the whole object, or `None` if the object does not exist.
+    pub async fn get(&self) -> Result<Option<Vec<u8>>> {
+        let Some(mut reader) = self.reader().await? else {
+            return Ok(None);
+        };
+        let mut data = Vec::with_capacity(usize::try_from(reader.manifest().size).unwrap_or(0));
+        reader.read_to_end(&mut data).await.map_err(from_io)?;
+        Ok(Some(data))
+    }
+
+    /// Writes the object, replacing the current version if it exists.
+    pub async fn put(&self, data: &[u8]) -> Result<()> {
+        let mut writer = self.writer();
+        writer.write_all(data).await.map_err(from_io)?;
+        writer.close().await.map_err(from_io)
+    }
+
+    /// Deletes the manifest and the chunks of the object. Returns whether the object existed.
+    pub async fn delete(&self) -> Result<bool> {
+        let Some(manifest) = self.manifest().await? else {
+            return Ok(false);
+        };
+        let existed = self
+            .client
+            .delete(&self.policy.write_policy, &self.key)
+            .await?;
+        delete_chunks(self.client, &self.policy, &self.key, &manifest).await?;
+        Ok(existed)
+    }
+
+    /// Opens a reader that streams the object, or returns `None` if the object does not exist.
+    pub async fn reader(&self) -> Result<Option<LargeObjectReader<'a>>> {
+        let manifest = self.manifest().await?;
+        Ok(manifest.map(|manifest| LargeObjectReader {
+            client: self.client,
+            policy: self.policy.clone(),
+            key: self.key.clone(),
+            manifest,
+            next_chunk: 0,
+            chunks: VecDeque::new(),
+            current: Vec::new(),
+            position: 0,
+            read: 0,
+            hasher: Ripemd160::new(),
+            inflight: None,
+            verified: false,
+        }))
+    }
+
+    /// Returns a writer for a new version of the object. The new version replaces the current one
+    /// when the writer is closed; a writer that is dropped before it is closed leaves the current
+    /// version in place.
+    pub fn writer(&self) -> LargeObjectWriter<'a> {
+        LargeObjectWriter {
+            client: self.client,
+            policy: self.policy.clone(),
+            key: self.key.clone(),
+            version: rand::random(),
+            buffer: Vec::new(),
+            pending: Vec::new(),
+            chunks: 0,
+            size: 0,
+            hasher: Ripemd160::new(),
+            inflight: None,
+            closing: false,
+            failed: false,
+        }
+    }
+}
+
+impl fmt::Debug for LargeObject<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("LargeObject")
+            .field("policy", &self.policy)
+            .field("key", &self.key)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Streaming reader of a [`LargeObject`]. Reads ahead up to
+/// [`LargeObjectPolicy::parallel_chunks`] chunks with a single batch request.
+pub struct LargeObjectReader<'a> {
+    client: &'a Client,
+    policy: LargeObjectPolicy,
+    key: Key,
+    manifest: LargeObjectManifest,
+    next_chunk: u32,
+    chunks: VecDeque<Vec<u8>>,
+    current: Vec<u8>,
+    position: usize,
+    read: u64,
+    hasher: Ripemd160,
+    inflight: Option<BoxFuture<'a, Result<Vec<Vec<u8>>>>>,
+    verified: bool,
+}
+
+impl LargeObjectReader<'_> {
+    /// Returns the manifest of the object that is read.
+    pub const fn manifest(&self) -> &LargeObjectManifest {
+        &self.manifest
+    }
+
+    fn prefetch(&mut self) {
+        let batch_size = u32::try_from(self.policy.parallel_chunks.max(1)).unwrap_or(u32::MAX);
+        if self.inflight.is_some()
+            || self.next_chunk >= self.manifest.chunks
+            || self.chunks.len() >= self.policy.parallel_chunks
+        {
+            return;
+        }
+        let start = self.next_chunk;
+        self.next_chunk = start.saturating_add(batch_size).min(self.manifest.chunks);
+        let client = self.client;
+        let policy = self.policy.clone();
+        let key = self.key.clone();
+        let version = self.manifest.version;
+        let range = start..self.next_chunk;
+        self.inflight = Some(Box::pin(async move {
+            read_chunks(client, &policy, &key, version, range).await
+        }));
+    }
+
+    fn verify(&mut self) -> Result<()> {
+        let checksum: [u8; 20] = self.hasher.finalize_reset().into();
+        if self.read != self.manifest.size || checksum != self.manifest.checksum {
+            return Err(Error::ClientError(
+                "Large object does not match the checksum of its manifest".to_string(),
+            ));
+        }
+        Ok(())
+    }
+}
+
+impl AsyncRead for LargeObjectReader<'_> {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut [u8],
+    ) -> Poll<io::Result<usize>> {
+        let this = self.get_mut();
+        loop {
+            if let Some(inflight) = this.inflight.as_mut() {
+                if let Poll::Ready(chunks) = inflight.as_mut().poll(cx) {
+                    this.inflight = None;
+                    this.chunks.extend(chunks.map_err(io::Error::other)?);
+                }
+            }
+
+            if this.position < this.current.len() {
+                let data = &this.current[this.position..];
+                let n = data.len().min(buf.len());
+                buf[..n].copy_from_slice(&data[..n]);
+                this.hasher.update(&data[..n]);
+                this.position += n;
+                this.read += n as u64;
+                return Poll::Ready(Ok(n));
+            }
+
+            if let Some(chunk) = this.chunks.pop_front() {
+                this.current = chunk;
+                this.position = 0;
+                this.prefetch();
+            } else if this.inflight.is_some() {
+                return Poll::Pending;
+            } else if this.next_chunk < this.manifest.chunks {
+                this.prefetch();
+            } else {
+                if !this.verified {
+                    this.verified = true;
+                    this.verify().map_err(io::Error::other)?;
+                }
+                return Poll::Ready(Ok(0));
+            }
+        }
+    }
+}
+
+impl fmt::Debug for LargeObjectReader<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("LargeObjectReader")
+            .field("key", &self.key)
+            .field("manifest", &self.manifest)
+            .field("read", &self.read)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Streaming writer of a [`LargeObject`]. Writes up to [`LargeObjectPolicy::parallel_chunks`]
+/// chunks concurrently.
+///
+/// The new version of the object replaces the current one when the writer is closed. The manifest
+/// is only swapped if it has not been changed by another writer since the writer was closed;
+/// otherwise closing fails with a generation error and the chunks of the new version are deleted.
+pub struct LargeObjectWriter<'a> {
+    client: &'a Client,
+    policy: LargeObjectPolicy,
+    key: Key,
+    version: i64,
+    buffer: Vec<u8>,
+    pending: Vec<(u32, Vec<u8>)>,
+    chunks: u32,
+    size: u64,
+    hasher: Ripemd160,
+    inflight: Option<BoxFuture<'a, Result<()>>>,
+    closing: bool,
+    failed: bool,
+}
+
+impl<'a> LargeObjectWriter<'a> {
+    fn poll_inflight(&mut self, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        if self.failed {
+            return Poll::Ready(Err(io::Error::other(Error::ClientError(
+                "Large object writer failed".to_string(),
+            ))));
+        }
+        if let Some(inflight) = self.inflight.as_mut() {
+            let res = ready!(inflight.as_mut().poll(cx));
+            self.inflight = None;
+            if let Err(err) = res {
+                self.failed = true;
+                return Poll::Ready(Err(io::Error::other(err)));
+            }
+        }
+        Poll::Ready(Ok(()))
+    }
+
+    fn push_chunk(&mut self) -> Result<()> {
+        let chunk = mem::take(&mut self.buffer);
+        self.pending.push((self.chunks, chunk));
+        self.chunks = self
+            .chunks
+            .checked_add(1)
+            .ok_or_else(|| Error::ClientError("Too many large object chunks".to_string()))?;
+        Ok(())
+    }
+
+    // Writes the pending chunks. If that fails, all chunks of the new version are deleted again.
+    fn start_flush(&mut self) {
+        let chunks = mem::take(&mut self.pending);
+        let client = self.client;
+        let policy = self.policy.clone();
+        let key = self.key.clone();
+        let manifest = self.manifest();
+        self.start(async move {
+            let res = write_chunks(client, &policy, &key, manifest.version, chunks).await;
+            if res.is_err() {
+                let _ = delete_chunks(client, &policy, &key, &manifest).await;
+            }
+            res
+        });
+    }
+
+    fn start(&mut self, fut: impl Future<Output = Result<()>> + Send + 'a) {
+        self.inflight = Some(Box::pin(fut));
+    }
+
+    fn manifest(&self) -> LargeObjectManifest {
+        LargeObjectManifest {
+            size: self.size,
+            chunks: self.chunks,
+            chunk_size: u32::try_from(self.policy.chunk_size).unwrap_or(u32::MAX),
+            checksum: self.hasher.clone().finalize().into(),
+            generation: 0,
+            version: self.version,
+        }
+    }
+}
+
+impl AsyncWrite for LargeObjectWriter<'_> {
+    fn poll_write(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        let this = self.get_mut();
+        ready!(this.poll_inflight(cx))?;
+        let chunk_size = this.policy.chunk_size;
+        if this.closing {
+            return Poll::Ready(Err(io::Error::other(Error::ClientError(
+                "Large object writer is closed".to_string(),
+            ))));
+        }
+        if chunk_size == 0 || u32::try_from(chunk_size).is_err() {
+            return Poll::Ready(Err(io::Error::other(Error::InvalidArgument(format!(
+                "Invalid large object chunk size {chunk_size}"
+            )))));
+        }
+
+        let n = buf.len().min(chunk_size - this.buffer.len());
+        this.buffer.extend_from_slice(&buf[..n]);
+        this.hasher.update(&buf[..n]);
+        this.size += n as u64;
+        if this.buffer.len() == chunk_size {
+            this.push_chunk().map_err(io::Error::other)?;
+            if this.pending.len() >= this.policy.parallel_chunks {
+                this.start_flush();
+            }
+        }
+        Poll::Ready(Ok(n))
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        let this = self.get_mut();
+        ready!(this.poll_inflight(cx))?;
+        if !this.pending.is_empty() {
+            this.start_flush();
+            ready!(this.poll_inflight(cx))?;
+        }
+        Poll::Ready(Ok(()))
+    }
+
+    fn poll_close(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        let this = self.get_mut();
+        loop {
+            ready!(this.poll_inflight(cx))?;
+            if this.closing {
+                return Poll::Ready(Ok(()));
+            }
+            if !this.buffer.is_empty() {
+                this.push_chunk().map_err(io::Error::other)?;
+            }
+            if !this.pending.is_empty() {
+                this.start_flush();
+                continue;
+            }
+
+            this.closing = true;
+            let client = this.client;
+            let policy = this.policy.clone();
+            let key = this.key.clone();
+            let manifest = this.manifest();
+            this.start(async move { swap_manifest(client, &policy, &key, manifest).await });
+        }
+    }
+}
+
+impl fmt::Debug for LargeObjectWriter<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("LargeObjectWriter")
+            .field("key", &self.key)
+            .field("size", &self.size)
+            .field("chunks", &self.chunks)
+            .finish_non_exhaustive()
+    }
+}
+
+// Chunk keys are derived from the digest of the object key, the version and the chunk index.
+fn chunk_key(key: &Key, version: i64, index: u32) -> Result<Key> {
+    let mut user_key = Vec::with_capacity(32);
+    user_key.extend_from_slice(&key.digest);
+    user_key.extend_from_slice(&version.to_be_bytes());
+    user_key.extend_from_slice(&index.to_be_bytes());
+    Key::new(
+        key.namespace.clone(),
+        key.set_name.clone(),
+        Value::Blob(user_key),
+    )
+}
+
+// Errors of the reader and writer are wrapped in `io::Error`s.
+fn from_io(err: io::Error) -> Error {
+    match err.downcast::<Error>() {
+        Ok(err) => err,
+        Err(err) => Error::Io(err),
+    }
+}
+
+async fn read_manifest(
+    client: &Client,
+    policy: &LargeObjectPolicy,
+    key: &Key,
+) -> Result<Option<LargeObjectManifest>> {
+    match client.get(&policy.read_policy, key, Bins::All).await {
+        Ok(record) => LargeObjectManifest::from_record(&record).map(Some),
+        Err(Error::ServerError(ResultCode::KeyNotFoundError, _, _)) => Ok(None),
+        Err(err) => Err(err),
+    }
+}
+
+async fn read_chunks(
+    client: &Client,
+    policy: &LargeObjectPolicy,
+    key: &Key,
+    version: i64,
+    range: Range<u32>,
+) -> Result<Vec<Vec<u8>>> {
+    let read_policy = BatchReadPolicy::default();
+    let ops = range
+        .map(|index| {
+            let chunk_key = chunk_key(key, version, index)?;
+            Ok(BatchOperation::read(&read_policy, chunk_key, Bins::All))
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    let results = client.batch(&policy.batch_policy, &ops).await?;
+    results
+        .into_iter()
+        .map(|result| match (result.result_code, result.record) {
+            (Some(ResultCode::Ok), Some(mut record)) => match record.bins.remove(DATA_BIN) {
+                Some(Value::Blob(data)) => Ok(data),
+                _ => Err(Error::BadResponse("Invalid large object chunk".to_string())),
+            },
+            (Some(ResultCode::KeyNotFoundError), _) => Err(Error::ClientError(
+                "Large object chunk not found; the object was overwritten or deleted".to_string(),
+            )),
+            (Some(rc), _) => Err(Error::ServerError(
+                rc,
+                false,
+                "Failed to read large object chunk".to_string(),
+            )),
+            (None, _) => Err(Error::BadResponse(
+                "Missing result for large object chunk".to_string(),
+            )),
+        })
+        .collect()
+}
+
+async fn write_chunks(
+    client: &Client,
+    policy: &LargeObjectPolicy,
+    key: &Key,
+    version: i64,
+    chunks: Vec<(u32, Vec<u8>)>,
+) -> Result<()> {
+    let mut write_policy = policy.write_policy.clone();
+    write_policy.record_exists_action = RecordExistsAction::Replace;
+    write_policy.generation_policy = GenerationPolicy::None;
+    let write_policy = &write_policy;
+
+    let writes = chunks.into_iter().map(|(index, data)| async move {
+        let chunk_key = chunk_key(key, version, index)?;
+        client
+            .put(write_policy, &chunk_key, &[as_bin!(DATA_BIN, data)])
+            .await
+    });
+    future::try_join_all(writes).await?;
+    Ok(())
+}
+
+async fn delete_chunks(
+    client: &Client,
+    policy: &LargeObjectPolicy,
+    key: &Key,
+    manifest: &LargeObjectManifest,
+) -> Result<()> {
+    if manifest.chunks == 0 {
+        return Ok(());
+    }
+    let delete_policy = BatchDeletePolicy::default();
+    let ops = (0..manifest.chunks)
+        .map(|index| {
+            let chunk_key = chunk_key(key, manifest.version, index)?;
+            Ok(BatchOperation::delete(&delete_policy, chunk_key))
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    let results = client.batch(&policy.batch_policy, &ops).await?;
+    for result in results {
+        match result.result_code {
+            Some(ResultCode::Ok | ResultCode::KeyNotFoundError) => (),
+            Some(rc) => {
+                return Err(Error::ServerError(
+                    rc,
+                    result.in_doubt,
+                    "Failed to delete large object chunk".to_string(),
+                ))
+            }
+            None => {
+                return Err(Error::BadResponse(
+                    "Missing result for large object chunk".to_string(),
+                ))
+            }
+        }
+    }
+    Ok(())
+}
+
+// Replaces the manifest, as long as it has not changed since it was read, and then deletes the
+// chunks of the previous version.
+async fn swap_manifest(
+    client: &Client,
+    policy: &LargeObjectPolicy,
+    key: &Key,
+    manifest: LargeObjectManifest,
+) -> Result<()> {
+    let previous = read_manifest(client, policy, key).await?;
+    let mut write_policy: WritePolicy = policy.write_policy.clone();
+    if let Some(ref previous) = previous {
+        write_policy.record_exists_action = RecordExistsAction::Replace;
+        write_policy.generation_policy = GenerationPolicy::ExpectGenEqual;
+        write_policy.generation = previous.generation;
+    } else {
+        write_policy.record_exists_action = RecordExistsAction::CreateOnly;
+        write_policy.generation_policy = GenerationPolicy::None;
+    }
+
+    if let Err(err) = client.put(&write_policy, key, &manifest.bins()).await {
+        // The manifest was not written, unless the result is in doubt.
+        if matches!(
+            err,
+            Error::ServerError(
+                ResultCode::GenerationError | ResultCode::KeyExistsError,
+                _,
+                _
+            )
+        ) {
+            let _ = delete_chunks(client, policy, key, &manifest).await;
+        }
+        return Err(err.chain_error("Failed to replace large object manifest"));
+    }
+
+    if let Some(previous) = previous {
+        if let Err(err) = delete_chunks(client, policy, key, &previous).await {
+            warn!("Failed to delete previous version of large object {key}: {err}");
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::{chunk_key, from_io, LargeObjectManifest};
+    use crate::errors::Error;
+    use crate::{Record, ResultCode};
+
+    #[test]
+    fn chunk_keys() {
+        let key = as_key!("test", "models", "resnet");
+        let first = chunk_key(&key, 7, 0).unwrap();
+        assert_eq!(first.namespace, "test");
+        assert_eq!(first.set_name, "models");
+        assert_ne!(first.digest, key.digest);
+        assert_ne!(first.digest, chunk_key(&key, 7, 1).unwrap().digest);
+        assert_ne!(first.digest, chunk_key(&key, 8, 0).unwrap().digest);
+        assert_eq!(first.digest, chunk_key(&key, 7, 0).unwrap().digest);
+    }
+
+    #[test]
+    fn manifest_round_trip() {
+        let manifest = LargeObjectManifest {
+            size: 9_000_000,
+            chunks: 18,
+            chunk_size: 512 * 1024,
+            checksum: [3; 20],
+            generation: 4,
+            version: -42,
+        };
+        let bins = manifest
+            .bins()
+            .into_iter()
+            .map(|bin| (bin.name, bin.value))
+            .collect();
+        let record = Record::new(None, bins, 4, 0);
+        assert_eq!(LargeObjectManifest::from_record(&record).unwrap(), manifest);
+
+        let record = Record::new(None, HashMap::new(), 1, 0);
+        assert!(LargeObjectManifest::from_record(&record).is_err());
+    }
+
+    #[test]
+    fn io_errors() {
+        let err = std::io::Error::other(Error::ServerError(
+            ResultCode::GenerationError,
+            false,
+            String::new(),
+        ));
+        assert!(matches!(
+            from_io(err),
+            Error::ServerError(ResultCode::GenerationError, _, _)
+        ));
+        let err = std::io::Error::from(std::io::ErrorKind::UnexpectedEof);
+        assert!(matches!(from_io(err), Error::Io(_)));
+    }
+}
@@ -144,6 +144,7 @@ extern crate rand;
 #[cfg(feature = "tls")]
 extern crate tokio_rustls;
 
+pub use aerospike_macro::FromBins;
 pub use batch::BatchDeletePolicy;
 pub use batch::BatchOperation;
 pub use batch::BatchReadPolicy;
@@ -152,20 +153,35 @@ pub use batch::BatchUDFPolicy;
 pub use batch::BatchWritePolicy;
 pub use bin::{Bin, Bins};
 pub use client::Client;
+pub use cluster::partition_map::{
+    NamespacePartitions, PartitionIssue, PartitionMap, PartitionProblem, PartitionRouting,
+};
 pub use cluster::version_parser::Version;
 pub use cluster::Node;
+pub use codec::{BinCodec, BinCodecs};
 pub use commands::particle_type::ParticleType;
 pub use errors::{Error, Result};
 pub use expressions::regex_flag::RegexFlag;
+#[doc(hidden)]
+pub use from_bins::{__decode_field, __field_value};
+pub use from_bins::{FromBins, FromValueView, RecordMeta};
+pub use geo::{AeroCircle, GeoMultiPolygon, GeoPoint, GeoPolygon, Geometry, ToGeoJson};
+pub use hll_sketch::HllSketch;
+pub use interceptor::{CommandContext, CommandInterceptor, CommandPolicy};
 pub use key::Key;
+pub use large_object::{LargeObject, LargeObjectManifest, LargeObjectReader, LargeObjectWriter};
+pub use multi_cluster::{ClusterStatus, MultiClusterClient};
 pub use net::Host;
 pub use net::ToHosts;
+pub use operations::OperateResults;
 pub use operations::{ListOrderType, ListPolicy, ListReturnType, ListSortFlags, ListWriteFlags};
 pub use operations::{MapPolicy, MapReturnType, MapWriteFlags, MapWriteMode};
+pub use ordered_record::{OrderedBins, OrderedRecord};
 pub use policy::{
     AdminPolicy, AuthMode, BasePolicy, BatchPolicy, ClientPolicy, CommitLevel, Concurrency,
-    ConsistencyLevel, Expiration, GenerationPolicy, Policy, QueryDuration, QueryPolicy, ReadPolicy,
-    ReadTouchTTL, RecordExistsAction, WritePolicy,
+    ConsistencyLevel, DualWrite, Expiration, GenerationPolicy, LargeObjectPolicy,
+    MultiClusterPolicy, Policy, QueryDuration, QueryPolicy, ReadPolicy, ReadTouchTTL,
+    RecordExistsAction, WritePolicy,
 };
 pub use privilege::{Privilege, PrivilegeCode};
 pub use query::{
@@ -173,9 +189,11 @@ pub use query::{
     Statement, UDFLang,
 };
 pub use record::Record;
+pub use record_view::{BinView, ListView, MapView, RecordView, ValueView};
 pub use result_code::ResultCode;
 pub use role::Role;
 pub use task::{DropIndexTask, ExecuteTask, IndexTask, RegisterTask, Task, UdfRemoveTask};
+pub use timestamp::EpochNanos;
 pub use user::User;
 pub use value::{FloatValue, Value};
 
@@ -188,21 +206,38 @@ mod bin;
 #[macro_use]
 mod key;
 mod batch;
+#[cfg(feature = "blocking")]
+pub mod blocking;
 mod client;
 mod cluster;
+pub mod codec;
 pub(crate) mod commands;
 mod common;
+#[cfg(any(feature = "config-toml", feature = "config-yaml"))]
+pub mod config;
 pub mod expressions;
+mod from_bins;
+mod geo;
+mod hll_sketch;
+mod interceptor;
+#[cfg(feature = "json")]
+pub mod json;
+mod large_object;
 mod msgpack;
+mod multi_cluster;
 mod net;
 pub mod operations;
+mod ordered_record;
 pub mod policy;
 mod privilege;
 pub mod query;
 mod record;
+mod record_view;
 mod result_code;
 mod role;
 pub mod task;
+mod timestamp;
+mod trace;
 mod user;
 
 #[cfg(test)]
@@ -19,7 +19,7 @@ use std::num::Wrapping;
 use crate::commands::buffer::Buffer;
 use crate::commands::ParticleType;
 use crate::operations::cdt::{CdtArgument, CdtOperation};
-use crate::operations::cdt_context::CdtContext;
+use crate::operations::cdt_context::{CdtContext, CtxType};
 use crate::operations::maps::MapOrder;
 use crate::value::{FloatValue, Value};
 use crate::{Error, Result};
@@ -69,12 +69,35 @@ pub fn pack_ctx_for_index(buf: &mut Option<&mut Buffer>, ctx: &[CdtContext]) ->
 
     for c in ctx {
         size += pack_integer(buf, i64::from(c.id));
-        size += pack_value(buf, &c.value)?;
+        size += pack_ctx_value(buf, c)?;
     }
 
     Ok(size)
 }
 
+// Packs the context of a path operation as a flat list of ids and values.
+pub fn pack_ctx_list(buf: &mut Option<&mut Buffer>, ctx: &[CdtContext]) -> Result<usize> {
+    let mut size = pack_array_begin(buf, ctx.len() * 2);
+    for c in ctx {
+        size += pack_integer(buf, i64::from(c.id | c.flags));
+        size += pack_ctx_value(buf, c)?;
+    }
+    Ok(size)
+}
+
+// Expression contexts hold the packed filter expression, which is written as is.
+pub fn pack_ctx_value(buf: &mut Option<&mut Buffer>, ctx: &CdtContext) -> Result<usize> {
+    match &ctx.value {
+        Value::Blob(exp) if ctx.id == CtxType::Exp as u8 => {
+            if let Some(ref mut buf) = *buf {
+                buf.write_bytes(exp);
+            }
+            Ok(exp.len())
+        }
+        value => pack_value(buf, value),
+    }
+}
+
 pub fn pack_cdt_op(
     buf: &mut Option<&mut Buffer>,
     cdt_op: &CdtOperation,
@@ -92,7 +115,7 @@ pub fn pack_cdt_op(
             } else {
                 size += pack_integer(buf, i64::from(c.id | c.flags));
             }
-            size += pack_value(buf, &c.value)?;
+            size += pack_ctx_value(buf, c)?;
         }
     }
 
@@ -157,7 +180,7 @@ pub fn pack_cdt_bit_op(
             } else {
                 size += pack_integer(buf, i64::from(c.id | c.flags));
             }
-            size += pack_value(buf, &c.value)?;
+            size += pack_ctx_value(buf, c)?;
         }
     }
 
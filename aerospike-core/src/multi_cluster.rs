@@ -0,0 +1,590 @@
+// Copyright 2015-2018 Aerospike, Inc.
+//
+// Portions may be licensed to Aerospike, Inc. under one or more contributor
+// license agreements.
+//
+// Licensed under the Apache License, Version 2.0 (the "License"); you may not
+// use this file except in compliance with the License. You may obtain a copy of
+// the License at http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS, WITHOUT
+// WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied. See the
+// License for the specific language governing permissions and limitations under
+// the License.
+
+use std::fmt;
+use std::future::Future;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Mutex, PoisonError};
+
+use aerospike_rt::time::{Duration, Instant};
+use futures::future;
+
+use crate::errors::{Error, Result};
+use crate::operations::Operation;
+use crate::policy::{DualWrite, MultiClusterPolicy, ReadPolicy, WritePolicy};
+use crate::{Bin, Bins, Client, FromBins, Key, Record, ResultCode};
+
+/// Health of one of the clusters of a [`MultiClusterClient`], as returned by
+/// [`MultiClusterClient::status`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ClusterStatus {
+    /// Name the cluster was registered with.
+    pub name: String,
+    /// Whether writes are sent to the cluster.
+    pub active: bool,
+    /// Whether reads are sent to the cluster first.
+    pub preferred_read: bool,
+    /// Whether commands are sent to the cluster. A cluster is unavailable while the client is
+    /// not connected to any of its nodes, and after `MultiClusterPolicy::failure_threshold`
+    /// consecutive failures until the recovery interval has passed.
+    pub available: bool,
+    /// Number of consecutive commands that failed with a connection, timeout or
+    /// availability error.
+    pub consecutive_failures: usize,
+}
+
+/// `MultiClusterClient` routes commands over the clients of several clusters, e.g. two data
+/// centers linked with XDR.
+///
+/// Reads are sent to the preferred read cluster, and fail over to the other clusters on
+/// connection, timeout or availability errors. Writes are sent to the active cluster, and
+/// optionally to all other clusters as well (see [`DualWrite`]). Clusters that keep failing
+/// are skipped for a recovery interval while other clusters are available.
+///
+/// Commands that are not covered by the methods of this type can be routed with
+/// [`read`](MultiClusterClient::read) and [`write`](MultiClusterClient::write).
+///
+/// # Examples
+///
+/// ```rust,edition2021
+/// # use aerospike::*;
+/// # #[tokio::main]
+/// # async fn main() -> aerospike::Result<()> {
+/// # let hosts = std::env::var("AEROSPIKE_HOSTS").unwrap_or_else(|_| "127.0.0.1:3000".to_string());
+/// let east = Client::new(&ClientPolicy::default(), &hosts).await?;
+/// let west = Client::new(&ClientPolicy::default(), &hosts).await?;
+/// let client = MultiClusterClient::new(
+///     &MultiClusterPolicy::default(),
+///     vec![("east".to_string(), east), ("west".to_string(), west)],
+/// )?;
+///
+/// let key = as_key!("test", "test", "multi");
+/// client.put(&WritePolicy::default(), &key, &[as_bin!("a", 1)]).await?;
+/// let record = client.get(&ReadPolicy::default(), &key, Bins::All).await?;
+///
+/// // Manual switchover of writes to the other data center.
+/// client.switch_active("west")?;
+/// # client.close().await?;
+/// # Ok(())
+/// # }
+/// ```
+pub struct MultiClusterClient {
+    clusters: Vec<ClusterEntry>,
+    policy: MultiClusterPolicy,
+    active: AtomicUsize,
+    preferred_read: AtomicUsize,
+}
+
+struct ClusterEntry {
+    name: String,
+    client: Client,
+    health: Health,
+}
+
+// Tracks consecutive failures of a cluster, and skips it for the recovery interval once the
+// failure threshold is reached.
+#[derive(Debug, Default)]
+struct Health {
+    consecutive_failures: AtomicUsize,
+    unavailable_until: Mutex<Option<Instant>>,
+}
+
+impl Health {
+    fn is_available(&self, now: Instant) -> bool {
+        self.unavailable_until
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .is_none_or(|until| now >= until)
+    }
+
+    fn success(&self) {
+        self.consecutive_failures.store(0, Ordering::Relaxed);
+        *self
+            .unavailable_until
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner) = None;
+    }
+
+    // Returns true if the failure made the cluster unavailable.
+    fn failure(&self, threshold: usize, recovery_interval: Duration, now: Instant) -> bool {
+        let failures = self.consecutive_failures.fetch_add(1, Ordering::Relaxed) + 1;
+        if failures < threshold {
+            return false;
+        }
+        *self
+            .unavailable_until
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner) = Some(now + recovery_interval);
+        true
+    }
+}
+
+// Errors that indicate the cluster, rather than the command, failed.
+fn is_availability_error(err: &Error) -> bool {
+    match err {
+        Error::Connection(_)
+        | Error::Timeout(_)
+        | Error::InvalidNode(_)
+        | Error::NoMoreConnections => true,
+        Error::ServerError(code, _, _) => matches!(
+            code,
+            ResultCode::Timeout
+                | ResultCode::PartitionUnavailable
+                | ResultCode::ClusterKeyMismatch
+                | ResultCode::DeviceOverload
+        ),
+        Error::Chain(_, cause) => is_availability_error(cause),
+        _ => false,
+    }
+}
+
+// Errors that guarantee a command was not applied by the cluster, e.g. because no connection
+// could be made or no node owns the key's partition. Other errors, notably timeouts, leave a
+// write in doubt, and sending it to another cluster could apply it twice.
+fn is_unsent_error(err: &Error) -> bool {
+    match err {
+        Error::Connection(_) | Error::InvalidNode(_) | Error::NoMoreConnections => true,
+        Error::ServerError(ResultCode::PartitionUnavailable, in_doubt, _) => !in_doubt,
+        Error::Chain(_, cause) => is_unsent_error(cause),
+        _ => false,
+    }
+}
+
+// Orders the clusters to send a command to: `first`, then the others in registration order,
+// with unavailable clusters moved to the end as a last resort.
+fn route(first: usize, len: usize, available: impl Fn(usize) -> bool) -> Vec<usize> {
+    let mut order: Vec<usize> = std::iter::once(first)
+        .chain((0..len).filter(|&idx| idx != first))
+        .collect();
+    order.sort_by_key(|&idx| !available(idx));
+    order
+}
+
+impl MultiClusterClient {
+    /// Creates a multi-cluster client over the clients of the named clusters. Fails if no
+    /// clusters are given, names are not unique, or the policy names an unknown cluster.
+    pub fn new(policy: &MultiClusterPolicy, clusters: Vec<(String, Client)>) -> Result<Self> {
+        if clusters.is_empty() {
+            return Err(Error::InvalidArgument(
+                "At least one cluster is required".to_string(),
+            ));
+        }
+
+        let clusters: Vec<ClusterEntry> = clusters
+            .into_iter()
+            .map(|(name, client)| ClusterEntry {
+                name,
+                client,
+                health: Health::default(),
+            })
+            .collect();
+        for (idx, cluster) in clusters.iter().enumerate() {
+            if clusters[..idx]
+                .iter()
+                .any(|other| other.name == cluster.name)
+            {
+                return Err(Error::InvalidArgument(format!(
+                    "Duplicate cluster name: {}",
+                    cluster.name
+                )));
+            }
+        }
+
+        let mut client = MultiClusterClient {
+            clusters,
+            policy: policy.clone(),
+            active: AtomicUsize::new(0),
+            preferred_read: AtomicUsize::new(0),
+        };
+        if let Some(name) = &policy.active_cluster {
+            client.active = AtomicUsize::new(client.index_of(name)?);
+        }
+        if let Some(name) = &policy.preferred_read_cluster {
+            client.preferred_read = AtomicUsize::new(client.index_of(name)?);
+        }
+        Ok(client)
+    }
+
+    fn index_of(&self, name: &str) -> Result<usize> {
+        self.clusters
+            .iter()
+            .position(|cluster| cluster.name == name)
+            .ok_or_else(|| Error::InvalidArgument(format!("Unknown cluster: {name}")))
+    }
+
+    /// Closes the clients of all clusters. Returns the first error, if any.
+    pub async fn close(&self) -> Result<()> {
+        let results =
+            future::join_all(self.clusters.iter().map(|cluster| cluster.client.close())).await;
+        results.into_iter().collect()
+    }
+
+    /// Returns the client of the named cluster.
+    pub fn cluster(&self, name: &str) -> Option<&Client> {
+        self.clusters
+            .iter()
+            .find(|cluster| cluster.name == name)
+            .map(|cluster| &cluster.client)
+    }
+
+    /// Returns the name of the cluster writes are sent to.
+    pub fn active_cluster(&self) -> &str {
+        &self.clusters[self.active.load(Ordering::Relaxed)].name
+    }
+
+    /// Returns the name of the cluster reads are sent to first.
+    pub fn preferred_read_cluster(&self) -> &str {
+        &self.clusters[self.preferred_read.load(Ordering::Relaxed)].name
+    }
+
+    /// Sends writes to the named cluster from now on, e.g. for a manual switchover between
+    /// data centers. Writes in flight are not affected.
+    pub fn switch_active(&self, name: &str) -> Result<()> {
+        let idx = self.index_of(name)?;
+        let previous = self.active.swap(idx, Ordering::Relaxed);
+        if previous != idx {
+            info!(
+                "Switched active cluster from {} to {}",
+                self.clusters[previous].name, name
+            );
+        }
+        Ok(())
+    }
+
+    /// Sends reads to the named cluster first from now on.
+    pub fn set_preferred_read_cluster(&self, name: &str) -> Result<()> {
+        let idx = self.index_of(name)?;
+        self.preferred_read.store(idx, Ordering::Relaxed);
+        Ok(())
+    }
+
+    /// Returns the health of every cluster, in the order the clusters were given to the client.
+    pub fn status(&self) -> Vec<ClusterStatus> {
+        let now = Instant::now();
+        let active = self.active.load(Ordering::Relaxed);
+        let preferred_read = self.preferred_read.load(Ordering::Relaxed);
+        self.clusters
+            .iter()
+            .enumerate()
+            .map(|(idx, cluster)| ClusterStatus {
+                name: cluster.name.clone(),
+                active: idx == active,
+                preferred_read: idx == preferred_read,
+                available: self.is_available(idx, now),
+                consecutive_failures: cluster.health.consecutive_failures.load(Ordering::Relaxed),
+            })
+            .collect()
+    }
+
+    fn is_available(&self, idx: usize, now: Instant) -> bool {
+        let cluster = &self.clusters[idx];
+        cluster.client.is_connected() && cluster.health.is_available(now)
+    }
+
+    fn route(&self, first: usize) -> Vec<usize> {
+        let now = Instant::now();
+        route(first, self.clusters.len(), |idx| {
+            self.is_available(idx, now)
+        })
+    }
+
+    fn record<T>(&self, idx: usize, res: &Result<T>) {
+        let cluster = &self.clusters[idx];
+        match res {
+            Err(err) if is_availability_error(err) => {
+                let recovery_interval =
+                    Duration::from_millis(u64::from(self.policy.recovery_interval));
+                let threshold = self.policy.failure_threshold;
+                if cluster
+                    .health
+                    .failure(threshold, recovery_interval, Instant::now())
+                {
+                    warn!("Cluster {} is unavailable: {}", cluster.name, err);
+                }
+            }
+            _ => cluster.health.success(),
+        }
+    }
+
+    // Sends the command to the clusters in order, until one of them does not fail with an error
+    // accepted by `retry`.
+    async fn failover<'a, T, F, Fut>(
+        &'a self,
+        order: Vec<usize>,
+        op: &F,
+        retry: fn(&Error) -> bool,
+    ) -> Result<T>
+    where
+        F: Fn(&'a Client) -> Fut + Sync,
+        Fut: Future<Output = Result<T>> + Send,
+        T: Send,
+    {
+        let mut last_err = None;
+        for idx in order {
+            let res = op(&self.clusters[idx].client).await;
+            self.record(idx, &res);
+            match res {
+                Err(err) if retry(&err) => last_err = Some(err),
+                res => return res,
+            }
+        }
+        Err(last_err.unwrap_or_else(|| Error::ClientError("No cluster available".to_string())))
+    }
+
+    /// Routes a read command: it is sent to the preferred read cluster, and to the next cluster
+    /// if it fails with a connection, timeout or availability error.
+    ///
+    /// `op` is called with the client of each cluster the command is sent to.
+    pub async fn read<'a, T, F, Fut>(&'a self, op: F) -> Result<T>
+    where
+        F: Fn(&'a Client) -> Fut + Sync,
+        Fut: Future<Output = Result<T>> + Send,
+        T: Send,
+    {
+        let order = self.route(self.preferred_read.load(Ordering::Relaxed));
+        self.failover(order, &op, is_availability_error).await
+    }
+
+    /// Routes a write command: it is sent to the active cluster, and to the other clusters as
+    /// defined by `MultiClusterPolicy::dual_write`. With `MultiClusterPolicy::failover_writes`,
+    /// the command is sent to the next cluster if the active one is unavailable, or fails with
+    /// an error that guarantees the command was not applied, such as a connection error. Writes
+    /// that time out may have been applied, and are not sent to another cluster.
+    ///
+    /// `op` is called with the client of each cluster the command is sent to. Returns the
+    /// active cluster's result.
+    pub async fn write<'a, T, F, Fut>(&'a self, op: F) -> Result<T>
+    where
+        F: Fn(&'a Client) -> Fut + Sync,
+        Fut: Future<Output = Result<T>> + Send,
+        T: Send,
+    {
+        let active = self.active.load(Ordering::Relaxed);
+        if self.policy.dual_write == DualWrite::Disabled {
+            let order = if self.policy.failover_writes {
+                self.route(active)
+            } else {
+                vec![active]
+            };
+            return self.failover(order, &op, is_unsent_error).await;
+        }
+
+        let op = &op;
+        let mut results: Vec<Result<T>> = future::join_all(self.clusters.iter().enumerate().map(
+            |(idx, cluster)| async move {
+                let res = op(&cluster.client).await;
+                self.record(idx, &res);
+                res
+            },
+        ))
+        .await;
+
+        for (idx, res) in results.iter().enumerate() {
+            if let Err(err) = res {
+                if idx != active {
+                    warn!(
+                        "Write to cluster {} failed: {}",
+                        self.clusters[idx].name, err
+                    );
+                }
+            }
+        }
+
+        let mut selected = active;
+        if self.policy.dual_write == DualWrite::Required {
+            if let Some(idx) = self
+                .route(active)
+                .into_iter()
+                .find(|&idx| results[idx].is_err())
+            {
+                selected = idx;
+            }
+        } else if self.policy.failover_writes
+            && matches!(&results[active], Err(err) if is_unsent_error(err))
+        {
+            if let Some(idx) = self
+                .route(active)
+                .into_iter()
+                .find(|&idx| results[idx].is_ok())
+            {
+                selected = idx;
+            }
+        }
+        results.swap_remove(selected)
+    }
+
+    /// Reads a record. See [`Client::get`].
+    pub async fn get<T>(&self, policy: &ReadPolicy, key: &Key, bins: T) -> Result<Record>
+    where
+        T: Into<Bins> + Send + Sync + 'static,
+    {
+        let bins = bins.into();
+        self.read(|client| client.get(policy, key, bins.clone()))
+            .await
+    }
+
+    /// Reads a record and decodes it into `T`. See [`Client::get_as`].
+    pub async fn get_as<T: FromBins>(
+        &self,
+        policy: &ReadPolicy,
+        key: &Key,
+        bins: Bins,
+    ) -> Result<T> {
+        self.read(|client| client.get_as::<T>(policy, key, bins.clone()))
+            .await
+    }
+
+    /// Determines if a record exists. See [`Client::exists`].
+    pub async fn exists(&self, policy: &ReadPolicy, key: &Key) -> Result<bool> {
+        self.read(|client| client.exists(policy, key)).await
+    }
+
+    /// Writes record bin(s). See [`Client::put`].
+    pub async fn put(&self, policy: &WritePolicy, key: &Key, bins: &[Bin]) -> Result<()> {
+        self.write(|client| client.put(policy, key, bins)).await
+    }
+
+    /// Adds integer bin values to existing record bin values. See [`Client::add`].
+    pub async fn add(&self, policy: &WritePolicy, key: &Key, bins: &[Bin]) -> Result<()> {
+        self.write(|client| client.add(policy, key, bins)).await
+    }
+
+    /// Appends bin string values to existing record bin values. See [`Client::append`].
+    pub async fn append(&self, policy: &WritePolicy, key: &Key, bins: &[Bin]) -> Result<()> {
+        self.write(|client| client.append(policy, key, bins)).await
+    }
+
+    /// Prepends bin string values to existing record bin values. See [`Client::prepend`].
+    pub async fn prepend(&self, policy: &WritePolicy, key: &Key, bins: &[Bin]) -> Result<()> {
+        self.write(|client| client.prepend(policy, key, bins)).await
+    }
+
+    /// Deletes a record. See [`Client::delete`].
+    pub async fn delete(&self, policy: &WritePolicy, key: &Key) -> Result<bool> {
+        self.write(|client| client.delete(policy, key)).await
+    }
+
+    /// Resets a record's time to expiration. See [`Client::touch`].
+    pub async fn touch(&self, policy: &WritePolicy, key: &Key) -> Result<()> {
+        self.write(|client| client.touch(policy, key)).await
+    }
+
+    /// Performs multiple read/write operations on a single key in one request, routed as a
+    /// write. See [`Client::operate`].
+    pub async fn operate(
+        &self,
+        policy: &WritePolicy,
+        key: &Key,
+        ops: &[Operation],
+    ) -> Result<Record> {
+        self.write(|client| client.operate(policy, key, ops)).await
+    }
+}
+
+impl fmt::Debug for MultiClusterClient {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("MultiClusterClient")
+            .field("clusters", &self.status())
+            .field("policy", &self.policy)
+            .finish_non_exhaustive()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use aerospike_rt::time::{Duration, Instant};
+
+    use super::{is_availability_error, is_unsent_error, route, Health};
+    use crate::{Error, ResultCode};
+
+    #[test]
+    fn route_order() {
+        assert_eq!(route(0, 3, |_| true), vec![0, 1, 2]);
+        assert_eq!(route(2, 3, |_| true), vec![2, 0, 1]);
+        assert_eq!(route(1, 3, |idx| idx != 1), vec![0, 2, 1]);
+        assert_eq!(route(0, 3, |_| false), vec![0, 1, 2]);
+    }
+
+    #[test]
+    fn health() {
+        let health = Health::default();
+        let now = Instant::now();
+        let recovery = Duration::from_secs(10);
+
+        assert!(!health.failure(3, recovery, now));
+        assert!(!health.failure(3, recovery, now));
+        assert!(health.is_available(now));
+        assert!(health.failure(3, recovery, now));
+        assert!(!health.is_available(now));
+        assert!(health.is_available(now + recovery));
+
+        // another failure after the recovery interval keeps the cluster unavailable
+        assert!(health.failure(3, recovery, now + recovery));
+        assert!(!health.is_available(now + recovery));
+
+        health.success();
+        assert!(health.is_available(now));
+        assert!(!health.failure(3, recovery, now));
+    }
+
+    #[test]
+    fn availability_errors() {
+        assert!(is_availability_error(&Error::Connection("refused".into())));
+        assert!(is_availability_error(&Error::Timeout("Timeout".into())));
+        assert!(is_availability_error(
+            &Error::Timeout("Timeout".into()).chain_error("Failed to read")
+        ));
+        assert!(is_availability_error(&Error::ServerError(
+            ResultCode::PartitionUnavailable,
+            false,
+            String::new()
+        )));
+        assert!(!is_availability_error(&Error::ServerError(
+            ResultCode::KeyNotFoundError,
+            false,
+            String::new()
+        )));
+        assert!(!is_availability_error(&Error::InvalidArgument(
+            String::new()
+        )));
+    }
+
+    #[test]
+    fn unsent_errors() {
+        assert!(is_unsent_error(&Error::Connection("refused".into())));
+        assert!(is_unsent_error(&Error::InvalidNode("no node".into())));
+        assert!(is_unsent_error(
+            &Error::NoMoreConnections.chain_error("Failed to get a connection")
+        ));
+        assert!(is_unsent_error(&Error::ServerError(
+            ResultCode::PartitionUnavailable,
+            false,
+            String::new()
+        )));
+
+        // timed out writes are in doubt
+        assert!(!is_unsent_error(&Error::Timeout("Timeout".into())));
+        assert!(!is_unsent_error(&Error::ServerError(
+            ResultCode::Timeout,
+            true,
+            String::new()
+        )));
+        assert!(!is_unsent_error(&Error::ServerError(
+            ResultCode::PartitionUnavailable,
+            true,
+            String::new()
+        )));
+    }
+}
@@ -87,6 +87,8 @@ pub struct Connection {
     pub(crate) conn: Netsocket,
 
     bytes_read: usize,
+    // bytes read before the last reset of `bytes_read`
+    bytes_received: usize,
 
     pub buffer: Buffer,
 
@@ -153,6 +155,7 @@ impl Connection {
             addr,
             buffer: Buffer::new(policy.buffer_reclaim_threshold),
             bytes_read: 0,
+            bytes_received: 0,
             conn: stream,
             socket_timeout: policy.timeout().as_millis() as u32,
             timeout_delay: 0,
@@ -186,6 +189,7 @@ impl Connection {
             addr: addr.into(),
             buffer: Buffer::new(policy.buffer_reclaim_threshold),
             bytes_read: 0,
+            bytes_received: 0,
             conn: stream,
             socket_timeout: policy.timeout().as_millis() as u32,
             timeout_delay: 0,
@@ -252,11 +256,13 @@ impl Connection {
 
     pub(crate) const fn set_state(&mut self, state: ConnectionState) {
         self.state = state;
+        self.bytes_received += self.bytes_read;
         self.bytes_read = 0;
     }
 
     pub(crate) const fn reset_state(&mut self) {
         self.state = ConnectionState::Ready;
+        self.bytes_received += self.bytes_read;
         self.bytes_read = 0;
     }
 
@@ -303,7 +309,7 @@ impl Connection {
     }
 
     // This function validates the message header.
-    pub(crate) fn validate_header(&self, header: u64) -> Result<()> {
+    pub(crate) fn validate_header(header: u64) -> Result<()> {
         let msg_version = (header & 0xFF00_0000_0000_0000) >> 56;
         if msg_version != 2 {
             return Err(Error::ClientError(format!(
@@ -329,7 +335,7 @@ impl Connection {
         self.set_state(ConnectionState::Ready);
 
         let proto = self.buffer.read_u64(Some(0));
-        self.validate_header(proto)?;
+        Self::validate_header(proto)?;
 
         Ok(res)
     }
@@ -491,6 +497,7 @@ impl Connection {
     }
 
     pub const fn bookmark(&mut self) {
+        self.bytes_received += self.bytes_read;
         self.bytes_read = 0;
     }
 
@@ -498,6 +505,11 @@ impl Connection {
         self.bytes_read
     }
 
+    // Total number of bytes read from the socket since the connection was opened.
+    pub(crate) const fn bytes_received(&self) -> usize {
+        self.bytes_received + self.bytes_read
+    }
+
     pub(crate) const fn should_attempt_recovery(&self) -> bool {
         self.can_recover_connection && self.timeout_delay > 0
     }
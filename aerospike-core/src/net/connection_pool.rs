@@ -14,8 +14,11 @@
 // the License.
 
 use std::ops::{Deref, DerefMut, Drop};
+use std::sync::atomic::{AtomicUsize, Ordering};
 use std::sync::Arc;
 
+use hazarc::AtomicArc;
+
 use crate::errors::{Error, Result};
 use crate::net::{Connection, ConnectionState, Host};
 use crate::policy::ClientPolicy;
@@ -28,9 +31,9 @@ struct SharedQueue {
     // Total number of connections associated with the queue.
     // These connections may be in flight and not in the queue.
     reserved: Mutex<usize>,
-    capacity: usize,
+    capacity: AtomicUsize,
     host: Host,
-    policy: ClientPolicy,
+    policy: AtomicArc<ClientPolicy>,
     hashed_pass: Option<String>,
 }
 
@@ -44,14 +47,21 @@ impl Queue {
         let shared = SharedQueue {
             connections: Mutex::new(VecDeque::with_capacity(capacity)),
             reserved: Mutex::new(0),
-            capacity,
+            capacity: AtomicUsize::new(capacity),
             host,
-            policy,
+            policy: AtomicArc::from(policy),
             hashed_pass,
         };
         Queue(Arc::new(shared))
     }
 
+    /// Changes the capacity and the `ClientPolicy` used for new connections. Connections
+    /// in excess of a reduced capacity are closed as they are returned to the queue.
+    pub fn set_policy(&self, capacity: usize, policy: Arc<ClientPolicy>) {
+        self.0.capacity.store(capacity, Ordering::Relaxed);
+        self.0.policy.store(policy);
+    }
+
     /// Checks if the queue has capacity for another connection.
     /// If so, it will increase the reserved value by one and return true.
     /// Otherwise, return false.
@@ -61,7 +71,7 @@ impl Queue {
             .reserved
             .lock()
             .unwrap_or_else(std::sync::PoisonError::into_inner);
-        if *reserved < self.0.capacity {
+        if *reserved < self.0.capacity.load(Ordering::Relaxed) {
             *reserved += 1;
             drop(reserved);
             return true;
@@ -91,9 +101,10 @@ impl Queue {
     /// Creates a new connection based on the queue's `ClientPolicy`.
     /// It does not check for the capacity of the queue.
     pub async fn make_conn(&self) -> Result<Connection> {
+        let policy = self.0.policy.load_owned();
         let conn = aerospike_rt::timeout(
-            self.0.policy.timeout(),
-            Connection::new(&self.0.host, &self.0.policy, self.0.hashed_pass.as_ref()),
+            policy.timeout(),
+            Connection::new(&self.0.host, &policy, self.0.hashed_pass.as_ref()),
         )
         .await;
 
@@ -141,10 +152,17 @@ impl Queue {
             .connections
             .lock()
             .unwrap_or_else(std::sync::PoisonError::into_inner);
-        if conn.state == ConnectionState::Ready && connections.len() < self.0.capacity {
+        if conn.state != ConnectionState::Ready {
+            // let it drop
+            return;
+        }
+        if connections.len() < self.0.capacity.load(Ordering::Relaxed) {
             connections.push_back(conn);
+        } else {
+            // the capacity was reduced, let it drop and release its reservation
+            drop(connections);
+            self.reduce_capacity();
         }
-        // otherwise let it drop
     }
 
     /// Removes all the connections from the queue.
@@ -195,19 +213,26 @@ impl ConnectionPool {
         host: Host,
         policy: ClientPolicy,
     ) -> Vec<Queue> {
-        let num_queues = usize::from(num_queues);
+        ConnectionPool::queue_capacities(num_conns, usize::from(num_queues))
+            .map(|capacity| Queue::with_capacity(capacity, host.clone(), policy.clone()))
+            .collect()
+    }
+
+    // Splits the connections evenly between the queues.
+    fn queue_capacities(num_conns: usize, num_queues: usize) -> impl Iterator<Item = usize> {
         let max = num_conns / num_queues;
-        let mut rem = num_conns % num_queues;
-        let mut queues = Vec::with_capacity(num_queues);
-        for _ in 0..num_queues {
-            let mut capacity = max;
-            if rem > 0 {
-                capacity += 1;
-                rem -= 1;
-            }
-            queues.push(Queue::with_capacity(capacity, host.clone(), policy.clone()));
+        let rem = num_conns % num_queues;
+        (0..num_queues).map(move |i| if i < rem { max + 1 } else { max })
+    }
+
+    /// Applies a changed `ClientPolicy` to the internal queues, splitting its
+    /// `max_conns_per_node` between them. The number of queues does not change.
+    pub fn set_policy(&self, policy: &Arc<ClientPolicy>) {
+        let capacities =
+            ConnectionPool::queue_capacities(policy.max_conns_per_node, self.queues.len());
+        for (queue, capacity) in self.queues.iter().zip(capacities) {
+            queue.set_policy(capacity, policy.clone());
         }
-        queues
     }
 
     /// Get a connection from one of the internal pools.
@@ -626,4 +651,35 @@ mod tests {
         assert_eq!(p.queues[1].reserved(), 1);
         assert_eq!(p.queues[1].num_conns(), 1);
     }
+
+    #[aerospike_macro::test]
+    async fn set_policy() {
+        let host = Host::new("some-url", 30000);
+        let mut policy = ClientPolicy {
+            max_conns_per_node: 6,
+            conn_pools_per_node: 2,
+            ..ClientPolicy::default()
+        };
+        let p = ConnectionPool::new(host.clone(), policy.clone());
+
+        let mut conns = vec![];
+        for hint in 0..6 {
+            conns.push(p.make_conn(hint).await.unwrap());
+        }
+        assert!(p.make_conn(0).await.is_err());
+
+        // shrink the pool while all connections are in flight
+        policy.max_conns_per_node = 3;
+        p.set_policy(&std::sync::Arc::new(policy));
+        assert_eq!(p.queues[0].reserved(), 3);
+
+        // the connections in excess are closed as they come back
+        drop(conns);
+        assert_eq!(p.num_conns(), 3);
+        assert_eq!(p.queues[0].reserved(), 2);
+        assert_eq!(p.queues[0].num_conns(), 2);
+        assert_eq!(p.queues[1].reserved(), 1);
+        assert_eq!(p.queues[1].num_conns(), 1);
+        assert!(p.make_conn(0).await.is_err());
+    }
 }
@@ -0,0 +1,250 @@
+// Copyright 2015-2026 Aerospike, Inc.
+//
+// Portions may be licensed to Aerospike, Inc. under one or more contributor
+// license agreements.
+//
+// Licensed under the Apache License, Version 2.0 (the "License"); you may not
+// use this file except in compliance with the License. You may obtain a copy of
+// the License at http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS, WITHOUT
+// WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied. See the
+// License for the specific language governing permissions and limitations under
+// the License.
+
+// Seed discovery from DNS SRV records. The system resolver has no SRV lookup, so the records
+// are looked up with hickory-resolver, using the system's resolver configuration. This requires
+// the `dns-srv` feature; without it, SRV seeds fail to resolve and are skipped.
+
+use std::time::Duration;
+
+#[cfg(feature = "blocking")]
+use self::resolver::lookup_srv;
+use self::resolver::lookup_srv_async;
+use crate::errors::Result;
+use crate::net::Host;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SrvRecord {
+    pub priority: u16,
+    pub weight: u16,
+    pub port: u16,
+    pub target: String,
+}
+
+// SRV names start with an underscore, e.g. `_aerospike._tcp.example.com`, which host names
+// cannot.
+pub fn is_srv_name(name: &str) -> bool {
+    name.starts_with('_')
+}
+
+// Replaces the seeds with SRV names by the targets of their SRV records. Other seeds are kept
+// as they are; their A/AAAA records are resolved when the seed is validated. Each SRV lookup
+// takes at most `timeout`.
+#[cfg(feature = "blocking")]
+pub fn resolve_seeds(seeds: &[Host], timeout: Duration) -> Vec<Host> {
+    let mut hosts = Vec::with_capacity(seeds.len());
+    for seed in seeds {
+        if is_srv_name(&seed.name) {
+            add_srv_targets(&mut hosts, seed, lookup_srv(&seed.name, timeout));
+        } else {
+            hosts.push(seed.clone());
+        }
+    }
+    hosts
+}
+
+// Same as `resolve_seeds`, but does not block the async runtime.
+pub async fn resolve_seeds_async(seeds: Vec<Host>, timeout: Duration) -> Vec<Host> {
+    let mut hosts = Vec::with_capacity(seeds.len());
+    for seed in seeds {
+        if is_srv_name(&seed.name) {
+            let records = lookup_srv_async(&seed.name, timeout).await;
+            add_srv_targets(&mut hosts, &seed, records);
+        } else {
+            hosts.push(seed);
+        }
+    }
+    hosts
+}
+
+fn add_srv_targets(hosts: &mut Vec<Host>, seed: &Host, records: Result<Vec<SrvRecord>>) {
+    match records {
+        Ok(records) => {
+            debug!("Resolved SRV records for seed {}: {:?}", seed.name, records);
+            hosts.extend(records.into_iter().map(|record| Host {
+                name: record.target,
+                tls_name: seed.tls_name.clone(),
+                port: record.port,
+            }));
+        }
+        Err(err) => {
+            log_error_chain!(err, "Failed to look up SRV records of seed {}", seed.name);
+        }
+    }
+}
+
+#[cfg(feature = "dns-srv")]
+mod resolver {
+    use std::time::Duration;
+
+    use hickory_resolver::config::{ResolverConfig, ResolverOpts};
+    use hickory_resolver::error::{ResolveErrorKind, ResolveResult};
+    use hickory_resolver::lookup::SrvLookup;
+    use hickory_resolver::system_conf::read_system_conf;
+
+    use super::SrvRecord;
+    use crate::errors::{Error, Result};
+
+    // Orders SRV records by priority, and by descending weight for the same priority.
+    pub fn sort_records(records: &mut [SrvRecord]) {
+        records.sort_by(|a, b| {
+            a.priority
+                .cmp(&b.priority)
+                .then_with(|| b.weight.cmp(&a.weight))
+        });
+    }
+
+    // The system's resolver configuration, with queries limited to `timeout`.
+    fn system_conf(timeout: Duration) -> Result<(ResolverConfig, ResolverOpts)> {
+        let (config, mut opts) = read_system_conf().map_err(|err| {
+            Error::Connection(format!(
+                "Failed to read the DNS resolver configuration: {err}"
+            ))
+        })?;
+        opts.timeout = opts.timeout.min(timeout);
+        Ok((config, opts))
+    }
+
+    fn srv_records(name: &str, lookup: ResolveResult<SrvLookup>) -> Result<Vec<SrvRecord>> {
+        let lookup = match lookup {
+            Ok(lookup) => lookup,
+            Err(err) if matches!(err.kind(), ResolveErrorKind::NoRecordsFound { .. }) => {
+                return Ok(vec![]);
+            }
+            Err(err) => {
+                return Err(Error::Connection(format!(
+                    "SRV lookup of {name} failed: {err}"
+                )));
+            }
+        };
+        let mut records: Vec<SrvRecord> = lookup
+            .iter()
+            .map(|srv| SrvRecord {
+                priority: srv.priority(),
+                weight: srv.weight(),
+                port: srv.port(),
+                target: srv.target().to_utf8().trim_end_matches('.').to_string(),
+            })
+            .collect();
+        sort_records(&mut records);
+        Ok(records)
+    }
+
+    // Looks up the SRV records of a name on the calling thread.
+    #[cfg(any(feature = "blocking", feature = "rt-async-std"))]
+    pub fn lookup_srv(name: &str, timeout: Duration) -> Result<Vec<SrvRecord>> {
+        let (config, opts) = system_conf(timeout)?;
+        let resolver = hickory_resolver::Resolver::new(config, opts).map_err(|err| {
+            Error::Connection(format!("Failed to create the DNS resolver: {err}"))
+        })?;
+        srv_records(name, resolver.srv_lookup(name))
+    }
+
+    #[cfg(all(feature = "rt-tokio", not(feature = "rt-async-std")))]
+    pub async fn lookup_srv_async(name: &str, timeout: Duration) -> Result<Vec<SrvRecord>> {
+        let (config, opts) = system_conf(timeout)?;
+        let resolver = hickory_resolver::TokioAsyncResolver::tokio(config, opts);
+        let lookup = aerospike_rt::timeout(timeout, resolver.srv_lookup(name))
+            .await
+            .map_err(|_| Error::Timeout(format!("SRV lookup of {name} timed out")))?;
+        srv_records(name, lookup)
+    }
+
+    // The async resolver of hickory needs a tokio runtime, so the lookup runs on a blocking task.
+    #[cfg(all(feature = "rt-async-std", not(feature = "rt-tokio")))]
+    pub async fn lookup_srv_async(name: &str, timeout: Duration) -> Result<Vec<SrvRecord>> {
+        let name = name.to_string();
+        aerospike_rt::task::spawn_blocking(move || lookup_srv(&name, timeout)).await
+    }
+}
+
+#[cfg(not(feature = "dns-srv"))]
+mod resolver {
+    use std::time::Duration;
+
+    use super::SrvRecord;
+    use crate::errors::{Error, Result};
+
+    pub fn lookup_srv(name: &str, _timeout: Duration) -> Result<Vec<SrvRecord>> {
+        Err(Error::InvalidArgument(format!(
+            "Looking up the SRV records of {name} requires the dns-srv feature"
+        )))
+    }
+
+    pub async fn lookup_srv_async(name: &str, timeout: Duration) -> Result<Vec<SrvRecord>> {
+        lookup_srv(name, timeout)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::resolve_seeds_async;
+    use crate::net::Host;
+
+    #[aerospike_macro::test]
+    async fn plain_seeds() {
+        let seeds = vec![
+            Host::new("db1.example.com", 3000),
+            Host::new("10.0.0.1", 3100),
+        ];
+        assert_eq!(
+            resolve_seeds_async(seeds.clone(), Duration::from_secs(1)).await,
+            seeds
+        );
+    }
+
+    #[aerospike_macro::test]
+    #[cfg(not(feature = "dns-srv"))]
+    async fn srv_seeds_require_feature() {
+        let seeds = vec![
+            Host::new("_aerospike._tcp.example.com", 3000),
+            Host::new("db1.example.com", 3000),
+        ];
+        assert_eq!(
+            resolve_seeds_async(seeds, Duration::from_secs(1)).await,
+            vec![Host::new("db1.example.com", 3000)]
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "dns-srv")]
+    fn record_order() {
+        use super::resolver::sort_records;
+        use super::SrvRecord;
+
+        let record = |priority, weight, port, target: &str| SrvRecord {
+            priority,
+            weight,
+            port,
+            target: target.to_string(),
+        };
+        let mut records = vec![
+            record(20, 0, 3000, "db3.example.com"),
+            record(10, 5, 3001, "db1.example.com"),
+            record(10, 10, 3002, "db2.example.com"),
+        ];
+        sort_records(&mut records);
+        assert_eq!(
+            records,
+            vec![
+                record(10, 10, 3002, "db2.example.com"),
+                record(10, 5, 3001, "db1.example.com"),
+                record(20, 0, 3000, "db3.example.com"),
+            ]
+        );
+    }
+}
@@ -23,5 +23,6 @@ pub use self::host::ToHosts;
 
 mod connection;
 mod connection_pool;
+pub mod dns;
 pub mod host;
 mod parser;
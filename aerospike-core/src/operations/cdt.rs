@@ -19,10 +19,88 @@ use std::sync::Arc;
 
 use crate::commands::buffer::Buffer;
 use crate::commands::ParticleType;
+use crate::expressions::Expression;
+use crate::msgpack::encoder::{pack_array_begin, pack_ctx_list, pack_integer};
 pub use crate::operations::cdt_context::CdtContext;
+use crate::operations::{Operation, OperationBin, OperationData, OperationType};
 use crate::Result;
 use crate::Value;
 
+// Operation code of path operations, shared by lists and maps.
+pub const CDT_SELECT: i64 = 0xfe;
+// Flag that makes a path operation apply its modify expression to the selected elements.
+pub const SELECT_APPLY: i64 = 4;
+
+/// Flags for [`select_by_path`], which determine what is returned for the selected elements.
+#[derive(Debug, Clone, Copy)]
+pub enum SelectFlags {
+    /// Return the bin with everything but the selected elements and their parents removed.
+    MatchingTree = 0,
+    /// Return a list of the selected elements.
+    Values = 1,
+    /// Return a list of the map keys of the selected elements.
+    MapKeys = 2,
+    /// Return a list of the map keys and values of the selected elements.
+    MapKeyValues = 3,
+    /// Do not fail if a path step does not apply to an element, e.g. a map step on a list.
+    NoFail = 0x10,
+}
+
+/// Something that can be resolved into a set of `SelectFlags`. Either a single `SelectFlags`,
+/// or a collection of them.
+pub trait ToSelectFlagsBitmask {
+    /// Convert to an i64 bitmask
+    fn to_bitmask(self) -> i64;
+}
+
+impl ToSelectFlagsBitmask for SelectFlags {
+    fn to_bitmask(self) -> i64 {
+        self as i64
+    }
+}
+
+impl<T: IntoIterator<Item = SelectFlags>> ToSelectFlagsBitmask for T {
+    fn to_bitmask(self) -> i64 {
+        let mut out = 0;
+        for val in self {
+            out |= val.to_bitmask();
+        }
+        out
+    }
+}
+
+/// Flags for [`modify_by_path`].
+#[derive(Debug, Clone, Copy)]
+pub enum ModifyFlags {
+    /// Default.
+    Default = 0,
+    /// Do not fail if a path step does not apply to an element, e.g. a map step on a list.
+    NoFail = 0x10,
+}
+
+/// Something that can be resolved into a set of `ModifyFlags`. Either a single `ModifyFlags`,
+/// or a collection of them.
+pub trait ToModifyFlagsBitmask {
+    /// Convert to an i64 bitmask
+    fn to_bitmask(self) -> i64;
+}
+
+impl ToModifyFlagsBitmask for ModifyFlags {
+    fn to_bitmask(self) -> i64 {
+        self as i64
+    }
+}
+
+impl<T: IntoIterator<Item = ModifyFlags>> ToModifyFlagsBitmask for T {
+    fn to_bitmask(self) -> i64 {
+        let mut out = 0;
+        for val in self {
+            out |= val.to_bitmask();
+        }
+        out
+    }
+}
+
 /// Argument value for CDT (list/map) operations in the wire protocol.
 #[derive(Debug, Clone)]
 pub enum CdtArgument {
@@ -90,3 +168,168 @@ impl fmt::Debug for CdtOperation {
         fmt::Debug::fmt(&CdtOperation { op, args }, f)
     }
 }
+
+/// Creates a path select operation.
+///
+/// Server selects the elements of the list or map bin that match the context, which may contain
+/// [`ctx_all_children`] and [`ctx_all_children_with_filter`] steps at any level, and returns them
+/// as specified by the flags.
+///
+/// Requires server version 8.1+.
+///
+/// [`ctx_all_children`]: crate::operations::cdt_context::ctx_all_children
+/// [`ctx_all_children_with_filter`]: crate::operations::cdt_context::ctx_all_children_with_filter
+pub fn select_by_path<TSF: ToSelectFlagsBitmask>(
+    bin: &str,
+    flags: TSF,
+    ctx: &[CdtContext],
+) -> Operation {
+    path_operation(OperationType::CdtRead, bin, flags.to_bitmask(), None, ctx)
+}
+
+/// Creates a path modify operation.
+///
+/// Server selects the elements of the list or map bin that match the context and replaces each
+/// of them with the result of the modify expression, in which the element is available through
+/// [`loop_var`](crate::expressions::loop_var).
+///
+/// Requires server version 8.1+.
+pub fn modify_by_path<TMF: ToModifyFlagsBitmask>(
+    bin: &str,
+    flags: TMF,
+    modify_exp: Expression,
+    ctx: &[CdtContext],
+) -> Operation {
+    let flags = flags.to_bitmask() | SELECT_APPLY;
+    path_operation(OperationType::CdtWrite, bin, flags, Some(modify_exp), ctx)
+}
+
+fn path_operation(
+    op: OperationType,
+    bin: &str,
+    flags: i64,
+    modify_exp: Option<Expression>,
+    ctx: &[CdtContext],
+) -> Operation {
+    let cdt_op = CdtOperation {
+        op: CDT_SELECT as u8,
+        encoder: Arc::new(move |buf, cdt_op, ctx| {
+            pack_path_op(buf, cdt_op, ctx, flags, modify_exp.as_ref())
+        }),
+        args: vec![CdtArgument::Int(flags)],
+    };
+    Operation {
+        op,
+        ctx: ctx.to_vec(),
+        bin: OperationBin::Name(bin.into()),
+        data: OperationData::CdtListOp(cdt_op),
+    }
+}
+
+// Path operations are packed as `[op, [ctx_id, ctx_value, ...], flags, modify_exp?]`.
+fn pack_path_op(
+    buf: &mut Option<&mut Buffer>,
+    cdt_op: &CdtOperation,
+    ctx: &[CdtContext],
+    flags: i64,
+    modify_exp: Option<&Expression>,
+) -> Result<usize> {
+    let mut size = pack_array_begin(buf, if modify_exp.is_some() { 4 } else { 3 });
+    size += pack_integer(buf, i64::from(cdt_op.op));
+    size += pack_ctx_list(buf, ctx)?;
+    size += pack_integer(buf, flags);
+    if let Some(exp) = modify_exp {
+        size += exp.pack(buf)?;
+    }
+    Ok(size)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{modify_by_path, select_by_path, ModifyFlags, SelectFlags};
+    use crate::commands::buffer::Buffer;
+    use crate::expressions::{gt, int_val, loop_var, ExpType, LoopVarPart};
+    use crate::operations::cdt_context::{
+        ctx_all_children, ctx_all_children_with_filter, ctx_list_index, ctx_map_key,
+    };
+    use crate::operations::{Operation, OperationData, OperationType};
+    use crate::Value;
+
+    // Packs the CDT payload of an operation, checking that the estimated size is exact.
+    fn pack(op: &Operation) -> Vec<u8> {
+        let OperationData::CdtListOp(cdt_op) = &op.data else {
+            panic!("not a CDT operation: {:?}", op);
+        };
+        let size = cdt_op.estimate_size(&op.ctx).unwrap();
+        let mut buf = Buffer::new(0);
+        buf.resize_buffer(size).unwrap();
+        assert_eq!(cdt_op.write_to(&mut buf, &op.ctx).unwrap(), size);
+        buf.data_buffer
+    }
+
+    #[test]
+    fn select_by_path_encoding() {
+        let op = select_by_path(
+            "bin",
+            SelectFlags::Values,
+            &[ctx_map_key(Value::from("a")), ctx_all_children()],
+        );
+        assert!(matches!(op.op, OperationType::CdtRead));
+        assert_eq!(
+            pack(&op),
+            vec![
+                0x93, // [op, ctx, flags]
+                0xcc, 0xfe, // select
+                0x94, 0x22, 0xa2, 0x03, b'a', 0x04, 0xc3, // [map key "a", all children]
+                0x01, // values
+            ]
+        );
+
+        let op = select_by_path(
+            "bin",
+            [SelectFlags::MapKeys, SelectFlags::NoFail],
+            &[ctx_list_index(-1)],
+        );
+        assert_eq!(pack(&op), vec![0x93, 0xcc, 0xfe, 0x92, 0x10, 0xff, 0x12]);
+    }
+
+    #[test]
+    fn select_by_path_with_filter_encoding() {
+        let filter = gt(loop_var(ExpType::INT, LoopVarPart::Value), int_val(10));
+        let op = select_by_path(
+            "bin",
+            SelectFlags::MatchingTree,
+            &[ctx_all_children_with_filter(&filter).unwrap()],
+        );
+
+        // the filter is written as is, without a blob header
+        let mut expected = vec![0x93, 0xcc, 0xfe, 0x92, 0x04];
+        expected.extend_from_slice(&filter.to_bytes().unwrap());
+        expected.push(0x00);
+        assert_eq!(pack(&op), expected);
+    }
+
+    #[test]
+    fn modify_by_path_encoding() {
+        let op = modify_by_path(
+            "bin",
+            ModifyFlags::Default,
+            int_val(5),
+            &[ctx_map_key(Value::from("a")), ctx_all_children()],
+        );
+        assert!(matches!(op.op, OperationType::CdtWrite));
+        assert_eq!(
+            pack(&op),
+            vec![
+                0x94, // [op, ctx, flags, modify_exp]
+                0xcc, 0xfe, // select
+                0x94, 0x22, 0xa2, 0x03, b'a', 0x04, 0xc3, // [map key "a", all children]
+                0x04, // apply
+                0x05, // modify expression
+            ]
+        );
+
+        let op = modify_by_path("bin", [ModifyFlags::NoFail], int_val(5), &[]);
+        assert_eq!(pack(&op), vec![0x94, 0xcc, 0xfe, 0x90, 0x14, 0x05]);
+    }
+}
@@ -17,6 +17,7 @@ use base64::{engine::general_purpose::STANDARD as BASE64, Engine as _};
 
 use crate::commands::buffer::Buffer;
 use crate::errors::Result;
+use crate::expressions::Expression;
 use crate::msgpack::encoder::pack_ctx_for_index;
 use crate::operations::lists::{list_order_flag, ListOrderType};
 use crate::operations::MapOrder;
@@ -26,6 +27,7 @@ use crate::Value;
 pub(crate) const DEFAULT_CTX: Vec<CdtContext> = vec![];
 
 pub(crate) enum CtxType {
+    Exp = 0x04,
     ListIndex = 0x10,
     ListRank = 0x11,
     ListValue = 0x13,
@@ -162,3 +164,30 @@ pub const fn ctx_map_value(key: Value) -> CdtContext {
         value: key,
     }
 }
+
+/// Selects all children of the current list or map.
+///
+/// Only valid in the context of [`select_by_path`](crate::operations::select_by_path) and
+/// [`modify_by_path`](crate::operations::modify_by_path) and their expression counterparts.
+pub fn ctx_all_children() -> CdtContext {
+    CdtContext {
+        id: CtxType::Exp as u8,
+        flags: 0,
+        // Packed `true` expression
+        value: Value::Blob(vec![0xc3]),
+    }
+}
+
+/// Selects the children of the current list or map for which the filter expression evaluates
+/// to true.
+///
+/// The child is available in the filter through [`loop_var`](crate::expressions::loop_var).
+/// Only valid in the context of [`select_by_path`](crate::operations::select_by_path) and
+/// [`modify_by_path`](crate::operations::modify_by_path) and their expression counterparts.
+pub fn ctx_all_children_with_filter(filter: &Expression) -> Result<CdtContext> {
+    Ok(CdtContext {
+        id: CtxType::Exp as u8,
+        flags: 0,
+        value: Value::Blob(filter.to_bytes()?),
+    })
+}
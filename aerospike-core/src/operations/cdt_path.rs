@@ -0,0 +1,469 @@
+// Copyright 2015-2020 Aerospike, Inc.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Typed builder for paths into nested lists and maps.
+//!
+//! A [`CdtPath`] is built from a sequence of steps, each of which selects an element of the
+//! list or map the previous steps lead to. The path tracks whether it leads to a list, a map or
+//! a value of unknown type, so that only steps and operations that apply to that type can be
+//! used.
+//!
+//! ```
+//! use aerospike::operations::cdt_context::{ctx_list_index, ctx_map_key, ctx_map_key_create};
+//! use aerospike::operations::cdt_path::path;
+//! use aerospike::operations::{ListPolicy, MapOrder, MapPolicy};
+//! use aerospike::Value;
+//!
+//! // Last element of the list stored under key "a", which is itself a list.
+//! let tags = path().map_key("a").list_index(-1);
+//! assert_eq!(tags.ctx(), &[ctx_map_key(Value::from("a")), ctx_list_index(-1)]);
+//! let op = tags.list_append(&ListPolicy::default(), "doc", Value::from("new"));
+//!
+//! // Create the map under key "profile" if it does not exist yet, then put into it.
+//! let profile = path().map_key_create("profile", MapOrder::KeyOrdered);
+//! assert_eq!(profile.ctx(), &[ctx_map_key_create(Value::from("profile"), MapOrder::KeyOrdered)]);
+//! let op = profile.map_put(&MapPolicy::default(), "doc", Value::from("name"), Value::from("Ann"));
+//! ```
+//!
+//! Steps and operations for the wrong type do not compile:
+//!
+//! ```compile_fail
+//! use aerospike::operations::cdt_path::path;
+//! use aerospike::operations::MapOrder;
+//!
+//! let path = path().map_key_create("profile", MapOrder::KeyOrdered).list_index(0);
+//! ```
+//!
+//! ```compile_fail
+//! use aerospike::operations::cdt_path::path;
+//! use aerospike::operations::{ListOrderType, MapPolicy};
+//! use aerospike::Value;
+//!
+//! let tags = path().list_index_create(0, ListOrderType::Ordered, false);
+//! let op = tags.map_put(&MapPolicy::default(), "doc", Value::from("a"), Value::from(1));
+//! ```
+
+use std::marker::PhantomData;
+
+use crate::operations::cdt_context::{
+    ctx_list_index, ctx_list_index_create, ctx_list_rank, ctx_list_value, ctx_map_index,
+    ctx_map_key, ctx_map_key_create, ctx_map_rank, ctx_map_value, CdtContext,
+};
+use crate::operations::lists::{self, ListOrderType, ListPolicy, ToListReturnTypeBitmask};
+use crate::operations::maps::{self, MapOrder, MapPolicy, ToMapReturnTypeBitmask};
+use crate::operations::Operation;
+use crate::value::MapLike;
+use crate::Value;
+
+/// Marker for paths that lead to a value of unknown type.
+#[derive(Debug, Clone, Copy)]
+pub struct Any;
+
+/// Marker for paths that lead to a list.
+#[derive(Debug, Clone, Copy)]
+pub struct List;
+
+/// Marker for paths that lead to a map.
+#[derive(Debug, Clone, Copy)]
+pub struct Map;
+
+mod sealed {
+    pub trait Sealed {}
+    impl Sealed for super::Any {}
+    impl Sealed for super::List {}
+    impl Sealed for super::Map {}
+}
+
+/// Implemented by the markers of paths that may lead to a list.
+pub trait ListTarget: sealed::Sealed {}
+impl ListTarget for Any {}
+impl ListTarget for List {}
+
+/// Implemented by the markers of paths that may lead to a map.
+pub trait MapTarget: sealed::Sealed {}
+impl MapTarget for Any {}
+impl MapTarget for Map {}
+
+/// Path to a nested list or map element. See the [module documentation](self).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CdtPath<T = Any> {
+    ctx: Vec<CdtContext>,
+    target: PhantomData<T>,
+}
+
+/// Returns an empty path, which leads to the bin itself.
+pub const fn path() -> CdtPath {
+    CdtPath {
+        ctx: vec![],
+        target: PhantomData,
+    }
+}
+
+impl Default for CdtPath {
+    fn default() -> Self {
+        path()
+    }
+}
+
+impl<T> CdtPath<T> {
+    fn step<U>(mut self, ctx: CdtContext) -> CdtPath<U> {
+        self.ctx.push(ctx);
+        CdtPath {
+            ctx: self.ctx,
+            target: PhantomData,
+        }
+    }
+
+    /// Returns the context that identifies the path, e.g. to pass it to expressions.
+    pub fn ctx(&self) -> &[CdtContext] {
+        &self.ctx
+    }
+
+    /// Converts the path into its context.
+    pub fn into_ctx(self) -> Vec<CdtContext> {
+        self.ctx
+    }
+
+    /// Returns the number of steps in the path.
+    pub const fn len(&self) -> usize {
+        self.ctx.len()
+    }
+
+    /// Returns whether the path has no steps, i.e. whether it leads to the bin itself.
+    pub const fn is_empty(&self) -> bool {
+        self.ctx.is_empty()
+    }
+
+    /// Applies the path to any list or map operation that is not covered by the helper methods.
+    #[must_use]
+    pub fn apply(&self, op: Operation) -> Operation {
+        op.context(self.ctx.clone())
+    }
+}
+
+impl<T> From<CdtPath<T>> for Vec<CdtContext> {
+    fn from(path: CdtPath<T>) -> Self {
+        path.ctx
+    }
+}
+
+impl<T: ListTarget> CdtPath<T> {
+    /// Selects the list element at the given index. Negative indexes count from the end of the
+    /// list.
+    #[must_use]
+    pub fn list_index(self, index: i64) -> CdtPath {
+        self.step(ctx_list_index(index))
+    }
+
+    /// Selects the list element at the given index and creates a list there if it does not exist.
+    /// If `pad` is set, the parent list is padded with nil values up to the index.
+    #[must_use]
+    pub fn list_index_create(self, index: i64, order: ListOrderType, pad: bool) -> CdtPath<List> {
+        self.step(ctx_list_index_create(index, order, pad))
+    }
+
+    /// Selects the list element with the given rank, where 0 is the smallest value and -1 the
+    /// largest.
+    #[must_use]
+    pub fn list_rank(self, rank: i64) -> CdtPath {
+        self.step(ctx_list_rank(rank))
+    }
+
+    /// Selects the list element with the given value.
+    #[must_use]
+    pub fn list_value(self, value: impl Into<Value>) -> CdtPath {
+        self.step(ctx_list_value(value.into()))
+    }
+
+    /// Creates a list at the path, if the path does not exist yet.
+    pub fn list_create(&self, bin: &str, order: ListOrderType, pad: bool) -> Operation {
+        self.apply(lists::create(bin, order, pad))
+    }
+
+    /// Appends a value to the list at the path.
+    pub fn list_append(&self, policy: &ListPolicy, bin: &str, value: Value) -> Operation {
+        self.apply(lists::append(policy, bin, value))
+    }
+
+    /// Appends values to the list at the path.
+    pub fn list_append_items(
+        &self,
+        policy: &ListPolicy,
+        bin: &str,
+        values: Vec<Value>,
+    ) -> Operation {
+        self.apply(lists::append_items(policy, bin, values))
+    }
+
+    /// Inserts a value into the list at the path.
+    pub fn list_insert(
+        &self,
+        policy: &ListPolicy,
+        bin: &str,
+        index: i64,
+        value: Value,
+    ) -> Operation {
+        self.apply(lists::insert(policy, bin, index, value))
+    }
+
+    /// Sets the element at the given index of the list at the path.
+    pub fn list_set(&self, bin: &str, index: i64, value: Value) -> Operation {
+        self.apply(lists::set(bin, index, value))
+    }
+
+    /// Increments the element at the given index of the list at the path.
+    pub fn list_increment(
+        &self,
+        policy: &ListPolicy,
+        bin: &str,
+        index: i64,
+        value: i64,
+    ) -> Operation {
+        self.apply(lists::increment(policy, bin, index, value))
+    }
+
+    /// Returns the element at the given index of the list at the path.
+    pub fn list_get(&self, bin: &str, index: i64) -> Operation {
+        self.apply(lists::get(bin, index))
+    }
+
+    /// Selects the element at the given index of the list at the path.
+    pub fn list_get_by_index<TLR: ToListReturnTypeBitmask>(
+        &self,
+        bin: &str,
+        index: i64,
+        return_type: TLR,
+    ) -> Operation {
+        self.apply(lists::get_by_index(bin, index, return_type))
+    }
+
+    /// Removes the element at the given index of the list at the path.
+    pub fn list_remove_by_index<TLR: ToListReturnTypeBitmask>(
+        &self,
+        bin: &str,
+        index: i64,
+        return_type: TLR,
+    ) -> Operation {
+        self.apply(lists::remove_by_index(bin, index, return_type))
+    }
+
+    /// Returns and removes the element at the given index of the list at the path.
+    pub fn list_pop(&self, bin: &str, index: i64) -> Operation {
+        self.apply(lists::pop(bin, index))
+    }
+
+    /// Returns the size of the list at the path.
+    pub fn list_size(&self, bin: &str) -> Operation {
+        self.apply(lists::size(bin))
+    }
+
+    /// Removes all elements of the list at the path.
+    pub fn list_clear(&self, bin: &str) -> Operation {
+        self.apply(lists::clear(bin))
+    }
+}
+
+impl<T: MapTarget> CdtPath<T> {
+    /// Selects the map entry with the given key.
+    #[must_use]
+    pub fn map_key(self, key: impl Into<Value>) -> CdtPath {
+        self.step(ctx_map_key(key.into()))
+    }
+
+    /// Selects the map entry with the given key and creates a map of the given order there if
+    /// the key does not exist.
+    #[must_use]
+    pub fn map_key_create(self, key: impl Into<Value>, order: MapOrder) -> CdtPath<Map> {
+        self.step(ctx_map_key_create(key.into(), order))
+    }
+
+    /// Selects the map entry at the given index in key order. Negative indexes count from the end
+    /// of the map.
+    #[must_use]
+    pub fn map_index(self, index: i64) -> CdtPath {
+        self.step(ctx_map_index(Value::Int(index)))
+    }
+
+    /// Selects the map entry with the given rank, where 0 is the smallest value and -1 the
+    /// largest.
+    #[must_use]
+    pub fn map_rank(self, rank: i64) -> CdtPath {
+        self.step(ctx_map_rank(rank))
+    }
+
+    /// Selects the map entry with the given value.
+    #[must_use]
+    pub fn map_value(self, value: impl Into<Value>) -> CdtPath {
+        self.step(ctx_map_value(value.into()))
+    }
+
+    /// Creates a map at the path, if the path does not exist yet.
+    pub fn map_create(&self, bin: &str, order: MapOrder) -> Operation {
+        maps::create(bin, order, self.ctx.clone())
+    }
+
+    /// Writes a key/value item to the map at the path.
+    pub fn map_put(&self, policy: &MapPolicy, bin: &str, key: Value, value: Value) -> Operation {
+        self.apply(maps::put(policy, bin, key, value))
+    }
+
+    /// Writes key/value items to the map at the path.
+    #[allow(clippy::implicit_hasher)]
+    pub fn map_put_items<M: MapLike<Value, Value>>(
+        &self,
+        policy: &MapPolicy,
+        bin: &str,
+        items: M,
+    ) -> Operation {
+        self.apply(maps::put_items(policy, bin, items))
+    }
+
+    /// Increments the value of the given key of the map at the path.
+    pub fn map_increment_value(
+        &self,
+        policy: &MapPolicy,
+        bin: &str,
+        key: Value,
+        incr: Value,
+    ) -> Operation {
+        self.apply(maps::increment_value(policy, bin, key, incr))
+    }
+
+    /// Selects the entry with the given key of the map at the path.
+    pub fn map_get_by_key<TMR: ToMapReturnTypeBitmask>(
+        &self,
+        bin: &str,
+        key: Value,
+        return_type: TMR,
+    ) -> Operation {
+        self.apply(maps::get_by_key(bin, key, return_type))
+    }
+
+    /// Removes the entry with the given key of the map at the path.
+    pub fn map_remove_by_key<TMR: ToMapReturnTypeBitmask>(
+        &self,
+        bin: &str,
+        key: Value,
+        return_type: TMR,
+    ) -> Operation {
+        self.apply(maps::remove_by_key(bin, key, return_type))
+    }
+
+    /// Returns the size of the map at the path.
+    pub fn map_size(&self, bin: &str) -> Operation {
+        self.apply(maps::size(bin))
+    }
+
+    /// Removes all entries of the map at the path.
+    pub fn map_clear(&self, bin: &str) -> Operation {
+        self.apply(maps::clear(bin))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{path, CdtPath};
+    use crate::operations::cdt_context::CdtContext;
+    use crate::operations::lists::{ListOrderType, ListPolicy};
+    use crate::operations::maps::{MapOrder, MapPolicy, MapReturnType};
+    use crate::Value;
+
+    fn ctx(id: u8, flags: u8, value: impl Into<Value>) -> CdtContext {
+        CdtContext {
+            id,
+            flags,
+            value: value.into(),
+        }
+    }
+
+    #[test]
+    fn steps() {
+        assert!(path().is_empty());
+        assert!(CdtPath::default().is_empty());
+
+        let nested = path()
+            .map_key("a")
+            .list_index(-1)
+            .list_rank(0)
+            .list_value("x")
+            .map_index(2)
+            .map_rank(-1)
+            .map_value(7);
+        assert_eq!(nested.len(), 7);
+        assert_eq!(
+            nested.ctx(),
+            &[
+                ctx(0x22, 0, "a"),
+                ctx(0x10, 0, -1),
+                ctx(0x11, 0, 0),
+                ctx(0x13, 0, "x"),
+                ctx(0x20, 0, 2),
+                ctx(0x21, 0, -1),
+                ctx(0x23, 0, 7),
+            ]
+        );
+    }
+
+    #[test]
+    fn create_steps() {
+        let created = path()
+            .map_key_create("profile", MapOrder::KeyOrdered)
+            .map_key_create("tags", MapOrder::KeyValueOrdered)
+            .map_key("list")
+            .list_index_create(3, ListOrderType::Unordered, true)
+            .list_index_create(0, ListOrderType::Unordered, false)
+            .list_index_create(-1, ListOrderType::Ordered, true);
+        assert_eq!(
+            created.into_ctx(),
+            vec![
+                ctx(0x22, 0x80, "profile"),
+                ctx(0x22, 0xc0, "tags"),
+                ctx(0x22, 0, "list"),
+                ctx(0x10, 0x80, 3),
+                ctx(0x10, 0x40, 0),
+                ctx(0x10, 0xc0, -1),
+            ]
+        );
+    }
+
+    #[test]
+    fn operations() {
+        let profile = path()
+            .map_key("users")
+            .map_key_create("ann", MapOrder::KeyOrdered);
+        let op = profile.map_put(
+            &MapPolicy::default(),
+            "doc",
+            Value::from("name"),
+            Value::from("Ann"),
+        );
+        assert_eq!(op.ctx, profile.ctx());
+        let op = profile.map_get_by_key("doc", Value::from("name"), MapReturnType::Value);
+        assert_eq!(op.ctx, profile.ctx());
+        assert_eq!(
+            profile.map_create("doc", MapOrder::KeyOrdered).ctx,
+            profile.ctx()
+        );
+
+        // operations on the bin itself have no context
+        let op = path().list_append(&ListPolicy::default(), "doc", Value::from(1));
+        assert!(op.ctx.is_empty());
+
+        // applying a path replaces the context of the operation
+        let tags = path().map_key("tags");
+        let op = tags.apply(path().list_index(0).list_size("doc"));
+        assert_eq!(op.ctx, vec![ctx(0x22, 0, "tags")]);
+        assert_eq!(Vec::from(tags), vec![ctx(0x22, 0, "tags")]);
+    }
+}
@@ -194,6 +194,7 @@ pub fn add_with_index_and_min_hash(
 /// Creates HLL set union operation.
 /// Server sets union of specified HLL objects with HLL bin.
 /// Server does not return a value.
+/// HLL objects can be built on the client with [`HllSketch`](crate::HllSketch).
 pub fn set_union(policy: &HLLPolicy, bin: &str, list: Vec<Value>) -> Operation {
     let cdt_op = CdtOperation {
         op: HLLOpType::SetUnion as u8,
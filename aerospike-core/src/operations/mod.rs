@@ -18,20 +18,28 @@
 pub mod bitwise;
 pub(crate) mod cdt;
 pub mod cdt_context;
+pub mod cdt_path;
 pub mod exp;
 pub mod hll;
 pub mod lists;
 pub mod maps;
+mod results;
 pub mod scalar;
 
 use self::cdt::CdtOperation;
+pub use self::cdt::{
+    modify_by_path, select_by_path, ModifyFlags, SelectFlags, ToModifyFlagsBitmask,
+    ToSelectFlagsBitmask,
+};
 pub use self::lists::{ListOrderType, ListPolicy, ListReturnType, ListSortFlags, ListWriteFlags};
 pub use self::maps::{MapOrder, MapPolicy, MapReturnType, MapWriteFlags, MapWriteMode};
+pub use self::results::OperateResults;
 pub use self::scalar::*;
 
 use crate::commands::buffer::Buffer;
 use crate::commands::ParticleType;
 pub use crate::operations::cdt_context::CdtContext;
+pub use crate::operations::cdt_path::CdtPath;
 use crate::operations::exp::ExpOperation;
 use crate::Result;
 use crate::Value;
@@ -0,0 +1,339 @@
+// Copyright 2015-2020 Aerospike, Inc.
+//
+// Portions may be licensed to Aerospike, Inc. under one or more contributor
+// license agreements.
+//
+// Licensed under the Apache License, Version 2.0 (the "License"); you may not
+// use this file except in compliance with the License. You may obtain a copy of
+// the License at http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS, WITHOUT
+// WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied. See the
+// License for the specific language governing permissions and limitations under
+// the License.
+
+use crate::errors::{Error, Result};
+use crate::operations::{Operation, OperationBin};
+use crate::{Record, Value};
+
+/// Results of [`Client::operate_results`](crate::Client::operate_results), indexed by the
+/// position of the operation in the submitted slice.
+///
+/// Operations that read or modify a named bin have a result, unless the server returned nil for
+/// them, e.g. for a `put` or a read of a bin that does not exist. Operations on the whole record,
+/// like `get`, `get_header`, `touch` and `delete`, never have a result; their effect is visible
+/// in the [record](Self::record) instead.
+///
+/// The bins returned by `get` can not be told apart from the results of the other operations in
+/// between two `get` operations, so those have no result either.
+///
+/// ```rust,edition2021
+/// # use aerospike::*;
+/// # #[tokio::main]
+/// # async fn main() {
+/// # let hosts = std::env::var("AEROSPIKE_HOSTS").unwrap();
+/// # let client = Client::new(&ClientPolicy::default(), &hosts).await.unwrap();
+/// let key = as_key!("test", "test", "mykey");
+/// let ops = vec![
+///     operations::add(&as_bin!("count", 1)),
+///     operations::get_bin("count"),
+///     operations::lists::size("tags"),
+/// ];
+/// let results = client.operate_results(&WritePolicy::default(), &key, &ops).await.unwrap();
+/// println!("count: {}, tags: {}", results.as_i64(1).unwrap(), results.as_i64(2).unwrap());
+/// # }
+/// ```
+#[derive(Debug, Clone)]
+pub struct OperateResults {
+    record: Record,
+    results: Vec<Option<Value>>,
+}
+
+impl OperateResults {
+    // Assigns the results, which the server returns in operation order, to the operations.
+    // Requires the command to have been sent with `respond_per_each_op`.
+    //
+    // Every operation on a named bin returns one result, and operations on the whole record
+    // return none, except for `get`, which returns all bins of the record. The number of those
+    // is not known, so the results are matched from the start up to the first `get`, and from
+    // the end back to the last one.
+    pub(crate) fn new(ops: &[Operation], record: Record, op_results: Vec<(String, Value)>) -> Self {
+        let mut results = vec![None; ops.len()];
+        let mut op_results = op_results.into_iter();
+
+        let first_all = ops
+            .iter()
+            .position(|op| matches!(op.bin, OperationBin::All))
+            .unwrap_or(ops.len());
+        for (i, op) in ops.iter().enumerate().take(first_all) {
+            if let OperationBin::Name(name) = &op.bin {
+                results[i] = bin_result(name, op_results.next());
+            }
+        }
+
+        if let Some(last_all) = ops
+            .iter()
+            .rposition(|op| matches!(op.bin, OperationBin::All))
+        {
+            for (i, op) in ops.iter().enumerate().skip(last_all + 1).rev() {
+                if let OperationBin::Name(name) = &op.bin {
+                    results[i] = bin_result(name, op_results.next_back());
+                }
+            }
+        }
+
+        OperateResults { record, results }
+    }
+
+    /// Returns the record, with the results of all operations merged by bin name, as returned by
+    /// [`Client::operate`](crate::Client::operate).
+    pub const fn record(&self) -> &Record {
+        &self.record
+    }
+
+    /// Converts the results into the record.
+    pub fn into_record(self) -> Record {
+        self.record
+    }
+
+    /// Returns the number of operations.
+    pub const fn len(&self) -> usize {
+        self.results.len()
+    }
+
+    /// Returns whether there are no operations.
+    pub const fn is_empty(&self) -> bool {
+        self.results.is_empty()
+    }
+
+    /// Returns the result of the operation at the given index, or `None` if it returned nothing.
+    pub fn get(&self, op_idx: usize) -> Option<&Value> {
+        self.results.get(op_idx).and_then(Option::as_ref)
+    }
+
+    /// Returns the result of the operation at the given index. Fails if the index is out of
+    /// bounds or the operation returned nothing.
+    pub fn value(&self, op_idx: usize) -> Result<&Value> {
+        match self.results.get(op_idx) {
+            Some(Some(value)) => Ok(value),
+            Some(None) => Err(Error::ClientError(format!(
+                "Operation {op_idx} returned no result"
+            ))),
+            None => Err(Error::InvalidArgument(format!(
+                "Operation index {op_idx} out of bounds, {} operations were submitted",
+                self.results.len()
+            ))),
+        }
+    }
+
+    /// Returns the integer result of the operation at the given index.
+    pub fn as_i64(&self, op_idx: usize) -> Result<i64> {
+        match self.value(op_idx)? {
+            Value::Int(val) => Ok(*val),
+            val => Err(type_error(op_idx, val, "integer")),
+        }
+    }
+
+    /// Returns the float result of the operation at the given index.
+    pub fn as_f64(&self, op_idx: usize) -> Result<f64> {
+        match self.value(op_idx)? {
+            Value::Float(val) => Ok(f64::from(val)),
+            val => Err(type_error(op_idx, val, "float")),
+        }
+    }
+
+    /// Returns the boolean result of the operation at the given index.
+    pub fn as_bool(&self, op_idx: usize) -> Result<bool> {
+        match self.value(op_idx)? {
+            Value::Bool(val) => Ok(*val),
+            val => Err(type_error(op_idx, val, "boolean")),
+        }
+    }
+
+    /// Returns the string result of the operation at the given index.
+    pub fn as_str(&self, op_idx: usize) -> Result<&str> {
+        match self.value(op_idx)? {
+            Value::String(val) | Value::GeoJSON(val) => Ok(val),
+            val => Err(type_error(op_idx, val, "string")),
+        }
+    }
+
+    /// Returns the blob result of the operation at the given index.
+    pub fn as_blob(&self, op_idx: usize) -> Result<&[u8]> {
+        match self.value(op_idx)? {
+            Value::Blob(val) | Value::HLL(val) => Ok(val),
+            val => Err(type_error(op_idx, val, "blob")),
+        }
+    }
+
+    /// Returns the list result of the operation at the given index.
+    pub fn as_list(&self, op_idx: usize) -> Result<&[Value]> {
+        match self.value(op_idx)? {
+            Value::List(val) => Ok(val),
+            val => Err(type_error(op_idx, val, "list")),
+        }
+    }
+
+    /// Returns the map result of the operation at the given index as key/value pairs, in the
+    /// order returned by the server for ordered maps.
+    pub fn as_map(&self, op_idx: usize) -> Result<Vec<(&Value, &Value)>> {
+        match self.value(op_idx)? {
+            Value::HashMap(map) => Ok(map.iter().collect()),
+            Value::OrderedMap(map) => Ok(map.iter().collect()),
+            Value::KeyValueList(list) => Ok(list.iter().map(|(k, v)| (k, v)).collect()),
+            val => Err(type_error(op_idx, val, "map")),
+        }
+    }
+}
+
+// Returns the value of a result if it is for the bin and not nil.
+fn bin_result(name: &str, result: Option<(String, Value)>) -> Option<Value> {
+    result
+        .filter(|(bin, value)| bin == name && !value.is_nil())
+        .map(|(_, value)| value)
+}
+
+fn type_error(op_idx: usize, val: &Value, expected: &str) -> Error {
+    Error::ClientError(format!(
+        "Operation {op_idx} returned a {} result, expected {expected}",
+        val.particle_type()
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::OperateResults;
+    use crate::operations::{self, lists};
+    use crate::{Record, Value};
+
+    fn results(ops: &[operations::Operation], op_results: Vec<(&str, Value)>) -> OperateResults {
+        let op_results = op_results
+            .into_iter()
+            .map(|(name, value)| (name.to_string(), value))
+            .collect();
+        OperateResults::new(ops, Record::new(None, HashMap::new(), 1, 0), op_results)
+    }
+
+    #[test]
+    fn results_by_operation() {
+        let ops = vec![
+            operations::put(&as_bin!("a", 1)),
+            operations::add(&as_bin!("b", 2)),
+            operations::get_bin("a"),
+            lists::size("l"),
+            operations::touch(),
+            operations::get_bin("b"),
+            lists::clear("l"),
+            operations::get_bin("missing"),
+        ];
+        let results = results(
+            &ops,
+            vec![
+                ("a", Value::Nil),
+                ("b", Value::Nil),
+                ("a", Value::from(1)),
+                ("l", Value::from(3)),
+                ("b", Value::from(5)),
+                ("l", Value::Nil),
+                ("missing", Value::Nil),
+            ],
+        );
+
+        assert_eq!(results.len(), ops.len());
+        assert_eq!(results.get(0), None);
+        assert_eq!(results.as_i64(2).unwrap(), 1);
+        assert_eq!(results.as_i64(3).unwrap(), 3);
+        assert_eq!(results.get(4), None);
+        assert_eq!(results.as_i64(5).unwrap(), 5);
+        assert_eq!(results.get(6), None);
+
+        let err = results.as_i64(7).unwrap_err();
+        assert_eq!(err.to_string(), "Operation 7 returned no result");
+        assert!(results.value(8).is_err());
+        assert!(results.as_list(2).is_err());
+    }
+
+    #[test]
+    fn results_with_get_all() {
+        let ops = vec![
+            operations::add(&as_bin!("a", 1)),
+            operations::get_bin("a"),
+            operations::get(),
+            lists::size("l"),
+            operations::get_bin("a"),
+        ];
+        let res = results(
+            &ops,
+            vec![
+                ("a", Value::Nil),
+                ("a", Value::from(2)),
+                // all bins of the record
+                ("l", Value::List(vec![Value::from(1)])),
+                ("a", Value::from(2)),
+                ("b", Value::from("x")),
+                ("l", Value::from(1)),
+                ("a", Value::from(2)),
+            ],
+        );
+
+        assert_eq!(res.get(0), None);
+        assert_eq!(res.as_i64(1).unwrap(), 2);
+        assert_eq!(res.get(2), None);
+        assert_eq!(res.as_i64(3).unwrap(), 1);
+        assert_eq!(res.as_i64(4).unwrap(), 2);
+
+        // the results between two gets are not known
+        let ops = vec![
+            operations::get(),
+            operations::get_bin("a"),
+            operations::get(),
+            lists::size("l"),
+        ];
+        let res = results(
+            &ops,
+            vec![
+                ("a", Value::from(1)),
+                ("a", Value::from(1)),
+                ("a", Value::from(1)),
+                ("l", Value::from(0)),
+            ],
+        );
+        assert_eq!(res.get(1), None);
+        assert_eq!(res.as_i64(3).unwrap(), 0);
+    }
+
+    #[test]
+    fn typed_results() {
+        let ops = vec![
+            operations::get_bin("s"),
+            operations::get_bin("f"),
+            lists::get_range_from("l", 0),
+            operations::get_bin("m"),
+        ];
+        let mut map = HashMap::new();
+        map.insert(Value::from("k"), Value::from(1));
+        let results = results(
+            &ops,
+            vec![
+                ("s", Value::from("x")),
+                ("f", Value::from(1.5)),
+                ("l", Value::List(vec![Value::from(1), Value::from(2)])),
+                ("m", Value::HashMap(map)),
+            ],
+        );
+
+        assert_eq!(results.as_str(0).unwrap(), "x");
+        assert!((results.as_f64(1).unwrap() - 1.5).abs() < f64::EPSILON);
+        assert_eq!(
+            results.as_list(2).unwrap(),
+            &[Value::from(1), Value::from(2)]
+        );
+        assert_eq!(
+            results.as_map(3).unwrap(),
+            vec![(&Value::from("k"), &Value::from(1))]
+        );
+    }
+}
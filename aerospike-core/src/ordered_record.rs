@@ -0,0 +1,324 @@
+// Copyright 2015-2020 Aerospike, Inc.
+//
+// Portions may be licensed to Aerospike, Inc. under one or more contributor
+// license agreements.
+//
+// Licensed under the Apache License, Version 2.0 (the "License"); you may not
+// use this file except in compliance with the License. You may obtain a copy of
+// the License at http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS, WITHOUT
+// WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied. See the
+// License for the specific language governing permissions and limitations under
+// the License.
+
+#[cfg(feature = "serialization")]
+use serde::ser::{SerializeMap, Serializer};
+#[cfg(feature = "serialization")]
+use serde::Serialize;
+
+use std::collections::HashMap;
+use std::fmt;
+use std::time::{Duration, SystemTime};
+
+use crate::errors;
+use crate::{BinView, FromBins, Key, Record, RecordMeta, Value};
+
+/// Database record that keeps its bins in the order returned by the server.
+///
+/// The server returns the bins in the order they were requested with
+/// [`Bins::Some`](crate::Bins::Some), and in the order they are stored in for
+/// [`Bins::All`](crate::Bins::All). Iteration, [`Display`](fmt::Display) and serialization follow
+/// that order, which makes the output deterministic, e.g. for the columns of an export. Multiple
+/// results for the same bin are merged into a single [`Value::MultiResult`] at the position of the
+/// first one, as for [`Record`].
+///
+/// Ordered records are read with the [`FromBins`] commands:
+///
+/// ```rust,edition2021
+/// # use aerospike::*;
+/// # #[tokio::main]
+/// # async fn main() {
+/// # let hosts = std::env::var("AEROSPIKE_HOSTS").unwrap();
+/// # let client = Client::new(&ClientPolicy::default(), &hosts).await.unwrap();
+/// let key = as_key!("test", "test", "mykey");
+/// let record: OrderedRecord = client
+///     .get_as(&ReadPolicy::default(), &key, Bins::All)
+///     .await
+///     .unwrap();
+/// for (name, value) in record.iter() {
+///     println!("{name}: {value}");
+/// }
+///
+/// // Selected bins, in the requested order
+/// let record: OrderedRecord = client
+///     .get_as(&ReadPolicy::default(), &key, ["b", "a"].into())
+///     .await
+///     .unwrap();
+/// # }
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serialization", derive(Serialize))]
+pub struct OrderedRecord {
+    /// Record key. When reading a single record from the database, the key is not set.
+    pub key: Option<Key>,
+
+    /// Named record bins, in the order returned by the server.
+    #[cfg_attr(feature = "serialization", serde(serialize_with = "serialize_bins"))]
+    bins: Vec<(String, Value)>,
+
+    /// Position of each bin in `bins`, by name.
+    #[cfg_attr(feature = "serialization", serde(skip))]
+    positions: HashMap<String, usize>,
+
+    /// Record modification count.
+    pub generation: u32,
+
+    /// Date record will expire, in seconds from Jan 01 2010, 00:00:00 UTC.
+    expiration: u32,
+}
+
+impl OrderedRecord {
+    /// Construct a new ordered record. The expiration is the record's void time in seconds since
+    /// Jan 01 2010, 00:00:00 UTC, or 0 if the record never expires. If a bin name occurs more than
+    /// once, [`get`](Self::get) returns the first value.
+    pub fn new(
+        key: Option<Key>,
+        bins: Vec<(String, Value)>,
+        generation: u32,
+        expiration: u32,
+    ) -> Self {
+        let mut positions = HashMap::with_capacity(bins.len());
+        for (pos, (name, _)) in bins.iter().enumerate() {
+            positions.entry(name.clone()).or_insert(pos);
+        }
+        OrderedRecord {
+            key,
+            bins,
+            positions,
+            generation,
+            expiration,
+        }
+    }
+
+    /// Returns the value of the bin with the given name.
+    pub fn get(&self, name: &str) -> Option<&Value> {
+        self.positions.get(name).map(|&pos| &self.bins[pos].1)
+    }
+
+    /// Returns the bin names and values, in order.
+    pub fn bins(&self) -> &[(String, Value)] {
+        &self.bins
+    }
+
+    /// Consumes the record and returns its bin names and values, in order.
+    pub fn into_bins(self) -> Vec<(String, Value)> {
+        self.bins
+    }
+
+    /// Returns an iterator over the bin names and values, in order.
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &Value)> {
+        self.bins.iter().map(|(name, value)| (name.as_str(), value))
+    }
+
+    /// Returns an iterator over the bin names, in order.
+    pub fn bin_names(&self) -> impl Iterator<Item = &str> {
+        self.bins.iter().map(|(name, _)| name.as_str())
+    }
+
+    /// Returns the number of bins.
+    pub const fn len(&self) -> usize {
+        self.bins.len()
+    }
+
+    /// Returns whether the record has no bins.
+    pub const fn is_empty(&self) -> bool {
+        self.bins.is_empty()
+    }
+
+    /// Returns the remaining time-to-live of the record, or `None` if the record never expires.
+    pub fn time_to_live(&self) -> Option<Duration> {
+        self.header().time_to_live()
+    }
+
+    /// Returns the point in time at which the record expires, or `None` if the record never
+    /// expires.
+    pub fn expiration_time(&self) -> Option<SystemTime> {
+        self.header().expiration_time()
+    }
+
+    /// Converts the ordered record into a [`Record`], which does not keep the bin order.
+    pub fn into_record(self) -> Record {
+        let bins: HashMap<String, Value> = self.bins.into_iter().collect();
+        Record::new(self.key, bins, self.generation, self.expiration)
+    }
+
+    fn header(&self) -> Record {
+        Record::new(None, HashMap::new(), self.generation, self.expiration)
+    }
+}
+
+impl From<OrderedRecord> for Record {
+    fn from(record: OrderedRecord) -> Self {
+        record.into_record()
+    }
+}
+
+impl<'a> IntoIterator for &'a OrderedRecord {
+    type Item = &'a (String, Value);
+    type IntoIter = std::slice::Iter<'a, (String, Value)>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.bins.iter()
+    }
+}
+
+/// Bins of an [`OrderedRecord`] that is being decoded, with the position of each bin by name. This
+/// is the [`FromBins::Builder`] of [`OrderedRecord`] and is not used directly.
+#[derive(Debug, Default)]
+pub struct OrderedBins {
+    bins: Vec<(String, Value)>,
+    positions: HashMap<String, usize>,
+}
+
+impl FromBins for OrderedRecord {
+    type Builder = OrderedBins;
+
+    fn decode_bin(builder: &mut Self::Builder, bin: &BinView<'_>) -> errors::Result<()> {
+        let value = bin.to_value()?;
+
+        // list/map operations may return multiple values for the same bin.
+        let Some(&pos) = builder.positions.get(bin.name()) else {
+            let name = bin.name().to_string();
+            builder.positions.insert(name.clone(), builder.bins.len());
+            builder.bins.push((name, value));
+            return Ok(());
+        };
+        match &mut builder.bins[pos].1 {
+            Value::MultiResult(list) => list.push(value),
+            prev => {
+                *prev = Value::MultiResult(vec![std::mem::replace(prev, Value::Nil), value]);
+            }
+        }
+        Ok(())
+    }
+
+    fn build(builder: Self::Builder, meta: RecordMeta) -> errors::Result<Self> {
+        Ok(OrderedRecord {
+            key: meta.key,
+            bins: builder.bins,
+            positions: builder.positions,
+            generation: meta.generation,
+            expiration: meta.expiration,
+        })
+    }
+}
+
+impl fmt::Display for OrderedRecord {
+    fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        write!(f, "key: {:?}", self.key)?;
+        write!(f, ", bins: {{")?;
+        for (i, (k, v)) in self.bins.iter().enumerate() {
+            if i > 0 {
+                write!(f, ", ")?;
+            }
+            write!(f, "{k}: {v}")?;
+        }
+        write!(f, "}}, generation: {}", self.generation)?;
+        write!(f, ", ttl: ")?;
+        match self.time_to_live() {
+            None => "none".fmt(f),
+            Some(duration) => duration.as_secs().fmt(f),
+        }
+    }
+}
+
+// Serializes the bins as a map, in order.
+#[cfg(feature = "serialization")]
+fn serialize_bins<S>(bins: &[(String, Value)], serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+{
+    let mut map = serializer.serialize_map(Some(bins.len()))?;
+    for (name, value) in bins {
+        map.serialize_entry(name, value)?;
+    }
+    map.end()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{OrderedBins, OrderedRecord};
+    use crate::record_view::push_op;
+    use crate::{FromBins, RecordMeta, RecordView, Value};
+
+    fn decode(data: &[u8], op_count: usize) -> OrderedRecord {
+        let view = RecordView::parse(data, 0, op_count, 3, 0).unwrap();
+        let mut bins = OrderedBins::default();
+        for bin in view.bins() {
+            OrderedRecord::decode_bin(&mut bins, bin).unwrap();
+        }
+        let meta = RecordMeta {
+            key: None,
+            generation: 3,
+            expiration: 0,
+        };
+        OrderedRecord::build(bins, meta).unwrap()
+    }
+
+    #[test]
+    fn server_order() {
+        let mut data = vec![];
+        for (name, value) in [("c", 1), ("a", 2), ("b", 3), ("a", 4), ("a", 5)] {
+            push_op(&mut data, name, 1, &i64::to_be_bytes(value));
+        }
+        let record = decode(&data, 5);
+
+        assert_eq!(record.bin_names().collect::<Vec<_>>(), vec!["c", "a", "b"]);
+        assert_eq!(
+            record.get("a"),
+            Some(&Value::MultiResult(vec![
+                Value::from(2),
+                Value::from(4),
+                Value::from(5)
+            ]))
+        );
+        assert_eq!(record.len(), 3);
+        assert_eq!(
+            record.to_string(),
+            "key: None, bins: {c: 1, a: [Int(2), Int(4), Int(5)], b: 3}, generation: 3, ttl: none"
+        );
+
+        let record = record.into_record();
+        assert_eq!(record.bins.get("b"), Some(&Value::from(3)));
+    }
+
+    #[test]
+    fn get_by_name() {
+        let bins = vec![
+            ("z".to_string(), Value::from(1)),
+            ("a".to_string(), Value::from(2)),
+            ("z".to_string(), Value::from(3)),
+        ];
+        let record = OrderedRecord::new(None, bins, 1, 0);
+        assert_eq!(record.get("z"), Some(&Value::from(1)));
+        assert_eq!(record.get("a"), Some(&Value::from(2)));
+        assert_eq!(record.get("b"), None);
+        assert_eq!(record.bins().len(), 3);
+    }
+
+    #[test]
+    #[cfg(feature = "serialization")]
+    fn serialize_in_order() {
+        let bins = vec![
+            ("z".to_string(), Value::from(1)),
+            ("a".to_string(), Value::from("x")),
+        ];
+        let record = OrderedRecord::new(None, bins, 1, 0);
+        assert_eq!(
+            serde_json::to_string(&record).unwrap(),
+            r#"{"key":null,"bins":{"z":1,"a":"x"},"generation":1,"expiration":0}"#
+        );
+    }
+}
@@ -13,10 +13,13 @@
 // limitations under the License.
 
 use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
 use std::time::Duration;
 
+use crate::codec::BinCodecs;
 use crate::commands::admin_command::AdminCommand;
 use crate::errors::{Error, Result};
+use crate::interceptor::CommandInterceptor;
 
 #[cfg(feature = "tls")]
 use tokio_rustls::rustls::ClientConfig;
@@ -148,8 +151,17 @@ pub struct ClientPolicy {
 
     /// `TendInterval` determines interval for checking for cluster state changes.
     /// Minimum possible interval is 10 Milliseconds.
+    /// Commands that fail to reach a partition's node trigger an extra tend between ticks.
     pub tend_interval: u32,
 
+    /// Maximum time in milliseconds for refreshing a node during a cluster tend, including the
+    /// partition map and rack requests. Nodes are refreshed in parallel, and the partition map is
+    /// updated once all of them have been refreshed or have timed out. A node that does not respond
+    /// in time counts as failed for that tend. A value of 0 uses `tend_interval`, or `timeout` if
+    /// that is shorter, so that a node that does not respond cannot hold up the next tend.
+    /// Default: 0
+    pub tend_timeout: u32,
+
     /// A IP translation table is used in cases where different clients
     /// use different server IP addresses. This may be necessary when
     /// using clients from both inside and outside a local area
@@ -185,6 +197,16 @@ pub struct ClientPolicy {
     /// Application id is used to identify an application so that client operations can be correlated
     /// with server side metrics.
     pub application_id: Option<String>,
+
+    /// Interceptors that are called before and after every command, and before a command is
+    /// retried. See [`CommandInterceptor`] for the commands that are intercepted.
+    /// Default is no interceptors.
+    pub interceptors: Vec<Arc<dyn CommandInterceptor>>,
+
+    /// Codecs that transform string and blob bin values on write and reverse the transformation
+    /// on read, e.g. to compress or encrypt them. See the [`codec`](crate::codec) module.
+    /// Default is no codecs.
+    pub codecs: BinCodecs,
 }
 
 impl Default for ClientPolicy {
@@ -198,12 +220,15 @@ impl Default for ClientPolicy {
             conn_pools_per_node: 1,
             fail_if_not_connected: true,
             tend_interval: 1000,
+            tend_timeout: 0,
             ip_map: None,
             use_services_alternate: false,
             cluster_name: None,
             buffer_reclaim_threshold: 65536,
             rack_ids: None,
             application_id: None,
+            interceptors: vec![],
+            codecs: BinCodecs::default(),
 
             #[cfg(feature = "tls")]
             tls_config: None,
@@ -217,6 +242,15 @@ impl ClientPolicy {
             return Err(Error::ClientError("minimum number of connections specified in the ClientPolicy is bigger than total connection pool size".into()));
         }
 
+        if self.max_conns_per_node > 0
+            && self.max_conns_per_node < usize::from(self.conn_pools_per_node)
+        {
+            return Err(Error::ClientError(format!(
+                "max_conns_per_node ({}) specified in the ClientPolicy is less than conn_pools_per_node ({})",
+                self.max_conns_per_node, self.conn_pools_per_node
+            )));
+        }
+
         Ok(())
     }
 
@@ -245,6 +279,15 @@ impl ClientPolicy {
         }
     }
 
+    pub(crate) fn tend_timeout(&self) -> Duration {
+        if self.tend_timeout > 0 {
+            Duration::from_millis(u64::from(self.tend_timeout))
+        } else {
+            let tend_interval = Duration::from_millis(u64::from(self.tend_interval.max(10)));
+            self.timeout().min(tend_interval)
+        }
+    }
+
     /// Set username and password to use when authenticating to the cluster.
     pub fn set_auth_mode(&mut self, auth_mode: AuthMode) -> Result<()> {
         self.auth_mode = auth_mode;
@@ -0,0 +1,60 @@
+// Copyright 2015-2018 Aerospike, Inc.
+//
+// Portions may be licensed to Aerospike, Inc. under one or more contributor
+// license agreements.
+//
+// Licensed under the Apache License, Version 2.0 (the "License"); you may not
+// use this file except in compliance with the License. You may obtain a copy of
+// the License at http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS, WITHOUT
+// WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied. See the
+// License for the specific language governing permissions and limitations under
+// the License.
+
+use crate::policy::{BatchPolicy, ReadPolicy, WritePolicy};
+
+/// `LargeObjectPolicy` encapsulates parameters for [`LargeObject`](crate::LargeObject) operations.
+#[derive(Debug, Clone)]
+pub struct LargeObjectPolicy {
+    /// Read policy for the manifest record.
+    pub read_policy: ReadPolicy,
+
+    /// Write policy for the manifest and chunk records. The expiration applies to all of them;
+    /// the record exists action and generation policy are set by the large object operations.
+    pub write_policy: WritePolicy,
+
+    /// Batch policy for reading and deleting chunk records.
+    pub batch_policy: BatchPolicy,
+
+    /// Maximum size of a chunk record in bytes. Must be smaller than the write-block-size of the
+    /// namespace, minus the record overhead.
+    ///
+    /// Default: 512 KiB
+    pub chunk_size: usize,
+
+    /// Number of chunks that are written concurrently, or read in one batch request.
+    ///
+    /// Default: 8
+    pub parallel_chunks: usize,
+}
+
+impl LargeObjectPolicy {
+    /// Creates a new large object policy instance.
+    pub fn new() -> Self {
+        LargeObjectPolicy::default()
+    }
+}
+
+impl Default for LargeObjectPolicy {
+    fn default() -> Self {
+        LargeObjectPolicy {
+            read_policy: ReadPolicy::default(),
+            write_policy: WritePolicy::default(),
+            batch_policy: BatchPolicy::default(),
+            chunk_size: 512 * 1024,
+            parallel_chunks: 8,
+        }
+    }
+}
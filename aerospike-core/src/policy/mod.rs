@@ -26,6 +26,8 @@ mod concurrency;
 mod consistency_level;
 mod expiration;
 mod generation_policy;
+mod large_object_policy;
+mod multi_cluster_policy;
 mod query_duration;
 mod query_policy;
 mod read_policy;
@@ -43,6 +45,8 @@ pub use self::concurrency::Concurrency;
 pub use self::consistency_level::ConsistencyLevel;
 pub use self::expiration::Expiration;
 pub use self::generation_policy::GenerationPolicy;
+pub use self::large_object_policy::LargeObjectPolicy;
+pub use self::multi_cluster_policy::{DualWrite, MultiClusterPolicy};
 pub use self::query_duration::QueryDuration;
 pub use self::query_policy::QueryPolicy;
 pub use self::read_policy::ReadPolicy;
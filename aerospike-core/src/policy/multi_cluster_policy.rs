@@ -0,0 +1,95 @@
+// Copyright 2015-2018 Aerospike, Inc.
+//
+// Portions may be licensed to Aerospike, Inc. under one or more contributor
+// license agreements.
+//
+// Licensed under the Apache License, Version 2.0 (the "License"); you may not
+// use this file except in compliance with the License. You may obtain a copy of
+// the License at http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS, WITHOUT
+// WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied. See the
+// License for the specific language governing permissions and limitations under
+// the License.
+
+/// `DualWrite` defines whether writes of a [`MultiClusterClient`](crate::MultiClusterClient)
+/// are also sent to the clusters that are not active.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum DualWrite {
+    /// Writes are only sent to the active cluster. Other clusters are expected to be kept in
+    /// sync by the server, e.g. with XDR.
+    #[default]
+    Disabled,
+
+    /// Writes are sent to all clusters concurrently, and succeed if the active cluster's write
+    /// succeeds. Failures on other clusters are logged and counted in their health.
+    BestEffort,
+
+    /// Writes are sent to all clusters concurrently, and succeed only if every cluster's write
+    /// succeeds.
+    Required,
+}
+
+/// `MultiClusterPolicy` encapsulates parameters for routing commands of a
+/// [`MultiClusterClient`](crate::MultiClusterClient).
+#[derive(Debug, Clone)]
+pub struct MultiClusterPolicy {
+    /// Name of the cluster reads are sent to first, e.g. the one in the local data center.
+    /// Reads fail over to the other clusters in the order they were given to the client.
+    ///
+    /// Default: the first cluster
+    pub preferred_read_cluster: Option<String>,
+
+    /// Name of the cluster writes are sent to.
+    ///
+    /// Default: the first cluster
+    pub active_cluster: Option<String>,
+
+    /// Whether writes are also sent to the clusters that are not active.
+    ///
+    /// Default: `DualWrite::Disabled`
+    pub dual_write: DualWrite,
+
+    /// Whether writes fail over to the next available cluster when the active one cannot be
+    /// reached. Writes fail over only when they were not sent to the active cluster, e.g. on
+    /// connection errors; a write that times out may have been applied, and fails instead.
+    /// Leave this disabled for active-passive setups where only a manual switchover may change
+    /// the cluster taking writes.
+    ///
+    /// Default: false
+    pub failover_writes: bool,
+
+    /// Number of consecutive connection, timeout or availability errors after which a cluster
+    /// is considered unavailable, and commands skip it while other clusters are available.
+    ///
+    /// Default: 5
+    pub failure_threshold: usize,
+
+    /// Time in milliseconds an unavailable cluster is skipped before commands try it again. A
+    /// successful command marks the cluster available again, another failure restarts the
+    /// interval.
+    ///
+    /// Default: 10000
+    pub recovery_interval: u32,
+}
+
+impl MultiClusterPolicy {
+    /// Creates a new multi-cluster policy instance.
+    pub fn new() -> Self {
+        MultiClusterPolicy::default()
+    }
+}
+
+impl Default for MultiClusterPolicy {
+    fn default() -> Self {
+        MultiClusterPolicy {
+            preferred_read_cluster: None,
+            active_cluster: None,
+            dual_write: DualWrite::default(),
+            failover_writes: false,
+            failure_threshold: 5,
+            recovery_interval: 10_000,
+        }
+    }
+}
@@ -17,7 +17,7 @@ use crate::commands::{buffer::Buffer, ParticleType};
 use crate::errors::Result;
 use crate::expressions::Expression;
 use crate::operations::CdtContext;
-use crate::{CollectionIndexType, Value};
+use crate::{CollectionIndexType, GeoPoint, Geometry, ToGeoJson, Value};
 
 /// Marker trait for types valid in equality and contains filters.
 ///
@@ -447,6 +447,40 @@ impl Filter {
         )
     }
 
+    /// Creates a geo-spatial "points within region" filter for queries with a typed region, like
+    /// a [`GeoPolygon`](crate::GeoPolygon) or an [`AeroCircle`](crate::AeroCircle).
+    ///
+    /// For queries on a collection index, use [`Filter::geo_within_geometry_cit`].
+    pub fn geo_within_geometry(bin_name: &str, region: impl Into<Geometry>) -> Self {
+        Filter::geo_within_region(bin_name, &region.into().to_geojson())
+    }
+
+    /// Creates a geo-spatial "points within region" filter for queries on a collection index
+    /// with a typed region.
+    pub fn geo_within_geometry_cit(
+        bin_name: &str,
+        region: impl Into<Geometry>,
+        cit: CollectionIndexType,
+    ) -> Self {
+        Filter::geo_within_region_cit(bin_name, &region.into().to_geojson(), cit)
+    }
+
+    /// Creates a geo-spatial "points within region" filter targeting a specific secondary index
+    /// by name with a typed region.
+    pub fn geo_within_geometry_by_index(index_name: &str, region: impl Into<Geometry>) -> Self {
+        Filter::geo_within_region_by_index(index_name, &region.into().to_geojson())
+    }
+
+    /// Creates a geo-spatial "points within region" filter targeting a specific secondary index
+    /// by name on a collection index with a typed region.
+    pub fn geo_within_geometry_by_index_cit(
+        index_name: &str,
+        region: impl Into<Geometry>,
+        cit: CollectionIndexType,
+    ) -> Self {
+        Filter::geo_within_region_by_index_cit(index_name, &region.into().to_geojson(), cit)
+    }
+
     // ========================================================================
     // Geo "within radius" filters
     // ========================================================================
@@ -563,6 +597,39 @@ impl Filter {
         Filter::new_by_index(index_name, cit, ParticleType::GEOJSON, point.clone(), point)
     }
 
+    /// Creates a geo-spatial "regions containing point" filter for queries with a typed point.
+    ///
+    /// For queries on a collection index, use [`Filter::geo_contains_point_cit`].
+    pub fn geo_contains_point(bin_name: &str, point: GeoPoint) -> Self {
+        Filter::geo_contains(bin_name, &point.to_geojson())
+    }
+
+    /// Creates a geo-spatial "regions containing point" filter for queries on a collection index
+    /// with a typed point.
+    pub fn geo_contains_point_cit(
+        bin_name: &str,
+        point: GeoPoint,
+        cit: CollectionIndexType,
+    ) -> Self {
+        Filter::geo_contains_cit(bin_name, &point.to_geojson(), cit)
+    }
+
+    /// Creates a geo-spatial "regions containing point" filter targeting a specific secondary
+    /// index by name with a typed point.
+    pub fn geo_contains_point_by_index(index_name: &str, point: GeoPoint) -> Self {
+        Filter::geo_contains_by_index(index_name, &point.to_geojson())
+    }
+
+    /// Creates a geo-spatial "regions containing point" filter targeting a specific secondary
+    /// index by name on a collection index with a typed point.
+    pub fn geo_contains_point_by_index_cit(
+        index_name: &str,
+        point: GeoPoint,
+        cit: CollectionIndexType,
+    ) -> Self {
+        Filter::geo_contains_by_index_cit(index_name, &point.to_geojson(), cit)
+    }
+
     // ========================================================================
     // Builder methods
     // ========================================================================
@@ -936,6 +1003,23 @@ mod tests {
         assert_eq!(f.bin_name, "bin1");
     }
 
+    #[test]
+    fn geo_typed_regions() {
+        use crate::{AeroCircle, GeoPoint};
+        let center = GeoPoint::new(-122.0, 37.5).unwrap();
+        let circle = AeroCircle::new(center, 50000.0).unwrap();
+
+        let f = Filter::geo_within_geometry("bin1", circle);
+        assert_eq!(f.bin_name, "bin1");
+        assert_eq!(f.begin, Value::String(circle.to_string()));
+        assert_eq!(f.end, f.begin);
+
+        let f = Filter::geo_contains_point_by_index_cit("idx", center, CollectionIndexType::List);
+        assert_eq!(f.index_name.as_deref(), Some("idx"));
+        assert_eq!(f.begin, Value::String(center.to_string()));
+        assert!(matches!(f.collection_index_type, CollectionIndexType::List));
+    }
+
     #[test]
     fn geo_within_region_with_expression() {
         use crate::expressions;
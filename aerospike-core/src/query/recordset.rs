@@ -22,34 +22,37 @@ use async_channel::{Receiver, Sender};
 
 use crate::errors::Result;
 use crate::query::{PartitionFilter, PartitionTracker};
-use crate::Record;
+use crate::{FromBins, Record};
 
 /// A stream over incoming records for a [`Recordset`] that can be iterated over either synchronously or asynchronously.
-pub struct RecordStream(Arc<Recordset>);
+pub struct RecordStream<T: FromBins = Record>(Arc<Recordset<T>>);
 
 /// Virtual collection of records retrieved through queries and scans.
 ///
 /// During a query/scan, multiple threads will retrieve records from the server nodes and put
 /// these records on an internal queue managed by the recordset. The single user thread consumes
 /// these records from the queue.
+///
+/// Records are decoded into `T` as they are read from the connection, see
+/// [`Client::query_as`](crate::Client::query_as).
 #[derive(Debug)]
-pub struct Recordset {
+pub struct Recordset<T: FromBins = Record> {
     instances: AtomicUsize,
-    rx: Receiver<Result<Record>>,
-    tx: Sender<Result<Record>>,
+    rx: Receiver<Result<T>>,
+    tx: Sender<Result<T>>,
     active: AtomicBool,
     task_id: AtomicU64,
     pub(crate) tracker: Arc<Mutex<PartitionTracker>>,
 }
 
-impl Drop for Recordset {
+impl<T: FromBins> Drop for Recordset<T> {
     fn drop(&mut self) {
         // close the recordset to finish all the commands sending data
         self.close();
     }
 }
 
-impl Recordset {
+impl<T: FromBins> Recordset<T> {
     pub(crate) fn new(
         rec_queue_size: usize,
         nodes: usize,
@@ -94,7 +97,7 @@ impl Recordset {
         let _ = self.tx.clone().send(Err(e)).await;
     }
 
-    pub(crate) async fn push(&self, record: Result<Record>) -> Result<()> {
+    pub(crate) async fn push(&self, record: Result<T>) -> Result<()> {
         match record {
             // Do not emit stream termination errors; they are used as signals only.
             Err(crate::Error::StreamTerminatedError()) => Ok(()),
@@ -127,23 +130,23 @@ impl Recordset {
 
     #[cfg(feature = "sync")]
     /// Returns a result from the queue if it exists. Otherwise, returns None.
-    pub fn next_record(&self) -> Option<Result<Record>> {
+    pub fn next_record(&self) -> Option<Result<T>> {
         self.rx.try_recv().ok()
     }
 
     /// Converts a reference to a [`Recordset`] into a [`RecordStream`] that can be used
     /// to iterate over records.
-    pub const fn into_stream(self: Arc<Self>) -> RecordStream {
+    pub const fn into_stream(self: Arc<Self>) -> RecordStream<T> {
         RecordStream(self)
     }
 }
 
 #[cfg(feature = "sync")]
-impl Iterator for &Recordset {
-    type Item = Result<Record>;
+impl<T: FromBins> Iterator for &Recordset<T> {
+    type Item = Result<T>;
 
     /// Implements a blocking iterator.
-    fn next(&mut self) -> Option<Result<Record>> {
+    fn next(&mut self) -> Option<Result<T>> {
         use futures::executor::block_on;
 
         loop {
@@ -163,8 +166,8 @@ impl Iterator for &Recordset {
     }
 }
 
-impl futures::Stream for RecordStream {
-    type Item = Result<Record>;
+impl<T: FromBins> futures::Stream for RecordStream<T> {
+    type Item = Result<T>;
 
     fn poll_next(
         self: std::pin::Pin<&mut Self>,
@@ -184,15 +187,15 @@ impl futures::Stream for RecordStream {
     }
 }
 
-impl AsRef<Recordset> for RecordStream {
-    fn as_ref(&self) -> &Recordset {
+impl<T: FromBins> AsRef<Recordset<T>> for RecordStream<T> {
+    fn as_ref(&self) -> &Recordset<T> {
         &self.0
     }
 }
 
 /// If the record stream is inactive, it will extract the `PartitionFilter` cursor to use in a future scan/query.
 /// It will still return nil if the `PartitionFilter` is already extracted.
-impl RecordStream {
+impl<T: FromBins> RecordStream<T> {
     /// Returns the partition filter from the recordset.
     pub async fn partition_filter(&self) -> Option<PartitionFilter> {
         self.0.partition_filter().await
@@ -17,9 +17,11 @@
 use serde::Serialize;
 
 use std::collections::HashMap;
+use std::convert::TryFrom;
 use std::fmt;
 use std::time::{Duration, SystemTime, UNIX_EPOCH};
 
+use crate::EpochNanos;
 use crate::Key;
 use crate::Value;
 
@@ -46,8 +48,10 @@ pub struct Record {
 }
 
 impl Record {
-    /// Construct a new Record. For internal use only.
-    pub(crate) const fn new(
+    /// Construct a new Record, e.g. to evaluate expressions against it locally. The expiration
+    /// is the record's void time in seconds since Jan 01 2010, 00:00:00 UTC, or 0 if the record
+    /// never expires.
+    pub const fn new(
         key: Option<Key>,
         bins: HashMap<String, Value>,
         generation: u32,
@@ -61,6 +65,11 @@ impl Record {
         }
     }
 
+    /// Returns the record's void time in seconds since Jan 01 2010, 00:00:00 UTC.
+    pub(crate) const fn expiration(&self) -> u32 {
+        self.expiration
+    }
+
     /// Returns the remaining time-to-live (TTL, a.k.a. expiration time) for the record or `None`
     /// if the record never expires.
     #[allow(clippy::option_if_let_else)]
@@ -78,6 +87,50 @@ impl Record {
             }
         }
     }
+
+    /// Returns the point in time at which the record expires, or `None` if the record never
+    /// expires.
+    pub fn expiration_time(&self) -> Option<SystemTime> {
+        match self.expiration {
+            0 => None,
+            secs_since_epoch => {
+                Some(*CITRUSLEAF_EPOCH + Duration::from_secs(u64::from(secs_since_epoch)))
+            }
+        }
+    }
+
+    /// Returns the point in time at which the record was last updated, read from the given bin.
+    ///
+    /// The server does not return the last update time with the record header, so it has to be
+    /// requested together with the record by reading the
+    /// [`last_update()`](crate::expressions::last_update) expression into a bin:
+    ///
+    /// ```rust,edition2021
+    /// # use aerospike::*;
+    /// # use aerospike::expressions::last_update;
+    /// # use aerospike::operations::exp::{read_exp, ExpReadFlags};
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// # let hosts = std::env::var("AEROSPIKE_HOSTS").unwrap();
+    /// # let client = Client::new(&ClientPolicy::default(), &hosts).await.unwrap();
+    /// let key = as_key!("test", "test", "mykey");
+    /// let ops = vec![
+    ///     operations::get(),
+    ///     read_exp("lut", &last_update(), ExpReadFlags::Default),
+    /// ];
+    /// let record = client.operate(&WritePolicy::default(), &key, &ops).await.unwrap();
+    /// println!("last updated: {:?}", record.last_update_time("lut"));
+    /// # }
+    /// ```
+    ///
+    /// Returns `None` if the bin does not exist or does not hold nanoseconds since the Unix
+    /// epoch.
+    pub fn last_update_time(&self, bin: &str) -> Option<SystemTime> {
+        let nanos = self.bins.get(bin)?.clone();
+        EpochNanos::<SystemTime>::try_from(nanos)
+            .ok()
+            .map(|time| time.0)
+    }
 }
 
 impl fmt::Display for Record {
@@ -102,8 +155,9 @@ impl fmt::Display for Record {
 #[cfg(test)]
 mod tests {
     use super::{Record, CITRUSLEAF_EPOCH};
+    use crate::Value;
     use std::collections::HashMap;
-    use std::time::{Duration, SystemTime};
+    use std::time::{Duration, SystemTime, UNIX_EPOCH};
 
     #[test]
     fn ttl_expiration_future() {
@@ -128,5 +182,28 @@ mod tests {
     fn ttl_never_expires() {
         let record = Record::new(None, HashMap::new(), 0, 0);
         assert_eq!(record.time_to_live(), None);
+        assert_eq!(record.expiration_time(), None);
+    }
+
+    #[test]
+    fn expiration_and_last_update_time() {
+        let mut bins = HashMap::new();
+        bins.insert(
+            "lut".to_string(),
+            Value::from(1_600_000_000_123_456_789_i64),
+        );
+        bins.insert("name".to_string(), Value::from("x"));
+        let record = Record::new(None, bins, 0, 100);
+
+        assert_eq!(
+            record.expiration_time(),
+            Some(*CITRUSLEAF_EPOCH + Duration::from_secs(100))
+        );
+        assert_eq!(
+            record.last_update_time("lut"),
+            Some(UNIX_EPOCH + Duration::new(1_600_000_000, 123_456_789))
+        );
+        assert_eq!(record.last_update_time("name"), None);
+        assert_eq!(record.last_update_time("missing"), None);
     }
 }
@@ -0,0 +1,823 @@
+// Copyright 2015-2020 Aerospike, Inc.
+//
+// Portions may be licensed to Aerospike, Inc. under one or more contributor
+// license agreements.
+//
+// Licensed under the Apache License, Version 2.0 (the "License"); you may not
+// use this file except in compliance with the License. You may obtain a copy of
+// the License at http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS, WITHOUT
+// WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied. See the
+// License for the specific language governing permissions and limitations under
+// the License.
+
+use std::collections::{BTreeMap, HashMap};
+use std::convert::TryInto;
+use std::str;
+use std::time::{Duration, SystemTime};
+
+use crate::codec::BinCodecs;
+use crate::commands::ParticleType;
+use crate::errors::{Error, Result};
+use crate::operations::MapOrder;
+use crate::{FloatValue, FromBins, Key, Record, RecordMeta, Value};
+
+/// Borrowed view of a record, passed to the callback of
+/// [`Client::get_with`](crate::Client::get_with).
+///
+/// The view points into the buffer of the connection the record was read from, so string and
+/// blob bins are available as slices and lists and maps are decoded lazily, without copying.
+/// Use [`BinView::to_value`] or [`RecordView::to_record`] for the data that has to outlive the
+/// callback.
+#[derive(Debug, Clone)]
+pub struct RecordView<'a> {
+    generation: u32,
+    expiration: u32,
+    bins: Vec<BinView<'a>>,
+}
+
+impl<'a> RecordView<'a> {
+    // Parses the field and operation headers of a record response body. The particle data is
+    // not decoded until it is accessed.
+    pub(crate) fn parse(
+        mut data: &'a [u8],
+        field_count: usize,
+        op_count: usize,
+        generation: u32,
+        expiration: u32,
+    ) -> Result<Self> {
+        for _ in 0..field_count {
+            let field_size = read_u32(&mut data)? as usize;
+            take(&mut data, field_size)?;
+        }
+
+        let mut bins = Vec::with_capacity(op_count);
+        for _ in 0..op_count {
+            let op_size = read_u32(&mut data)? as usize;
+            let header = take(&mut data, 4)?;
+            let name_size = header[3] as usize;
+            let name = str::from_utf8(take(&mut data, name_size)?)?;
+            let particle_size = op_size.checked_sub(4 + name_size).ok_or_else(truncated)?;
+            bins.push(BinView {
+                name,
+                particle_type: header[1],
+                data: take(&mut data, particle_size)?,
+            });
+        }
+
+        Ok(RecordView {
+            generation,
+            expiration,
+            bins,
+        })
+    }
+
+    /// Returns the record modification count.
+    pub const fn generation(&self) -> u32 {
+        self.generation
+    }
+
+    /// Returns the remaining time-to-live of the record, or `None` if the record never expires.
+    pub fn time_to_live(&self) -> Option<Duration> {
+        self.header().time_to_live()
+    }
+
+    /// Returns the point in time at which the record expires, or `None` if the record never
+    /// expires.
+    pub fn expiration_time(&self) -> Option<SystemTime> {
+        self.header().expiration_time()
+    }
+
+    /// Returns the bins of the record, in the order returned by the server.
+    pub fn bins(&self) -> &[BinView<'a>] {
+        &self.bins
+    }
+
+    /// Returns the bin with the given name.
+    pub fn bin(&self, name: &str) -> Option<&BinView<'a>> {
+        self.bins.iter().find(|bin| bin.name == name)
+    }
+
+    /// Copies the view into an owned record.
+    pub fn to_record(&self) -> Result<Record> {
+        let mut bins = HashMap::with_capacity(self.bins.len());
+        for bin in &self.bins {
+            let value = bin.to_value()?;
+            if !value.is_nil() {
+                bins.insert(bin.name.to_string(), value);
+            }
+        }
+        Ok(Record::new(None, bins, self.generation, self.expiration))
+    }
+
+    /// Decodes the record into a [`FromBins`] type. Bins with nil values are skipped.
+    pub fn decode<T: FromBins>(&self) -> Result<T> {
+        let mut builder = T::Builder::default();
+        for bin in &self.bins {
+            if bin.particle_type != ParticleType::NULL as u8 {
+                T::decode_bin(&mut builder, bin)?;
+            }
+        }
+        self.build(builder)
+    }
+
+    // Like `decode`, but reverses the bin codecs of the record with the given key first.
+    pub(crate) fn decode_with<T: FromBins>(&self, codecs: &BinCodecs, key: &Key) -> Result<T> {
+        let mut builder = T::Builder::default();
+        for bin in &self.bins {
+            if bin.particle_type != ParticleType::NULL as u8 {
+                codecs.decode_bin::<T>(key, &mut builder, bin)?;
+            }
+        }
+        self.build(builder)
+    }
+
+    fn build<T: FromBins>(&self, builder: T::Builder) -> Result<T> {
+        T::build(
+            builder,
+            RecordMeta {
+                key: None,
+                generation: self.generation,
+                expiration: self.expiration,
+            },
+        )
+    }
+
+    fn header(&self) -> Record {
+        Record::new(None, HashMap::new(), self.generation, self.expiration)
+    }
+}
+
+/// Borrowed view of a single bin of a [`RecordView`].
+#[derive(Debug, Clone, Copy)]
+pub struct BinView<'a> {
+    name: &'a str,
+    particle_type: u8,
+    data: &'a [u8],
+}
+
+impl<'a> BinView<'a> {
+    pub(crate) const fn new(name: &'a str, particle_type: u8, data: &'a [u8]) -> Self {
+        BinView {
+            name,
+            particle_type,
+            data,
+        }
+    }
+
+    // Creates a view of a bin from the name and particle data of an operation.
+    pub(crate) fn from_op(particle_type: u8, data: &'a [u8], name_size: usize) -> Result<Self> {
+        if data.len() < name_size {
+            return Err(truncated());
+        }
+        let (name, data) = data.split_at(name_size);
+        Ok(BinView {
+            name: str::from_utf8(name)?,
+            particle_type,
+            data,
+        })
+    }
+
+    /// Returns the name of the bin.
+    pub const fn name(&self) -> &'a str {
+        self.name
+    }
+
+    /// Returns the type of the bin value.
+    pub fn particle_type(&self) -> ParticleType {
+        ParticleType::from(self.particle_type)
+    }
+
+    /// Returns the bin value as encoded by the server.
+    pub const fn raw(&self) -> &'a [u8] {
+        self.data
+    }
+
+    /// Decodes the bin value into a borrowed view.
+    pub fn value(&self) -> Result<ValueView<'a>> {
+        let data = self.data;
+        match self.particle_type() {
+            ParticleType::NULL => Ok(ValueView::Nil),
+            ParticleType::INTEGER => Ok(ValueView::Int(i64::from_be_bytes(fixed(data)?))),
+            ParticleType::FLOAT => Ok(ValueView::Float(f64::from_be_bytes(fixed(data)?))),
+            ParticleType::STRING => Ok(ValueView::String(str::from_utf8(data)?)),
+            ParticleType::BLOB => Ok(ValueView::Blob(data)),
+            ParticleType::HLL => Ok(ValueView::HLL(data)),
+            ParticleType::BOOL => Ok(ValueView::Bool(data.first().is_some_and(|b| *b != 0))),
+            ParticleType::GEOJSON => {
+                // Flags, cell count and cells precede the GeoJSON string.
+                let mut data = data;
+                let header = take(&mut data, 3)?;
+                let ncells = u16::from_be_bytes([header[1], header[2]]) as usize;
+                take(&mut data, ncells * 8)?;
+                Ok(ValueView::GeoJSON(str::from_utf8(data)?))
+            }
+            ParticleType::LIST if data.is_empty() => Ok(ValueView::List(ListView::default())),
+            ParticleType::MAP if data.is_empty() => Ok(ValueView::Map(MapView::default())),
+            ParticleType::LIST | ParticleType::MAP => {
+                let mut data = data;
+                unpack_value(&mut data)
+            }
+            ParticleType::DIGEST | ParticleType::LDT => Err(Error::ClientError(format!(
+                "Bin {} has unsupported particle type {}",
+                self.name, self.particle_type
+            ))),
+        }
+    }
+
+    /// Returns the value of a string or `GeoJSON` bin.
+    pub fn as_str(&self) -> Option<&'a str> {
+        match self.value() {
+            Ok(ValueView::String(val) | ValueView::GeoJSON(val)) => Some(val),
+            _ => None,
+        }
+    }
+
+    /// Returns the value of a blob or HLL bin.
+    pub fn as_blob(&self) -> Option<&'a [u8]> {
+        match self.particle_type() {
+            ParticleType::BLOB | ParticleType::HLL => Some(self.data),
+            _ => None,
+        }
+    }
+
+    /// Returns the value of an integer bin.
+    pub fn as_i64(&self) -> Option<i64> {
+        self.value().ok()?.as_i64()
+    }
+
+    /// Returns the value of a float bin.
+    pub fn as_f64(&self) -> Option<f64> {
+        self.value().ok()?.as_f64()
+    }
+
+    /// Returns the value of a boolean bin.
+    pub fn as_bool(&self) -> Option<bool> {
+        self.value().ok()?.as_bool()
+    }
+
+    /// Returns a lazy iterator over the elements of a list bin.
+    pub fn as_list(&self) -> Option<ListView<'a>> {
+        match self.value() {
+            Ok(ValueView::List(list)) => Some(list),
+            _ => None,
+        }
+    }
+
+    /// Returns a lazy iterator over the entries of a map bin.
+    pub fn as_map(&self) -> Option<MapView<'a>> {
+        match self.value() {
+            Ok(ValueView::Map(map)) => Some(map),
+            _ => None,
+        }
+    }
+
+    /// Copies the bin value into an owned [`Value`].
+    pub fn to_value(&self) -> Result<Value> {
+        self.value()?.to_value()
+    }
+}
+
+/// Borrowed view of a bin value or of an element of a list or map bin.
+#[derive(Debug, Clone, Copy)]
+pub enum ValueView<'a> {
+    /// Empty value.
+    Nil,
+    /// Boolean value.
+    Bool(bool),
+    /// Integer value.
+    Int(i64),
+    /// Floating point value.
+    Float(f64),
+    /// String value.
+    String(&'a str),
+    /// Byte array value.
+    Blob(&'a [u8]),
+    /// `GeoJSON` value.
+    GeoJSON(&'a str),
+    /// `HyperLogLog` value.
+    HLL(&'a [u8]),
+    /// List value, decoded on iteration.
+    List(ListView<'a>),
+    /// Map value, decoded on iteration.
+    Map(MapView<'a>),
+}
+
+impl<'a> ValueView<'a> {
+    /// Returns the integer value.
+    pub const fn as_i64(&self) -> Option<i64> {
+        match self {
+            ValueView::Int(val) => Some(*val),
+            _ => None,
+        }
+    }
+
+    /// Returns the float value.
+    pub const fn as_f64(&self) -> Option<f64> {
+        match self {
+            ValueView::Float(val) => Some(*val),
+            _ => None,
+        }
+    }
+
+    /// Returns the boolean value.
+    pub const fn as_bool(&self) -> Option<bool> {
+        match self {
+            ValueView::Bool(val) => Some(*val),
+            _ => None,
+        }
+    }
+
+    /// Returns the string or `GeoJSON` value.
+    pub const fn as_str(&self) -> Option<&'a str> {
+        match self {
+            ValueView::String(val) | ValueView::GeoJSON(val) => Some(val),
+            _ => None,
+        }
+    }
+
+    /// Returns the blob or HLL value.
+    pub const fn as_blob(&self) -> Option<&'a [u8]> {
+        match self {
+            ValueView::Blob(val) | ValueView::HLL(val) => Some(val),
+            _ => None,
+        }
+    }
+
+    /// Returns the list value.
+    pub const fn as_list(&self) -> Option<ListView<'a>> {
+        match self {
+            ValueView::List(list) => Some(*list),
+            _ => None,
+        }
+    }
+
+    /// Returns the map value.
+    pub const fn as_map(&self) -> Option<MapView<'a>> {
+        match self {
+            ValueView::Map(map) => Some(*map),
+            _ => None,
+        }
+    }
+
+    /// Copies the value into an owned [`Value`].
+    pub fn to_value(&self) -> Result<Value> {
+        Ok(match *self {
+            ValueView::Nil => Value::Nil,
+            ValueView::Bool(val) => Value::Bool(val),
+            ValueView::Int(val) => Value::Int(val),
+            ValueView::Float(val) => Value::Float(FloatValue::from(val)),
+            ValueView::String(val) => Value::String(val.to_string()),
+            ValueView::Blob(val) => Value::Blob(val.to_vec()),
+            ValueView::GeoJSON(val) => Value::GeoJSON(val.to_string()),
+            ValueView::HLL(val) => Value::HLL(val.to_vec()),
+            ValueView::List(list) => {
+                Value::List(list.map(|item| item?.to_value()).collect::<Result<_>>()?)
+            }
+            ValueView::Map(map) => map.to_value()?,
+        })
+    }
+}
+
+/// Lazy iterator over the elements of a list, yielding an error if the list is malformed.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ListView<'a> {
+    data: &'a [u8],
+    remaining: usize,
+}
+
+impl ListView<'_> {
+    /// Returns the number of elements that have not been iterated over yet.
+    pub const fn len(&self) -> usize {
+        self.remaining
+    }
+
+    /// Returns whether all elements have been iterated over.
+    pub const fn is_empty(&self) -> bool {
+        self.remaining == 0
+    }
+}
+
+impl<'a> Iterator for ListView<'a> {
+    type Item = Result<ValueView<'a>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.remaining == 0 {
+            return None;
+        }
+        self.remaining -= 1;
+        let item = unpack_value(&mut self.data);
+        if item.is_err() {
+            self.remaining = 0;
+        }
+        Some(item)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (0, Some(self.remaining))
+    }
+}
+
+/// Lazy iterator over the entries of a map, yielding an error if the map is malformed.
+#[derive(Debug, Clone, Copy)]
+pub struct MapView<'a> {
+    data: &'a [u8],
+    remaining: usize,
+    order: MapOrder,
+}
+
+impl Default for MapView<'_> {
+    fn default() -> Self {
+        MapView {
+            data: &[],
+            remaining: 0,
+            order: MapOrder::Unordered,
+        }
+    }
+}
+
+impl MapView<'_> {
+    /// Returns the number of entries that have not been iterated over yet.
+    pub const fn len(&self) -> usize {
+        self.remaining
+    }
+
+    /// Returns whether all entries have been iterated over.
+    pub const fn is_empty(&self) -> bool {
+        self.remaining == 0
+    }
+
+    /// Returns the order of the map. Entries of key ordered maps and of index and rank range
+    /// results are iterated in that order.
+    pub const fn order(&self) -> MapOrder {
+        self.order
+    }
+
+    fn to_value(self) -> Result<Value> {
+        let order = self.order;
+        let entries = self
+            .map(|entry| {
+                let (key, val) = entry?;
+                Ok((key.to_value()?, val.to_value()?))
+            })
+            .collect::<Result<Vec<_>>>()?;
+        Ok(match order {
+            MapOrder::Unordered => Value::HashMap(entries.into_iter().collect::<HashMap<_, _>>()),
+            MapOrder::KeyOrdered => {
+                Value::OrderedMap(entries.into_iter().collect::<BTreeMap<_, _>>())
+            }
+            MapOrder::KeyValueOrdered => Value::KeyValueList(entries),
+        })
+    }
+}
+
+impl<'a> Iterator for MapView<'a> {
+    type Item = Result<(ValueView<'a>, ValueView<'a>)>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.remaining == 0 {
+            return None;
+        }
+        self.remaining -= 1;
+        let entry =
+            unpack_value(&mut self.data).and_then(|key| Ok((key, unpack_value(&mut self.data)?)));
+        if entry.is_err() {
+            self.remaining = 0;
+        }
+        Some(entry)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (0, Some(self.remaining))
+    }
+}
+
+fn truncated() -> Error {
+    Error::BadResponse("Unexpected end of record data".to_string())
+}
+
+fn take<'a>(data: &mut &'a [u8], len: usize) -> Result<&'a [u8]> {
+    if data.len() < len {
+        return Err(truncated());
+    }
+    let (head, tail) = data.split_at(len);
+    *data = tail;
+    Ok(head)
+}
+
+fn fixed<const N: usize>(data: &[u8]) -> Result<[u8; N]> {
+    data.get(..N)
+        .and_then(|bytes| bytes.try_into().ok())
+        .ok_or_else(truncated)
+}
+
+fn read_u8(data: &mut &[u8]) -> Result<u8> {
+    Ok(take(data, 1)?[0])
+}
+
+fn read_u16(data: &mut &[u8]) -> Result<u16> {
+    Ok(u16::from_be_bytes(fixed(take(data, 2)?)?))
+}
+
+fn read_u32(data: &mut &[u8]) -> Result<u32> {
+    Ok(u32::from_be_bytes(fixed(take(data, 4)?)?))
+}
+
+fn read_u64(data: &mut &[u8]) -> Result<u64> {
+    Ok(u64::from_be_bytes(fixed(take(data, 8)?)?))
+}
+
+// Same wire format as `msgpack::decoder`, but borrowing from the data instead of copying.
+// Lists and maps are skipped over to find their extent, and decoded when iterated.
+fn unpack_value<'a>(data: &mut &'a [u8]) -> Result<ValueView<'a>> {
+    let obj_type = read_u8(data)?;
+
+    match obj_type {
+        0x00..=0x7f => Ok(ValueView::Int(i64::from(obj_type))),
+        0x80..=0x8f => unpack_map(data, (obj_type & 0x0f) as usize),
+        0x90..=0x9f => unpack_list(data, (obj_type & 0x0f) as usize),
+        0xa0..=0xbf => unpack_blob(data, (obj_type & 0x1f) as usize),
+        0xc0 => Ok(ValueView::Nil),
+        0xc2 => Ok(ValueView::Bool(false)),
+        0xc3 => Ok(ValueView::Bool(true)),
+        0xc4 | 0xd9 => {
+            let count = read_u8(data)?;
+            unpack_blob(data, count as usize)
+        }
+        0xc5 | 0xda => {
+            let count = read_u16(data)?;
+            unpack_blob(data, count as usize)
+        }
+        0xc6 | 0xdb => {
+            let count = read_u32(data)?;
+            unpack_blob(data, count as usize)
+        }
+        0xc7..=0xc9 | 0xd4..=0xd8 => {
+            // Type extensions are skipped, as done by the owned decoder.
+            skip_ext(data, obj_type)?;
+            Ok(ValueView::Nil)
+        }
+        0xca => Ok(ValueView::Float(f64::from(f32::from_be_bytes(fixed(
+            take(data, 4)?,
+        )?)))),
+        0xcb => Ok(ValueView::Float(f64::from_be_bytes(fixed(take(data, 8)?)?))),
+        0xcc => Ok(ValueView::Int(i64::from(read_u8(data)?))),
+        0xcd => Ok(ValueView::Int(i64::from(read_u16(data)?))),
+        0xce => Ok(ValueView::Int(i64::from(read_u32(data)?))),
+        0xcf | 0xd3 => Ok(ValueView::Int(read_u64(data)? as i64)),
+        0xd0 => Ok(ValueView::Int(i64::from(read_u8(data)? as i8))),
+        0xd1 => Ok(ValueView::Int(i64::from(read_u16(data)? as i16))),
+        0xd2 => Ok(ValueView::Int(i64::from(read_u32(data)? as i32))),
+        0xdc => {
+            let count = read_u16(data)?;
+            unpack_list(data, count as usize)
+        }
+        0xdd => {
+            let count = read_u32(data)?;
+            unpack_list(data, count as usize)
+        }
+        0xde => {
+            let count = read_u16(data)?;
+            unpack_map(data, count as usize)
+        }
+        0xdf => {
+            let count = read_u32(data)?;
+            unpack_map(data, count as usize)
+        }
+        0xe0..=0xff => Ok(ValueView::Int(i64::from(obj_type as i8))),
+        _ => Err(Error::BadResponse(format!(
+            "Error unpacking value of type '{obj_type:x}'"
+        ))),
+    }
+}
+
+fn unpack_blob<'a>(data: &mut &'a [u8], count: usize) -> Result<ValueView<'a>> {
+    if count == 0 {
+        return Err(truncated());
+    }
+    let vtype = read_u8(data)?;
+    let bytes = take(data, count - 1)?;
+
+    match ParticleType::from(vtype) {
+        ParticleType::STRING => Ok(ValueView::String(str::from_utf8(bytes)?)),
+        ParticleType::BLOB => Ok(ValueView::Blob(bytes)),
+        ParticleType::GEOJSON => Ok(ValueView::GeoJSON(str::from_utf8(bytes)?)),
+        _ => Err(Error::BadResponse(format!(
+            "Error while unpacking BLOB. Type-header with code `{vtype}` not recognized."
+        ))),
+    }
+}
+
+fn unpack_list<'a>(data: &mut &'a [u8], mut count: usize) -> Result<ValueView<'a>> {
+    if count > 0 && data.first().copied().is_some_and(is_ext) {
+        unpack_value(data)?;
+        count -= 1;
+    }
+
+    let start = *data;
+    skip_values(data, count)?;
+    Ok(ValueView::List(ListView {
+        data: &start[..start.len() - data.len()],
+        remaining: count,
+    }))
+}
+
+fn unpack_map<'a>(data: &mut &'a [u8], mut count: usize) -> Result<ValueView<'a>> {
+    let mut order = MapOrder::Unordered;
+    if count > 0 && data.first().copied().is_some_and(is_ext) {
+        order = map_order(data);
+        skip_values(data, 2)?;
+        count -= 1;
+    }
+
+    let start = *data;
+    skip_values(data, count * 2)?;
+    Ok(ValueView::Map(MapView {
+        data: &start[..start.len() - data.len()],
+        remaining: count,
+        order,
+    }))
+}
+
+fn map_order(data: &[u8]) -> MapOrder {
+    match data {
+        [0xc7, 0, map_bits, ..] if map_bits & 0x08 != 0 => MapOrder::KeyValueOrdered,
+        [0xc7, 0, map_bits, ..] if map_bits & 0x01 != 0 => MapOrder::KeyOrdered,
+        _ => MapOrder::Unordered,
+    }
+}
+
+fn skip_ext(data: &mut &[u8], obj_type: u8) -> Result<()> {
+    let count = match obj_type {
+        0xc7 => read_u8(data)? as usize,
+        0xc8 => read_u16(data)? as usize,
+        0xc9 => read_u32(data)? as usize,
+        _ => 1 << (obj_type - 0xd4),
+    };
+    take(data, 1 + count)?;
+    Ok(())
+}
+
+// Skips over the given number of values without decoding them.
+fn skip_values(data: &mut &[u8], mut count: usize) -> Result<()> {
+    while count > 0 {
+        count -= 1;
+        let obj_type = read_u8(data)?;
+        let len = match obj_type {
+            0x00..=0x7f | 0xc0 | 0xc2 | 0xc3 | 0xe0..=0xff => 0,
+            0x80..=0x8f => {
+                count += (obj_type & 0x0f) as usize * 2;
+                0
+            }
+            0x90..=0x9f => {
+                count += (obj_type & 0x0f) as usize;
+                0
+            }
+            0xa0..=0xbf => (obj_type & 0x1f) as usize,
+            0xc4 | 0xd9 => read_u8(data)? as usize,
+            0xc5 | 0xda => read_u16(data)? as usize,
+            0xc6 | 0xdb => read_u32(data)? as usize,
+            0xc7..=0xc9 | 0xd4..=0xd8 => {
+                skip_ext(data, obj_type)?;
+                0
+            }
+            0xcc | 0xd0 => 1,
+            0xcd | 0xd1 => 2,
+            0xca | 0xce | 0xd2 => 4,
+            0xcb | 0xcf | 0xd3 => 8,
+            0xdc => {
+                count += read_u16(data)? as usize;
+                0
+            }
+            0xdd => {
+                count += read_u32(data)? as usize;
+                0
+            }
+            0xde => {
+                count += read_u16(data)? as usize * 2;
+                0
+            }
+            0xdf => {
+                count += read_u32(data)? as usize * 2;
+                0
+            }
+            _ => {
+                return Err(Error::BadResponse(format!(
+                    "Error unpacking value of type '{obj_type:x}'"
+                )))
+            }
+        };
+        take(data, len)?;
+    }
+    Ok(())
+}
+
+const fn is_ext(byte: u8) -> bool {
+    matches!(byte, 0xc7 | 0xc8 | 0xc9 | 0xd4 | 0xd5 | 0xd6 | 0xd7 | 0xd8)
+}
+
+// Appends a bin to the operations of a response, as the server sends them.
+#[cfg(test)]
+pub fn push_op(data: &mut Vec<u8>, name: &str, particle_type: u8, particle: &[u8]) {
+    let size = (4 + name.len() + particle.len()) as u32;
+    data.extend_from_slice(&size.to_be_bytes());
+    data.extend_from_slice(&[1, particle_type, 0, name.len() as u8]);
+    data.extend_from_slice(name.as_bytes());
+    data.extend_from_slice(particle);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{push_op, RecordView, ValueView};
+    use crate::commands::buffer::Buffer;
+    use crate::commands::ReadCommand;
+    use crate::operations::MapOrder;
+    use crate::Value;
+
+    fn record_data() -> Vec<u8> {
+        let mut data = vec![];
+        push_op(&mut data, "s", 3, b"hello");
+        push_op(&mut data, "b", 4, &[1, 2, 3]);
+        push_op(&mut data, "i", 1, &(-5_i64).to_be_bytes());
+        // [1, "a", [2, 300]]
+        push_op(
+            &mut data,
+            "l",
+            20,
+            &[0x93, 0x01, 0xa2, 0x03, b'a', 0x92, 0x02, 0xcd, 0x01, 0x2c],
+        );
+        // Key ordered map {"x": [7], "y": -1}
+        push_op(
+            &mut data,
+            "m",
+            19,
+            &[
+                0x83, 0xc7, 0x00, 0x01, 0xc0, 0xa2, 0x03, b'x', 0x91, 0x07, 0xa2, 0x03, b'y', 0xff,
+            ],
+        );
+        data
+    }
+
+    #[test]
+    fn borrowed_bins() {
+        let data = record_data();
+        let record = RecordView::parse(&data, 0, 5, 3, 0).unwrap();
+
+        assert_eq!(record.generation(), 3);
+        assert_eq!(record.time_to_live(), None);
+        assert_eq!(record.bins().len(), 5);
+        assert_eq!(record.bin("s").unwrap().as_str(), Some("hello"));
+        assert_eq!(record.bin("b").unwrap().as_blob(), Some(&[1, 2, 3][..]));
+        assert_eq!(record.bin("i").unwrap().as_i64(), Some(-5));
+        assert_eq!(record.bin("i").unwrap().as_str(), None);
+        assert!(record.bin("missing").is_none());
+
+        let mut list = record.bin("l").unwrap().as_list().unwrap();
+        assert_eq!(list.len(), 3);
+        assert_eq!(list.next().unwrap().unwrap().as_i64(), Some(1));
+        assert_eq!(list.next().unwrap().unwrap().as_str(), Some("a"));
+        let nested = list.next().unwrap().unwrap().as_list().unwrap();
+        let nested = nested
+            .map(|v| v.unwrap().as_i64().unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(nested, vec![2, 300]);
+        assert!(list.next().is_none());
+
+        let map = record.bin("m").unwrap().as_map().unwrap();
+        assert!(matches!(map.order(), MapOrder::KeyOrdered));
+        let entries = map.map(Result::unwrap).collect::<Vec<_>>();
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].0.as_str(), Some("x"));
+        assert!(matches!(entries[0].1, ValueView::List(_)));
+        assert_eq!(entries[1].1.as_i64(), Some(-1));
+    }
+
+    #[test]
+    fn same_as_owned_record() {
+        let data = record_data();
+        let view = RecordView::parse(&data, 0, 5, 3, 0)
+            .unwrap()
+            .to_record()
+            .unwrap();
+
+        let mut buffer = Buffer::new(1024);
+        buffer.data_buffer = data;
+        let record = ReadCommand::parse_record(&mut buffer, 5, 0, 3, 0).unwrap();
+        assert_eq!(view.bins, record.bins);
+        assert!(matches!(view.bins["m"], Value::OrderedMap(_)));
+    }
+
+    #[test]
+    fn truncated_data() {
+        let mut data = record_data();
+        data.truncate(data.len() - 1);
+        assert!(RecordView::parse(&data, 0, 5, 3, 0).is_err());
+
+        let mut data = vec![];
+        push_op(&mut data, "l", 20, &[0x92, 0x01]);
+        let record = RecordView::parse(&data, 0, 1, 0, 0).unwrap();
+        assert!(record.bin("l").unwrap().value().is_err());
+    }
+}
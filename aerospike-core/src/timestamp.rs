@@ -0,0 +1,327 @@
+// Copyright 2015-2020 Aerospike, Inc.
+//
+// Portions may be licensed to Aerospike, Inc. under one or more contributor
+// license agreements.
+//
+// Licensed under the Apache License, Version 2.0 (the "License"); you may not
+// use this file except in compliance with the License. You may obtain a copy of
+// the License at http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS, WITHOUT
+// WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied. See the
+// License for the specific language governing permissions and limitations under
+// the License.
+
+// Conversion between points in time and integer values. `SystemTime` is always supported,
+// `chrono::DateTime<Utc>` with the `chrono` feature and `time::OffsetDateTime` with the `time`
+// feature. Points in time are stored as milliseconds since the Unix epoch, or as nanoseconds
+// when wrapped in `EpochNanos`, which is also the unit of the `last_update()` and `void_time()`
+// expressions.
+
+use std::convert::TryFrom;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use crate::Value;
+
+const NANOS_PER_MILLI: i128 = 1_000_000;
+#[cfg(feature = "chrono")]
+const NANOS_PER_SEC: i128 = 1_000_000_000;
+
+/// Wrapper that converts a point in time to and from an integer [`Value`] holding nanoseconds
+/// since the Unix epoch, instead of the default milliseconds.
+///
+/// Nanoseconds can only represent points in time between the years 1677 and 2262; points in time
+/// outside that range are converted to `i64::MIN` or `i64::MAX`.
+///
+/// ```
+/// use std::convert::TryFrom;
+/// use std::time::{Duration, SystemTime, UNIX_EPOCH};
+/// use aerospike::{EpochNanos, Value};
+///
+/// let time = UNIX_EPOCH + Duration::from_nanos(1_500);
+/// assert_eq!(Value::from(time), Value::Int(0));
+/// assert_eq!(Value::from(EpochNanos(time)), Value::Int(1_500));
+///
+/// let EpochNanos(read) = EpochNanos::<SystemTime>::try_from(Value::Int(1_500)).unwrap();
+/// assert_eq!(read, time);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct EpochNanos<T>(pub T);
+
+// Nanoseconds since the Unix epoch, negative for earlier points in time.
+fn system_time_nanos(time: SystemTime) -> i128 {
+    match time.duration_since(UNIX_EPOCH) {
+        Ok(since) => since.as_nanos() as i128,
+        Err(err) => -(err.duration().as_nanos() as i128),
+    }
+}
+
+fn epoch_offset(offset: Duration, before_epoch: bool) -> Option<SystemTime> {
+    if before_epoch {
+        UNIX_EPOCH.checked_sub(offset)
+    } else {
+        UNIX_EPOCH.checked_add(offset)
+    }
+}
+
+// Saturates at the bounds of `i64`, so that points in time out of range still compare correctly.
+fn to_int(val: i128) -> Value {
+    let val = i64::try_from(val).unwrap_or(if val < 0 { i64::MIN } else { i64::MAX });
+    Value::Int(val)
+}
+
+fn from_int<T>(val: &Value) -> Result<i64, String> {
+    match val {
+        Value::Int(val) => Ok(*val),
+        _ => Err(format!(
+            "Invalid type conversion from Value::{} to {}",
+            val.particle_type(),
+            std::any::type_name::<T>()
+        )),
+    }
+}
+
+fn out_of_range<T>(val: i64) -> String {
+    format!(
+        "Value {val} is out of the range of {}",
+        std::any::type_name::<T>()
+    )
+}
+
+/// Converts to milliseconds since the Unix epoch, rounded down and saturated to the range of
+/// `i64`.
+impl From<SystemTime> for Value {
+    fn from(time: SystemTime) -> Value {
+        to_int(system_time_nanos(time).div_euclid(NANOS_PER_MILLI))
+    }
+}
+
+/// Converts to nanoseconds since the Unix epoch, saturated to the range of `i64`.
+impl From<EpochNanos<SystemTime>> for Value {
+    fn from(time: EpochNanos<SystemTime>) -> Value {
+        to_int(system_time_nanos(time.0))
+    }
+}
+
+impl TryFrom<Value> for SystemTime {
+    type Error = String;
+    fn try_from(val: Value) -> std::result::Result<Self, Self::Error> {
+        let millis = from_int::<Self>(&val)?;
+        epoch_offset(Duration::from_millis(millis.unsigned_abs()), millis < 0)
+            .ok_or_else(|| out_of_range::<Self>(millis))
+    }
+}
+
+impl TryFrom<Value> for EpochNanos<SystemTime> {
+    type Error = String;
+    fn try_from(val: Value) -> std::result::Result<Self, Self::Error> {
+        let nanos = from_int::<SystemTime>(&val)?;
+        epoch_offset(Duration::from_nanos(nanos.unsigned_abs()), nanos < 0)
+            .map(EpochNanos)
+            .ok_or_else(|| out_of_range::<SystemTime>(nanos))
+    }
+}
+
+#[cfg(feature = "chrono")]
+mod chrono_impls {
+    use std::convert::TryFrom;
+
+    use chrono::{DateTime, Utc};
+
+    use super::{from_int, out_of_range, to_int, EpochNanos, NANOS_PER_SEC};
+    use crate::Value;
+
+    /// Converts to milliseconds since the Unix epoch, rounded down.
+    impl From<DateTime<Utc>> for Value {
+        fn from(time: DateTime<Utc>) -> Value {
+            Value::Int(time.timestamp_millis())
+        }
+    }
+
+    /// Converts to nanoseconds since the Unix epoch, saturated to the range of `i64`.
+    impl From<EpochNanos<DateTime<Utc>>> for Value {
+        fn from(time: EpochNanos<DateTime<Utc>>) -> Value {
+            let nanos = i128::from(time.0.timestamp()) * NANOS_PER_SEC
+                + i128::from(time.0.timestamp_subsec_nanos());
+            to_int(nanos)
+        }
+    }
+
+    impl TryFrom<Value> for DateTime<Utc> {
+        type Error = String;
+        fn try_from(val: Value) -> std::result::Result<Self, Self::Error> {
+            let millis = from_int::<Self>(&val)?;
+            DateTime::from_timestamp_millis(millis).ok_or_else(|| out_of_range::<Self>(millis))
+        }
+    }
+
+    impl TryFrom<Value> for EpochNanos<DateTime<Utc>> {
+        type Error = String;
+        fn try_from(val: Value) -> std::result::Result<Self, Self::Error> {
+            let nanos = from_int::<DateTime<Utc>>(&val)?;
+            Ok(EpochNanos(DateTime::from_timestamp_nanos(nanos)))
+        }
+    }
+}
+
+#[cfg(feature = "time")]
+mod time_impls {
+    use std::convert::TryFrom;
+
+    use time::OffsetDateTime;
+
+    use super::{from_int, out_of_range, to_int, EpochNanos, NANOS_PER_MILLI};
+    use crate::Value;
+
+    /// Converts to milliseconds since the Unix epoch, rounded down.
+    impl From<OffsetDateTime> for Value {
+        fn from(time: OffsetDateTime) -> Value {
+            to_int(time.unix_timestamp_nanos().div_euclid(NANOS_PER_MILLI))
+        }
+    }
+
+    /// Converts to nanoseconds since the Unix epoch, saturated to the range of `i64`.
+    impl From<EpochNanos<OffsetDateTime>> for Value {
+        fn from(time: EpochNanos<OffsetDateTime>) -> Value {
+            to_int(time.0.unix_timestamp_nanos())
+        }
+    }
+
+    impl TryFrom<Value> for OffsetDateTime {
+        type Error = String;
+        fn try_from(val: Value) -> std::result::Result<Self, Self::Error> {
+            let millis = from_int::<Self>(&val)?;
+            OffsetDateTime::from_unix_timestamp_nanos(i128::from(millis) * NANOS_PER_MILLI)
+                .map_err(|_| out_of_range::<Self>(millis))
+        }
+    }
+
+    impl TryFrom<Value> for EpochNanos<OffsetDateTime> {
+        type Error = String;
+        fn try_from(val: Value) -> std::result::Result<Self, Self::Error> {
+            let nanos = from_int::<OffsetDateTime>(&val)?;
+            OffsetDateTime::from_unix_timestamp_nanos(i128::from(nanos))
+                .map(EpochNanos)
+                .map_err(|_| out_of_range::<OffsetDateTime>(nanos))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::convert::TryFrom;
+    use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+    use super::EpochNanos;
+    use crate::Value;
+
+    #[test]
+    fn system_time_millis() {
+        let time = UNIX_EPOCH + Duration::from_millis(1_600_000_000_123);
+        assert_eq!(Value::from(time), Value::Int(1_600_000_000_123));
+        assert_eq!(SystemTime::try_from(Value::from(time)).unwrap(), time);
+
+        let before = UNIX_EPOCH - Duration::from_micros(1_500);
+        assert_eq!(Value::from(before), Value::Int(-2));
+        assert_eq!(
+            SystemTime::try_from(Value::Int(-2)).unwrap(),
+            UNIX_EPOCH - Duration::from_millis(2)
+        );
+        assert!(SystemTime::try_from(Value::from("x")).is_err());
+    }
+
+    #[test]
+    fn system_time_nanos() {
+        let time = UNIX_EPOCH + Duration::new(1_600_000_000, 123_456_789);
+        let value = Value::from(EpochNanos(time));
+        assert_eq!(value, Value::Int(1_600_000_000_123_456_789));
+        assert_eq!(EpochNanos::<SystemTime>::try_from(value).unwrap().0, time);
+
+        let before = UNIX_EPOCH - Duration::from_nanos(5);
+        assert_eq!(Value::from(EpochNanos(before)), Value::Int(-5));
+        assert_eq!(
+            EpochNanos::<SystemTime>::try_from(Value::Int(-5))
+                .unwrap()
+                .0,
+            before
+        );
+    }
+
+    #[test]
+    fn system_time_nanos_bounds() {
+        let max = UNIX_EPOCH + Duration::from_nanos(i64::MAX as u64);
+        assert_eq!(Value::from(EpochNanos(max)), Value::Int(i64::MAX));
+        assert_eq!(
+            EpochNanos::<SystemTime>::try_from(Value::Int(i64::MAX))
+                .unwrap()
+                .0,
+            max
+        );
+        let min = UNIX_EPOCH - Duration::from_nanos(i64::MIN.unsigned_abs());
+        assert_eq!(Value::from(EpochNanos(min)), Value::Int(i64::MIN));
+        assert_eq!(
+            EpochNanos::<SystemTime>::try_from(Value::Int(i64::MIN))
+                .unwrap()
+                .0,
+            min
+        );
+
+        // points in time after 2262 and before 1677 saturate
+        let after = max + Duration::from_nanos(1);
+        assert_eq!(Value::from(EpochNanos(after)), Value::Int(i64::MAX));
+        let after = UNIX_EPOCH + Duration::from_secs(10_000_000_000);
+        assert_eq!(Value::from(EpochNanos(after)), Value::Int(i64::MAX));
+        assert_eq!(Value::from(after), Value::Int(10_000_000_000_000));
+        let before = min - Duration::from_nanos(1);
+        assert_eq!(Value::from(EpochNanos(before)), Value::Int(i64::MIN));
+    }
+
+    #[test]
+    #[cfg(feature = "chrono")]
+    fn chrono_date_time() {
+        use chrono::{DateTime, Utc};
+
+        let time = DateTime::<Utc>::from_timestamp(1_600_000_000, 123_456_789).unwrap();
+        assert_eq!(Value::from(time), Value::Int(1_600_000_000_123));
+        assert_eq!(
+            Value::from(EpochNanos(time)),
+            Value::Int(1_600_000_000_123_456_789)
+        );
+        let value = Value::Int(1_600_000_000_123_456_789);
+        assert_eq!(
+            EpochNanos::<DateTime<Utc>>::try_from(value).unwrap().0,
+            time
+        );
+        assert!(DateTime::<Utc>::try_from(Value::Int(i64::MAX)).is_err());
+
+        let far = DateTime::<Utc>::from_timestamp(10_000_000_000, 0).unwrap();
+        assert_eq!(Value::from(EpochNanos(far)), Value::Int(i64::MAX));
+        assert_eq!(
+            Value::from(EpochNanos(DateTime::<Utc>::MIN_UTC)),
+            Value::Int(i64::MIN)
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "time")]
+    fn time_offset_date_time() {
+        use time::OffsetDateTime;
+
+        let nanos = 1_600_000_000_123_456_789;
+        let time = OffsetDateTime::from_unix_timestamp_nanos(i128::from(nanos)).unwrap();
+        assert_eq!(Value::from(time), Value::Int(1_600_000_000_123));
+        assert_eq!(Value::from(EpochNanos(time)), Value::Int(nanos));
+        assert_eq!(
+            EpochNanos::<OffsetDateTime>::try_from(Value::Int(nanos))
+                .unwrap()
+                .0,
+            time
+        );
+        assert!(OffsetDateTime::try_from(Value::Int(i64::MAX)).is_err());
+
+        let far = OffsetDateTime::from_unix_timestamp(10_000_000_000).unwrap();
+        assert_eq!(Value::from(EpochNanos(far)), Value::Int(i64::MAX));
+        assert_eq!(Value::from(far), Value::Int(10_000_000_000_000));
+    }
+}
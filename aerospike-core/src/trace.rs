@@ -0,0 +1,328 @@
+// Copyright 2015-2020 Aerospike, Inc.
+//
+// Portions may be licensed to Aerospike, Inc. under one or more contributor
+// license agreements.
+//
+// Licensed under the Apache License, Version 2.0 (the "License"); you may not
+// use this file except in compliance with the License. You may obtain a copy of
+// the License at http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS, WITHOUT
+// WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied. See the
+// License for the specific language governing permissions and limitations under
+// the License.
+
+// Spans and events emitted when the `tracing` feature is enabled.
+//
+// Every command runs inside an `aerospike.command` span. The span is created with the
+// namespace, set, operation and key digest of the command; the node, attempt, bytes sent and
+// received and the result code are recorded on it while the command executes. Retries and
+// connection pool waits, whether or not they got a connection, are emitted as events inside the
+// command span, and each cluster tend cycle runs in its own `aerospike.tend` span.
+//
+// Without the feature, all functions in this module are no-ops that compile away, so callers
+// do not need to be conditionally compiled.
+
+pub use self::imp::*;
+
+#[cfg(feature = "tracing")]
+mod imp {
+    use std::fmt::Write;
+    use std::future::Future;
+
+    use aerospike_rt::time::Instant;
+    use tracing::field::{display, Empty};
+    use tracing::Instrument;
+
+    use crate::cluster::Node;
+    use crate::errors::{Error, Result};
+    use crate::{Key, ResultCode};
+
+    pub use tracing::Span;
+
+    // Measures how long it took to acquire a connection from a node's pool.
+    pub struct Stopwatch(Instant);
+
+    impl Stopwatch {
+        pub fn start() -> Self {
+            Stopwatch(Instant::now())
+        }
+    }
+
+    pub fn command_span(
+        operation: &'static str,
+        namespace: &str,
+        set_name: &str,
+        digest: Option<&[u8; 20]>,
+    ) -> Span {
+        let digest = digest.map(|digest| {
+            digest.iter().fold(String::with_capacity(40), |mut s, b| {
+                let _ = write!(s, "{b:02x}");
+                s
+            })
+        });
+        tracing::info_span!(
+            "aerospike.command",
+            operation,
+            namespace,
+            set = set_name,
+            digest = digest.as_deref(),
+            node = Empty,
+            attempt = Empty,
+            bytes_sent = Empty,
+            bytes_received = Empty,
+            result_code = Empty,
+        )
+    }
+
+    pub fn key_span(operation: &'static str, key: &Key) -> Span {
+        command_span(operation, &key.namespace, &key.set_name, Some(&key.digest))
+    }
+
+    pub fn current() -> Span {
+        Span::current()
+    }
+
+    pub fn instrument<F: Future>(span: Span, fut: F) -> impl Future<Output = F::Output> {
+        fut.instrument(span)
+    }
+
+    pub fn record_attempt(span: &Span, node: &Node, attempt: usize) {
+        span.record("node", node.name());
+        span.record("attempt", attempt);
+    }
+
+    pub fn record_bytes_sent(span: &Span, bytes: usize) {
+        span.record("bytes_sent", bytes);
+    }
+
+    pub fn record_bytes_received(span: &Span, bytes: usize) {
+        span.record("bytes_received", bytes);
+    }
+
+    pub fn record_result<T>(span: &Span, res: &Result<T>) {
+        match res {
+            Ok(_) => span.record("result_code", display(ResultCode::Ok)),
+            Err(
+                Error::ServerError(rc, ..)
+                | Error::BatchError(_, rc, ..)
+                | Error::BatchLastError(_, rc, ..),
+            ) => span.record("result_code", display(rc)),
+            Err(err) => span.record("result_code", display(err)),
+        };
+    }
+
+    pub fn retry(attempt: usize) {
+        tracing::debug!(attempt, "retrying command");
+    }
+
+    pub fn connection_acquired<T>(node: &Node, stopwatch: Stopwatch, res: &Result<T>) {
+        let wait_us = stopwatch.0.elapsed().as_micros() as u64;
+        match res {
+            Ok(_) => tracing::trace!(node = node.name(), wait_us, "acquired connection"),
+            Err(err) => tracing::debug!(
+                node = node.name(),
+                wait_us,
+                error = %err,
+                "failed to acquire connection"
+            ),
+        }
+    }
+
+    pub fn tend_span() -> Span {
+        tracing::debug_span!("aerospike.tend")
+    }
+
+    pub fn tend_cycle(nodes: usize, refreshed: usize, added: usize, removed: usize) {
+        tracing::debug!(nodes, refreshed, added, removed, "tend cycle completed");
+    }
+}
+
+#[cfg(not(feature = "tracing"))]
+#[allow(clippy::missing_const_for_fn)]
+mod imp {
+    use std::future::Future;
+
+    use crate::cluster::Node;
+    use crate::errors::Result;
+    use crate::Key;
+
+    #[derive(Clone)]
+    pub struct Span;
+
+    pub struct Stopwatch;
+
+    impl Stopwatch {
+        #[inline]
+        pub const fn start() -> Self {
+            Stopwatch
+        }
+    }
+
+    #[inline]
+    pub fn command_span(
+        _operation: &'static str,
+        _namespace: &str,
+        _set_name: &str,
+        _digest: Option<&[u8; 20]>,
+    ) -> Span {
+        Span
+    }
+
+    #[inline]
+    pub fn key_span(_operation: &'static str, _key: &Key) -> Span {
+        Span
+    }
+
+    #[inline]
+    pub fn current() -> Span {
+        Span
+    }
+
+    #[inline]
+    pub fn instrument<F: Future>(_span: Span, fut: F) -> F {
+        fut
+    }
+
+    #[inline]
+    pub fn record_attempt(_span: &Span, _node: &Node, _attempt: usize) {}
+
+    #[inline]
+    pub fn record_bytes_sent(_span: &Span, _bytes: usize) {}
+
+    #[inline]
+    pub fn record_bytes_received(_span: &Span, _bytes: usize) {}
+
+    #[inline]
+    pub fn record_result<T>(_span: &Span, _res: &Result<T>) {}
+
+    #[inline]
+    pub fn retry(_attempt: usize) {}
+
+    #[inline]
+    pub fn connection_acquired<T>(_node: &Node, _stopwatch: Stopwatch, _res: &Result<T>) {}
+
+    #[inline]
+    pub fn tend_span() -> Span {
+        Span
+    }
+
+    #[inline]
+    pub fn tend_cycle(_nodes: usize, _refreshed: usize, _added: usize, _removed: usize) {}
+}
+
+#[cfg(all(test, feature = "tracing"))]
+mod tests {
+    use std::collections::BTreeMap;
+    use std::fmt;
+    use std::sync::{Arc, Mutex};
+
+    use tracing::field::{Field, Visit};
+    use tracing::span::{Attributes, Id, Record};
+    use tracing::{Event, Level, Metadata, Subscriber};
+
+    use super::{connection_acquired, Stopwatch};
+    use crate::cluster::node_validator::NodeValidator;
+    use crate::cluster::Node;
+    use crate::errors::Error;
+    use crate::net::Host;
+    use crate::policy::ClientPolicy;
+    use crate::Version;
+
+    // Subscriber that keeps the level and fields of every event.
+    #[derive(Default)]
+    struct Capture(Mutex<Vec<(Level, BTreeMap<String, String>)>>);
+
+    struct Fields(BTreeMap<String, String>);
+
+    impl Visit for Fields {
+        fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+            self.0
+                .insert(field.name().to_string(), format!("{value:?}"));
+        }
+    }
+
+    impl Subscriber for Capture {
+        fn enabled(&self, _metadata: &Metadata<'_>) -> bool {
+            true
+        }
+
+        fn new_span(&self, _span: &Attributes<'_>) -> Id {
+            Id::from_u64(1)
+        }
+
+        fn record(&self, _span: &Id, _values: &Record<'_>) {}
+
+        fn record_follows_from(&self, _span: &Id, _follows: &Id) {}
+
+        fn event(&self, event: &Event<'_>) {
+            let mut fields = Fields(BTreeMap::new());
+            event.record(&mut fields);
+            self.0
+                .lock()
+                .unwrap()
+                .push((*event.metadata().level(), fields.0));
+        }
+
+        fn enter(&self, _span: &Id) {}
+
+        fn exit(&self, _span: &Id) {}
+    }
+
+    fn test_node(policy: ClientPolicy) -> Node {
+        let nv = Arc::new(NodeValidator {
+            name: "test-node".to_string(),
+            aliases: vec![Host::new("127.0.0.1", 3000)],
+            services: vec![],
+            address: "127.0.0.1:3000".to_string(),
+            client_policy: policy.clone(),
+            use_new_info: true,
+            version: Version::default(),
+        });
+        Node::new(policy, nv)
+    }
+
+    #[aerospike_macro::test]
+    async fn connection_events() {
+        let node = test_node(ClientPolicy {
+            max_conns_per_node: 1,
+            ..ClientPolicy::default()
+        });
+        let acquired = node.get_connection(0).await;
+        // the pool is exhausted while the first connection is in use
+        let exhausted = node.get_connection(0).await;
+        let failed: crate::Result<()> = Err(Error::Connection("Connection refused".to_string()));
+
+        let capture = Arc::new(Capture::default());
+        tracing::subscriber::with_default(capture.clone(), || {
+            connection_acquired(&node, Stopwatch::start(), &acquired);
+            connection_acquired(&node, Stopwatch::start(), &exhausted);
+            connection_acquired(&node, Stopwatch::start(), &failed);
+        });
+
+        let events = std::mem::take(&mut *capture.0.lock().unwrap());
+        assert_eq!(events.len(), 3);
+        for (_, fields) in &events {
+            assert_eq!(fields["node"], "\"test-node\"");
+            assert!(fields.contains_key("wait_us"));
+        }
+
+        let (level, fields) = &events[0];
+        assert_eq!(*level, Level::TRACE);
+        assert_eq!(fields["message"], "acquired connection");
+        assert!(!fields.contains_key("error"));
+
+        let (level, fields) = &events[1];
+        assert_eq!(*level, Level::DEBUG);
+        assert_eq!(fields["message"], "failed to acquire connection");
+        let Err(err) = &exhausted else {
+            panic!("the pool should be exhausted");
+        };
+        assert_eq!(fields["error"], err.to_string());
+
+        let (_, fields) = &events[2];
+        assert_eq!(fields["message"], "failed to acquire connection");
+        assert!(fields["error"].contains("Connection refused"));
+    }
+}
@@ -0,0 +1,879 @@
+// Expression DSL behind the `exp!` macro.
+//
+// The input is parsed into a small syntax tree, the types of bins are inferred from the literals
+// and operators around them, and the tree is then lowered to the builder functions of
+// `aerospike::expressions`. Type errors are reported at the offending token.
+
+use std::collections::HashMap;
+
+use proc_macro2::{Span, TokenStream, TokenTree};
+use quote::quote;
+use syn::ext::IdentExt;
+use syn::parse::{ParseStream, Parser};
+use syn::{Error, Ident, Lit, LitStr, Result, Token};
+
+pub fn expand(input: TokenStream) -> Result<TokenStream> {
+    // The first token is a bracketed path to the client crate, e.g. `[$crate]`.
+    let mut tokens = input.into_iter();
+    let krate = match tokens.next() {
+        Some(TokenTree::Group(group)) => group.stream(),
+        _ => return Err(Error::new(Span::call_site(), "expected crate path")),
+    };
+    let body: TokenStream = tokens.collect();
+    if body.is_empty() {
+        return Err(Error::new(Span::call_site(), "expected an expression"));
+    }
+
+    let node = parse.parse2(body)?;
+    let mut checker = Checker::default();
+    loop {
+        checker.changed = false;
+        checker.check(&node, None)?;
+        if !checker.changed {
+            break;
+        }
+    }
+    checker.resolved(&node)?;
+
+    let gen = Gen {
+        exp: quote!(#krate::expressions),
+        checker: &checker,
+    };
+    gen.node(&node)
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+enum Ty {
+    Int,
+    Float,
+    Str,
+    Bool,
+    Blob,
+    List,
+    Map,
+    Geo,
+    Hll,
+}
+
+impl Ty {
+    fn from_ident(ident: &Ident) -> Result<Self> {
+        let ty = match ident.to_string().as_str() {
+            "int" => Ty::Int,
+            "float" => Ty::Float,
+            "string" => Ty::Str,
+            "bool" => Ty::Bool,
+            "blob" => Ty::Blob,
+            "list" => Ty::List,
+            "map" => Ty::Map,
+            "geo" => Ty::Geo,
+            "hll" => Ty::Hll,
+            _ => {
+                return Err(Error::new(
+                    ident.span(),
+                    "unknown type, expected one of int, float, string, bool, blob, list, map, geo or hll",
+                ))
+            }
+        };
+        Ok(ty)
+    }
+
+    const fn name(self) -> &'static str {
+        match self {
+            Ty::Int => "int",
+            Ty::Float => "float",
+            Ty::Str => "string",
+            Ty::Bool => "bool",
+            Ty::Blob => "blob",
+            Ty::List => "list",
+            Ty::Map => "map",
+            Ty::Geo => "geo",
+            Ty::Hll => "hll",
+        }
+    }
+
+    const fn exp_type(self) -> &'static str {
+        match self {
+            Ty::Int => "INT",
+            Ty::Float => "FLOAT",
+            Ty::Str => "STRING",
+            Ty::Bool => "BOOL",
+            Ty::Blob => "BLOB",
+            Ty::List => "LIST",
+            Ty::Map => "MAP",
+            Ty::Geo => "GEO",
+            Ty::Hll => "HLL",
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum BinOp {
+    Or,
+    And,
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+    BitOr,
+    BitXor,
+    BitAnd,
+    Shl,
+    Shr,
+    Add,
+    Sub,
+    Mul,
+    Div,
+    Rem,
+}
+
+impl BinOp {
+    const fn precedence(self) -> u8 {
+        match self {
+            BinOp::Or => 1,
+            BinOp::And => 2,
+            BinOp::Eq | BinOp::Ne | BinOp::Lt | BinOp::Le | BinOp::Gt | BinOp::Ge => 3,
+            BinOp::BitOr => 4,
+            BinOp::BitXor => 5,
+            BinOp::BitAnd => 6,
+            BinOp::Shl | BinOp::Shr => 7,
+            BinOp::Add | BinOp::Sub => 8,
+            BinOp::Mul | BinOp::Div | BinOp::Rem => 9,
+        }
+    }
+
+    const fn is_comparison(self) -> bool {
+        self.precedence() == 3
+    }
+
+    const fn symbol(self) -> &'static str {
+        match self {
+            BinOp::Or => "||",
+            BinOp::And => "&&",
+            BinOp::Eq => "==",
+            BinOp::Ne => "!=",
+            BinOp::Lt => "<",
+            BinOp::Le => "<=",
+            BinOp::Gt => ">",
+            BinOp::Ge => ">=",
+            BinOp::BitOr => "|",
+            BinOp::BitXor => "^",
+            BinOp::BitAnd => "&",
+            BinOp::Shl => "<<",
+            BinOp::Shr => ">>",
+            BinOp::Add => "+",
+            BinOp::Sub => "-",
+            BinOp::Mul => "*",
+            BinOp::Div => "/",
+            BinOp::Rem => "%",
+        }
+    }
+
+    // Operators that can be flattened into a single variadic builder call on both sides.
+    const fn is_associative(self) -> bool {
+        matches!(
+            self,
+            BinOp::Or
+                | BinOp::And
+                | BinOp::BitOr
+                | BinOp::BitXor
+                | BinOp::BitAnd
+                | BinOp::Add
+                | BinOp::Mul
+        )
+    }
+}
+
+#[derive(Clone, PartialEq, Eq, Hash)]
+enum Var {
+    Bin(String),
+    Key,
+}
+
+enum Node {
+    Bin {
+        var: Var,
+        name: LitStr,
+        ty: Option<Ty>,
+        span: Span,
+    },
+    Meta {
+        name: Ident,
+        arg: Option<Lit>,
+    },
+    Lit(Lit),
+    Splice {
+        tokens: TokenStream,
+        ty: Option<Ty>,
+        span: Span,
+    },
+    Not(Box<Node>, Span),
+    Neg(Box<Node>, Span),
+    Binary {
+        op: BinOp,
+        lhs: Box<Node>,
+        rhs: Box<Node>,
+        span: Span,
+    },
+    Call {
+        func: Ident,
+        args: Vec<Node>,
+    },
+}
+
+impl Node {
+    fn span(&self) -> Span {
+        match self {
+            Node::Bin { span, .. }
+            | Node::Splice { span, .. }
+            | Node::Not(_, span)
+            | Node::Neg(_, span)
+            | Node::Binary { span, .. } => *span,
+            Node::Meta { name, .. } => name.span(),
+            Node::Lit(lit) => lit.span(),
+            Node::Call { func, .. } => func.span(),
+        }
+    }
+}
+
+fn parse(input: ParseStream) -> Result<Node> {
+    let node = parse_binary(input, 0)?;
+    if !input.is_empty() {
+        return Err(input.error("expected an operator"));
+    }
+    Ok(node)
+}
+
+fn peek_op(input: ParseStream) -> Option<BinOp> {
+    let op = if input.peek(Token![||]) {
+        BinOp::Or
+    } else if input.peek(Token![&&]) {
+        BinOp::And
+    } else if input.peek(Token![==]) {
+        BinOp::Eq
+    } else if input.peek(Token![!=]) {
+        BinOp::Ne
+    } else if input.peek(Token![<<]) {
+        BinOp::Shl
+    } else if input.peek(Token![>>]) {
+        BinOp::Shr
+    } else if input.peek(Token![<=]) {
+        BinOp::Le
+    } else if input.peek(Token![>=]) {
+        BinOp::Ge
+    } else if input.peek(Token![<]) {
+        BinOp::Lt
+    } else if input.peek(Token![>]) {
+        BinOp::Gt
+    } else if input.peek(Token![|]) {
+        BinOp::BitOr
+    } else if input.peek(Token![^]) {
+        BinOp::BitXor
+    } else if input.peek(Token![&]) {
+        BinOp::BitAnd
+    } else if input.peek(Token![+]) {
+        BinOp::Add
+    } else if input.peek(Token![-]) {
+        BinOp::Sub
+    } else if input.peek(Token![*]) {
+        BinOp::Mul
+    } else if input.peek(Token![/]) {
+        BinOp::Div
+    } else if input.peek(Token![%]) {
+        BinOp::Rem
+    } else {
+        return None;
+    };
+    Some(op)
+}
+
+// Consumes the operator returned by `peek_op` and returns its span.
+fn parse_op(input: ParseStream, op: BinOp) -> Result<Span> {
+    let span = match op {
+        BinOp::Or => input.parse::<Token![||]>()?.spans[0],
+        BinOp::And => input.parse::<Token![&&]>()?.spans[0],
+        BinOp::Eq => input.parse::<Token![==]>()?.spans[0],
+        BinOp::Ne => input.parse::<Token![!=]>()?.spans[0],
+        BinOp::Shl => input.parse::<Token![<<]>()?.spans[0],
+        BinOp::Shr => input.parse::<Token![>>]>()?.spans[0],
+        BinOp::Le => input.parse::<Token![<=]>()?.spans[0],
+        BinOp::Ge => input.parse::<Token![>=]>()?.spans[0],
+        BinOp::Lt => input.parse::<Token![<]>()?.span,
+        BinOp::Gt => input.parse::<Token![>]>()?.span,
+        BinOp::BitOr => input.parse::<Token![|]>()?.span,
+        BinOp::BitXor => input.parse::<Token![^]>()?.span,
+        BinOp::BitAnd => input.parse::<Token![&]>()?.span,
+        BinOp::Add => input.parse::<Token![+]>()?.span,
+        BinOp::Sub => input.parse::<Token![-]>()?.span,
+        BinOp::Mul => input.parse::<Token![*]>()?.span,
+        BinOp::Div => input.parse::<Token![/]>()?.span,
+        BinOp::Rem => input.parse::<Token![%]>()?.span,
+    };
+    Ok(span)
+}
+
+fn parse_binary(input: ParseStream, min_precedence: u8) -> Result<Node> {
+    let mut lhs = parse_unary(input)?;
+    while let Some(op) = peek_op(input) {
+        if op.precedence() < min_precedence {
+            break;
+        }
+        let span = parse_op(input, op)?;
+        let rhs = parse_binary(input, op.precedence() + 1)?;
+        if op.is_comparison() && peek_op(input).is_some_and(BinOp::is_comparison) {
+            return Err(input.error("comparison operators cannot be chained"));
+        }
+        lhs = Node::Binary {
+            op,
+            lhs: Box::new(lhs),
+            rhs: Box::new(rhs),
+            span,
+        };
+    }
+    Ok(lhs)
+}
+
+fn parse_unary(input: ParseStream) -> Result<Node> {
+    if input.peek(Token![!]) {
+        let span = input.parse::<Token![!]>()?.span;
+        return Ok(Node::Not(Box::new(parse_unary(input)?), span));
+    }
+    if input.peek(Token![-]) {
+        let span = input.parse::<Token![-]>()?.span;
+        return Ok(Node::Neg(Box::new(parse_unary(input)?), span));
+    }
+    parse_primary(input)
+}
+
+fn parse_annotation(input: ParseStream) -> Result<Option<Ty>> {
+    if input.peek(Token![:]) && !input.peek(Token![::]) {
+        input.parse::<Token![:]>()?;
+        return Ty::from_ident(&input.parse()?).map(Some);
+    }
+    Ok(None)
+}
+
+fn parse_primary(input: ParseStream) -> Result<Node> {
+    if input.peek(Token![$]) {
+        let span = input.parse::<Token![$]>()?.span;
+        if input.peek(Token![.]) {
+            input.parse::<Token![.]>()?;
+            return parse_meta(input);
+        }
+        let name = if input.peek(LitStr) {
+            input.parse::<LitStr>()?
+        } else {
+            let ident = Ident::parse_any(input)?;
+            LitStr::new(&ident.to_string(), ident.span())
+        };
+        return Ok(Node::Bin {
+            var: Var::Bin(name.value()),
+            name,
+            ty: parse_annotation(input)?,
+            span,
+        });
+    }
+
+    if input.peek(syn::token::Paren) {
+        let content;
+        syn::parenthesized!(content in input);
+        return parse(&content);
+    }
+
+    if input.peek(syn::token::Brace) {
+        let content;
+        let brace = syn::braced!(content in input);
+        return Ok(Node::Splice {
+            tokens: content.parse()?,
+            ty: parse_annotation(input)?,
+            span: brace.span.join(),
+        });
+    }
+
+    if input.peek(Lit) {
+        let lit = input.parse::<Lit>()?;
+        return match lit {
+            Lit::Int(_) | Lit::Float(_) | Lit::Str(_) | Lit::Bool(_) => Ok(Node::Lit(lit)),
+            _ => Err(Error::new(lit.span(), "unsupported literal")),
+        };
+    }
+
+    if input.peek(Ident) && input.peek2(syn::token::Paren) {
+        let func = input.parse::<Ident>()?;
+        let content;
+        syn::parenthesized!(content in input);
+        let args = content.parse_terminated(|input| parse_binary(input, 0), Token![,])?;
+        return Ok(Node::Call {
+            func,
+            args: args.into_iter().collect(),
+        });
+    }
+
+    if input.peek(Ident) {
+        let ident = input.parse::<Ident>()?;
+        return Err(Error::new(
+            ident.span(),
+            format!(
+                "unexpected identifier, write bins as `${ident}` and Rust expressions as `{{...}}`"
+            ),
+        ));
+    }
+
+    Err(input.error("expected a bin, literal, `{...}` or `(...)`"))
+}
+
+fn parse_meta(input: ParseStream) -> Result<Node> {
+    let name = Ident::parse_any(input)?;
+    match name.to_string().as_str() {
+        "key" => Ok(Node::Bin {
+            var: Var::Key,
+            name: LitStr::new("key", name.span()),
+            ty: parse_annotation(input)?,
+            span: name.span(),
+        }),
+        "digest_modulo" => {
+            let content;
+            syn::parenthesized!(content in input);
+            let arg = content.parse::<Lit>()?;
+            if !matches!(arg, Lit::Int(_)) {
+                return Err(Error::new(arg.span(), "expected an integer"));
+            }
+            Ok(Node::Meta {
+                name,
+                arg: Some(arg),
+            })
+        }
+        _ if meta_type(&name).is_some() => Ok(Node::Meta { name, arg: None }),
+        _ => Err(Error::new(name.span(), "unknown record metadata")),
+    }
+}
+
+fn meta_type(name: &Ident) -> Option<Ty> {
+    let ty = match name.to_string().as_str() {
+        "ttl" | "void_time" | "last_update" | "since_update" | "record_size" | "device_size"
+        | "memory_size" | "digest_modulo" => Ty::Int,
+        "set_name" => Ty::Str,
+        "key_exists" | "is_tombstone" => Ty::Bool,
+        _ => return None,
+    };
+    Some(ty)
+}
+
+#[derive(Default)]
+struct Checker {
+    vars: HashMap<Var, Ty>,
+    changed: bool,
+}
+
+impl Checker {
+    // Returns the type of the node, or `None` if it is not known yet. The hint is the type the
+    // surrounding expression expects and is used to infer the type of bins.
+    fn check(&mut self, node: &Node, hint: Option<Ty>) -> Result<Option<Ty>> {
+        match node {
+            Node::Bin { var, name, ty, .. } => {
+                let known = self.vars.get(var).copied();
+                match (*ty, known) {
+                    (Some(ty), Some(known)) if ty != known => Err(Error::new(
+                        name.span(),
+                        format!(
+                            "`{}` is used both as {} and as {}",
+                            name.value(),
+                            known.name(),
+                            ty.name()
+                        ),
+                    )),
+                    (Some(ty), _) | (None, Some(ty)) => {
+                        self.define(var, ty);
+                        Ok(Some(ty))
+                    }
+                    (None, None) => {
+                        if let Some(hint) = hint {
+                            self.define(var, hint);
+                        }
+                        Ok(hint)
+                    }
+                }
+            }
+            Node::Meta { name, .. } => Ok(meta_type(name)),
+            Node::Lit(lit) => Ok(Some(lit_type(lit))),
+            Node::Splice { ty, .. } => Ok(*ty),
+            Node::Not(arg, _) => {
+                let ty = self.check(arg, hint)?;
+                expect(ty, &[Ty::Bool, Ty::Int], arg.span(), "`!`")?;
+                Ok(ty)
+            }
+            Node::Neg(arg, _) => {
+                let ty = self.check(arg, hint)?;
+                expect(ty, &[Ty::Int, Ty::Float], arg.span(), "`-`")?;
+                Ok(ty)
+            }
+            Node::Binary { op, lhs, rhs, span } => {
+                let what = format!("`{}`", op.symbol());
+                match op {
+                    BinOp::Or | BinOp::And => {
+                        for arg in [lhs, rhs] {
+                            let ty = self.check(arg, Some(Ty::Bool))?;
+                            expect(ty, &[Ty::Bool], arg.span(), &what)?;
+                        }
+                        Ok(Some(Ty::Bool))
+                    }
+                    BinOp::Eq | BinOp::Ne | BinOp::Lt | BinOp::Le | BinOp::Gt | BinOp::Ge => {
+                        self.unify(&[lhs.as_ref(), rhs.as_ref()], None, *span)?;
+                        Ok(Some(Ty::Bool))
+                    }
+                    BinOp::BitOr | BinOp::BitXor | BinOp::BitAnd => {
+                        let ty = self.unify(&[lhs.as_ref(), rhs.as_ref()], hint, *span)?;
+                        expect(ty, &[Ty::Int, Ty::Bool], *span, &what)?;
+                        Ok(ty)
+                    }
+                    BinOp::Shl | BinOp::Shr | BinOp::Rem => {
+                        let ty = self.unify(&[lhs.as_ref(), rhs.as_ref()], Some(Ty::Int), *span)?;
+                        expect(ty, &[Ty::Int], *span, &what)?;
+                        Ok(ty)
+                    }
+                    BinOp::Add | BinOp::Sub | BinOp::Mul | BinOp::Div => {
+                        let ty = self.unify(&[lhs.as_ref(), rhs.as_ref()], hint, *span)?;
+                        expect(ty, &[Ty::Int, Ty::Float], *span, &what)?;
+                        Ok(ty)
+                    }
+                }
+            }
+            Node::Call { func, args } => self.check_call(func, args, hint),
+        }
+    }
+
+    fn check_call(&mut self, func: &Ident, args: &[Node], hint: Option<Ty>) -> Result<Option<Ty>> {
+        let name = func.to_string();
+        let what = format!("`{name}()`");
+        let arity = |min: usize, max: usize| {
+            if args.len() < min || args.len() > max {
+                let expected = if min == max {
+                    min.to_string()
+                } else {
+                    format!("{min} to {max}")
+                };
+                return Err(Error::new(
+                    func.span(),
+                    format!("{what} takes {expected} arguments, found {}", args.len()),
+                ));
+            }
+            Ok(())
+        };
+
+        match name.as_str() {
+            "abs" => {
+                arity(1, 1)?;
+                let ty = self.check(&args[0], hint)?;
+                expect(ty, &[Ty::Int, Ty::Float], args[0].span(), &what)?;
+                Ok(ty)
+            }
+            "ceil" | "floor" | "to_int" => {
+                arity(1, 1)?;
+                let ty = self.check(&args[0], Some(Ty::Float))?;
+                expect(ty, &[Ty::Float], args[0].span(), &what)?;
+                Ok(Some(if name == "to_int" { Ty::Int } else { Ty::Float }))
+            }
+            "to_float" => {
+                arity(1, 1)?;
+                let ty = self.check(&args[0], Some(Ty::Int))?;
+                expect(ty, &[Ty::Int], args[0].span(), &what)?;
+                Ok(Some(Ty::Float))
+            }
+            "pow" | "log" => {
+                arity(2, 2)?;
+                for arg in args {
+                    let ty = self.check(arg, Some(Ty::Float))?;
+                    expect(ty, &[Ty::Float], arg.span(), &what)?;
+                }
+                Ok(Some(Ty::Float))
+            }
+            "min" | "max" => {
+                arity(1, usize::MAX)?;
+                let args: Vec<&Node> = args.iter().collect();
+                let ty = self.unify(&args, hint, func.span())?;
+                expect(ty, &[Ty::Int, Ty::Float], func.span(), &what)?;
+                Ok(ty)
+            }
+            "regex" => {
+                arity(2, 3)?;
+                let ty = self.check(&args[0], Some(Ty::Str))?;
+                expect(ty, &[Ty::Str], args[0].span(), &what)?;
+                if !matches!(args[1], Node::Lit(Lit::Str(_))) {
+                    return Err(Error::new(args[1].span(), "expected a string literal"));
+                }
+                if let Some(flags) = args.get(2) {
+                    if !matches!(flags, Node::Lit(Lit::Int(_)) | Node::Splice { .. }) {
+                        return Err(Error::new(
+                            flags.span(),
+                            "expected an integer literal or `{...}`",
+                        ));
+                    }
+                }
+                Ok(Some(Ty::Bool))
+            }
+            _ => Err(Error::new(
+                func.span(),
+                "unknown function, expected one of abs, ceil, floor, pow, log, min, max, to_int, to_float or regex",
+            )),
+        }
+    }
+
+    // Checks that all nodes have the same type and returns it.
+    fn unify(&mut self, nodes: &[&Node], hint: Option<Ty>, span: Span) -> Result<Option<Ty>> {
+        let mut tys = Vec::with_capacity(nodes.len());
+        for node in nodes {
+            tys.push(self.check(node, hint)?);
+        }
+        let Some(ty) = tys.iter().flatten().next().copied() else {
+            return Ok(None);
+        };
+        for (node, found) in nodes.iter().zip(&mut tys) {
+            if found.is_none() {
+                *found = self.check(node, Some(ty))?;
+            }
+        }
+        for found in tys.iter().flatten() {
+            if *found != ty {
+                return Err(Error::new(
+                    span,
+                    format!("mismatched types: {} and {}", ty.name(), found.name()),
+                ));
+            }
+        }
+        Ok(Some(ty))
+    }
+
+    fn define(&mut self, var: &Var, ty: Ty) {
+        if self.vars.insert(var.clone(), ty).is_none() {
+            self.changed = true;
+        }
+    }
+
+    // Fails if the type of a bin could not be inferred.
+    fn resolved(&self, node: &Node) -> Result<()> {
+        match node {
+            Node::Bin { var, name, .. } if !self.vars.contains_key(var) => {
+                let msg = match var {
+                    Var::Bin(_) => format!(
+                        "cannot infer the type of bin `{}`, annotate it like `${}: int`",
+                        name.value(),
+                        name.value()
+                    ),
+                    Var::Key => "cannot infer the type of the key, annotate it like `$.key: int`"
+                        .to_string(),
+                };
+                Err(Error::new(name.span(), msg))
+            }
+            Node::Not(arg, _) | Node::Neg(arg, _) => self.resolved(arg),
+            Node::Binary { lhs, rhs, .. } => {
+                self.resolved(lhs)?;
+                self.resolved(rhs)
+            }
+            Node::Call { args, .. } => args.iter().try_for_each(|arg| self.resolved(arg)),
+            _ => Ok(()),
+        }
+    }
+}
+
+fn lit_type(lit: &Lit) -> Ty {
+    match lit {
+        Lit::Float(_) => Ty::Float,
+        Lit::Str(_) => Ty::Str,
+        Lit::Bool(_) => Ty::Bool,
+        _ => Ty::Int,
+    }
+}
+
+fn expect(found: Option<Ty>, allowed: &[Ty], span: Span, what: &str) -> Result<()> {
+    match found {
+        Some(found) if !allowed.contains(&found) => {
+            let allowed: Vec<&str> = allowed.iter().map(|ty| ty.name()).collect();
+            Err(Error::new(
+                span,
+                format!(
+                    "{what} expects {}, found {}",
+                    allowed.join(" or "),
+                    found.name()
+                ),
+            ))
+        }
+        _ => Ok(()),
+    }
+}
+
+struct Gen<'a> {
+    exp: TokenStream,
+    checker: &'a Checker,
+}
+
+impl Gen<'_> {
+    fn ty(&self, node: &Node) -> Option<Ty> {
+        // All bins are resolved at this point, so checking again only looks up their types.
+        let mut checker = Checker {
+            vars: self.checker.vars.clone(),
+            changed: false,
+        };
+        checker.check(node, None).ok().flatten()
+    }
+
+    fn node(&self, node: &Node) -> Result<TokenStream> {
+        let exp = &self.exp;
+        let tokens = match node {
+            Node::Bin { var, name, .. } => {
+                let ty = self.checker.vars[var];
+                match var {
+                    Var::Bin(_) => {
+                        let func = Ident::new(&format!("{}_bin", ty.name()), name.span());
+                        quote!(#exp::#func(::std::string::String::from(#name)))
+                    }
+                    Var::Key => {
+                        let exp_type = Ident::new(ty.exp_type(), name.span());
+                        quote!(#exp::key(#exp::ExpType::#exp_type))
+                    }
+                }
+            }
+            Node::Meta { name, arg } => quote!(#exp::#name(#arg)),
+            Node::Lit(lit) => self.lit(lit, false),
+            Node::Splice { tokens, .. } => quote!({ #tokens }),
+            Node::Not(arg, _) => {
+                let func = if self.ty(arg) == Some(Ty::Int) {
+                    quote!(int_not)
+                } else {
+                    quote!(not)
+                };
+                let arg = self.node(arg)?;
+                quote!(#exp::#func(#arg))
+            }
+            Node::Neg(arg, _) => match arg.as_ref() {
+                Node::Lit(lit @ (Lit::Int(_) | Lit::Float(_))) => self.lit(lit, true),
+                _ => {
+                    let arg = self.node(arg)?;
+                    quote!(#exp::num_sub(::std::vec![#arg]))
+                }
+            },
+            Node::Binary { op, lhs, rhs, .. } => self.binary(*op, lhs, rhs)?,
+            Node::Call { func, args } => self.call(func, args)?,
+        };
+        Ok(tokens)
+    }
+
+    fn lit(&self, lit: &Lit, negate: bool) -> TokenStream {
+        let exp = &self.exp;
+        let sign = if negate { quote!(-) } else { quote!() };
+        match lit {
+            Lit::Float(_) => quote!(#exp::float_val(#sign #lit)),
+            Lit::Str(_) => quote!(#exp::string_val(::std::string::String::from(#lit))),
+            Lit::Bool(_) => quote!(#exp::bool_val(#lit)),
+            _ => quote!(#exp::int_val(#sign #lit)),
+        }
+    }
+
+    fn binary(&self, op: BinOp, lhs: &Node, rhs: &Node) -> Result<TokenStream> {
+        let exp = &self.exp;
+        let bool_operands = self.ty(lhs) == Some(Ty::Bool) || self.ty(rhs) == Some(Ty::Bool);
+        let func = match op {
+            BinOp::Eq => "eq",
+            BinOp::Ne => "ne",
+            BinOp::Lt => "lt",
+            BinOp::Le => "le",
+            BinOp::Gt => "gt",
+            BinOp::Ge => "ge",
+            BinOp::Shl => "int_lshift",
+            BinOp::Shr => "int_arshift",
+            BinOp::Rem => "num_mod",
+            BinOp::Or => "or",
+            BinOp::And => "and",
+            BinOp::BitOr if bool_operands => "or",
+            BinOp::BitXor if bool_operands => "xor",
+            BinOp::BitAnd if bool_operands => "and",
+            BinOp::BitOr => "int_or",
+            BinOp::BitXor => "int_xor",
+            BinOp::BitAnd => "int_and",
+            BinOp::Add => "num_add",
+            BinOp::Sub => "num_sub",
+            BinOp::Mul => "num_mul",
+            BinOp::Div => "num_div",
+        };
+        let func = Ident::new(func, Span::call_site());
+
+        if op.is_comparison() || matches!(op, BinOp::Shl | BinOp::Shr | BinOp::Rem) {
+            let (lhs, rhs) = (self.node(lhs)?, self.node(rhs)?);
+            return Ok(quote!(#exp::#func(#lhs, #rhs)));
+        }
+
+        let mut operands = vec![];
+        flatten(op, lhs, &mut operands);
+        if op.is_associative() {
+            flatten(op, rhs, &mut operands);
+        } else {
+            operands.push(rhs);
+        }
+        let operands = operands
+            .into_iter()
+            .map(|node| self.node(node))
+            .collect::<Result<Vec<_>>>()?;
+        Ok(quote!(#exp::#func(::std::vec![#(#operands),*])))
+    }
+
+    fn call(&self, func: &Ident, args: &[Node]) -> Result<TokenStream> {
+        let exp = &self.exp;
+        let name = func.to_string();
+        if name == "regex" {
+            let bin = self.node(&args[0])?;
+            let pattern = &args[1];
+            let Node::Lit(pattern) = pattern else {
+                unreachable!()
+            };
+            let flags = match args.get(2) {
+                Some(Node::Lit(flags)) => quote!(#flags),
+                Some(Node::Splice { tokens, .. }) => quote!({ #tokens }),
+                _ => quote!(0),
+            };
+            return Ok(
+                quote!(#exp::regex_compare(::std::string::String::from(#pattern), #flags, #bin)),
+            );
+        }
+
+        let args = args
+            .iter()
+            .map(|arg| self.node(arg))
+            .collect::<Result<Vec<_>>>()?;
+        let tokens = match name.as_str() {
+            "min" | "max" => {
+                let func = Ident::new(&name, func.span());
+                quote!(#exp::#func(::std::vec![#(#args),*]))
+            }
+            _ => {
+                let func = match name.as_str() {
+                    "abs" | "ceil" | "floor" | "pow" | "log" => {
+                        Ident::new(&format!("num_{name}"), func.span())
+                    }
+                    _ => func.clone(),
+                };
+                quote!(#exp::#func(#(#args),*))
+            }
+        };
+        Ok(tokens)
+    }
+}
+
+// Collects the operands of a chain of the same operator, e.g. `a + b + c`.
+fn flatten<'n>(op: BinOp, node: &'n Node, out: &mut Vec<&'n Node>) {
+    match node {
+        Node::Binary {
+            op: inner,
+            lhs,
+            rhs,
+            ..
+        } if *inner == op && !op.is_comparison() => {
+            flatten(op, lhs, out);
+            if op.is_associative() {
+                flatten(op, rhs, out);
+            } else {
+                out.push(rhs);
+            }
+        }
+        _ => out.push(node),
+    }
+}
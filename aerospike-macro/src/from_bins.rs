@@ -0,0 +1,191 @@
+use proc_macro2::TokenStream;
+use quote::{format_ident, quote};
+use syn::spanned::Spanned;
+use syn::{Data, DeriveInput, Error, Fields, LitStr, Path, Result};
+
+// Bin names are limited to 15 bytes by the server.
+const MAX_BIN_NAME_LEN: usize = 15;
+
+enum FieldKind {
+    Bin { name: String, default: bool },
+    Key,
+    Generation,
+}
+
+pub fn expand(input: TokenStream) -> Result<TokenStream> {
+    let input: DeriveInput = syn::parse2(input)?;
+    let name = &input.ident;
+
+    if !input.generics.params.is_empty() {
+        return Err(Error::new(
+            input.generics.span(),
+            "FromBins cannot be derived for generic types",
+        ));
+    }
+
+    let mut krate: Path = syn::parse_quote!(::aerospike);
+    for attr in &input.attrs {
+        if !attr.path().is_ident("aerospike") {
+            continue;
+        }
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("crate") {
+                krate = meta.value()?.parse::<LitStr>()?.parse()?;
+                Ok(())
+            } else {
+                Err(meta.error("unsupported aerospike attribute"))
+            }
+        })?;
+    }
+
+    let fields = match &input.data {
+        Data::Struct(data) => match &data.fields {
+            Fields::Named(fields) => &fields.named,
+            _ => {
+                return Err(Error::new(
+                    input.ident.span(),
+                    "FromBins can only be derived for structs with named fields",
+                ))
+            }
+        },
+        _ => {
+            return Err(Error::new(
+                input.ident.span(),
+                "FromBins can only be derived for structs",
+            ))
+        }
+    };
+
+    let mut bin_names: Vec<String> = vec![];
+    let mut builder_fields = vec![];
+    let mut decode_arms = vec![];
+    let mut build_fields = vec![];
+
+    for field in fields {
+        let ident = field.ident.as_ref().expect("named field");
+        let ty = &field.ty;
+
+        let mut rename = None;
+        let mut kind = None;
+        let mut default = false;
+        for attr in &field.attrs {
+            if !attr.path().is_ident("aerospike") {
+                continue;
+            }
+            attr.parse_nested_meta(|meta| {
+                if meta.path.is_ident("rename") {
+                    let lit: LitStr = meta.value()?.parse()?;
+                    rename = Some(lit);
+                } else if meta.path.is_ident("default") {
+                    default = true;
+                } else if meta.path.is_ident("key") {
+                    kind = Some(FieldKind::Key);
+                } else if meta.path.is_ident("generation") {
+                    kind = Some(FieldKind::Generation);
+                } else {
+                    return Err(meta.error("unsupported aerospike field attribute"));
+                }
+                Ok(())
+            })?;
+        }
+
+        let kind = match kind {
+            Some(kind) => {
+                if rename.is_some() || default {
+                    return Err(Error::new(
+                        field.span(),
+                        "key and generation fields cannot be renamed or have a default",
+                    ));
+                }
+                kind
+            }
+            None => {
+                let name = match &rename {
+                    Some(lit) => lit.value(),
+                    None => ident.to_string(),
+                };
+                let span = rename.as_ref().map_or_else(|| ident.span(), LitStr::span);
+                if name.is_empty() || name.len() > MAX_BIN_NAME_LEN {
+                    return Err(Error::new(
+                        span,
+                        format!(
+                            "bin name `{name}` must be between 1 and {MAX_BIN_NAME_LEN} bytes long"
+                        ),
+                    ));
+                }
+                if bin_names.contains(&name) {
+                    return Err(Error::new(span, format!("duplicate bin name `{name}`")));
+                }
+                bin_names.push(name.clone());
+                FieldKind::Bin { name, default }
+            }
+        };
+
+        match kind {
+            FieldKind::Bin { name, default } => {
+                builder_fields.push(quote! { #ident: ::std::option::Option<#ty> });
+                decode_arms.push(quote! {
+                    #name => #krate::__decode_field(&mut builder.#ident, bin)
+                });
+                if default {
+                    build_fields.push(quote! {
+                        #ident: builder.#ident.unwrap_or_default()
+                    });
+                } else {
+                    build_fields.push(quote! {
+                        #ident: #krate::__field_value(builder.#ident, #name)?
+                    });
+                }
+            }
+            FieldKind::Key => build_fields.push(quote! { #ident: meta.key }),
+            FieldKind::Generation => build_fields.push(quote! { #ident: meta.generation }),
+        }
+    }
+
+    let builder = format_ident!("__{}Builder", name);
+    let bins = if bin_names.is_empty() {
+        quote! { #krate::Bins::None }
+    } else {
+        quote! { #krate::Bins::Some(vec![#(#bin_names.to_string()),*]) }
+    };
+
+    Ok(quote! {
+        const _: () = {
+            #[doc(hidden)]
+            #[derive(Default)]
+            pub struct #builder {
+                #(#builder_fields,)*
+            }
+
+            #[automatically_derived]
+            impl #krate::FromBins for #name {
+                type Builder = #builder;
+
+                fn bins() -> #krate::Bins {
+                    #bins
+                }
+
+                #[allow(unused_variables)]
+                fn decode_bin(
+                    builder: &mut Self::Builder,
+                    bin: &#krate::BinView<'_>,
+                ) -> #krate::Result<()> {
+                    match bin.name() {
+                        #(#decode_arms,)*
+                        _ => ::std::result::Result::Ok(()),
+                    }
+                }
+
+                #[allow(unused_variables)]
+                fn build(
+                    builder: Self::Builder,
+                    meta: #krate::RecordMeta,
+                ) -> #krate::Result<Self> {
+                    ::std::result::Result::Ok(#name {
+                        #(#build_fields,)*
+                    })
+                }
+            }
+        };
+    })
+}
@@ -2,6 +2,25 @@ extern crate proc_macro;
 use proc_macro::TokenStream;
 use quote::quote;
 
+mod exp;
+mod from_bins;
+
+#[doc(hidden)]
+#[proc_macro]
+pub fn exp(input: TokenStream) -> TokenStream {
+    exp::expand(input.into())
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+/// Derives `FromBins`, see the trait for the supported attributes.
+#[proc_macro_derive(FromBins, attributes(aerospike))]
+pub fn derive_from_bins(input: TokenStream) -> TokenStream {
+    from_bins::expand(input.into())
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
 #[doc(hidden)]
 #[proc_macro_attribute]
 pub fn test(_attr: TokenStream, input: TokenStream) -> TokenStream {
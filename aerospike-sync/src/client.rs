@@ -19,15 +19,15 @@ use std::vec::Vec;
 
 use crate::expressions::Expression;
 use aerospike_core::errors::Result;
-use aerospike_core::operations::{CdtContext, Operation};
+use aerospike_core::operations::{CdtContext, OperateResults, Operation};
 use aerospike_core::query::PartitionFilter;
 use aerospike_core::DropIndexTask;
 use aerospike_core::UdfRemoveTask;
 use aerospike_core::{
     AdminPolicy, BatchOperation, BatchPolicy, BatchRecord, Bin, Bins, ClientPolicy,
-    CollectionIndexType, ExecuteTask, IndexTask, IndexType, Key, Node, Privilege, QueryPolicy,
-    ReadPolicy, Record, Recordset, RegisterTask, Role, Statement, ToHosts, UDFLang, User, Value,
-    WritePolicy,
+    CollectionIndexType, ExecuteTask, FromBins, IndexTask, IndexType, Key, Node, Privilege,
+    QueryPolicy, ReadPolicy, Record, RecordView, Recordset, RegisterTask, Role, Statement, ToHosts,
+    UDFLang, User, Value, WritePolicy,
 };
 use futures::executor::block_on;
 
@@ -120,6 +120,12 @@ impl Client {
         self.async_client.nodes()
     }
 
+    /// Adds hosts to the seeds the client connects to when it has no nodes. See
+    /// [`aerospike_core::Client::add_seeds`].
+    pub fn add_seeds(&self, hosts: &(dyn ToHosts + Send + Sync)) -> Result<()> {
+        self.async_client.add_seeds(hosts)
+    }
+
     /// Read record for the specified key. Depending on the bins value provided, all record bins,
     /// only selected record bins or only the record headers will be returned. The policy can be
     /// used to specify timeouts.
@@ -179,6 +185,67 @@ impl Client {
         block_on(self.async_client.get(policy, key, bins))
     }
 
+    /// Read record for the specified key and pass a borrowed view of it to the callback, while
+    /// the response is still in the connection buffer. String and blob bins are not copied and
+    /// list and map bins are decoded lazily. Returns the result of the callback.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,edition2021
+    /// # use aerospike_sync::*;
+    /// # let rt = tokio::runtime::Runtime::new().unwrap();
+    /// # let _guard = rt.enter();
+    ///
+    /// # let hosts = std::env::var("AEROSPIKE_HOSTS").unwrap_or_else(|_| "127.0.0.1:3000".to_string());
+    /// # let client = Client::new(&ClientPolicy::default(), &hosts).unwrap();
+    /// let key = as_key!("test", "test", "image");
+    /// let size = client.get_with(&ReadPolicy::default(), &key, ["data"], |record| {
+    ///     record.bin("data").and_then(|bin| bin.as_blob()).map_or(0, <[u8]>::len)
+    /// });
+    /// ```
+    pub fn get_with<T, F, R>(
+        &self,
+        policy: &ReadPolicy,
+        key: &Key,
+        bins: T,
+        view_fn: F,
+    ) -> Result<R>
+    where
+        T: Into<Bins> + Send + Sync + 'static,
+        F: FnOnce(RecordView<'_>) -> R + Send,
+        R: Send,
+    {
+        block_on(self.async_client.get_with(policy, key, bins, view_fn))
+    }
+
+    /// Read record for the specified key and decode it into a [`FromBins`] type. Only the bins
+    /// the type uses are read, and they are decoded straight into its fields.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,edition2021
+    /// # use aerospike_sync::*;
+    /// # let rt = tokio::runtime::Runtime::new().unwrap();
+    /// # let _guard = rt.enter();
+    ///
+    /// #[derive(FromBins)]
+    /// #[aerospike(crate = "aerospike_sync")]
+    /// struct User {
+    ///     name: String,
+    /// }
+    ///
+    /// # let hosts = std::env::var("AEROSPIKE_HOSTS").unwrap_or_else(|_| "127.0.0.1:3000".to_string());
+    /// # let client = Client::new(&ClientPolicy::default(), &hosts).unwrap();
+    /// let key = as_key!("test", "users", "ann");
+    /// match client.get_as::<User>(&ReadPolicy::default(), &key, User::bins()) {
+    ///     Ok(user) => println!("name: {}", user.name),
+    ///     Err(err) => println!("Error fetching user: {}", err),
+    /// }
+    /// ```
+    pub fn get_as<T: FromBins>(&self, policy: &ReadPolicy, key: &Key, bins: Bins) -> Result<T> {
+        block_on(self.async_client.get_as(policy, key, bins))
+    }
+
     /// Read multiple record for specified batch keys in one batch call. This method allows
     /// different namespaces/bins to be requested for each key in the batch. If the `BatchRead` key
     /// field is not found, the corresponding record field will be `None`. The policy can be used
@@ -224,6 +291,18 @@ impl Client {
         block_on(self.async_client.batch(policy, batch_records))
     }
 
+    /// Read multiple records in one batch call and decode them into a [`FromBins`] type. The
+    /// result has one entry per key, in the same order, which is `None` if the record does not
+    /// exist.
+    pub fn batch_get_as<T: FromBins>(
+        &self,
+        policy: &BatchPolicy,
+        keys: &[Key],
+        bins: Bins,
+    ) -> Result<Vec<Option<T>>> {
+        block_on(self.async_client.batch_get_as(policy, keys, bins))
+    }
+
     /// Write record bin(s). The policy specifies the transaction timeout, record expiration and
     /// how the transaction is handled when the record already exists.
     ///
@@ -268,6 +347,32 @@ impl Client {
         block_on(self.async_client.put(policy, key, bins))
     }
 
+    /// Write a JSON document to the record with the given key. Depending on the layout, each
+    /// top-level field of the document is stored in its own bin, or the whole document is stored
+    /// in a single bin. Requires the `json` feature.
+    #[cfg(feature = "json")]
+    pub fn put_json(
+        &self,
+        policy: &WritePolicy,
+        key: &Key,
+        layout: &aerospike_core::json::JsonLayout,
+        doc: &serde_json::Value,
+    ) -> Result<()> {
+        block_on(self.async_client.put_json(policy, key, layout, doc))
+    }
+
+    /// Read a JSON document written by [`put_json`](Self::put_json) with the same layout.
+    /// Requires the `json` feature.
+    #[cfg(feature = "json")]
+    pub fn get_json(
+        &self,
+        policy: &ReadPolicy,
+        key: &Key,
+        layout: &aerospike_core::json::JsonLayout,
+    ) -> Result<serde_json::Value> {
+        block_on(self.async_client.get_json(policy, key, layout))
+    }
+
     /// Add integer bin values to existing record bin values. The policy specifies the transaction
     /// timeout, record expiration and how the transaction is handled when the record already
     /// exists. This call only works for integer values.
@@ -406,6 +511,40 @@ impl Client {
         block_on(self.async_client.operate(policy, key, ops))
     }
 
+    /// Read/Write multiple bins for a specified key in one database call, like
+    /// [`operate`](Self::operate), and return the result of each operation separately.
+    ///
+    /// The command is sent with [`respond_per_each_op`](WritePolicy::respond_per_each_op) set, so
+    /// that every result can be assigned to the operation that produced it.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,edition2021
+    /// # use aerospike_sync::*;
+    /// # let rt = tokio::runtime::Runtime::new().unwrap();
+    /// # let _guard = rt.enter();
+    ///
+    /// # let hosts = std::env::var("AEROSPIKE_HOSTS").unwrap_or_else(|_| "127.0.0.1:3000".to_string());
+    /// # let client = Client::new(&ClientPolicy::default(), &hosts).unwrap();
+    /// let key = as_key!("test", "test", "mykey");
+    /// let ops = vec![
+    ///     operations::add(&as_bin!("a", 42)),
+    ///     operations::get_bin("a"),
+    /// ];
+    /// match client.operate_results(&WritePolicy::default(), &key, &ops) {
+    ///     Ok(results) => println!("The new value is {}", results.as_i64(1).unwrap()),
+    ///     Err(err) => println!("Error writing record: {}", err),
+    /// }
+    /// ```
+    pub fn operate_results(
+        &self,
+        policy: &WritePolicy,
+        key: &Key,
+        ops: &[Operation],
+    ) -> Result<OperateResults> {
+        block_on(self.async_client.operate_results(policy, key, ops))
+    }
+
     /// Register a package containing user-defined functions (UDF) with the cluster. This
     /// asynchronous server call will return before the command is complete. The client registers
     /// the UDF package with a single, random cluster node; from there a copy will get distributed
@@ -543,6 +682,20 @@ impl Client {
         block_on(self.async_client.query(policy, partition_filter, statement))
     }
 
+    /// Execute a query on all server nodes like [`query`](Self::query), but decode the records
+    /// into a [`FromBins`] type as they are read.
+    pub fn query_as<T: FromBins>(
+        &self,
+        policy: &QueryPolicy,
+        partition_filter: PartitionFilter,
+        statement: Statement,
+    ) -> Result<Arc<Recordset<T>>> {
+        block_on(
+            self.async_client
+                .query_as(policy, partition_filter, statement),
+        )
+    }
+
     /// Execute a query and apply operations to matching records on the server.
     /// Returns an `ExecuteTask` that can be used to monitor the progress of the
     /// background job.
@@ -0,0 +1,57 @@
+//! CRUD operations using the **blocking** client.
+//!
+//! Unlike the sync client, the blocking client does not need an async runtime: all network
+//! I/O and cluster tending happen on the calling thread, so there is no `#[tokio::main]`.
+//!
+//! Run with the blocking feature enabled:
+//!
+//! ```bash
+//! cargo run --example crud_blocking --no-default-features --features "async,rt-tokio,blocking"
+//! ```
+
+#[macro_use]
+extern crate aerospike;
+
+use std::env;
+use std::time::Instant;
+
+use aerospike::blocking::Client;
+use aerospike::operations;
+use aerospike::{Bins, ClientPolicy, ReadPolicy, WritePolicy};
+
+fn main() {
+    let cpolicy = ClientPolicy::default();
+    let hosts = env::var("AEROSPIKE_HOSTS").unwrap_or_else(|_| "127.0.0.1:3000".to_string());
+    let client = Client::new(&cpolicy, &hosts).expect("Failed to connect to cluster");
+
+    let now = Instant::now();
+    let rpolicy = ReadPolicy::default();
+    let wpolicy = WritePolicy::default();
+    let key = as_key!("test", "test", "test");
+
+    let bins = [as_bin!("int", 999), as_bin!("str", "Hello, World!")];
+    client.put(&wpolicy, &key, &bins).unwrap();
+    let rec = client.get(&rpolicy, &key, Bins::All).unwrap();
+    println!("Record: {}", rec);
+
+    client.touch(&wpolicy, &key).unwrap();
+    let rec = client.get(&rpolicy, &key, Bins::None).unwrap();
+    println!("Record Header: {}", rec);
+
+    let exists = client.exists(&rpolicy, &key).unwrap();
+    println!("exists: {}", exists);
+
+    let bin = as_bin!("int", 1);
+    let ops = &[operations::add(&bin), operations::get()];
+    let op_rec = client.operate(&wpolicy, &key, ops).unwrap();
+    println!("operate: {}", op_rec);
+
+    let existed = client.delete(&wpolicy, &key).unwrap();
+    println!("existed (should be true): {}", existed);
+
+    let existed = client.delete(&wpolicy, &key).unwrap();
+    println!("existed (should be false): {}", existed);
+
+    client.close();
+    println!("total time: {:?}", now.elapsed());
+}
@@ -0,0 +1,80 @@
+// Copyright 2015-2018 Aerospike, Inc.
+//
+// Portions may be licensed to Aerospike, Inc. under one or more contributor
+// license agreements.
+//
+// Licensed under the Apache License, Version 2.0 (the "License"); you may not
+// use this file except in compliance with the License. You may obtain a copy of
+// the License at http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS, WITHOUT
+// WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied. See the
+// License for the specific language governing permissions and limitations under
+// the License.
+
+use std::fs;
+
+use aerospike::config::ConfigWatcher;
+use aerospike::{as_bin, as_key, Bins};
+use aerospike_rt::time::Duration;
+
+use crate::common;
+
+#[aerospike_macro::test]
+async fn watcher_applies_file_changes() {
+    let client = common::client().await;
+    let namespace: &str = common::namespace();
+    let initial = client.cluster.client_policy();
+
+    let path = std::env::temp_dir().join(format!("aerospike-{}.toml", std::process::id()));
+    fs::write(
+        &path,
+        "[client]\ntimeout = 4321\nmax_conns_per_node = 64\n\n[write]\nsend_key = true\n",
+    )
+    .unwrap();
+
+    let watcher = ConfigWatcher::start(&client, &path, Duration::from_secs(60)).unwrap();
+    let policy = client.cluster.client_policy();
+    assert_eq!(policy.timeout, 4321);
+    assert_eq!(policy.max_conns_per_node, 64);
+    assert_eq!(policy.tend_interval, initial.tend_interval);
+    for node in client.nodes() {
+        assert_eq!(node.client_policy().max_conns_per_node, 64);
+    }
+    assert!(watcher.defaults().write.send_key);
+
+    // the client keeps working with the applied policies
+    let key = as_key!(namespace, "config", 1);
+    let bin = as_bin!("a", 1);
+    client
+        .put(&watcher.defaults().write, &key, &[bin])
+        .await
+        .unwrap();
+    let record = client
+        .get(&watcher.defaults().read, &key, Bins::All)
+        .await
+        .unwrap();
+    assert!(record.key.is_some());
+
+    // unchanged content is not applied again
+    assert!(!watcher.reload().unwrap());
+
+    // removed values revert to the initial policy
+    fs::write(&path, "[read]\nmax_retries = 0\n").unwrap();
+    assert!(watcher.reload().unwrap());
+    let policy = client.cluster.client_policy();
+    assert_eq!(policy.timeout, initial.timeout);
+    assert_eq!(policy.max_conns_per_node, initial.max_conns_per_node);
+    assert!(!watcher.defaults().write.send_key);
+    assert_eq!(watcher.defaults().read.base_policy.max_retries, 0);
+
+    // an invalid file keeps the current settings
+    fs::write(&path, "[client]\ntimeout = \"slow\"\n").unwrap();
+    assert!(watcher.reload().is_err());
+    assert_eq!(watcher.defaults().read.base_policy.max_retries, 0);
+
+    drop(watcher);
+    fs::remove_file(&path).unwrap();
+    client.close().await.unwrap();
+}
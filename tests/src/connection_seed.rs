@@ -15,7 +15,9 @@
 
 use std::time::Instant;
 
-use aerospike::{as_bin, as_key, AdminPolicy, Bins, Client, Key, ReadPolicy, WritePolicy};
+use aerospike::{
+    as_bin, as_key, AdminPolicy, Bins, Client, Key, PartitionProblem, ReadPolicy, WritePolicy,
+};
 use aerospike_rt::time::Duration;
 
 use crate::common;
@@ -357,3 +359,46 @@ async fn probe_remove(client: &Client, ns: &str, set: &str, _bin: &str, n: i64)
     }
     (ok, fail)
 }
+
+#[aerospike_macro::test]
+async fn request_tend_coalesces_concurrent_requests() {
+    let client = common::client().await;
+    let node_count = client.nodes().len();
+
+    let start = Instant::now();
+    let requests = (0..32).map(|_| client.cluster.request_tend(None));
+    futures::future::join_all(requests).await;
+
+    // All requests are served by a single tend cycle, rather than by 32 consecutive ones.
+    assert!(start.elapsed() < Duration::from_secs(3));
+    assert_eq!(client.nodes().len(), node_count);
+
+    client.close().await.unwrap();
+}
+
+#[aerospike_macro::test]
+async fn partition_map_routes_every_partition() {
+    let client = common::client().await;
+    let node_names: Vec<String> = client
+        .nodes()
+        .iter()
+        .map(|n| n.name().to_string())
+        .collect();
+
+    let map = client.partition_map();
+    let partitions = &map.namespaces[common::namespace()];
+    assert_eq!(partitions.partitions.len(), 4096);
+    for routing in &partitions.partitions {
+        let master = routing.master().expect("partition without master");
+        assert!(node_names.iter().any(|name| name == master));
+    }
+    assert!(!map
+        .issues()
+        .iter()
+        .any(|issue| issue.problem == PartitionProblem::NoMaster));
+    for name in &node_names {
+        assert!(map.generations[name] >= 0);
+    }
+
+    client.close().await.unwrap();
+}
@@ -2,7 +2,9 @@ use crate::common;
 
 use aerospike::operations::hll;
 use aerospike::operations::hll::HLLPolicy;
-use aerospike::{as_key, as_list, as_val, Bins, FloatValue, ReadPolicy, Value, WritePolicy};
+use aerospike::{
+    as_key, as_list, as_val, Bins, FloatValue, HllSketch, ReadPolicy, Value, WritePolicy,
+};
 
 #[aerospike_macro::test]
 async fn hll() {
@@ -88,6 +90,21 @@ async fn hll() {
     ]);
     assert_eq!(*rec.bins.get("bin").unwrap(), val, "Union does not match");
 
+    let mut sketch = HllSketch::new(4, 0).unwrap();
+    sketch.add(&Value::from("asd123")).unwrap();
+    let rec = client
+        .get(&rpolicy, &key, Bins::from(["bin"]))
+        .await
+        .unwrap();
+    let Value::HLL(data) = rec.bins.get("bin").unwrap() else {
+        panic!("HLL bin is not an HLL value");
+    };
+    assert_eq!(
+        HllSketch::from_bytes(data).unwrap(),
+        sketch,
+        "Client sketch does not match"
+    );
+
     let ops = &vec![hll::refresh_count("bin")];
     let rec = client.operate(&wpolicy, &key, ops).await.unwrap();
     assert_eq!(
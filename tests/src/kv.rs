@@ -13,7 +13,8 @@
 // License for the specific language governing permissions and limitations under
 // the License.
 use aerospike::{
-    as_bin, as_blob, as_geo, as_key, as_list, as_map, as_val, Bins, ReadPolicy, Value, WritePolicy,
+    as_bin, as_blob, as_geo, as_key, as_list, as_map, as_val, Bins, OrderedRecord, ReadPolicy,
+    Value, WritePolicy,
 };
 use aerospike::{operations, Error, Expiration, ReadTouchTTL, ResultCode};
 use aerospike_rt::sleep;
@@ -135,6 +136,15 @@ async fn connect() {
     let record = client.get(&policy, &key, Bins::None).await.unwrap();
     assert_eq!(record.bins.len(), 0);
 
+    let record: OrderedRecord = client
+        .get_as(&policy, &key, ["bin f64", "bin999"].into())
+        .await
+        .unwrap();
+    assert_eq!(
+        record.bin_names().collect::<Vec<_>>(),
+        vec!["bin f64", "bin999"]
+    );
+
     let exists = client.exists(&policy, &key).await.unwrap();
     assert!(exists);
 
@@ -0,0 +1,108 @@
+// Copyright 2015-2018 Aerospike, Inc.
+//
+// Portions may be licensed to Aerospike, Inc. under one or more contributor
+// license agreements.
+//
+// Licensed under the Apache License, Version 2.0 (the "License"); you may not
+// use this file except in compliance with the License. You may obtain a copy of
+// the License at http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS, WITHOUT
+// WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied. See the
+// License for the specific language governing permissions and limitations under
+// the License.
+
+use aerospike::{as_key, LargeObjectPolicy};
+use futures::{AsyncReadExt, AsyncWriteExt};
+
+use crate::common;
+
+fn policy() -> LargeObjectPolicy {
+    let mut policy = LargeObjectPolicy::default();
+    policy.chunk_size = 1000;
+    policy.parallel_chunks = 3;
+    policy
+}
+
+fn data(size: usize, seed: u8) -> Vec<u8> {
+    (0..size)
+        .map(|i| (i as u8).wrapping_mul(31) ^ seed)
+        .collect()
+}
+
+#[aerospike_macro::test]
+async fn large_object_put_get() {
+    let client = common::client().await;
+    let namespace: &str = common::namespace();
+    let set_name = &common::rand_str(10);
+    let key = as_key!(namespace, set_name, "object");
+    let policy = policy();
+    let object = client.large_object(&policy, &key);
+
+    assert_eq!(object.get().await.unwrap(), None);
+    assert!(!object.delete().await.unwrap());
+
+    let first = data(10_500, 1);
+    object.put(&first).await.unwrap();
+    let manifest = object.manifest().await.unwrap().unwrap();
+    assert_eq!(manifest.size, 10_500);
+    assert_eq!(manifest.chunks, 11);
+    assert_eq!(manifest.chunk_size, 1000);
+    assert_eq!(object.get().await.unwrap(), Some(first));
+
+    // overwriting swaps the manifest
+    let second = data(2_000, 2);
+    object.put(&second).await.unwrap();
+    assert_eq!(object.manifest().await.unwrap().unwrap().chunks, 2);
+    assert_eq!(object.get().await.unwrap(), Some(second));
+
+    object.put(&[]).await.unwrap();
+    assert_eq!(object.get().await.unwrap(), Some(vec![]));
+
+    assert!(object.delete().await.unwrap());
+    assert_eq!(object.get().await.unwrap(), None);
+
+    client.close().await.unwrap();
+}
+
+#[aerospike_macro::test]
+async fn large_object_streaming() {
+    let client = common::client().await;
+    let namespace: &str = common::namespace();
+    let set_name = &common::rand_str(10);
+    let key = as_key!(namespace, set_name, "object");
+    let policy = policy();
+    let object = client.large_object(&policy, &key);
+
+    let expected = data(25_123, 3);
+    let mut writer = object.writer();
+    for part in expected.chunks(777) {
+        writer.write_all(part).await.unwrap();
+    }
+    writer.flush().await.unwrap();
+    assert_eq!(object.manifest().await.unwrap(), None);
+    writer.close().await.unwrap();
+
+    let mut reader = object.reader().await.unwrap().unwrap();
+    assert_eq!(reader.manifest().size, 25_123);
+    let mut actual = Vec::new();
+    let mut buf = [0; 333];
+    loop {
+        let n = reader.read(&mut buf).await.unwrap();
+        if n == 0 {
+            break;
+        }
+        actual.extend_from_slice(&buf[..n]);
+    }
+    assert_eq!(actual, expected);
+
+    // a writer that is dropped before it is closed leaves the object in place
+    let mut writer = object.writer();
+    writer.write_all(&data(5_000, 4)).await.unwrap();
+    drop(writer);
+    assert_eq!(object.get().await.unwrap(), Some(expected));
+
+    object.delete().await.unwrap();
+    client.close().await.unwrap();
+}
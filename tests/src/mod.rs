@@ -25,6 +25,8 @@ mod cdt_bitwise;
 mod cdt_list;
 mod cdt_map;
 mod cleanup;
+#[cfg(feature = "config-toml")]
+mod config;
 mod connection_seed;
 mod exp;
 mod exp_bitwise;
@@ -35,6 +37,8 @@ mod exp_op;
 mod hll;
 mod index;
 mod kv;
+mod large_object;
+mod multi_cluster;
 mod query;
 mod scan;
 #[cfg(feature = "serialization")]
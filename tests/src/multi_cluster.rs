@@ -0,0 +1,134 @@
+// Copyright 2015-2018 Aerospike, Inc.
+//
+// Portions may be licensed to Aerospike, Inc. under one or more contributor
+// license agreements.
+//
+// Licensed under the Apache License, Version 2.0 (the "License"); you may not
+// use this file except in compliance with the License. You may obtain a copy of
+// the License at http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS, WITHOUT
+// WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied. See the
+// License for the specific language governing permissions and limitations under
+// the License.
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use aerospike::{
+    as_bin, as_key, Bins, DualWrite, Error, MultiClusterClient, MultiClusterPolicy, ReadPolicy,
+    WritePolicy,
+};
+
+use crate::common;
+
+// Both "clusters" connect to the test cluster, so every write is visible through either.
+async fn multi_client(policy: &MultiClusterPolicy) -> MultiClusterClient {
+    let clusters = vec![
+        ("east".to_string(), common::client().await),
+        ("west".to_string(), common::client().await),
+    ];
+    MultiClusterClient::new(policy, clusters).unwrap()
+}
+
+#[aerospike_macro::test]
+async fn multi_cluster_read_write() {
+    let client = multi_client(&MultiClusterPolicy::default()).await;
+    let namespace: &str = common::namespace();
+    let set_name = &common::rand_str(10);
+    let key = as_key!(namespace, set_name, "multi");
+    let wpolicy = WritePolicy::default();
+
+    client
+        .put(&wpolicy, &key, &[as_bin!("a", 1)])
+        .await
+        .unwrap();
+    client
+        .add(&wpolicy, &key, &[as_bin!("a", 2)])
+        .await
+        .unwrap();
+    let record = client
+        .get(&ReadPolicy::default(), &key, Bins::All)
+        .await
+        .unwrap();
+    assert_eq!(record.bins["a"], 3.into());
+
+    // commands that are not wrapped are routed with a closure
+    let rpolicy = ReadPolicy::default();
+    let exists = client.read(|c| c.exists(&rpolicy, &key)).await.unwrap();
+    assert!(exists);
+
+    assert!(client.delete(&wpolicy, &key).await.unwrap());
+    assert!(!client.exists(&ReadPolicy::default(), &key).await.unwrap());
+
+    client.close().await.unwrap();
+}
+
+#[aerospike_macro::test]
+async fn multi_cluster_switchover() {
+    let mut policy = MultiClusterPolicy::default();
+    policy.active_cluster = Some("west".to_string());
+    policy.dual_write = DualWrite::Required;
+    let client = multi_client(&policy).await;
+    assert_eq!(client.active_cluster(), "west");
+    assert_eq!(client.preferred_read_cluster(), "east");
+
+    client.switch_active("east").unwrap();
+    assert_eq!(client.active_cluster(), "east");
+    assert!(client.switch_active("north").is_err());
+
+    let status = client.status();
+    assert_eq!(status.len(), 2);
+    assert!(status[0].active && status[0].preferred_read && status[0].available);
+    assert!(!status[1].active && status[1].available);
+
+    let namespace: &str = common::namespace();
+    let set_name = &common::rand_str(10);
+    let key = as_key!(namespace, set_name, "switchover");
+    client
+        .put(&WritePolicy::default(), &key, &[as_bin!("a", 1)])
+        .await
+        .unwrap();
+    let record = client
+        .cluster("west")
+        .unwrap()
+        .get(&ReadPolicy::default(), &key, Bins::All)
+        .await
+        .unwrap();
+    assert_eq!(record.bins["a"], 1.into());
+
+    client.close().await.unwrap();
+}
+
+#[aerospike_macro::test]
+async fn multi_cluster_write_failover() {
+    let mut policy = MultiClusterPolicy::default();
+    policy.failover_writes = true;
+    let client = multi_client(&policy).await;
+
+    // a write that was not sent fails over to the next cluster
+    let calls = AtomicUsize::new(0);
+    let res: aerospike::Result<()> = client
+        .write(|_| async {
+            calls.fetch_add(1, Ordering::Relaxed);
+            Err(Error::Connection(
+                "Could not open network connection".to_string(),
+            ))
+        })
+        .await;
+    assert!(matches!(res, Err(Error::Connection(_))));
+    assert_eq!(calls.load(Ordering::Relaxed), 2);
+
+    // a timed out write may have been applied, and is not sent again
+    let calls = AtomicUsize::new(0);
+    let res: aerospike::Result<()> = client
+        .write(|_| async {
+            calls.fetch_add(1, Ordering::Relaxed);
+            Err(Error::Timeout("Timeout after 3 tries".to_string()))
+        })
+        .await;
+    assert!(matches!(res, Err(Error::Timeout(_))));
+    assert_eq!(calls.load(Ordering::Relaxed), 1);
+
+    client.close().await.unwrap();
+}